@@ -1,4 +1,4 @@
-use crate::data::state::ClientState;
+use crate::data::state::{ClientState, PageData};
 use crate::pages::layout::LayoutRender;
 use crate::pages::pigpage::PigPageRender;
 use crate::pages::{RenderPage, Routes};
@@ -21,6 +21,9 @@ pub struct PigWebClient {
 
     /// The last hash which was requested
     last_hash: String,
+
+    /// The title last given to the browser tab
+    last_title: String,
 }
 
 impl Default for PigWebClient {
@@ -30,6 +33,7 @@ impl Default for PigWebClient {
             layout: LayoutRender::default(),
             page_render: Box::new(PigPageRender::default()),
             last_hash: String::new(),
+            last_title: String::new(),
         }
     }
 }
@@ -46,14 +50,21 @@ impl eframe::App for PigWebClient {
 
             // get the route from the url
             let route = match url.pathname.as_str() {
-                "/pigs" | "/" => Routes::Pigs,
+                "/" => self.state.settings.landing_page,
+                "/pigs" => Routes::Pigs,
                 "/bulk" => Routes::Bulk,
+                "/review" => Routes::Review,
+                "/settings" => Routes::Settings,
+                "/tags" => Routes::Tags,
                 "/users" => Routes::Users,
                 _ => Routes::NotFound,
             };
 
             // If the route has changed, update the state to reflect it
             if route != self.state.route {
+                // Errors from the previous page aren't relevant anymore
+                self.state.pages.layout.close_scope(self.state.route);
+
                 self.last_hash = url.hash.to_owned();
                 self.state.route = route;
                 self.page_render = self.state.route.get_renderer();
@@ -67,14 +78,24 @@ impl eframe::App for PigWebClient {
                 self.page_render.on_url_update(ui.ctx(), &mut self.state, &url);
             }
 
+            // Pick up changes made by other users before the page is shown
+            let following = self.state.can_request("/api/changes");
+            self.state.changes.update(ui.ctx(), following);
+
             // Render the page
-            self.page_render.ui(ui, &mut self.state, &url)
+            self.page_render.ui(ui, &mut self.state, &url);
+
+            // Fetch any usernames the page needed
+            self.state.usernames.update();
+
+            self.update_title(ui.ctx());
         });
     }
 
     // Called by the framework to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, Self::APP_KEY, &self.state);
+        self.state.pages.save(storage);
     }
 }
 
@@ -88,9 +109,13 @@ impl PigWebClient {
         // Note that you must enable the `persistence` feature for this to work.
         let mut state: ClientState =
             cc.storage.and_then(|storage| eframe::get_value(storage, Self::APP_KEY)).unwrap_or_default();
+        state.pages = PageData::load(cc.storage);
 
         // Setup styles
-        state.colorix = style::set_styles(cc);
+        state.colorix = style::set_styles(cc, &state.settings);
+
+        // Lets the instance logo be loaded from a URL
+        egui_extras::install_image_loaders(&cc.egui_ctx);
 
         let mut res: PigWebClient = Self { state, ..Self::default() };
 
@@ -104,6 +129,25 @@ impl PigWebClient {
         res
     }
 
+    /// Sets the browser tab's title to the selection, page, and instance name,
+    /// e.g. "Porkchop – Pigs – PigWeb", so tabs and history entries can be
+    /// told apart
+    fn update_title(&mut self, ctx: &egui::Context) {
+        let instance = self.state.branding.as_ref().map(|branding| branding.name.as_str()).unwrap_or("PigWeb");
+        let title = self
+            .page_render
+            .selection_title(&self.state)
+            .into_iter()
+            .chain([self.state.route.title().to_owned(), instance.to_owned()])
+            .collect::<Vec<String>>()
+            .join(" – ");
+
+        if title != self.last_title {
+            set_title(ctx, &title);
+            self.last_title = title;
+        }
+    }
+
     /// Parses current web browser URL from eframe
     fn url_from_webinfo(info: &WebInfo) -> ParsedURL {
         let mut url = parse_url(info.location.url.as_str());
@@ -111,3 +155,18 @@ impl PigWebClient {
         url
     }
 }
+
+/// Sets the title of the browser tab
+#[cfg(target_arch = "wasm32")]
+fn set_title(_ctx: &egui::Context, title: &str) {
+    // eframe doesn't pass viewport titles on to the page, so set it on the document ourselves
+    if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+        document.set_title(title);
+    }
+}
+
+/// Sets the title of the window
+#[cfg(not(target_arch = "wasm32"))]
+fn set_title(ctx: &egui::Context, title: &str) {
+    ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.to_owned()));
+}
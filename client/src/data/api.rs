@@ -1,14 +1,31 @@
 #![allow(dead_code)]
 
 use crate::data::state::ClientState;
+use crate::pages::Routes;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use egui::Context;
 use ehttp::{Credentials, Headers, Method, Request, Response};
-use log::{debug, error};
-use pigweb_common::bulk::{BulkImport, BulkPatch, BulkQuery};
+use log::{debug, error, warn};
+use pigweb_common::audit::PigChange;
+use pigweb_common::bulk::{BulkDiff, BulkDuplicate, BulkImport, BulkPatch, BulkQuery, BulkReview, BulkSuggestion};
+use pigweb_common::changes::ChangeBatch;
+use pigweb_common::errors::ErrorCode;
+use pigweb_common::filters::SavedFilter;
+use pigweb_common::instance::{Branding, Environment, ProviderHealth};
+use pigweb_common::notifications::Notification;
 use pigweb_common::pigs::{Pig, PigQuery};
-use pigweb_common::users::{Roles, User, UserFetchResponse, UserQuery};
-use pigweb_common::{query, yuri, AUTH_API_ROOT, BULK_API_ROOT, PIG_API_ROOT, USER_API_ROOT};
+use pigweb_common::tags::Tag;
+use pigweb_common::tokens::{ApiToken, ApiTokenRequest, ApiTokenSecret};
+use pigweb_common::users::{Capabilities, Roles, User, UserFetchResponse, UserQuery, WithCreator};
+use pigweb_common::{
+    query, yuri, AUDIT_API_ROOT, AUTH_API_ROOT, BULK_API_ROOT, CLIENT_VERSION_HEADER, FILTER_API_ROOT,
+    NOTIFICATION_API_ROOT, PIG_API_ROOT, TAG_API_ROOT, TOKEN_API_ROOT, USER_API_ROOT,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::oneshot;
 use tokio::sync::oneshot::{Receiver, Sender};
 use uuid::Uuid;
@@ -22,6 +39,61 @@ use uuid::Uuid;
 /// we get to free up the memory.
 type MaybeWaiting<T> = Option<Receiver<Result<T, ApiError>>>;
 
+/// How to retry requests which fail because the server couldn't be reached
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// The most times a request is sent, including the first
+    pub max_attempts: u32,
+
+    /// How long to wait before the first retry. This doubles after each one.
+    pub initial_delay: Duration,
+
+    /// The longest to wait between attempts
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 5, initial_delay: Duration::from_millis(500), max_delay: Duration::from_secs(8) }
+    }
+}
+
+impl RetryPolicy {
+    /// Gets how long to wait after the given attempt failed
+    fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))).min(self.max_delay)
+    }
+}
+
+/// The retry settings for a single request, given to endpoints which support
+/// retrying. The attempt counter is shared with the handler so the UI can show
+/// it.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    /// How to retry the request, or [None] if it shouldn't be
+    policy: Option<RetryPolicy>,
+
+    /// Which attempt the request is on, starting at 1
+    attempt: Arc<AtomicU32>,
+}
+
+/// The version of this client, sent with every request so the server can save
+/// which build each session was started from
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Changes which were rejected because the user's session expired, waiting to
+/// be picked up by [`take_expired_requests`]
+static EXPIRED_REQUESTS: Mutex<Vec<ReplayRequest>> = Mutex::new(Vec::new());
+
+/// How many requests are waiting on a response, including ones waiting to be
+/// retried
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// How many requests which could've changed something have been answered,
+/// anything other than a GET. Cached results from before then may be out of
+/// date, see [`crate::data::cache::QueryCache`].
+static CHANGES: AtomicU32 = AtomicU32::new(0);
+
 /// Represents the status of a request
 pub enum Status<T> {
     /// The request is done, here's the value
@@ -42,8 +114,22 @@ struct ApiErrorWrapper {
     error: ApiError,
 }
 
+/// How serious a notification is, which changes how it's shown
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Severity {
+    /// Something the user might want to know, but nothing went wrong
+    Info,
+
+    /// Something went wrong, but it was handled
+    Warning,
+
+    /// Something went wrong and the user may need to do something about it
+    #[default]
+    Error,
+}
+
 /// Represents an error encountered when handling API requests
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ApiError {
     /// The HTTP code returned by the server. Not set for local errors (JSON parsing)
     pub code: Option<u16>,
@@ -53,12 +139,58 @@ pub struct ApiError {
 
     /// A brief description of what the error is
     pub description: String,
+
+    /// What went wrong, so we can react without reading the description. Not
+    /// set for local errors.
+    pub kind: Option<ErrorCode>,
+
+    /// The id of the request which failed, so it can be found in the server's
+    /// logs. Not set for local errors.
+    pub request_id: Option<Uuid>,
+
+    /// The page this error came from. When set, the error is dismissed once
+    /// the page is closed. Otherwise, it stays until the user dismisses it.
+    #[serde(skip)]
+    pub scope: Option<Routes>,
+
+    /// How serious the error is
+    #[serde(skip)]
+    pub severity: Severity,
+
+    /// When the error was first shown to the user, in seconds since the app
+    /// started. Used to dismiss transient errors automatically.
+    #[serde(skip)]
+    pub shown_at: Option<f64>,
+
+    /// How many more times the same error happened after this one
+    #[serde(skip)]
+    pub repeats: u32,
+
+    /// When the error last happened
+    #[serde(skip)]
+    pub last_seen: DateTime<Utc>,
+
+    /// The method and url of the request which caused the error, if any
+    #[serde(skip)]
+    pub request: Option<String>,
 }
 
 impl ApiError {
     /// Creates a new ApiError with the given description
     pub fn new(description: String) -> Self {
-        Self { code: None, reason: None, description }
+        Self {
+            code: None,
+            reason: None,
+            description,
+            kind: None,
+            request_id: None,
+            scope: None,
+            severity: Severity::default(),
+            shown_at: None,
+            repeats: 0,
+            last_seen: Utc::now(),
+            request: None,
+        }
     }
 
     /// Sets the HTTP status code to the given value
@@ -72,15 +204,141 @@ impl ApiError {
         self.reason = Some(reason);
         self
     }
+
+    /// Sets what went wrong
+    pub fn with_kind(mut self, kind: ErrorCode) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Sets the method and url of the request which caused the error
+    pub fn with_request(mut self, request: String) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    /// Sets how serious the error is
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Whether the error should go away on its own. Anything less serious than
+    /// an error does, as do errors which will likely fix themselves, e.g. the
+    /// server being briefly unreachable.
+    pub fn is_transient(&self) -> bool {
+        self.severity != Severity::Error
+            || matches!(
+                self.kind,
+                Some(ErrorCode::Unavailable | ErrorCode::DatabaseUnavailable | ErrorCode::RateLimited)
+            )
+    }
+
+    /// Whether the given error is the same as this one, ignoring when each
+    /// happened
+    pub fn is_same(&self, other: &ApiError) -> bool {
+        self.code == other.code
+            && self.reason == other.reason
+            && self.description == other.description
+            && self.kind == other.kind
+            && self.scope == other.scope
+    }
+
+    /// Sets the page this error came from
+    pub fn with_scope(mut self, scope: Routes) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+}
+
+/// A request which would have changed something on the server, but failed
+/// because the user's session expired. This is kept around so the change can be
+/// sent again once the user signs back in rather than being lost.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReplayRequest {
+    /// The HTTP method, only methods which change data are replayed
+    pub method: String,
+
+    /// The full url the request was sent to
+    pub url: String,
+
+    /// The headers sent with the request
+    pub headers: Vec<(String, String)>,
+
+    /// The body of the request
+    pub body: Vec<u8>,
+}
+
+impl ReplayRequest {
+    /// Keeps a copy of the request if it changes data on the server, otherwise
+    /// there's no point in sending it again
+    fn from_request(req: &Request) -> Option<Self> {
+        let method = match req.method {
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::PATCH => "PATCH",
+            Method::DELETE => "DELETE",
+            _ => return None,
+        };
+
+        Some(Self {
+            method: method.to_owned(),
+            url: req.url.to_owned(),
+            headers: req.headers.headers.to_owned(),
+            body: req.body.to_owned(),
+        })
+    }
+
+    /// Converts this back into a request which can be submitted
+    fn to_request(&self) -> Request {
+        let method = match self.method.as_str() {
+            "PUT" => Method::PUT,
+            "PATCH" => Method::PATCH,
+            "DELETE" => Method::DELETE,
+            _ => Method::POST,
+        };
+
+        Request {
+            method,
+            credentials: Credentials::SameOrigin,
+            headers: Headers { headers: self.headers.to_owned() },
+            body: self.body.to_owned(),
+            ..Request::get(self.url.as_str())
+        }
+    }
+}
+
+/// Returns every change rejected because the user's session expired since the
+/// last time this was called
+pub fn take_expired_requests() -> Vec<ReplayRequest> {
+    EXPIRED_REQUESTS.lock().map(|mut reqs| std::mem::take(&mut *reqs)).unwrap_or_default()
+}
+
+/// Returns how many requests sent by any endpoint are still waiting on a response
+pub fn requests_in_flight() -> usize {
+    IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+/// Returns how many requests which could've changed something on the server
+/// have been answered so far
+pub fn changes_made() -> u32 {
+    CHANGES.load(Ordering::Relaxed)
 }
 
 /// Helper to get ApiErrors from Responses
 impl From<Response> for ApiError {
     fn from(res: Response) -> Self {
-        res.json::<ApiErrorWrapper>()
+        let mut err = res
+            .json::<ApiErrorWrapper>()
             .map_err(|err| ApiErrorWrapper { error: std::io::Error::from(err).into() })
             .unwrap_or_else(|e| e)
-            .error
+            .error;
+
+        // Errors which didn't come from the server itself, e.g. from a proxy, may not have these
+        err.code = err.code.or(Some(res.status));
+        err.kind = err.kind.or(Some(ErrorCode::from_status(res.status)));
+        err.request_id = err.request_id.or(res.headers.get("x-request-id").and_then(|id| Uuid::try_parse(id).ok()));
+        err
     }
 }
 
@@ -88,7 +346,7 @@ impl From<Response> for ApiError {
 /// to convert a JSON parse error into an error we care about.
 impl From<std::io::Error> for ApiError {
     fn from(err: std::io::Error) -> Self {
-        Self { code: None, reason: Some(err.kind().to_string()), description: err.to_string() }
+        Self::new(err.to_string()).with_reason(err.kind().to_string())
     }
 }
 
@@ -99,6 +357,7 @@ impl From<std::io::Error> for ApiError {
 /// - `received(state)` returns the response value as an option and performs
 ///   default error handling if something went wrong (shows a modal)
 /// - `discard()` forgets the previous request which was made
+/// - `attempt()` returns which attempt the last request is on
 ///
 /// This is designed around immediate-mode GUIs or anything which needs to be
 /// refreshed constantly and where you only care about the last thing submitted
@@ -110,6 +369,11 @@ impl From<std::io::Error> for ApiError {
 /// - The output type expected from the server
 /// - The expression actually making the request, should return a [`Receiver`]
 ///
+/// Idempotent GET endpoints can put `retry` before the expression. Their
+/// handlers get a `with_retry(policy)` function, and the expression receives
+/// a [`Retry`] as its second parameter which should be given to
+/// [`fetch_and_send_with_retry`].
+///
 /// Example:
 /// ```rust
 /// endpoint!(PigDeleteHandler, Uuid, Response, |input: Uuid| {
@@ -138,38 +402,71 @@ impl From<std::io::Error> for ApiError {
 /// ```
 // this must defined BEFORE the individual endpoints
 macro_rules! endpoint {
+    // Endpoints which can be retried also receive the handler's retry settings
+    ($name:ident, $input:ty, $output:ty, retry $requester:expr) => {
+        endpoint!(@handler $name, $input, $output);
+
+        impl $name {
+            /// Submit a request with the given input to this endpoint
+            pub fn request(&mut self, input: $input) {
+                // Start a new counter so retries of an old request can't change it.
+                // Replacing the receiver drops the old one, so only the latest
+                // request's response is ever received, even if an older one
+                // arrives after it.
+                self.attempt = Arc::new(AtomicU32::new(1));
+                let retry = Retry { policy: self.retry, attempt: self.attempt.clone() };
+                self.receiver = Some($requester(input, retry));
+            }
+
+            /// Automatically retries requests which fail because the server
+            /// couldn't be reached, waiting longer after each attempt
+            pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+                self.retry = Some(policy);
+                self
+            }
+        }
+    };
     ($name:ident, $input:ty, $output:ty, $requester:expr) => {
+        endpoint!(@handler $name, $input, $output);
+
+        impl $name {
+            /// Submit a request with the given input to this endpoint
+            pub fn request(&mut self, input: $input) {
+                self.attempt = Arc::new(AtomicU32::new(1));
+                self.receiver = Some($requester(input));
+            }
+        }
+    };
+    (@handler $name:ident, $input:ty, $output:ty) => {
         #[derive(Debug)]
         pub struct $name {
             receiver: MaybeWaiting<$output>,
+            retry: Option<RetryPolicy>,
+            attempt: Arc<AtomicU32>,
         }
 
         impl Default for $name {
             fn default() -> Self {
-                Self { receiver: None }
+                Self { receiver: None, retry: None, attempt: Arc::new(AtomicU32::new(0)) }
             }
         }
 
         impl $name {
-            /// Submit a request with the given input to this endpoint
-            pub fn request(&mut self, input: $input) {
-                self.receiver = Some($requester(input));
-            }
-
             /// Returns Some if the endpoint gave a successful response.
             ///
             /// If resolve() returns error 401, clears the user's session and
-            /// forces them to sign in again. Displays any other error.
+            /// forces them to sign in again. Displays any other error until the
+            /// current page is closed.
             ///
             /// No action is taken if the status is still pending.
             pub fn received(&mut self, state: &mut ClientState) -> Option<$output> {
                 match self.resolve() {
                     Status::Received(res) => Some(res),
                     Status::Errored(err) => {
-                        if err.code == Some(401) {
+                        if err.kind == Some(ErrorCode::Unauthorized) {
                             state.authorized = None;
                         } else {
-                            state.pages.layout.display_error.push(err);
+                            state.page_error(err);
                         }
                         None
                     }
@@ -189,6 +486,17 @@ macro_rules! endpoint {
                 status
             }
 
+            /// Returns which attempt the last request is on, starting at 1.
+            /// This is only more than 1 if the request is being retried.
+            pub fn attempt(&self) -> u32 {
+                self.attempt.load(Ordering::Relaxed)
+            }
+
+            /// Whether a request was sent which hasn't been resolved yet
+            pub fn is_pending(&self) -> bool {
+                self.receiver.is_some()
+            }
+
             /// Cancels the current request to this endpoint, ignoring any
             /// response.
             pub fn discard(&mut self) {
@@ -198,11 +506,93 @@ macro_rules! endpoint {
     };
 }
 
+/// API for the log of changes made to the pig list
+#[derive(Debug, Default)]
+pub struct AuditApi {
+    /// Fetches the most recently created and renamed pigs
+    pub modified: AuditRecentHandler,
+
+    /// Fetches the most recently deleted pigs
+    pub deleted: AuditRecentHandler,
+}
+
+endpoint!(AuditRecentHandler, bool, Vec<PigChange>, |deleted: bool| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(AUDIT_API_ROOT, "recent" ;? query!("deleted" = deleted.to_string().as_str())))
+    };
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the list of changes
+        res.json::<Vec<PigChange>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(ChangePollHandler, (Option<u64>, &Context), ChangeBatch, |input: (Option<u64>, &Context)| {
+    let (tx, rx) = oneshot::channel();
+    let ctx = input.1.clone();
+
+    // Without a cursor, the server responds right away with where to start from
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(match input.0 {
+            Some(cursor) => yuri!("/api", "changes" ;? query!("since" = cursor.to_string().as_str())),
+            None => yuri!("/api", "changes"),
+        })
+    };
+
+    fetch_in_background(req, tx, move |res| {
+        // The server may have held onto this for a while, make sure the response is noticed
+        ctx.request_repaint();
+
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the batch of changes
+        res.json::<ChangeBatch>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
 /// API for the current user's session and permissions
 #[derive(Debug, Default)]
 pub struct AuthApi {
     /// If the user is signed in, returns a list of roles, otherwise [None]
     pub is_authenticated: AuthCheckHandler,
+
+    /// Fetches the roles needed to use each endpoint
+    pub capabilities: CapabilitiesHandler,
+
+    /// Fetches the signed in user
+    pub me: AuthMeHandler,
+
+    /// Fetches how this instance presents itself, which works while signed out
+    pub branding: BrandingHandler,
+
+    /// Fetches which kind of deployment this is, which works while signed out
+    pub environment: EnvironmentHandler,
+
+    /// Fetches whether the OIDC provider could be reached the last time the
+    /// server checked
+    pub oidc_health: OidcHealthHandler,
+
+    /// Renews the user's session before it expires, returning when the new
+    /// one does
+    pub renew: AuthRenewHandler,
 }
 
 endpoint!(AuthCheckHandler, bool, Option<BTreeSet<Roles>>, |_ignored: bool| {
@@ -231,91 +621,62 @@ endpoint!(AuthCheckHandler, bool, Option<BTreeSet<Roles>>, |_ignored: bool| {
     rx
 });
 
-/// The API for importing multiple names at a time
-#[derive(Debug, Default)]
-pub struct BulkApi {
-    /// Creates a new import from the given list of names
-    pub create: BulkCreateHandler,
-
-    /// Applies the given changes to the import, returning the changes for
-    /// convenience upon success
-    pub patch: BulkPatchHandler,
-
-    /// Fetches all imports which the user can access and matches the given
-    /// query
-    pub fetch: BulkFetchHandler,
-}
-
-endpoint!(BulkCreateHandler, &Vec<String>, BulkImport, |input| {
+endpoint!(AuthMeHandler, bool, User, |_ignored: bool| {
     let (tx, rx) = oneshot::channel();
 
-    // If the JSON POST request was generated successfully
-    let req = Request::post_json(yuri!(BULK_API_ROOT, "create"), input);
-    if let Ok(req) = req {
-        // Add correct options to the request
-        let req = Request {
-            credentials: Credentials::SameOrigin,
-            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
-            ..req
-        };
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(AUTH_API_ROOT, "me"))
+    };
 
-        // Now actually submit the request, then relay the result to the channel sender
-        fetch_and_send(req, tx, |res| {
-            // Handle errors
-            if res.status >= 400 {
-                return Err(res.into());
-            }
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
 
-            // Convert the response to the correct type
-            res.json::<BulkImport>().map_err(|err| std::io::Error::from(err).into())
-        });
-    } else {
-        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
-    }
+        // Convert the response to the correct type
+        res.json::<User>().map_err(|err| std::io::Error::from(err).into())
+    });
 
     rx
 });
 
-endpoint!(BulkPatchHandler, BulkPatch, BulkPatch, |input: BulkPatch| {
+endpoint!(AuthRenewHandler, bool, NaiveDateTime, |_ignored: bool| {
     let (tx, rx) = oneshot::channel();
 
-    // If the JSON POST request was generated successfully
-    let req = Request::post_json(yuri!(BULK_API_ROOT, "patch"), &input);
-    if let Ok(req) = req {
-        // Add correct options to the request
-        let req = Request {
-            method: Method::PATCH,
-            credentials: Credentials::SameOrigin,
-            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
-            ..req
-        };
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::post(yuri!(AUTH_API_ROOT, "renew"), vec![])
+    };
 
-        // Now actually submit the request, then relay the result to the channel sender
-        // No fancy processing needed for this one
-        fetch_and_send(req, tx, |res| {
-            // Handle errors
-            if res.status >= 400 {
-                return Err(res.into());
-            }
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
 
-            Ok(input)
-        });
-    } else {
-        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
-    }
+        // Convert the response to the correct type
+        res.json::<NaiveDateTime>().map_err(|err| std::io::Error::from(err).into())
+    });
 
     rx
 });
 
-endpoint!(BulkFetchHandler, &BulkQuery, Vec<BulkImport>, |input: &BulkQuery| {
+endpoint!(CapabilitiesHandler, bool, Capabilities, |_ignored: bool| {
     let (tx, rx) = oneshot::channel();
 
     // Submit the request to the server
     let req = Request {
         credentials: Credentials::SameOrigin,
         headers: Headers::new(&[("Accept", "application/json")]),
-        ..Request::get(input.to_yuri())
+        ..Request::get(yuri!("/api", "capabilities"))
     };
+
     fetch_and_send(req, tx, |res| {
         // Handle errors
         if res.status >= 400 {
@@ -323,73 +684,992 @@ endpoint!(BulkFetchHandler, &BulkQuery, Vec<BulkImport>, |input: &BulkQuery| {
         }
 
         // Convert the response to the correct type
-        res.json::<Vec<BulkImport>>().map_err(|err| std::io::Error::from(err).into())
+        res.json::<Capabilities>().map_err(|err| std::io::Error::from(err).into())
     });
 
     rx
 });
 
-/// The API for working with pigs
-#[derive(Debug, Default)]
-pub struct PigApi {
-    /// Create a new pig given the name as a &str
-    pub create: PigCreateHandler,
-
-    /// Update a pig given the updated Pig struct
-    pub update: PigUpdateHandler,
-
-    /// Delete a pig given the Uuid
-    pub delete: PigDeleteHandler,
-
-    /// Searches for pigs baesd on the given &str query
-    pub fetch: PigFetchHandler,
-}
-
-endpoint!(PigCreateHandler, &str, Pig, |input| {
+endpoint!(BrandingHandler, bool, Branding, |_ignored: bool| {
     let (tx, rx) = oneshot::channel();
 
     // Submit the request to the server
     let req = Request {
         credentials: Credentials::SameOrigin,
-        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
-        ..Request::post(yuri!(PIG_API_ROOT, "create" ;? query!("name" = input)), vec![])
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!("/api", "branding"))
     };
+
     fetch_and_send(req, tx, |res| {
         // Handle errors
         if res.status >= 400 {
             return Err(res.into());
         }
 
-        // Convert the response to a pig object
-        res.json::<Pig>().map_err(|err| std::io::Error::from(err).into())
+        // Convert the response to the correct type
+        res.json::<Branding>().map_err(|err| std::io::Error::from(err).into())
     });
 
     rx
 });
 
-endpoint!(PigUpdateHandler, &Pig, Response, |input| {
+endpoint!(EnvironmentHandler, bool, Environment, |_ignored: bool| {
     let (tx, rx) = oneshot::channel();
 
-    // If the JSON POST was generated successfully
-    let req = Request::post_json(yuri!(PIG_API_ROOT, "update"), input);
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!("/api", "environment"))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<Environment>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(OidcHealthHandler, bool, Option<ProviderHealth>, |_ignored: bool| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!("/api", "oidc", "health"))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<Option<ProviderHealth>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+/// The API for importing multiple names at a time
+#[derive(Debug, Default)]
+pub struct BulkApi {
+    /// Creates a new import from the given list of names. If the bool is true,
+    /// the import is created even if the same list was imported recently.
+    pub create: BulkCreateHandler,
+
+    /// Applies the given changes to the import, returning the changes for
+    /// convenience upon success
+    pub patch: BulkPatchHandler,
+
+    /// Moves the given rejected names from a finished import back to pending,
+    /// returning the updated import
+    pub reopen: BulkReopenHandler,
+
+    /// Assigns the import to the given user for review, or unassigns it if
+    /// there's no user, returning the updated import
+    pub assign: BulkAssignHandler,
+
+    /// Closes the import without finishing it, or opens it again if the bool
+    /// is false, returning the updated import
+    pub close: BulkCloseHandler,
+
+    /// Fetches every import with names flagged for a moderator to review
+    pub flagged: BulkFlaggedHandler,
+
+    /// Approves or rejects a flagged name, returning the updated import
+    pub review: BulkReviewHandler,
+
+    /// Splits the import's pending names evenly into the given number of
+    /// imports, returning the new ones
+    pub split: BulkSplitHandler,
+
+    /// Compares the given list of names to an existing import
+    pub diff: BulkDiffHandler,
+
+    /// Fetches all pigs with a name similar to the given one, along with who
+    /// created them and which import they came from
+    pub duplicates: BulkDuplicatesHandler,
+
+    /// Checks the given list of names for typos, returning a correction for
+    /// each name which seems misspelled
+    pub spellcheck: BulkSpellcheckHandler,
+
+    /// Fetches all imports which the user can access and matches the given
+    /// query
+    pub fetch: BulkFetchHandler,
+}
+
+endpoint!(BulkCreateHandler, (&Vec<String>, bool), BulkImport, |input: (&Vec<String>, bool)| {
+    let (tx, rx) = oneshot::channel();
+    let (names, force) = input;
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "create" ;? query!("force" = force.to_string().as_str())), names);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<BulkImport>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkPatchHandler, BulkPatch, BulkPatch, |input: BulkPatch| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "patch"), &input);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            method: Method::PATCH,
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        // No fancy processing needed for this one
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            Ok(input)
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkReopenHandler, (Uuid, &Vec<String>), BulkImport, |input: (Uuid, &Vec<String>)| {
+    let (tx, rx) = oneshot::channel();
+    let (id, names) = input;
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "reopen" ;? query!("id" = id.to_string().as_str())), names);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<BulkImport>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkAssignHandler, (Uuid, Option<Uuid>), BulkImport, |input: (Uuid, Option<Uuid>)| {
+    let (tx, rx) = oneshot::channel();
+    let (id, assignee) = input;
+
+    // Only include the assignee if we have one, leaving it out unassigns the import
+    let params = match assignee {
+        Some(assignee) => query!("id" = id.to_string().as_str(), "assignee" = assignee.to_string().as_str()),
+        None => query!("id" = id.to_string().as_str()),
+    };
+
+    // Convert method type to PATCH, ::get method is just a good starter
+    let req = Request {
+        method: Method::PATCH,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::get(yuri!(BULK_API_ROOT, "assign" ;? params))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<BulkImport>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(BulkCloseHandler, (Uuid, bool), BulkImport, |input: (Uuid, bool)| {
+    let (tx, rx) = oneshot::channel();
+    let (id, closed) = input;
+
+    // Convert method type to PATCH, ::get method is just a good starter
+    let req = Request {
+        method: Method::PATCH,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::get(
+            yuri!(BULK_API_ROOT, "close" ;? query!("id" = id.to_string().as_str(), "closed" = closed.to_string().as_str())),
+        )
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<BulkImport>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(BulkFlaggedHandler, bool, Vec<BulkImport>, |_ignored: bool| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(BULK_API_ROOT, "flagged"))
+    };
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<Vec<BulkImport>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(BulkReviewHandler, &BulkReview, BulkImport, |input: &BulkReview| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON request was generated successfully
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "review"), input);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            method: Method::PATCH,
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<BulkImport>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkSplitHandler, (Uuid, usize), Vec<BulkImport>, |input: (Uuid, usize)| {
+    let (tx, rx) = oneshot::channel();
+    let (id, parts) = input;
+
+    // An empty list of names tells the server to split evenly by the number of parts
+    let params = query!("id" = id.to_string().as_str(), "parts" = parts.to_string().as_str());
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "split" ;? params), &Vec::<String>::new());
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<Vec<BulkImport>>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkDiffHandler, (Uuid, &Vec<String>), BulkDiff, |input: (Uuid, &Vec<String>)| {
+    let (tx, rx) = oneshot::channel();
+    let (id, names) = input;
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "diff" ;? query!("id" = id.to_string().as_str())), names);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<BulkDiff>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkSpellcheckHandler, &Vec<String>, Vec<BulkSuggestion>, |input: &Vec<String>| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(BULK_API_ROOT, "spellcheck"), input);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<Vec<BulkSuggestion>>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(BulkDuplicatesHandler, &str, Vec<BulkDuplicate>, |input: &str| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(BULK_API_ROOT, "duplicates" ;? query!("name" = input)))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<Vec<BulkDuplicate>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(BulkFetchHandler, &BulkQuery, Vec<WithCreator<BulkImport>>, retry |input: &BulkQuery, retry: Retry| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(input.to_yuri())
+    };
+    fetch_and_send_with_retry(req, tx, retry, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<Vec<WithCreator<BulkImport>>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+/// The API for saving and loading pig filters
+#[derive(Debug, Default)]
+pub struct FilterApi {
+    /// Saves the given filter, returning it with the id assigned by the server
+    pub create: FilterCreateHandler,
+
+    /// Delete a filter given the Uuid
+    pub delete: FilterDeleteHandler,
+
+    /// Fetches all filters the user saved or which have been shared
+    pub fetch: FilterFetchHandler,
+}
+
+endpoint!(FilterCreateHandler, &SavedFilter, SavedFilter, |input| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(FILTER_API_ROOT, "create"), input);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "application/json")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the correct type
+            res.json::<SavedFilter>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(FilterDeleteHandler, Uuid, Response, |input: Uuid| {
+    let (tx, rx) = oneshot::channel();
+
+    // Convert method type to DELETE, ::get method is just a good starter
+    let req = Request {
+        method: Method::DELETE,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::get(yuri!(FILTER_API_ROOT, "delete" ;? query!("id" = input.to_string().as_str())))
+    };
+
+    // Submit the request, no fancy processing needed for this one
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        Ok(res)
+    });
+
+    rx
+});
+
+endpoint!(FilterFetchHandler, bool, Vec<SavedFilter>, |_ignored: bool| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(FILTER_API_ROOT, "fetch"))
+    };
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the list of filters
+        res.json::<Vec<SavedFilter>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+/// The API for the user's notifications
+#[derive(Debug, Default)]
+pub struct NotificationApi {
+    /// Fetches the user's most recent notifications, newest first
+    pub list: NotificationListHandler,
+
+    /// Marks the notification with the given id as read, or all of them if
+    /// there's no id, returning how many were marked
+    pub read: NotificationReadHandler,
+}
+
+endpoint!(NotificationListHandler, bool, Vec<Notification>, |_ignored: bool| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(NOTIFICATION_API_ROOT, "list"))
+    };
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the list of notifications
+        res.json::<Vec<Notification>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(NotificationReadHandler, Option<Uuid>, usize, |input: Option<Uuid>| {
+    let (tx, rx) = oneshot::channel();
+
+    // Without an id, every notification is marked as read
+    let url = match input {
+        Some(id) => yuri!(NOTIFICATION_API_ROOT, "read" ;? query!("id" = id.to_string().as_str())),
+        None => yuri!(NOTIFICATION_API_ROOT, "read"),
+    };
+
+    // Convert method type to PATCH, ::get method is just a good starter
+    let req = Request {
+        method: Method::PATCH,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(url)
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the number of notifications marked
+        res.json::<usize>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+/// The API for working with pigs
+#[derive(Debug, Default)]
+pub struct PigApi {
+    /// Create a new pig given the name as a &str
+    pub create: PigCreateHandler,
+
+    /// Update a pig given the updated Pig struct
+    pub update: PigUpdateHandler,
+
+    /// Delete a pig given the Uuid
+    pub delete: PigDeleteHandler,
+
+    /// Delete every pig with one of the given Uuids, returning how many were
+    /// deleted
+    pub batch_delete: PigBatchDeleteHandler,
+
+    /// Add the given tag to every pig with one of the given Uuids, returning
+    /// how many didn't already have it
+    pub batch_tag: PigBatchTagHandler,
+
+    /// Archive or unarchive the pig with the given Uuid, returning the updated
+    /// pig
+    pub archive: PigArchiveHandler,
+
+    /// Archive every pig matching the given query, returning how many were
+    /// archived. If the bool is true, nothing is archived and the count is how
+    /// many would be.
+    pub archive_matching: PigArchiveMatchingHandler,
+
+    /// Searches for pigs baesd on the given &str query
+    pub fetch: PigFetchHandler,
+}
+
+endpoint!(PigCreateHandler, &str, Pig, |input| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::post(yuri!(PIG_API_ROOT, "create" ;? query!("name" = input)), vec![])
+    };
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to a pig object
+        res.json::<Pig>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(PigUpdateHandler, &Pig, Response, |input| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST was generated successfully
+    let req = Request::post_json(yuri!(PIG_API_ROOT, "update"), input);
+    if let Ok(req) = req {
+        // Convert the request type from POST to PUT
+        let req = Request {
+            method: Method::PUT,
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "application/json")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        // No fancy processing needed for this one
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            Ok(res)
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(PigDeleteHandler, Uuid, Response, |input: Uuid| {
+    let (tx, rx) = oneshot::channel();
+
+    // Convert method type to DELETE, ::get method is just a good starter
+    let req = Request {
+        method: Method::DELETE,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::get(yuri!(PIG_API_ROOT, "delete" ;? query!("id" = input.to_string().as_str())))
+    };
+
+    // Submit the request, no fancy processing needed for this one
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        Ok(res)
+    });
+
+    rx
+});
+
+endpoint!(PigBatchDeleteHandler, &Vec<Uuid>, usize, |input| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST was generated successfully
+    let req = Request::post_json(yuri!(PIG_API_ROOT, "batch_delete"), input);
+    if let Ok(req) = req {
+        // Convert the request type from POST to DELETE
+        let req = Request {
+            method: Method::DELETE,
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "application/json")]),
+            ..req
+        };
+
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the number of pigs
+            res.json::<usize>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(PigBatchTagHandler, (&str, &Vec<Uuid>), usize, |input: (&str, &Vec<Uuid>)| {
+    let (tx, rx) = oneshot::channel();
+    let (tag, ids) = input;
+
+    // If the JSON POST was generated successfully
+    let req = Request::post_json(yuri!(PIG_API_ROOT, "batch_tag" ;? query!("tag" = tag)), ids);
+    if let Ok(req) = req {
+        // Convert the request type from POST to PATCH
+        let req = Request {
+            method: Method::PATCH,
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "application/json")]),
+            ..req
+        };
+
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the number of pigs
+            res.json::<usize>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
+endpoint!(PigArchiveHandler, (Uuid, bool), Pig, |input: (Uuid, bool)| {
+    let (tx, rx) = oneshot::channel();
+    let (id, archived) = input;
+
+    // Convert method type to PATCH, ::get method is just a good starter
+    let params = query!("id" = id.to_string().as_str(), "archived" = archived.to_string().as_str());
+    let req = Request {
+        method: Method::PATCH,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(PIG_API_ROOT, "archive" ;? params))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to a pig object
+        res.json::<Pig>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(PigArchiveMatchingHandler, (&PigQuery, bool), usize, |input: (&PigQuery, bool)| {
+    let (tx, rx) = oneshot::channel();
+    let (query, dry_run) = input;
+
+    // Convert method type to PATCH, ::get method is just a good starter
+    let url = format!("{}&dry_run={}", yuri!(PIG_API_ROOT, "archive_matching" ;? query!(query)), dry_run);
+    let req = Request {
+        method: Method::PATCH,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(url)
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the number of pigs
+        res.json::<usize>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(PigFetchHandler, PigQuery, Vec<WithCreator<Pig>>, retry |params: PigQuery, retry: Retry| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(params.to_yuri())
+    };
+    fetch_and_send_with_retry(req, tx, retry, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to a pig object
+        res.json::<Vec<WithCreator<Pig>>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+/// The API for working with pig tags
+#[derive(Debug, Default)]
+pub struct TagApi {
+    /// Replaces the first tag with the second on every pig, merging them if
+    /// the second is already in use. Returns the number of pigs changed.
+    pub rename: TagRenameHandler,
+
+    /// Removes the tag from every pig, returning the number of pigs changed
+    pub delete: TagDeleteHandler,
+
+    /// Fetches every tag in use along with how many pigs have it
+    pub fetch: TagFetchHandler,
+}
+
+endpoint!(TagRenameHandler, (&str, &str), usize, |input: (&str, &str)| {
+    let (tx, rx) = oneshot::channel();
+    let (from, to) = input;
+
+    // Convert method type to PATCH, ::get method is just a good starter
+    let req = Request {
+        method: Method::PATCH,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::get(yuri!(TAG_API_ROOT, "rename" ;? query!("from" = from, "to" = to)))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the number of pigs
+        res.json::<usize>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(TagDeleteHandler, &str, usize, |input: &str| {
+    let (tx, rx) = oneshot::channel();
+
+    // Convert method type to DELETE, ::get method is just a good starter
+    let req = Request {
+        method: Method::DELETE,
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::get(yuri!(TAG_API_ROOT, "delete" ;? query!("name" = input)))
+    };
+
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the number of pigs
+        res.json::<usize>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(TagFetchHandler, bool, Vec<Tag>, retry |_ignored: bool, retry: Retry| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(TAG_API_ROOT, "fetch"))
+    };
+    fetch_and_send_with_retry(req, tx, retry, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the correct type
+        res.json::<Vec<Tag>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+/// The API for managing the user's API tokens
+#[derive(Debug, Default)]
+pub struct TokenApi {
+    /// Creates a token, returning it along with its secret
+    pub create: TokenCreateHandler,
+
+    /// Fetches all of the user's tokens
+    pub list: TokenListHandler,
+
+    /// Revokes the token with the given id
+    pub revoke: TokenRevokeHandler,
+
+    /// Replaces the secret of the token with the given id, returning the new one
+    pub rotate: TokenRotateHandler,
+}
+
+endpoint!(TokenCreateHandler, &ApiTokenRequest, ApiTokenSecret, |input| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(TOKEN_API_ROOT, "create"), input);
     if let Ok(req) = req {
-        // Convert the request type from POST to PUT
+        // Add correct options to the request
         let req = Request {
-            method: Method::PUT,
             credentials: Credentials::SameOrigin,
             headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "application/json")]),
             ..req
         };
 
         // Now actually submit the request, then relay the result to the channel sender
-        // No fancy processing needed for this one
         fetch_and_send(req, tx, |res| {
             // Handle errors
             if res.status >= 400 {
                 return Err(res.into());
             }
 
-            Ok(res)
+            // Convert the response to the correct type
+            res.json::<ApiTokenSecret>().map_err(|err| std::io::Error::from(err).into())
         });
     } else {
         tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
@@ -398,7 +1678,29 @@ endpoint!(PigUpdateHandler, &Pig, Response, |input| {
     rx
 });
 
-endpoint!(PigDeleteHandler, Uuid, Response, |input: Uuid| {
+endpoint!(TokenListHandler, bool, Vec<ApiToken>, |_ignored: bool| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request to the server
+    let req = Request {
+        credentials: Credentials::SameOrigin,
+        headers: Headers::new(&[("Accept", "application/json")]),
+        ..Request::get(yuri!(TOKEN_API_ROOT, "list"))
+    };
+    fetch_and_send(req, tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        // Convert the response to the list of tokens
+        res.json::<Vec<ApiToken>>().map_err(|err| std::io::Error::from(err).into())
+    });
+
+    rx
+});
+
+endpoint!(TokenRevokeHandler, Uuid, Response, |input: Uuid| {
     let (tx, rx) = oneshot::channel();
 
     // Convert method type to DELETE, ::get method is just a good starter
@@ -406,7 +1708,7 @@ endpoint!(PigDeleteHandler, Uuid, Response, |input: Uuid| {
         method: Method::DELETE,
         credentials: Credentials::SameOrigin,
         headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
-        ..Request::get(yuri!(PIG_API_ROOT, "delete" ;? query!("id" = input.to_string().as_str())))
+        ..Request::get(yuri!(TOKEN_API_ROOT, "revoke" ;? query!("id" = input.to_string().as_str())))
     };
 
     // Submit the request, no fancy processing needed for this one
@@ -422,14 +1724,14 @@ endpoint!(PigDeleteHandler, Uuid, Response, |input: Uuid| {
     rx
 });
 
-endpoint!(PigFetchHandler, PigQuery, Vec<Pig>, |params: PigQuery| {
+endpoint!(TokenRotateHandler, Uuid, ApiTokenSecret, |input: Uuid| {
     let (tx, rx) = oneshot::channel();
 
     // Submit the request to the server
     let req = Request {
         credentials: Credentials::SameOrigin,
-        headers: Headers::new(&[("Accept", "application/json")]),
-        ..Request::get(params.to_yuri())
+        headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "text/plain; charset=utf-8")]),
+        ..Request::post(yuri!(TOKEN_API_ROOT, "rotate" ;? query!("id" = input.to_string().as_str())), vec![])
     };
     fetch_and_send(req, tx, |res| {
         // Handle errors
@@ -437,8 +1739,8 @@ endpoint!(PigFetchHandler, PigQuery, Vec<Pig>, |params: PigQuery| {
             return Err(res.into());
         }
 
-        // Convert the response to a pig object
-        res.json::<Vec<Pig>>().map_err(|err| std::io::Error::from(err).into())
+        // Convert the response to the token and its new secret
+        res.json::<ApiTokenSecret>().map_err(|err| std::io::Error::from(err).into())
     });
 
     rx
@@ -451,6 +1753,9 @@ pub struct UserApi {
     /// based on permissions--which fit the query
     pub fetch: UserFetchHandler,
 
+    /// Fetch the usernames of the users with the given ids
+    pub names: UserNamesHandler,
+
     /// Fetch a list of roles for each user which fits the query
     pub roles: UserRolesHandler,
 
@@ -458,7 +1763,7 @@ pub struct UserApi {
     pub expire: UserExpireHandler,
 }
 
-endpoint!(UserFetchHandler, UserQuery, UserFetchResponse, |params: UserQuery| {
+endpoint!(UserFetchHandler, UserQuery, UserFetchResponse, retry |params: UserQuery, retry: Retry| {
     let (tx, rx) = oneshot::channel();
 
     // Submit the request to the server
@@ -467,7 +1772,7 @@ endpoint!(UserFetchHandler, UserQuery, UserFetchResponse, |params: UserQuery| {
         headers: Headers::new(&[("Accept", "application/json")]),
         ..Request::get(params.to_yuri())
     };
-    fetch_and_send(req, tx, |res| {
+    fetch_and_send_with_retry(req, tx, retry, |res| {
         // Handle errors
         if res.status >= 400 {
             return Err(res.into());
@@ -480,6 +1785,36 @@ endpoint!(UserFetchHandler, UserQuery, UserFetchResponse, |params: UserQuery| {
     rx
 });
 
+endpoint!(UserNamesHandler, &BTreeSet<Uuid>, BTreeMap<Uuid, String>, |input| {
+    let (tx, rx) = oneshot::channel();
+
+    // If the JSON POST request was generated successfully
+    let req = Request::post_json(yuri!(USER_API_ROOT, "names"), input);
+    if let Ok(req) = req {
+        // Add correct options to the request
+        let req = Request {
+            credentials: Credentials::SameOrigin,
+            headers: Headers::new(&[("Accept", "application/json"), ("Content-Type", "application/json")]),
+            ..req
+        };
+
+        // Now actually submit the request, then relay the result to the channel sender
+        fetch_and_send(req, tx, |res| {
+            // Handle errors
+            if res.status >= 400 {
+                return Err(res.into());
+            }
+
+            // Convert the response to the map
+            res.json::<BTreeMap<Uuid, String>>().map_err(|err| std::io::Error::from(err).into())
+        });
+    } else {
+        tx.send(Err(std::io::Error::from(req.unwrap_err()).into())).unwrap_or_default()
+    }
+
+    rx
+});
+
 endpoint!(UserRolesHandler, UserQuery, BTreeMap<Uuid, BTreeSet<Roles>>, |params: UserQuery| {
     let (tx, rx) = oneshot::channel();
 
@@ -527,30 +1862,175 @@ endpoint!(UserExpireHandler, Uuid, User, |input: Uuid| {
     rx
 });
 
+/// Sends a change again which previously failed due to an expired session
+#[derive(Debug, Default)]
+pub struct ReplayApi {
+    /// Resubmits the request exactly as it was originally sent
+    pub replay: ReplayHandler,
+}
+
+endpoint!(ReplayHandler, &ReplayRequest, Response, |input: &ReplayRequest| {
+    let (tx, rx) = oneshot::channel();
+
+    // Submit the request, no fancy processing needed for this one
+    fetch_and_send(input.to_request(), tx, |res| {
+        // Handle errors
+        if res.status >= 400 {
+            return Err(res.into());
+        }
+
+        Ok(res)
+    });
+
+    rx
+});
+
 /// Submits the given request, then if successful, processes the on_response
-/// callback and submits the return value from it to the tx channel sender.
+/// callback and submits the return value from it to the tx channel sender. If
+/// the request would have changed something but the user's session expired, a
+/// copy is kept so it can be replayed after they sign back in.
 fn fetch_and_send<T: 'static + Send>(
     req: Request,
     tx: Sender<Result<T, ApiError>>,
     on_response: impl 'static + Send + FnOnce(Response) -> Result<T, ApiError>,
 ) {
     debug!("Sending request: {req:?}\nBody: {}", String::from_utf8(req.body.clone()).unwrap_or_default());
+    let replay = ReplayRequest::from_request(&req);
+    let request = format!("{:?} {}", req.method, req.url);
+    let changes = !matches!(req.method, Method::GET);
 
     // No fancy processing needed for this one
-    ehttp::fetch(req, |result| {
-        tx.send(match result {
-            Ok(res) => {
-                debug!("Received response: {res:?}\nBody: {}", res.text().unwrap_or_default());
-                on_response(res)
+    IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+    fetch(req, move |result| {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+        if changes {
+            CHANGES.fetch_add(1, Ordering::Relaxed);
+        }
+        tx.send(handle_result(result, request, replay, on_response)).unwrap_or_default()
+    });
+}
+
+/// Submits the given request like [`fetch_and_send`], but without counting it
+/// as in flight or as a change. This is for requests the server holds onto
+/// until it has something to say, so the user isn't told we're always busy.
+fn fetch_in_background<T: 'static + Send>(
+    req: Request,
+    tx: Sender<Result<T, ApiError>>,
+    on_response: impl 'static + Send + FnOnce(Response) -> Result<T, ApiError>,
+) {
+    debug!("Sending request: {req:?}");
+    let request = format!("{:?} {}", req.method, req.url);
+
+    fetch(req, move |result| tx.send(handle_result(result, request, None, on_response)).unwrap_or_default());
+}
+
+/// Submits the given request like [`fetch_and_send`], but if the server
+/// couldn't be reached or responds with 502 or 503, sends it again after
+/// waiting according to the retry policy. Only GET requests are retried, and
+/// retrying stops once the handler discards the request.
+fn fetch_and_send_with_retry<T: 'static + Send>(
+    req: Request,
+    tx: Sender<Result<T, ApiError>>,
+    retry: Retry,
+    on_response: impl 'static + Send + FnOnce(Response) -> Result<T, ApiError>,
+) {
+    let policy = match retry.policy {
+        Some(policy) if matches!(req.method, Method::GET) => policy,
+        _ => return fetch_and_send(req, tx, on_response),
+    };
+
+    debug!("Sending request: {req:?}\nBody: {}", String::from_utf8(req.body.clone()).unwrap_or_default());
+    let next = req.clone();
+
+    // Retries are still the same request, so it's only counted once
+    if retry.attempt.load(Ordering::Relaxed) <= 1 {
+        IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fetch(req, move |result| {
+        let attempt = retry.attempt.load(Ordering::Relaxed);
+        let unavailable = match &result {
+            Ok(res) => res.status == 502 || res.status == 503,
+            Err(_) => true,
+        };
+
+        if unavailable && attempt < policy.max_attempts && !tx.is_closed() {
+            let delay = policy.delay(attempt);
+            warn!("Attempt {} of {} failed for {}, retrying in {:?}", attempt, policy.max_attempts, next.url, delay);
+            retry.attempt.store(attempt + 1, Ordering::Relaxed);
+            run_after(delay, move || fetch_and_send_with_retry(next, tx, retry, on_response));
+            return;
+        }
+
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+        let request = format!("{:?} {}", next.method, next.url);
+        tx.send(handle_result(result, request, None, on_response)).unwrap_or_default()
+    });
+}
+
+/// Sends the request with [`CLIENT_VERSION`] in its headers, calling back with
+/// the result
+fn fetch(mut req: Request, on_done: impl 'static + Send + FnOnce(ehttp::Result<Response>)) {
+    req.headers.insert(CLIENT_VERSION_HEADER, CLIENT_VERSION);
+    ehttp::fetch(req, on_done)
+}
+
+/// Turns the result of a request into the value it should be sent to the
+/// handler as, keeping a copy of the request if the user's session expired.
+/// Any error is tagged with the given description of the request.
+fn handle_result<T>(
+    result: ehttp::Result<Response>,
+    request: String,
+    replay: Option<ReplayRequest>,
+    on_response: impl FnOnce(Response) -> Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    let res = match result {
+        Ok(res) => {
+            debug!("Received response: {res:?}\nBody: {}", res.text().unwrap_or_default());
+
+            // Hold onto the change so it isn't lost when the user signs back in
+            if res.status == 401 {
+                if let (Some(replay), Ok(mut expired)) = (replay, EXPIRED_REQUESTS.lock()) {
+                    expired.push(replay);
+                }
             }
-            Err(msg) => {
-                // when we reach this branch, it's *usually* that we didn't get a response.
-                // HTTP error codes are handled by the success branch here.
-                error!("Encountered fetch error: {:?}", msg.to_owned());
-                Err(ApiError::new(msg.to_owned()).with_reason("No response".to_owned()))
+
+            on_response(res)
+        }
+        Err(msg) => {
+            // when we reach this branch, it's *usually* that we didn't get a response.
+            // HTTP error codes are handled by the success branch here.
+            error!("Encountered fetch error: {:?}", msg.to_owned());
+            Err(ApiError::new(msg.to_owned()).with_reason("No response".to_owned()).with_kind(ErrorCode::Unavailable))
+        }
+    };
+
+    res.map_err(|err| err.with_request(request))
+}
+
+/// Runs the given function once the delay has passed, without blocking
+#[cfg(not(target_arch = "wasm32"))]
+fn run_after(delay: Duration, f: impl 'static + Send + FnOnce()) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        f();
+    });
+}
+
+/// Runs the given function once the delay has passed, without blocking
+#[cfg(target_arch = "wasm32")]
+fn run_after(delay: Duration, f: impl 'static + Send + FnOnce()) {
+    wasm_bindgen_futures::spawn_local(async move {
+        // There's no sleep on the web, so wait on a promise resolved by setTimeout
+        let timeout = js_sys::Promise::new(&mut |resolve, _reject| {
+            if let Some(window) = web_sys::window() {
+                window
+                    .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, delay.as_millis() as i32)
+                    .unwrap_or_default();
             }
-        })
-        .unwrap_or_default()
+        });
+        wasm_bindgen_futures::JsFuture::from(timeout).await.unwrap_or_default();
+        f();
     });
 }
 
@@ -0,0 +1,104 @@
+use crate::data::api::changes_made;
+use crate::data::reconcile::{Identified, Reconciliation};
+use chrono::{DateTime, Duration, Utc};
+use pigweb_common::bulk::BulkImport;
+use pigweb_common::pigs::Pig;
+use std::collections::VecDeque;
+
+/// The most results remembered for each kind of list
+const CACHE_SIZE: usize = 20;
+
+/// How long results are remembered for, in seconds. Anything older is too
+/// stale to be worth showing, even for a moment.
+const CACHE_MAX_AGE: i64 = 5 * 60;
+
+/// A list of results along with what's needed to tell whether it's still
+/// worth showing
+#[derive(Debug)]
+struct CacheEntry<T> {
+    /// The query the results were fetched with, see [`QueryCache`]
+    key: String,
+
+    /// When the results were received
+    received: DateTime<Utc>,
+
+    /// How many changes had been made when the results were received, see
+    /// [`changes_made`]
+    changes: u32,
+
+    /// The results themselves
+    items: Vec<T>,
+}
+
+/// Remembers the results of recent fetches by the query they were fetched
+/// with, usually its yuri. Pages show the cached results right away when a
+/// search is revisited, then replace them once the fresh results are in.
+///
+/// Results are forgotten once they're too old or anything has been changed
+/// since they were received, so a page never shows a pig which was just
+/// deleted.
+#[derive(Debug)]
+pub struct QueryCache<T> {
+    /// Every remembered result, oldest first
+    entries: VecDeque<CacheEntry<T>>,
+}
+
+impl<T> Default for QueryCache<T> {
+    fn default() -> Self {
+        Self { entries: VecDeque::new() }
+    }
+}
+
+impl<T: Clone> QueryCache<T> {
+    /// Returns the results last fetched for the given query, if they're still
+    /// worth showing
+    pub fn get(&self, key: &str) -> Option<Vec<T>> {
+        let oldest = Utc::now() - Duration::seconds(CACHE_MAX_AGE);
+        self.entries
+            .iter()
+            .find(|entry| entry.key == key)
+            .filter(|entry| entry.received > oldest && entry.changes == changes_made())
+            .map(|entry| entry.items.clone())
+    }
+
+    /// Remembers the results fetched for the given query, forgetting the
+    /// oldest results if there are too many
+    pub fn insert(&mut self, key: String, items: &Vec<T>) {
+        self.entries.retain(|entry| entry.key != key);
+        self.entries.push_back(CacheEntry { key, received: Utc::now(), changes: changes_made(), items: items.clone() });
+
+        while self.entries.len() > CACHE_SIZE {
+            self.entries.pop_front();
+        }
+    }
+}
+
+impl<T: Clone + Identified> QueryCache<T> {
+    /// Brings the remembered results up to date with changes made by someone
+    /// else. Deleted records are removed from every result, but results with
+    /// a record which was updated are forgotten, since we don't have its
+    /// latest version.
+    pub fn reconcile(&mut self, changes: &Reconciliation) {
+        if changes.refetch {
+            self.entries.clear();
+            return;
+        }
+
+        self.entries.retain(|entry| !entry.items.iter().any(|item| changes.changed.contains(&item.id())));
+        for entry in self.entries.iter_mut() {
+            changes.remove_deleted(&mut entry.items);
+        }
+    }
+}
+
+/// The results of recent fetches for each list which is slow to load, see
+/// [`QueryCache`]
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    /// Pigs fetched for the pig page's sidebar
+    pub pigs: QueryCache<Pig>,
+
+    /// Imports fetched for the bulk page's sidebar, oldest first like they're
+    /// sent by the server
+    pub imports: QueryCache<BulkImport>,
+}
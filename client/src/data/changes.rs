@@ -0,0 +1,246 @@
+use crate::data::api::{ChangePollHandler, Status};
+use crate::data::reconcile::Reconciliation;
+use egui::Context;
+use ewebsock::{Options, WsEvent, WsMessage, WsReceiver, WsSender};
+use log::warn;
+use pigweb_common::changes::{ChangeBatch, ChangeTarget};
+use std::fmt;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long to wait before polling or reconnecting again after it fails, in
+/// seconds. This doubles after each failure in a row.
+const POLL_RETRY_DELAY: f64 = 1.0;
+
+/// The longest to wait before polling again, in seconds
+const POLL_MAX_DELAY: f64 = 60.0;
+
+/// Follows the server's change feed so pages can keep what they're showing
+/// up to date with changes made by other users. The server pushes changes
+/// over a WebSocket as they happen. If one can't be opened, e.g. because a
+/// proxy doesn't allow them, we poll instead. The server holds each poll
+/// until something changes, so another is sent as soon as one is answered.
+/// If the socket closes or polling fails, we wait longer before each retry.
+#[derive(Debug, Default)]
+pub struct ChangeFeed {
+    /// The socket the server pushes changes over, if it's open
+    socket: Option<ChangeSocket>,
+
+    /// Whether a socket couldn't be opened, so we're polling instead
+    socket_unsupported: bool,
+
+    /// Handles polling the server for changes
+    poll: ChangePollHandler,
+
+    /// Whether we're still waiting on a response to the last poll
+    polling: bool,
+
+    /// Where in the feed we are, or [`None`] if we haven't asked yet
+    cursor: Option<u64>,
+
+    /// How many polls in a row have failed
+    failures: u32,
+
+    /// When to poll next, in seconds since the app started
+    next_poll: f64,
+
+    /// The changes received this frame, if any
+    received: Option<ChangeBatch>,
+}
+
+impl ChangeFeed {
+    /// Picks up any changes received since the last frame and polls again if
+    /// it's time to. This should be called once per frame before the page is
+    /// shown. When not `enabled`, e.g. because the user is signed out,
+    /// polling stops and starts over from the latest cursor once enabled.
+    pub fn update(&mut self, ctx: &Context, enabled: bool) {
+        self.received = None;
+
+        if !enabled {
+            self.socket = None;
+            self.socket_unsupported = false;
+            self.poll.discard();
+            self.polling = false;
+            self.cursor = None;
+            return;
+        }
+
+        let now = ctx.input(|i| i.time);
+        if !self.socket_unsupported {
+            self.update_socket(ctx, now);
+            return;
+        }
+
+        if self.polling {
+            match self.poll.resolve() {
+                Status::Received(batch) => self.receive(batch),
+                Status::Errored(err) => {
+                    warn!("Unable to poll for changes: {:?}", err);
+                    self.retry_later(now);
+                }
+                Status::Pending => return,
+            }
+
+            self.polling = false;
+        }
+
+        if now < self.next_poll {
+            // make sure we come back to poll even if the user isn't doing anything
+            ctx.request_repaint_after(Duration::from_secs_f64(self.next_poll - now));
+            return;
+        }
+
+        self.poll.request((self.cursor, ctx));
+        self.polling = true;
+    }
+
+    /// Picks up the next batch of changes pushed over the socket, opening it
+    /// first if it isn't open yet
+    fn update_socket(&mut self, ctx: &Context, now: f64) {
+        if self.socket.is_none() {
+            if now < self.next_poll {
+                // make sure we come back to reconnect even if the user isn't doing anything
+                ctx.request_repaint_after(Duration::from_secs_f64(self.next_poll - now));
+                return;
+            }
+
+            match ChangeSocket::connect(ctx, self.cursor) {
+                Some(socket) => self.socket = Some(socket),
+                None => {
+                    self.socket_unsupported = true;
+                    return;
+                }
+            }
+        }
+
+        // Only one batch is received each frame, any others wait for the next
+        while let Some(event) = self.socket.as_ref().and_then(|socket| socket.receiver.try_recv()) {
+            match event {
+                WsEvent::Opened => {
+                    if let Some(socket) = self.socket.as_mut() {
+                        socket.opened = true;
+                    }
+                }
+                WsEvent::Message(WsMessage::Text(text)) => match serde_json::from_str::<ChangeBatch>(&text) {
+                    Ok(batch) => {
+                        self.receive(batch);
+                        ctx.request_repaint();
+                        return;
+                    }
+                    Err(err) => warn!("Unable to read changes from the server: {:?}", err),
+                },
+                WsEvent::Message(_) => {}
+                WsEvent::Error(err) => {
+                    warn!("Lost the connection for changes: {}", err);
+                    self.socket_closed(now);
+                    return;
+                }
+                WsEvent::Closed => {
+                    self.socket_closed(now);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Forgets the socket once it's closed. If it never opened, the server
+    /// or a proxy in front of it doesn't support them, so we poll instead.
+    /// Otherwise, it's opened again after a while.
+    fn socket_closed(&mut self, now: f64) {
+        if self.socket.take().is_some_and(|socket| socket.opened) {
+            self.retry_later(now);
+        } else {
+            warn!("Unable to open a WebSocket for changes, polling for them instead");
+            self.socket_unsupported = true;
+        }
+    }
+
+    /// Moves our cursor up to the given batch's, keeping the batch so pages
+    /// can reconcile it. The first batch only tells us where to start from.
+    fn receive(&mut self, batch: ChangeBatch) {
+        let started = self.cursor.replace(batch.cursor).is_some();
+        if started && (batch.reset || !batch.events.is_empty()) {
+            self.received = Some(batch);
+        }
+
+        self.failures = 0;
+    }
+
+    /// Waits longer before polling or reconnecting again after each failure
+    /// in a row
+    fn retry_later(&mut self, now: f64) {
+        let delay = POLL_RETRY_DELAY * 2f64.powi(self.failures.min(16) as i32);
+        self.next_poll = now + delay.min(POLL_MAX_DELAY);
+        self.failures += 1;
+    }
+
+    /// Works out how to bring a list of records of the given kind up to date
+    /// with the changes received this frame, if any of them are about it
+    pub fn reconcile(&self, target: ChangeTarget) -> Option<Reconciliation> {
+        self.received.as_ref().and_then(|batch| Reconciliation::new(batch, target))
+    }
+
+    /// Whether the record with the given id may have been changed by anything
+    /// received this frame
+    pub fn affects_id(&self, target: ChangeTarget, id: &Uuid) -> bool {
+        self.received.as_ref().is_some_and(|batch| {
+            batch.reset
+                || batch
+                    .events
+                    .iter()
+                    .any(|event| event.target == target && event.id.is_none_or(|changed| changed == *id))
+        })
+    }
+}
+
+/// A WebSocket the server pushes changes over, see [`ChangeFeed`]
+struct ChangeSocket {
+    /// Only kept so the socket stays open, it closes once this is dropped
+    _sender: WsSender,
+
+    /// Receives whatever happens to the socket
+    receiver: WsReceiver,
+
+    /// Whether the socket opened, meaning the server and any proxies in front
+    /// of it support them
+    opened: bool,
+}
+
+impl ChangeSocket {
+    /// Opens a socket starting from the given cursor, which repaints whenever
+    /// something arrives. Returns [`None`] if it can't be opened here.
+    fn connect(ctx: &Context, cursor: Option<u64>) -> Option<Self> {
+        let url = socket_url(cursor)?;
+        let ctx = ctx.clone();
+
+        match ewebsock::connect_with_wakeup(url, Options::default(), move || ctx.request_repaint()) {
+            Ok((sender, receiver)) => Some(Self { _sender: sender, receiver, opened: false }),
+            Err(err) => {
+                warn!("Unable to open a WebSocket for changes: {}", err);
+                None
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ChangeSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeSocket").field("opened", &self.opened).finish_non_exhaustive()
+    }
+}
+
+/// The address of the change feed's WebSocket on the server the page was
+/// loaded from, starting from the given cursor
+#[cfg(target_arch = "wasm32")]
+fn socket_url(cursor: Option<u64>) -> Option<String> {
+    let location = web_sys::window()?.location();
+    let scheme = if location.protocol().ok()? == "https:" { "wss" } else { "ws" };
+    let since = cursor.map(|cursor| format!("?since={}", cursor)).unwrap_or_default();
+    Some(format!("{}://{}/api/changes/ws{}", scheme, location.host().ok()?, since))
+}
+
+/// Natively, there's no page to tell which server to connect to
+#[cfg(not(target_arch = "wasm32"))]
+fn socket_url(_cursor: Option<u64>) -> Option<String> {
+    None
+}
@@ -1,2 +1,8 @@
 pub(crate) mod api;
+pub(crate) mod cache;
+pub(crate) mod changes;
+pub(crate) mod persist;
+pub(crate) mod reconcile;
+pub(crate) mod settings;
 pub(crate) mod state;
+pub(crate) mod usernames;
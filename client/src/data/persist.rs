@@ -0,0 +1,70 @@
+use eframe::Storage;
+use log::info;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Data saved on the user's device under its own key, separately from the
+/// rest of [`crate::data::state::ClientState`]. If one page's format changes,
+/// only that page loses what it saved.
+pub trait Persisted: Default + Serialize + DeserializeOwned {
+    /// The storage key the data is saved under
+    const KEY: &'static str;
+
+    /// The version of the data's format. Bump this whenever a change means
+    /// data saved by an older client can't be trusted, so it's dropped
+    /// instead of loaded.
+    const VERSION: u32;
+
+    /// Runs once after the data is loaded. Anything referring to records on
+    /// the server should be marked to be checked before it's trusted, since
+    /// they may have been changed or deleted while the client was closed.
+    fn restored(&mut self) {}
+}
+
+/// Saved data along with the version of its format, see
+/// [`Persisted::VERSION`]
+#[derive(Deserialize, Serialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+/// Just the version of some saved data, so it can be checked before trying to
+/// read data which may not be in the current format
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Loads the data saved under [`Persisted::KEY`]. Returns the default if
+/// nothing was saved, it's from a different version, or it can't be read.
+pub fn load<T: Persisted>(storage: Option<&dyn Storage>) -> T {
+    let Some(storage) = storage else {
+        return T::default();
+    };
+
+    match eframe::get_value::<Version>(storage, T::KEY) {
+        Some(Version { version }) if version == T::VERSION => {}
+        Some(Version { version }) => {
+            info!("Dropping saved {} data from version {}, expected version {}.", T::KEY, version, T::VERSION);
+            return T::default();
+        }
+        None => return T::default(),
+    }
+
+    match eframe::get_value::<Versioned<T>>(storage, T::KEY) {
+        Some(Versioned { data: mut res, .. }) => {
+            res.restored();
+            res
+        }
+        None => {
+            info!("Dropping saved {} data which couldn't be read.", T::KEY);
+            T::default()
+        }
+    }
+}
+
+/// Saves the given data under [`Persisted::KEY`] along with its version
+pub fn save<T: Persisted>(storage: &mut dyn Storage, data: &T) {
+    eframe::set_value(storage, T::KEY, &Versioned { version: T::VERSION, data });
+}
@@ -0,0 +1,92 @@
+use pigweb_common::bulk::BulkImport;
+use pigweb_common::changes::{ChangeAction, ChangeBatch, ChangeTarget};
+use pigweb_common::pigs::Pig;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Records which can be kept up to date by a [`Reconciliation`]
+pub trait Identified {
+    /// The id of this record, matching [`pigweb_common::changes::ChangeEvent::id`]
+    fn id(&self) -> Uuid;
+}
+
+impl Identified for Pig {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl Identified for BulkImport {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+}
+
+/// How to bring a list of records up to date with a [`ChangeBatch`] without
+/// fetching all of it again. Deleted records are removed right away, while
+/// only the records which were created or updated are fetched, see
+/// [`Reconciliation::merge`].
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    /// Records which were deleted
+    pub deleted: BTreeSet<Uuid>,
+
+    /// Records which were created or updated, whose latest versions need to
+    /// be fetched to see whether they still belong in the list
+    pub changed: BTreeSet<Uuid>,
+
+    /// Whether too much changed to tell, so the whole list has to be fetched
+    /// again
+    pub refetch: bool,
+}
+
+impl Reconciliation {
+    /// Sorts the events in the given batch about records of the given kind by
+    /// what has to be done with them. Returns [`None`] if none of them are
+    /// about that kind of record.
+    pub fn new(batch: &ChangeBatch, target: ChangeTarget) -> Option<Self> {
+        if !batch.affects(target) {
+            return None;
+        }
+
+        let mut res = Self { refetch: batch.reset, ..Self::default() };
+        for event in batch.events.iter().filter(|event| event.target == target) {
+            match (event.action, event.id) {
+                (_, None) => res.refetch = true,
+                (ChangeAction::Deleted, Some(id)) => {
+                    res.changed.remove(&id);
+                    res.deleted.insert(id);
+                }
+                (_, Some(id)) => {
+                    res.changed.insert(id);
+                }
+            }
+        }
+
+        Some(res)
+    }
+
+    /// Removes every deleted record from the given list
+    pub fn remove_deleted<T: Identified>(&self, items: &mut Vec<T>) {
+        items.retain(|item| !self.deleted.contains(&item.id()));
+    }
+
+    /// Replaces each changed record in the given list with its fetched
+    /// version. The records were fetched with the same query as the list, so
+    /// any which weren't fetched don't belong in it anymore and are removed.
+    /// Returns `false` if any fetched record isn't in the list yet, since only
+    /// fetching the whole list can tell where it goes.
+    pub fn merge<T: Identified>(changed: &BTreeSet<Uuid>, items: &mut Vec<T>, fetched: Vec<T>) -> bool {
+        let mut merged = true;
+        items.retain(|item| !changed.contains(&item.id()) || fetched.iter().any(|f| f.id() == item.id()));
+
+        for record in fetched {
+            match items.iter_mut().find(|item| item.id() == record.id()) {
+                Some(item) => *item = record,
+                None => merged = false,
+            }
+        }
+
+        merged
+    }
+}
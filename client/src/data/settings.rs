@@ -0,0 +1,80 @@
+use crate::pages::Routes;
+use crate::ui::style::TIME_FMT;
+use pigweb_common::DEFAULT_API_RESPONSE_LIMIT;
+
+/// Preferences the user can change on the settings page. These are stored on
+/// the user's device along with the rest of [`crate::data::state::ClientState`].
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Settings {
+    /// How timestamps are shown
+    pub time_format: TimeFormat,
+
+    /// Whether to show timestamps relative to now, e.g. "3 hours ago", with
+    /// the full time in a tooltip. Otherwise, it's the other way around.
+    pub relative_times: bool,
+
+    /// How many results are shown on each page of a list, unless it's changed
+    /// on the list itself
+    pub page_size: u32,
+
+    /// The accent color for the theme as RGB. Uses the default pink if
+    /// [`None`].
+    pub accent_color: Option<[u8; 3]>,
+
+    /// Every color in the theme as RGB, if the user made their own in the
+    /// theme editor. Takes precedence over [`Self::accent_color`].
+    pub palette: Option<[[u8; 3]; 12]>,
+
+    /// Whether to ask before deleting anything
+    pub confirm_delete: bool,
+
+    /// The page opened when visiting the root of the site
+    pub landing_page: Routes,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            time_format: TimeFormat::default(),
+            relative_times: true,
+            page_size: DEFAULT_API_RESPONSE_LIMIT,
+            accent_color: None,
+            palette: None,
+            confirm_delete: true,
+            landing_page: Routes::Pigs,
+        }
+    }
+}
+
+/// The ways timestamps can be shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum TimeFormat {
+    /// e.g. `Sun, Apr 6 2025 14:05:09`
+    #[default]
+    Long,
+
+    /// e.g. `Sun, Apr 6 2025 02:05:09 PM`
+    Long12Hour,
+
+    /// e.g. `2025-04-06 14:05`
+    Short,
+
+    /// e.g. `2025-04-06T14:05:09`
+    Iso8601,
+}
+
+impl TimeFormat {
+    /// Every format, in the order they should be listed
+    pub const ALL: [TimeFormat; 4] = [Self::Long, Self::Long12Hour, Self::Short, Self::Iso8601];
+
+    /// The [`chrono::format::strftime`] pattern for this format
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            Self::Long => TIME_FMT,
+            Self::Long12Hour => "%a, %b %e %Y %r",
+            Self::Short => "%Y-%m-%d %H:%M",
+            Self::Iso8601 => "%Y-%m-%dT%H:%M:%S",
+        }
+    }
+}
@@ -1,10 +1,21 @@
+use crate::data::api::ApiError;
+use crate::data::cache::ResultCache;
+use crate::data::changes::ChangeFeed;
+use crate::data::persist;
+use crate::data::settings::Settings;
+use crate::data::usernames::UsernameCache;
 use crate::pages::bulkpage::BulkPage;
 use crate::pages::layout::Layout;
 use crate::pages::pigpage::PigPage;
 use crate::pages::Routes;
+use eframe::Storage;
 use egui_colors::Colorix;
-use pigweb_common::users::Roles;
-use std::collections::BTreeSet;
+use pigweb_common::bulk::{BulkImport, BulkQuery};
+use pigweb_common::instance::{Branding, Environment};
+use pigweb_common::pigs::{Pig, PigQuery};
+use pigweb_common::users::{Capabilities, Roles, User};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
 
 /// Persistent data stored on the user's device by the client. This should be
 /// used for data the user is actively working with where changes may be lost
@@ -16,20 +27,71 @@ pub struct ClientState {
     /// List of roles the user has. None if the user isn't authenticated
     pub authorized: Option<BTreeSet<Roles>>,
 
+    /// The signed in user, once we've fetched them
+    #[serde(skip)]
+    pub user: Option<User>,
+
+    /// The roles needed to use each API endpoint, if we've loaded them
+    #[serde(skip)]
+    pub capabilities: Option<Capabilities>,
+
+    /// How this instance presents itself, once we've fetched it
+    #[serde(skip)]
+    pub branding: Option<Branding>,
+
+    /// Which kind of deployment this is, once we've fetched it
+    #[serde(skip)]
+    pub environment: Option<Environment>,
+
     /// Global theme info
     #[serde(skip)]
     pub colorix: Colorix,
 
+    /// Usernames for each user id we've had to show
+    #[serde(skip)]
+    pub usernames: UsernameCache,
+
+    /// Data fetched after signing in, waiting for the page which needs it
+    #[serde(skip)]
+    pub prefetched: Prefetched,
+
+    /// Recent results shown while the fresh ones are fetched
+    #[serde(skip)]
+    pub cache: ResultCache,
+
+    /// Changes made by other users since the last frame
+    #[serde(skip)]
+    pub changes: ChangeFeed,
+
     /// The current route
     pub route: Routes,
 
-    /// Data storage for individual pages
+    /// The user's preferences
+    pub settings: Settings,
+
+    /// Data storage for individual pages. Each page is saved under its own
+    /// key, see [`PageData::load`].
+    #[serde(skip)]
     pub pages: PageData,
 }
 
 impl Default for ClientState {
     fn default() -> Self {
-        Self { authorized: None, colorix: Colorix::default(), route: Routes::Pigs, pages: PageData::default() }
+        Self {
+            authorized: None,
+            user: None,
+            capabilities: None,
+            branding: None,
+            environment: None,
+            colorix: Colorix::default(),
+            usernames: UsernameCache::default(),
+            prefetched: Prefetched::default(),
+            cache: ResultCache::default(),
+            changes: ChangeFeed::default(),
+            route: Routes::Pigs,
+            settings: Settings::default(),
+            pages: PageData::default(),
+        }
     }
 }
 
@@ -39,10 +101,75 @@ impl ClientState {
     pub fn has_role(&self, role: Roles) -> bool {
         self.authorized.as_ref().is_some_and(|roles| roles.contains(&role))
     }
+
+    /// Whether the authenticated user can use the given API endpoint, e.g.
+    /// `/api/users/expire`. Returns `false` if the user isn't authenticated.
+    /// If we don't know which roles the endpoint needs yet, assumes it can be
+    /// used and lets the server decide.
+    pub fn can_request(&self, endpoint: &str) -> bool {
+        match (self.authorized.as_ref(), self.capabilities.as_ref()) {
+            (Some(roles), Some(capabilities)) => capabilities.allows(endpoint, roles),
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// The most items the user can fetch at once. Returns [`None`] if there's
+    /// no limit, or we don't know it yet and should let the server decide.
+    pub fn max_limit(&self) -> Option<u32> {
+        match (self.authorized.as_ref(), self.capabilities.as_ref()) {
+            (Some(roles), Some(capabilities)) => capabilities.limit_for(roles),
+            _ => None,
+        }
+    }
+
+    /// Forgets everything the user has saved on this device, other than
+    /// whether they're signed in. The cleared data is saved over the old data
+    /// the next time the app saves.
+    pub fn clear_local_data(&mut self) {
+        self.settings = Settings::default();
+        self.pages = PageData::default();
+        self.prefetched = Prefetched::default();
+        self.cache = ResultCache::default();
+    }
+
+    /// Displays an error which came from the current page. It's dismissed
+    /// automatically when the page is closed.
+    pub fn page_error(&mut self, err: ApiError) {
+        let route = self.route;
+        self.pages.layout.push_error(err.with_scope(route));
+    }
+}
+
+/// Data fetched right after signing in so pages can show it as soon as
+/// they're opened instead of waiting on their own requests. Each page takes
+/// its data when opened, but only if it was fetched with the same query the
+/// page would have used.
+#[derive(Debug, Default)]
+pub struct Prefetched {
+    /// The pigs for the saved search and the query used to fetch them
+    pub pigs: Option<(PigQuery, Vec<Pig>)>,
+
+    /// The imports for the saved sidebar tab and the query used to fetch them
+    pub imports: Option<(BulkQuery, Vec<BulkImport>)>,
+
+    /// The username of each user
+    pub usernames: Option<BTreeMap<Uuid, String>>,
 }
 
-#[derive(Default, serde::Deserialize, serde::Serialize)]
-#[serde(default)]
+impl Prefetched {
+    /// Takes the prefetched pigs if they were fetched with the given query
+    pub fn take_pigs(&mut self, query: &PigQuery) -> Option<Vec<Pig>> {
+        self.pigs.take().filter(|(fetched, _)| fetched == query).map(|(_, pigs)| pigs)
+    }
+
+    /// Takes the prefetched imports if they were fetched with the given query
+    pub fn take_imports(&mut self, query: &BulkQuery) -> Option<Vec<BulkImport>> {
+        self.imports.take().filter(|(fetched, _)| fetched == query).map(|(_, imports)| imports)
+    }
+}
+
+#[derive(Default)]
 pub struct PageData {
     /// The common layout shown on all pages
     pub layout: Layout,
@@ -53,3 +180,18 @@ pub struct PageData {
     /// Page for managing bulk imports
     pub bulk: BulkPage,
 }
+
+impl PageData {
+    /// Loads each page's data saved on the user's device. A page whose data is
+    /// missing, unreadable, or from an older version starts over on its own.
+    pub fn load(storage: Option<&dyn Storage>) -> Self {
+        Self { layout: persist::load(storage), pigs: persist::load(storage), bulk: persist::load(storage) }
+    }
+
+    /// Saves each page's data on the user's device
+    pub fn save(&self, storage: &mut dyn Storage) {
+        persist::save(storage, &self.layout);
+        persist::save(storage, &self.pigs);
+        persist::save(storage, &self.bulk);
+    }
+}
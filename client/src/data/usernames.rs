@@ -0,0 +1,78 @@
+use crate::data::api::{Status, UserNamesHandler};
+use log::error;
+use pigweb_common::users::WithCreator;
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+/// Resolves user ids to usernames for display. Unknown ids are queued when
+/// they're looked up and fetched together once per frame by
+/// [`UsernameCache::update`].
+#[derive(Debug, Default)]
+pub struct UsernameCache {
+    /// Handles fetching usernames from the API
+    fetch: UserNamesHandler,
+
+    /// Whether we're still waiting on a response to the last fetch
+    fetching: bool,
+
+    /// Every username we've resolved so far
+    usernames: BTreeMap<Uuid, String>,
+
+    /// Ids which have been looked up but not requested yet
+    queued: BTreeSet<Uuid>,
+
+    /// Ids which have already been requested, so we don't ask again
+    requested: BTreeSet<Uuid>,
+}
+
+impl UsernameCache {
+    /// Returns the username for the given id if we have it. Otherwise, the id
+    /// is queued to be fetched.
+    pub fn get(&mut self, id: &Uuid) -> Option<&String> {
+        if !self.usernames.contains_key(id) && !self.requested.contains(id) {
+            self.queued.insert(id.to_owned());
+        }
+
+        self.usernames.get(id)
+    }
+
+    /// Saves the creator usernames the server included with the given items
+    /// and returns the items themselves. The creator's id is read from each
+    /// item with the given function.
+    pub fn take_creators<T>(&mut self, items: Vec<WithCreator<T>>, creator: impl Fn(&T) -> Uuid) -> Vec<T> {
+        items
+            .into_iter()
+            .map(|with| {
+                if let Some(username) = with.creator_username {
+                    self.usernames.insert(creator(&with.item), username);
+                }
+                with.item
+            })
+            .collect()
+    }
+
+    /// Saves the given usernames, e.g. from a fetch made somewhere else
+    pub fn extend(&mut self, usernames: &BTreeMap<Uuid, String>) {
+        self.usernames.extend(usernames.iter().map(|(id, username)| (id.to_owned(), username.to_owned())));
+    }
+
+    /// Saves any usernames we've received and requests all queued ids at once.
+    /// Ids the server doesn't know about aren't requested again.
+    pub fn update(&mut self) {
+        if self.fetching {
+            match self.fetch.resolve() {
+                Status::Received(res) => self.usernames.extend(res),
+                Status::Errored(err) => error!("Unable to fetch usernames: {:?}", err),
+                Status::Pending => return,
+            }
+
+            self.fetching = false;
+        }
+
+        if !self.queued.is_empty() {
+            self.fetch.request(&self.queued);
+            self.requested.append(&mut self.queued);
+            self.fetching = true;
+        }
+    }
+}
@@ -1,26 +1,45 @@
-use crate::data::api::{ApiError, BulkApi, BulkFetchHandler, PigCreateHandler, PigFetchHandler};
+use crate::data::api::{
+    ApiError, BulkApi, BulkFetchHandler, BulkPatchHandler, PigCreateHandler, PigDeleteHandler, PigFetchHandler,
+    RetryPolicy, Severity, Status, UserFetchHandler,
+};
+use crate::data::persist::Persisted;
+use crate::data::reconcile::Reconciliation;
 use crate::data::state::ClientState;
+use crate::pages::errpage::ErrPageRender;
 use crate::pages::RenderPage;
 use crate::ui::modal::Modal;
 use crate::ui::style::{
-    COLOR_ACCEPTED, COLOR_REJECTED, PANEL_WIDTH_MEDIUM, PANEL_WIDTH_SMALL, SPACE_MEDIUM, TABLE_ROW_HEIGHT_LARGE,
-    TABLE_ROW_HEIGHT_SMALL, TIME_FMT,
+    COLOR_ACCEPTED, COLOR_REJECTED, COLOR_WARNING, PANEL_WIDTH_MEDIUM, PANEL_WIDTH_SMALL, SPACE_MEDIUM, SPACE_SMALL,
+    TABLE_ROW_HEIGHT_LARGE, TABLE_ROW_HEIGHT_SMALL,
+};
+use crate::ui::time::{format_time, time_label};
+use crate::ui::{
+    add_properties_row, label_dot, paginator, properties_list, request_spinner, selectable_list, spaced_heading,
+    username_label, wrapped_singleline_layouter, Pagination,
 };
-use crate::ui::{add_properties_row, properties_list, selectable_list, spaced_heading, wrapped_singleline_layouter};
 use crate::update_url_hash;
-use chrono::Local;
 use egui::{
-    Align, Button, CentralPanel, Context, Label, Layout, OpenUrl, Panel, RichText, ScrollArea, Sense, TextEdit, Ui,
-    Widget,
+    Align, Button, CentralPanel, ComboBox, Context, DragValue, Key, Label, Layout, Modifiers, OpenUrl, Panel, RichText,
+    ScrollArea, Sense, TextEdit, Ui, Widget,
 };
 use egui_extras::{Column, TableBuilder};
-use log::{debug, error};
-use pigweb_common::bulk::{BulkImport, BulkPatch, BulkQuery, PatchAction};
+use log::{debug, error, info};
+use pigweb_common::bulk::{
+    BulkDiff, BulkDuplicate, BulkImport, BulkPatch, BulkQuery, BulkStatus, BulkSuggestion, PatchAction,
+};
+use pigweb_common::changes::ChangeTarget;
+use pigweb_common::errors::ErrorCode;
 use pigweb_common::pigs::{Pig, PigQuery};
-use pigweb_common::users::Roles;
+use pigweb_common::scripts::{dominant_script, is_mixed_script, Script};
+use pigweb_common::users::{Roles, UserQuery};
+use pigweb_common::{yuri, BULK_API_ROOT};
+use std::collections::{BTreeMap, BTreeSet};
 use urlable::ParsedURL;
 use uuid::Uuid;
 
+/// The number of applied [`BulkPatch`]es to remember so they can be undone
+const UNDO_HISTORY_LENGTH: usize = 10;
+
 /// An action which should only be performed when there are no unsaved changes.
 /// When this isn't [BulkPageDirtyAction::None], shows a modal with a warning
 /// before performing the action and resetting itself to None.
@@ -47,6 +66,75 @@ pub enum SelectedImportedPig {
     Rejected(String),
 }
 
+impl SelectedImportedPig {
+    /// Whether both refer to the same name in the same list. Accepted pigs
+    /// are compared by id since they may have been edited since.
+    pub fn is_same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Pending(a), Self::Pending(b)) => a == b,
+            (Self::Accepted(a), Self::Accepted(b)) => a.id == b.id,
+            (Self::Rejected(a), Self::Rejected(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Which pending names are listed, going by the script they're written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptFilter {
+    /// Every pending name
+    All,
+
+    /// Names written in more than one script, see [`is_mixed_script`]
+    Mixed,
+
+    /// Names mostly written in the given script, see [`dominant_script`]
+    Only(Script),
+}
+
+impl ScriptFilter {
+    /// Whether the given name should be listed
+    pub fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Mixed => is_mixed_script(name),
+            Self::Only(script) => dominant_script(name) == Some(*script),
+        }
+    }
+
+    /// What the filter is shown as
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::All => "All scripts",
+            Self::Mixed => "Mixed scripts",
+            Self::Only(script) => script.name(),
+        }
+    }
+}
+
+/// Which [`BulkImport`]s are listed in the sidebar
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum BulkSidebarTab {
+    /// Imports which still have pending names
+    InProgress,
+
+    /// Imports with no pending names left
+    Finished,
+
+    /// Imports which were closed before every name was processed
+    Closed,
+
+    /// Imports the user created, regardless of status
+    Mine,
+
+    /// Imports the user has been assigned to review, regardless of status
+    Assigned,
+
+    /// Every import the user can see. Only differs from [`Self::Mine`] for
+    /// [`Roles::BulkAdmin`]s.
+    All,
+}
+
 /// Persistent data storage for [`crate::pages::Routes::Bulk`].
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -61,13 +149,68 @@ pub struct BulkPage {
     /// [`selected_pig`] to prevent sync issues
     pub updated_name: String,
 
+    /// Which imports are listed in the sidebar
+    pub sidebar_tab: BulkSidebarTab,
+
+    /// Only imports with names containing this are listed in the sidebar
+    pub sidebar_filter: String,
+
+    /// Which page of imports is listed in the sidebar
+    pub sidebar_pagination: Pagination,
+
     /// Whether we have unsaved changes
     dirty: bool,
+
+    /// Whether the selection was loaded from the user's device and hasn't
+    /// been checked against the server yet, see [`Persisted::restored`]
+    #[serde(skip)]
+    unverified: bool,
+}
+
+impl BulkPage {
+    /// Builds a [`BulkQuery`] for the imports which match the sidebar tab and
+    /// filter
+    pub fn sidebar_query(&self) -> BulkQuery {
+        let query = match self.sidebar_tab {
+            BulkSidebarTab::InProgress => BulkQuery::default().with_status(BulkStatus::InProgress),
+            BulkSidebarTab::Finished => BulkQuery::default().with_status(BulkStatus::Finished),
+            BulkSidebarTab::Closed => BulkQuery::default().with_status(BulkStatus::Closed),
+            BulkSidebarTab::Mine => BulkQuery::default().with_mine(true),
+            BulkSidebarTab::Assigned => BulkQuery::default().with_assigned(true),
+            BulkSidebarTab::All => BulkQuery::default(),
+        }
+        .with_limit(self.sidebar_pagination.limit())
+        .with_offset(self.sidebar_pagination.offset());
+
+        if self.sidebar_filter.is_empty() {
+            query
+        } else {
+            query.with_name(&self.sidebar_filter)
+        }
+    }
 }
 
 impl Default for BulkPage {
     fn default() -> Self {
-        Self { selected_import: None, selected_pig: None, updated_name: String::default(), dirty: false }
+        Self {
+            selected_import: None,
+            selected_pig: None,
+            updated_name: String::default(),
+            sidebar_tab: BulkSidebarTab::InProgress,
+            sidebar_filter: String::default(),
+            sidebar_pagination: Pagination::default(),
+            dirty: false,
+            unverified: false,
+        }
+    }
+}
+
+impl Persisted for BulkPage {
+    const KEY: &'static str = "pigweb.bulk";
+    const VERSION: u32 = 1;
+
+    fn restored(&mut self) {
+        self.unverified = self.selected_import.is_some();
     }
 }
 
@@ -83,24 +226,66 @@ pub struct BulkPageRender {
     /// [`BulkImport`]
     fetch_accepted_pigs: PigFetchHandler,
 
-    /// Handles API data to load any duplicate pigs from the currently selected
-    /// pending name
-    fetch_duplicate_pigs: PigFetchHandler,
-
     /// Handles API data when creating a pig from a pending name
     create_pig: PigCreateHandler,
 
+    /// Handles API data when submitting the inverse of a previous patch
+    undo_patch: BulkPatchHandler,
+
+    /// Handles API data when deleting a pig created by an undone patch
+    delete_pig: PigDeleteHandler,
+
+    /// Handles API data when refetching an import which changed before a
+    /// patch to it went through
+    fetch_stale_import: BulkFetchHandler,
+
+    /// Handles API data when refetching the selected import after someone
+    /// else changed it
+    fetch_changed_import: BulkFetchHandler,
+
+    /// Handles API data when checking a selection loaded from the user's
+    /// device still exists
+    fetch_restored_selection: BulkFetchHandler,
+
+    /// The id of the selection loaded from the user's device which we're
+    /// checking
+    restored_selection: Option<Uuid>,
+
+    /// Handles API data when fetching imports in the sidebar someone else
+    /// changed, see [`Reconciliation`]
+    fetch_changed_imports: BulkFetchHandler,
+
+    /// The imports someone else changed which we're still fetching
+    changed_imports: BTreeSet<Uuid>,
+
+    /// Handles API data for the users imports can be assigned to
+    fetch_users: UserFetchHandler,
+
+    /// A mapping of user ids to usernames, used to show and pick who an import
+    /// is assigned to
+    usernames: Option<BTreeMap<Uuid, String>>,
+
     /// All imports the user has access to see, shows up on the sidebar
     all_imports: Option<Vec<BulkImport>>,
 
+    /// The yuri of the last sidebar query sent, which its results are cached
+    /// under
+    fetched_imports_query: Option<String>,
+
     /// The full data for all accepted pigs in the [`BulkImport`]
     accepted_pigs: Option<Vec<Pig>>,
 
-    /// All pigs similar to the selected pending name
-    duplicate_pigs: Option<Vec<Pig>>,
+    /// All pigs similar to the selected pending name and where they came from
+    duplicate_pigs: Option<Vec<BulkDuplicate>>,
 
     /// The selection pig from [duplicate_pigs]
-    selected_duplicate: Option<Pig>,
+    selected_duplicate: Option<BulkDuplicate>,
+
+    /// The pending name [`similar_pending`] was last found for
+    similar_to: Option<String>,
+
+    /// Other pending names in the import similar to the selected one
+    similar_pending: Vec<String>,
 
     /// Modal which warns you when there's unsaved changes
     dirty_modal: BulkPageDirtyAction,
@@ -108,25 +293,103 @@ pub struct BulkPageRender {
     /// The text box to paste the names you wish to import into
     raw_names: String,
 
+    /// The previous import to compare the pasted names against
+    compare_import: Option<Uuid>,
+
+    /// How the pasted names compare to [`compare_import`]
+    diff: Option<BulkDiff>,
+
+    /// Pasted names which may have typos, along with whether to apply the
+    /// suggested correction
+    suggestions: Option<Vec<(BulkSuggestion, bool)>>,
+
+    /// How many imports to split the selected import's pending names into
+    split_parts: usize,
+
+    /// Which pending names to list, by the script they're written in
+    script_filter: ScriptFilter,
+
+    /// Whether to group pending names by the script they're written in
+    /// instead of listing them in the order they were imported
+    sort_by_script: bool,
+
     /// Whether to show the modal for a URL where no BulkImport exists
     not_found_modal: bool,
+
+    /// Whether to show the modal to confirm closing the selected import
+    close_modal: bool,
+
+    /// Whether to copy the selected import's pending names when it's closed,
+    /// so they can be imported somewhere else
+    close_copy_pending: bool,
+
+    /// Whether to show the modal warning the pasted names were already
+    /// imported recently
+    duplicate_import_modal: bool,
+
+    /// The most recently applied patches to the selected import, newest last
+    history: Vec<BulkPatch>,
+
+    /// The last patch submitted, kept in case it needs to be reapplied
+    sent_patch: Option<BulkPatch>,
+
+    /// The last undo patch submitted, kept in case it needs to be reapplied
+    sent_undo: Option<BulkPatch>,
+
+    /// A patch rejected because the import changed, waiting to be reapplied to
+    /// the fresh import. The bool is whether it's undoing another patch.
+    stale_patch: Option<(BulkPatch, bool)>,
+
+    /// The selected import as the server last confirmed it. Patches are shown
+    /// as soon as they're submitted, so this is what they're rolled back to if
+    /// the server rejects them.
+    confirmed_import: Option<BulkImport>,
 }
 
 impl Default for BulkPageRender {
     fn default() -> Self {
         Self {
-            bulk_api: BulkApi::default(),
+            bulk_api: BulkApi {
+                fetch: BulkFetchHandler::default().with_retry(RetryPolicy::default()),
+                ..BulkApi::default()
+            },
             fetch_url_selection: BulkFetchHandler::default(),
             fetch_accepted_pigs: PigFetchHandler::default(),
-            fetch_duplicate_pigs: PigFetchHandler::default(),
             create_pig: PigCreateHandler::default(),
+            undo_patch: BulkPatchHandler::default(),
+            delete_pig: PigDeleteHandler::default(),
+            fetch_stale_import: BulkFetchHandler::default(),
+            fetch_changed_import: BulkFetchHandler::default(),
+            fetch_restored_selection: BulkFetchHandler::default(),
+            restored_selection: None,
+            fetch_changed_imports: BulkFetchHandler::default(),
+            changed_imports: BTreeSet::new(),
+            fetch_users: UserFetchHandler::default(),
+            usernames: None,
             all_imports: None,
+            fetched_imports_query: None,
             accepted_pigs: None,
             duplicate_pigs: None,
             selected_duplicate: None,
+            similar_to: None,
+            similar_pending: Vec::new(),
             dirty_modal: BulkPageDirtyAction::None,
             raw_names: String::default(),
+            compare_import: None,
+            diff: None,
+            suggestions: None,
+            split_parts: 2,
+            script_filter: ScriptFilter::All,
+            sort_by_script: false,
             not_found_modal: false,
+            close_modal: false,
+            close_copy_pending: false,
+            duplicate_import_modal: false,
+            history: Vec::new(),
+            sent_patch: None,
+            sent_undo: None,
+            stale_patch: None,
+            confirmed_import: None,
         }
     }
 }
@@ -147,15 +410,12 @@ impl RenderPage for BulkPageRender {
                             "The selection has been updated via url! Previous Selection: {:?}",
                             state.pages.bulk.selected_import.as_ref()
                         );
-                        self.fetch_url_selection.request(&BulkQuery::default().with_id(&uuid).with_limit(1));
+                        self.fetch_url_selection
+                            .request(&BulkQuery::default().with_id(&uuid).with_limit(1).with_creator_username());
                     }
                 }
                 Err(err) => {
-                    state
-                        .pages
-                        .layout
-                        .display_error
-                        .push(ApiError::new(err.to_string()).with_reason("Unable to parse UUID.".to_owned()));
+                    state.page_error(ApiError::new(err.to_string()).with_reason("Unable to parse UUID.".to_owned()));
                     update_url_hash(ctx, url, None);
                     error!("Unable to parse hash \"{:?}\", err: {:?}", &stripped_hash, err);
                 }
@@ -167,19 +427,50 @@ impl RenderPage for BulkPageRender {
     }
 
     fn open(&mut self, _ctx: &Context, state: &mut ClientState, _url: &ParsedURL) {
-        self.query_imports();
+        // Use the data fetched after signing in if it's for the same imports
+        match state.prefetched.take_imports(&state.pages.bulk.sidebar_query()) {
+            Some(mut imports) => {
+                imports.reverse(); // show newest first
+                self.all_imports = Some(imports);
+            }
+            None => self.query_imports(state),
+        }
+
         self.query_duplicates(state);
         self.update_accepted_pigs(state);
+
+        match state.prefetched.usernames.take() {
+            Some(usernames) => self.usernames = Some(usernames),
+            None => self.fetch_users.request(UserQuery::default()),
+        }
+
+        // The selection may have been changed or deleted since it was saved
+        if std::mem::take(&mut state.pages.bulk.unverified) {
+            self.restored_selection = state.pages.bulk.selected_import.as_ref().map(|import| import.id);
+            if let Some(id) = self.restored_selection.as_ref() {
+                self.fetch_restored_selection
+                    .request(&BulkQuery::default().with_id(id).with_limit(1).with_creator_username());
+            }
+        }
+    }
+
+    fn selection_title(&self, state: &ClientState) -> Option<String> {
+        state.pages.bulk.selected_import.as_ref().map(|import| import.name.to_owned())
     }
 
     fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
         if !(state.has_role(Roles::BulkEditor) || state.has_role(Roles::BulkAdmin)) {
-            // TODO 403 Forbidden
+            ErrPageRender::forbidden(&[Roles::BulkEditor, Roles::BulkAdmin]).ui(ui, state, url);
             return;
         }
 
         self.process_promises(ui.ctx(), state, url);
 
+        // shortcuts only make sense when we're editing an import
+        if state.pages.bulk.selected_import.as_ref().is_some_and(|import| import.is_open()) {
+            self.handle_shortcuts(ui.ctx(), state, url);
+        }
+
         Panel::left("left_panel").resizable(false).show_inside(ui, |ui| {
             self.populate_sidebar(ui, state, url);
         });
@@ -195,56 +486,192 @@ impl RenderPage for BulkPageRender {
 impl BulkPageRender {
     /// Checks all APIs for data received from previously submitted requests
     fn process_promises(&mut self, ctx: &Context, state: &mut ClientState, url: &ParsedURL) {
-        // import was created
-        if let Some(import) = self.bulk_api.create.received(state) {
-            state.pages.bulk.dirty = false;
-            state.pages.bulk.selected_import = Some(import);
-            self.raw_names = String::default();
+        // someone else changed the imports, make sure we're showing the latest of them
+        if let Some(changes) = state.changes.reconcile(ChangeTarget::Import) {
+            self.reconcile(state, changes);
+
+            // our own patches take priority, they'll find out about the change if they conflict
+            if let Some(id) = state.pages.bulk.selected_import.as_ref().map(|import| import.id) {
+                if !self.is_patching() && state.changes.affects_id(ChangeTarget::Import, &id) {
+                    self.fetch_changed_import
+                        .request(&BulkQuery::default().with_id(&id).with_limit(1).with_creator_username());
+                }
+            }
+        }
 
-            // refresh these things
-            update_url_hash(ctx, url, Some(state.pages.bulk.selected_import.as_ref().unwrap().id));
-            self.query_imports();
-            self.update_accepted_pigs(state);
+        // put the latest versions of the changed imports in place, unless there's new ones we don't know where to put
+        if let Some(imports) = self.fetch_changed_imports.received(state) {
+            let imports = state.usernames.take_creators(imports, |import| import.creator);
+            let changed = std::mem::take(&mut self.changed_imports);
+            let merged = self.all_imports.as_mut().is_some_and(|all| Reconciliation::merge(&changed, all, imports));
+
+            match self.all_imports.as_ref().filter(|_| merged) {
+                Some(all) => {
+                    let mut cached = all.to_owned();
+                    cached.reverse(); // the cache keeps them in the order the server sent them
+                    state.cache.imports.insert(state.pages.bulk.sidebar_query().to_yuri(), &cached);
+                }
+                None => self.refresh_imports(state),
+            }
         }
 
-        // did the submitted changes go through?
-        if let Some(patch) = self.bulk_api.patch.received(state) {
-            // update our lists to reflect the changes made by the patch
-            if let Some(sel) = state.pages.bulk.selected_import.as_mut() {
-                patch.update_import(sel);
+        // show someone else's changes to the selected import without losing our place in it
+        if let Some(mut imports) = self
+            .fetch_changed_import
+            .received(state)
+            .map(|imports| state.usernames.take_creators(imports, |import| import.creator))
+        {
+            if let Some(import) = imports.pop().filter(|_| !self.is_patching()) {
+                if let Some(imports) = self.all_imports.as_mut() {
+                    let pos = imports.iter().position(|r| r.id.eq(&import.id));
+                    pos.and_then(|i| Some(imports[i] = import.clone()));
+                }
 
-                // if import is complete, auto refresh our selected import
-                if sel.pending.len() == 0 {
-                    self.fetch_url_selection.request(&BulkQuery::default().with_id(&sel.id));
+                if state.pages.bulk.selected_import.as_ref().is_some_and(|sel| sel.id == import.id) {
+                    self.refresh_selected_import(state, import);
                 }
+            }
+        }
 
-                // update our selected item in the list of all imports
-                if let Some(imports) = self.all_imports.as_mut() {
-                    let pos = imports.iter().position(|r| r.id.eq(&sel.id));
-                    pos.and_then(|i| Some(imports[i] = sel.clone()));
+        // a selection saved before it was changed or deleted shouldn't be shown as it was
+        if let Some(mut imports) = self
+            .fetch_restored_selection
+            .received(state)
+            .map(|imports| state.usernames.take_creators(imports, |import| import.creator))
+        {
+            let restored = self.restored_selection.take();
+            if restored.is_some() && restored == state.pages.bulk.selected_import.as_ref().map(|import| import.id) {
+                match imports.pop() {
+                    Some(_) if self.is_patching() => {}
+                    Some(import) => self.refresh_selected_import(state, import),
+                    None => {
+                        info!("Dropping the restored selection {:?}, it no longer exists.", restored);
+                        self.dirty_modal = BulkPageDirtyAction::SelectImport(None);
+                        self.do_dirty_action(ctx, state, url);
+                    }
                 }
-            } else {
-                self.query_imports();
             }
+        }
 
-            // reset the state
-            self.update_accepted_pigs(state);
-            self.duplicate_pigs = Some(Vec::new());
-            self.selected_duplicate = None;
-            state.pages.bulk.dirty = false;
-            state.pages.bulk.selected_pig = None;
-            state.pages.bulk.updated_name = String::default();
+        // import was created
+        match self.bulk_api.create.resolve() {
+            Status::Received(import) => {
+                state.pages.bulk.dirty = false;
+                state.pages.bulk.selected_import = Some(import);
+                self.raw_names = String::default();
+
+                // refresh these things
+                update_url_hash(ctx, url, Some(state.pages.bulk.selected_import.as_ref().unwrap().id));
+                self.query_imports(state);
+                self.update_accepted_pigs(state);
+            }
+            // the same names were imported recently, ask before importing them again
+            Status::Errored(err) if err.kind == Some(ErrorCode::Conflict) => self.duplicate_import_modal = true,
+            Status::Errored(err) if err.kind == Some(ErrorCode::Unauthorized) => state.authorized = None,
+            Status::Errored(err) => state.page_error(err),
+            Status::Pending => {}
+        }
+
+        // did the submitted changes go through? they're already shown, so only failures need handling
+        match self.bulk_api.patch.resolve() {
+            Status::Received(patch) => {
+                self.on_patch_confirmed(state);
 
-            // TODO automatically select next pending name?
+                // remember the patch so it can be undone
+                self.history.push(patch);
+                if self.history.len() > UNDO_HISTORY_LENGTH {
+                    self.history.remove(0);
+                }
+            }
+            // someone else changed the import first, get the new version and try again
+            Status::Errored(err) if err.kind == Some(ErrorCode::Conflict) => {
+                self.confirmed_import = None;
+                self.refetch_stale(self.sent_patch.take(), false)
+            }
+            Status::Errored(err) if err.kind == Some(ErrorCode::Unauthorized) => state.authorized = None,
+            Status::Errored(err) => {
+                state.page_error(err.with_reason("Changes Not Saved".to_owned()));
+                self.rollback_patch(state, self.sent_patch.take(), false);
+            }
+            Status::Pending => {}
+        }
+
+        // undoing is the same as any other patch, it just shouldn't be undone itself
+        match self.undo_patch.resolve() {
+            Status::Received(_) => self.on_patch_confirmed(state),
+            Status::Errored(err) if err.kind == Some(ErrorCode::Conflict) => {
+                self.confirmed_import = None;
+                self.refetch_stale(self.sent_undo.take(), true)
+            }
+            Status::Errored(err) if err.kind == Some(ErrorCode::Unauthorized) => state.authorized = None,
+            Status::Errored(err) => {
+                state.page_error(err.with_reason("Changes Not Saved".to_owned()));
+                self.rollback_patch(state, self.sent_undo.take(), true);
+            }
+            Status::Pending => {}
+        }
+
+        // reapply the rejected patch to the fresh import, if it still makes sense
+        if let Some(mut imports) = self
+            .fetch_stale_import
+            .received(state)
+            .map(|imports| state.usernames.take_creators(imports, |import| import.creator))
+        {
+            if let (Some(import), Some((patch, undo))) = (imports.pop(), self.stale_patch.take()) {
+                if state.pages.bulk.selected_import.as_ref().is_some_and(|sel| sel.id == import.id) {
+                    state.pages.bulk.selected_import = Some(import.clone());
+                }
+
+                if let Some(imports) = self.all_imports.as_mut() {
+                    let pos = imports.iter().position(|r| r.id.eq(&import.id));
+                    pos.and_then(|i| Some(imports[i] = import.clone()));
+                }
+
+                if import.closed.is_some() {
+                    // nobody can change a closed import, don't leave behind the pig we made for it
+                    self.delete_created_pigs(&patch, undo);
+
+                    state.page_error(
+                        ApiError::new("This import was closed before your changes went through.".to_owned())
+                            .with_reason("Import Closed".to_owned())
+                            .with_severity(Severity::Warning),
+                    );
+                } else if patch.applies_to(&import) {
+                    debug!("Reapplying patch to BulkImport {:?} at revision {}", import.id, import.revision);
+                    self.submit_patch(state, patch, import.revision, undo);
+                } else {
+                    // the name was already taken care of, don't leave behind the pig we made for it
+                    self.delete_created_pigs(&patch, undo);
+
+                    state.page_error(
+                        ApiError::new("Someone else reviewed this name before your changes went through.".to_owned())
+                            .with_reason("Conflicting Changes".to_owned())
+                            .with_severity(Severity::Warning),
+                    );
+                }
+            }
         }
 
         // updates the left sidebar data
-        if let Some(mut imports) = self.bulk_api.fetch.received(state) {
+        if let Some(mut imports) = self
+            .bulk_api
+            .fetch
+            .received(state)
+            .map(|imports| state.usernames.take_creators(imports, |import| import.creator))
+        {
+            if let Some(key) = self.fetched_imports_query.take() {
+                state.cache.imports.insert(key, &imports);
+            }
+
             imports.reverse(); // show newest first
             self.all_imports = Some(imports);
         }
 
-        if let Some(mut imports) = self.fetch_url_selection.received(state) {
+        if let Some(mut imports) = self
+            .fetch_url_selection
+            .received(state)
+            .map(|imports| state.usernames.take_creators(imports, |import| import.creator))
+        {
             // This request should have been made with limit = 1
             // therefore, the only pig is the one we want
             if let Some(sel) = imports.pop() {
@@ -265,37 +692,213 @@ impl BulkPageRender {
             }
         }
 
-        if let Some(pigs) = self.fetch_accepted_pigs.received(state) {
+        if let Some(pigs) =
+            self.fetch_accepted_pigs.received(state).map(|pigs| state.usernames.take_creators(pigs, |pig| pig.creator))
+        {
             self.accepted_pigs = Some(pigs);
         }
 
-        if let Some(pigs) = self.fetch_duplicate_pigs.received(state) {
+        if let Some(pigs) = self.bulk_api.duplicates.received(state) {
             self.duplicate_pigs = Some(pigs);
         }
 
+        // the import is back in progress, show the edit screen again
+        if let Some(import) = self.bulk_api.reopen.received(state) {
+            if let Some(imports) = self.all_imports.as_mut() {
+                let pos = imports.iter().position(|r| r.id.eq(&import.id));
+                pos.and_then(|i| Some(imports[i] = import.clone()));
+            }
+
+            state.pages.bulk.selected_import = Some(import);
+            state.pages.bulk.selected_pig = None;
+            state.pages.bulk.updated_name = String::default();
+            self.update_accepted_pigs(state);
+        }
+
+        // the import was closed or opened again, which changes the screen it's shown on
+        if let Some(import) = self.bulk_api.close.received(state) {
+            if state.pages.bulk.selected_import.as_ref().is_some_and(|sel| sel.id == import.id) {
+                state.pages.bulk.selected_import = Some(import);
+                state.pages.bulk.selected_pig = None;
+                state.pages.bulk.updated_name = String::default();
+                self.update_accepted_pigs(state);
+            }
+
+            // it may not belong on the current sidebar tab anymore
+            self.query_imports(state);
+        }
+
+        // the import was assigned to someone else, update it everywhere
+        if let Some(import) = self.bulk_api.assign.received(state) {
+            if let Some(imports) = self.all_imports.as_mut() {
+                let pos = imports.iter().position(|r| r.id.eq(&import.id));
+                pos.and_then(|i| Some(imports[i] = import.clone()));
+            }
+
+            // only the assignee changed, no need to reset the rest of the selection
+            if let Some(selected) = state.pages.bulk.selected_import.as_mut() {
+                if selected.id == import.id {
+                    selected.assignee = import.assignee;
+                }
+            }
+        }
+
+        // the selected import was split, refresh it since it has less pending now
+        if self.bulk_api.split.received(state).is_some() {
+            if let Some(import) = state.pages.bulk.selected_import.as_ref() {
+                self.fetch_url_selection
+                    .request(&BulkQuery::default().with_id(&import.id).with_limit(1).with_creator_username());
+            }
+
+            self.query_imports(state);
+        }
+
+        if let Some(diff) = self.bulk_api.diff.received(state) {
+            self.diff = Some(diff);
+        }
+
+        if let Some(suggestions) = self.bulk_api.spellcheck.received(state) {
+            self.suggestions = Some(suggestions.into_iter().map(|suggestion| (suggestion, true)).collect());
+        }
+
+        if let Some(res) = self.fetch_users.received(state) {
+            self.usernames = res.usernames;
+        }
+
+        // nothing else needs to happen once a pig is deleted, this just displays any errors
+        self.delete_pig.received(state);
+
         // When a pig is created, submit a patch request to update the import
         if let Some(pig) = self.create_pig.received(state) {
             if let Some(import) = state.pages.bulk.selected_import.as_ref() {
-                if let Some(sel) = state.pages.bulk.selected_pig.as_ref() {
-                    match sel {
-                        SelectedImportedPig::Pending(name) => {
-                            let patch = BulkPatch::new(&import.id)
-                                .pending(PatchAction::REMOVE(name.to_owned()))
-                                .accepted(PatchAction::ADD(pig.id));
-                            self.bulk_api.patch.request(patch);
-                        }
-                        _ => {}
-                    }
+                if let Some(SelectedImportedPig::Pending(name)) = state.pages.bulk.selected_pig.as_ref() {
+                    let patch = BulkPatch::new(&import.id, import.revision)
+                        .pending(PatchAction::REMOVE(name.to_owned()))
+                        .accepted(PatchAction::ADD(pig.id));
+                    let revision = import.revision;
+                    self.submit_patch(state, patch, revision, false);
                 }
             }
         }
     }
 
+    /// Forgets the rollback point once the server confirms a patch went
+    /// through, and refreshes the selected import if it's complete
+    fn on_patch_confirmed(&mut self, state: &mut ClientState) {
+        self.confirmed_import = None;
+
+        if let Some(sel) = state.pages.bulk.selected_import.as_ref().filter(|sel| sel.pending.is_empty()) {
+            self.fetch_url_selection.request(&BulkQuery::default().with_id(&sel.id).with_creator_username());
+        }
+    }
+
+    /// Goes back to the import as the server last confirmed it after a patch
+    /// was rejected. Any pigs created for the patch are deleted, since they
+    /// were never added to the import.
+    fn rollback_patch(&mut self, state: &mut ClientState, patch: Option<BulkPatch>, undo: bool) {
+        if let Some(import) = self.confirmed_import.take() {
+            if state.pages.bulk.selected_import.as_ref().is_some_and(|sel| sel.id == import.id) {
+                state.pages.bulk.selected_import = Some(import.clone());
+            }
+
+            if let Some(imports) = self.all_imports.as_mut() {
+                let pos = imports.iter().position(|r| r.id.eq(&import.id));
+                pos.and_then(|i| Some(imports[i] = import.clone()));
+            }
+
+            self.update_accepted_pigs(state);
+        }
+
+        if let Some(patch) = patch {
+            self.delete_created_pigs(&patch, undo);
+        }
+    }
+
+    /// Deletes the pigs a patch would have accepted, for when it won't be
+    /// applied after all. Undo patches never create pigs.
+    fn delete_created_pigs(&mut self, patch: &BulkPatch, undo: bool) {
+        if let Some(accepted) = patch.accepted.as_ref().filter(|_| !undo) {
+            for action in accepted {
+                if let PatchAction::ADD(id) = action {
+                    self.delete_pig.request(*id);
+                }
+            }
+        }
+    }
+
+    /// Updates the selected import and resets the edit screen as soon as a
+    /// patch is submitted, without waiting for the server to confirm it
+    fn apply_patch_locally(&mut self, state: &mut ClientState, patch: &BulkPatch) {
+        // update our lists to reflect the changes made by the patch
+        if let Some(sel) = state.pages.bulk.selected_import.as_mut().filter(|sel| sel.id == patch.id) {
+            // keep what the server last confirmed, in case the patch is rejected
+            if self.confirmed_import.is_none() {
+                self.confirmed_import = Some(sel.clone());
+            }
+
+            patch.update_import(sel);
+
+            // update our selected item in the list of all imports
+            if let Some(imports) = self.all_imports.as_mut() {
+                let pos = imports.iter().position(|r| r.id.eq(&sel.id));
+                pos.and_then(|i| Some(imports[i] = sel.clone()));
+            }
+        } else {
+            self.query_imports(state);
+        }
+
+        // reset the state
+        self.update_accepted_pigs(state);
+        self.duplicate_pigs = Some(Vec::new());
+        self.selected_duplicate = None;
+        state.pages.bulk.dirty = false;
+        state.pages.bulk.selected_pig = None;
+        state.pages.bulk.updated_name = String::default();
+
+        // TODO automatically select next pending name?
+    }
+
     /// The sidebar listing all [`BulkImport`]s the user has access to
     fn populate_sidebar(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
+        let settings = state.settings.clone();
         ui.set_width(PANEL_WIDTH_SMALL);
         spaced_heading(ui, "Bulk Imports");
 
+        // Status tabs, refresh the list when they change
+        let mut tab = state.pages.bulk.sidebar_tab;
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut tab, BulkSidebarTab::InProgress, "In Progress");
+            ui.selectable_value(&mut tab, BulkSidebarTab::Finished, "Finished");
+            ui.selectable_value(&mut tab, BulkSidebarTab::Closed, "Closed");
+            ui.selectable_value(&mut tab, BulkSidebarTab::Mine, "Mine");
+            ui.selectable_value(&mut tab, BulkSidebarTab::Assigned, "Assigned to me");
+
+            // only admins can see imports other than their own
+            if state.has_role(Roles::BulkAdmin) {
+                ui.selectable_value(&mut tab, BulkSidebarTab::All, "All");
+            }
+        });
+
+        if tab != state.pages.bulk.sidebar_tab {
+            state.pages.bulk.sidebar_tab = tab;
+            state.pages.bulk.sidebar_pagination.reset();
+            self.query_imports(state);
+        }
+
+        // Filter by name
+        if ui.add(TextEdit::singleline(&mut state.pages.bulk.sidebar_filter).hint_text("Filter")).changed() {
+            state.pages.bulk.sidebar_pagination.reset();
+            self.query_imports(state);
+        }
+
+        ui.add_space(SPACE_SMALL);
+
+        let shown = self.all_imports.as_ref().map(|imports| imports.len());
+        let max_limit = state.max_limit();
+        if paginator(ui, "import_pages", &mut state.pages.bulk.sidebar_pagination, shown, max_limit) {
+            self.query_imports(state);
+        }
+
         // Only render the results table if we have results to show
         if self.all_imports.as_ref().is_some_and(|imports| !imports.is_empty()) {
             let clicked: Option<Option<BulkImport>> =
@@ -306,11 +909,19 @@ impl BulkPageRender {
 
                     // Make sure we can't select the text or else we can't click the row behind
                     row.col(|ui| {
-                        let start_time = import.started.and_utc().with_timezone(&Local);
-                        Label::new(start_time.format(TIME_FMT).to_string() + " " + import.name.as_str())
-                            .selectable(false)
-                            .truncate()
-                            .ui(ui);
+                        // Lay out right to left so the progress badge is always visible and the name gets truncated
+                        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                            let progress = format!("{}/{}", import.reviewed_count(), import.total_count());
+                            Label::new(RichText::new(progress).small().weak())
+                                .selectable(false)
+                                .ui(ui)
+                                .on_hover_text("Names reviewed out of the total in this import");
+
+                            ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                                let start_time = format_time(import.started, &settings);
+                                Label::new(start_time + " " + import.name.as_str()).selectable(false).truncate().ui(ui);
+                            });
+                        });
                     });
 
                     selected
@@ -323,7 +934,7 @@ impl BulkPageRender {
         } else if self.all_imports.is_none() {
             // Still waiting on results, this should only happen when waiting
             // since otherwise it'll be an empty vec
-            ui.vertical_centered(|ui| ui.spinner());
+            ui.vertical_centered(|ui| request_spinner(ui, self.bulk_api.fetch.attempt()));
         }
     }
 
@@ -331,8 +942,8 @@ impl BulkPageRender {
     /// [`BulkImport`] is selected and whether it's finished.
     fn populate_center(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
         if let Some(import) = state.pages.bulk.selected_import.as_ref() {
-            if import.finished.is_some() {
-                // if we have a finished import selected, show the finished screen
+            if !import.is_open() {
+                // if we have a finished or closed import selected, show the finished screen
                 self.populate_center_finished(ui, state, url);
             } else {
                 // if the selected import has pending names, show the editor
@@ -357,10 +968,13 @@ impl BulkPageRender {
         // submit button
         let add_button = Button::new("+ Add All Pigs");
         if ui.add_enabled(!self.raw_names.is_empty(), add_button).clicked() {
-            let names = self.raw_names.lines().map(|l: &str| l.to_string()).collect::<Vec<String>>();
-            self.bulk_api.create.request(&names);
+            self.create_import(false);
         }
 
+        self.compare_preview(ui);
+        self.spelling_preview(ui);
+        self.script_preview(ui);
+
         // text box to paste all names into
         ui.centered_and_justified(|ui| {
             ScrollArea::vertical().show(ui, |ui| {
@@ -369,8 +983,127 @@ impl BulkPageRender {
         });
     }
 
+    /// Shows a dropdown to pick a previous import and compare the pasted names
+    /// to it, along with the results of the last comparison.
+    fn compare_preview(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            let imports = self.all_imports.as_ref();
+            let selected_name = imports
+                .and_then(|imports| imports.iter().find(|i| self.compare_import.is_some_and(|id| id == i.id)))
+                .map(|i| i.name.to_owned())
+                .unwrap_or("Compare to previous import".to_owned());
+
+            ComboBox::from_id_salt("compare_import").selected_text(selected_name).show_ui(ui, |ui| {
+                for import in imports.unwrap_or(&Vec::new()) {
+                    ui.selectable_value(&mut self.compare_import, Some(import.id), import.name.as_str());
+                }
+            });
+
+            let can_compare = self.compare_import.is_some() && !self.raw_names.is_empty();
+            if ui.add_enabled(can_compare, Button::new("🔍 Compare")).clicked() {
+                let names = self.raw_names.lines().map(|l: &str| l.to_string()).collect::<Vec<String>>();
+                self.bulk_api.diff.request((self.compare_import.unwrap(), &names));
+            }
+        });
+
+        if let Some(diff) = self.diff.as_ref() {
+            let mut keep_new = false;
+
+            ui.collapsing("Comparison", |ui| {
+                ui.label(format!("{} new", diff.added.len()));
+                ui.label(format!("{} still pending", diff.pending.len()));
+                ui.label(format!("{} already accepted", diff.accepted.len()));
+                ui.label(format!("{} previously rejected", diff.rejected.len()));
+
+                keep_new = ui.button("Keep Only New Names").clicked();
+            });
+
+            // Replace the pasted names so only what's changed gets imported
+            if keep_new {
+                self.raw_names = diff.added.join("\n");
+                self.diff = None;
+            }
+        }
+    }
+
+    /// Shows a button to check the pasted names for typos, along with each
+    /// suggested correction which can be picked and applied.
+    fn spelling_preview(&mut self, ui: &mut Ui) {
+        if ui.add_enabled(!self.raw_names.is_empty(), Button::new("🔤 Check Spelling")).clicked() {
+            let names = self.raw_names.lines().map(|l: &str| l.to_string()).collect::<Vec<String>>();
+            self.bulk_api.spellcheck.request(&names);
+        }
+
+        if let Some(suggestions) = self.suggestions.as_mut() {
+            let mut apply = false;
+
+            ui.collapsing(format!("{} Possible Typos", suggestions.len()), |ui| {
+                for (suggestion, selected) in suggestions.iter_mut() {
+                    ui.checkbox(selected, format!("{} → {}", suggestion.name, suggestion.suggestion));
+                }
+
+                apply = ui.button("Apply Selected").clicked();
+            });
+
+            // Replace each selected name with its correction
+            if apply {
+                self.raw_names = self
+                    .raw_names
+                    .lines()
+                    .map(|line| {
+                        suggestions
+                            .iter()
+                            .find(|(suggestion, selected)| *selected && suggestion.name == line.trim())
+                            .map(|(suggestion, _)| suggestion.suggestion.as_str())
+                            .unwrap_or(line)
+                    })
+                    .collect::<Vec<&str>>()
+                    .join("\n");
+                self.suggestions = None;
+            }
+        }
+    }
+
+    /// Shows how many pasted names are written in each script, along with any
+    /// written in more than one, which are likely to be lookalikes of another
+    /// name.
+    fn script_preview(&mut self, ui: &mut Ui) {
+        if self.raw_names.is_empty() {
+            return;
+        }
+
+        ui.collapsing("Scripts", |ui| {
+            let names = self.raw_names.lines().map(str::trim).filter(|name| !name.is_empty());
+
+            let mut counts: BTreeMap<Script, usize> = BTreeMap::new();
+            let mut mixed = Vec::new();
+            for name in names {
+                if let Some(script) = dominant_script(name) {
+                    *counts.entry(script).or_default() += 1;
+                }
+
+                if is_mixed_script(name) {
+                    mixed.push(name);
+                }
+            }
+
+            for (script, count) in counts {
+                ui.label(format!("{} {}", count, script.name()));
+            }
+
+            if !mixed.is_empty() {
+                ui.label(RichText::new(format!("{} in mixed scripts:", mixed.len())).color(COLOR_WARNING));
+                for name in mixed {
+                    ui.label(name);
+                }
+            }
+        });
+    }
+
     /// Shows the edit screen in the center of the page
     fn populate_center_edit(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
+        let settings = state.settings.clone();
+
         // right sidepanel showing duplicates of the selected pending pig
         // this is added before the central panel because that must always come last
         Panel::right("duplicate_pigs").resizable(false).show_inside(ui, |ui| {
@@ -382,14 +1115,16 @@ impl BulkPageRender {
             if !state.pages.bulk.updated_name.is_empty()
                 && self.duplicate_pigs.as_ref().is_some_and(|pigs| !pigs.is_empty())
             {
-                let clicked: Option<Option<Pig>> =
-                    selectable_list(ui, self.duplicate_pigs.as_ref().unwrap(), |row, pig| {
-                        let selected = self.selected_duplicate.as_ref().is_some_and(|select| select.id == pig.id);
+                let clicked: Option<Option<BulkDuplicate>> =
+                    selectable_list(ui, self.duplicate_pigs.as_ref().unwrap(), |row, dupe| {
+                        let selected =
+                            self.selected_duplicate.as_ref().is_some_and(|select| select.pig.id == dupe.pig.id);
                         row.set_selected(selected);
 
                         // Make sure we can't select the text or else we can't click the row behind
                         row.col(|ui| {
-                            Label::new(&pig.name).selectable(false).truncate().ui(ui);
+                            label_dot(ui, dupe.pig.label);
+                            Label::new(&dupe.pig.name).selectable(false).truncate().ui(ui);
                         });
 
                         selected
@@ -399,9 +1134,69 @@ impl BulkPageRender {
                 if let Some(clicked) = clicked {
                     self.selected_duplicate = clicked;
                 }
+
+                // Show where the selected duplicate came from so reviewers can tell whether it's legit
+                if let Some(dupe) = self.selected_duplicate.as_ref() {
+                    spaced_heading(ui, "Origin");
+
+                    ui.push_id("duplicate_origin", |ui| {
+                        properties_list(ui).body(|mut body| {
+                            add_properties_row(&mut body, TABLE_ROW_HEIGHT_SMALL, "added", |ui| {
+                                match (dupe.import_id, dupe.import_name.as_ref()) {
+                                    (Some(id), Some(name)) => {
+                                        if ui.link(name).on_hover_text("Open this import").clicked() {
+                                            ui.ctx().open_url(OpenUrl::same_tab(
+                                                "/bulk#".to_owned() + id.to_string().as_str(),
+                                            ));
+                                        }
+                                    }
+                                    _ => {
+                                        ui.label("manually");
+                                    }
+                                }
+                            });
+
+                            add_properties_row(&mut body, TABLE_ROW_HEIGHT_SMALL, "created at", |ui| {
+                                time_label(ui, dupe.pig.created, &settings);
+                            });
+
+                            add_properties_row(&mut body, TABLE_ROW_HEIGHT_SMALL, "created by", |ui| {
+                                match dupe.creator_name.as_ref() {
+                                    Some(username) => ui.label(username),
+                                    None => ui.code(dupe.pig.creator.to_string()),
+                                };
+                            });
+                        });
+                    });
+                }
             } else if self.duplicate_pigs.is_none() {
                 ui.vertical_centered(|ui| ui.spinner());
             }
+
+            // other names in this import which should probably be resolved together with this one
+            self.update_similar_pending(state);
+            if !self.similar_pending.is_empty() {
+                spaced_heading(ui, "Similar Pending");
+
+                // the duplicates table is in the same ui, this needs a different id
+                let clicked: Option<Option<String>> = ui
+                    .push_id("similar_pending", |ui| {
+                        selectable_list(ui, &self.similar_pending, |row, name| {
+                            row.col(|ui| {
+                                Label::new(name).selectable(false).truncate().ui(ui);
+                            });
+
+                            false
+                        })
+                    })
+                    .inner;
+
+                // jump to the similar name so it can be resolved next
+                if let Some(Some(name)) = clicked {
+                    let action = BulkPageDirtyAction::SelectPig(Some(SelectedImportedPig::Pending(name)));
+                    self.warn_if_dirty(ui.ctx(), state, url, action);
+                }
+            }
         });
 
         // center panel with properties of the whole import and editor for the pending name
@@ -415,6 +1210,31 @@ impl BulkPageRender {
                 spaced_heading(ui, "In Progress");
                 self.import_properties_list(ui, state, is_admin);
 
+                let can_split = state.can_request(&yuri!(BULK_API_ROOT, "split"));
+                let can_close = state.can_request(&yuri!(BULK_API_ROOT, "close"));
+                if can_split || can_close {
+                    ui.add_space(SPACE_SMALL);
+                }
+
+                ui.horizontal(|ui| {
+                    // admins can divide big imports up between reviewers
+                    if can_split {
+                        let pending = state.pages.bulk.selected_import.as_ref().map_or(0, |i| i.pending.len());
+                        ui.add(DragValue::new(&mut self.split_parts).range(2..=pending.max(2)).suffix(" parts"));
+
+                        let split_button = Button::new("✂ Split");
+                        if ui.add_enabled(pending >= self.split_parts, split_button).clicked() {
+                            let id = state.pages.bulk.selected_import.as_ref().unwrap().id;
+                            self.bulk_api.split.request((id, self.split_parts));
+                        }
+                    }
+
+                    // for imports which won't be finished, so they stop showing up as in progress
+                    if can_close && ui.button("🗙 Close").on_hover_text("Close without finishing").clicked() {
+                        self.close_modal = true;
+                    }
+                });
+
                 // title for edit section
                 spaced_heading(ui, "Add Names");
 
@@ -434,29 +1254,32 @@ impl BulkPageRender {
                         .add_enabled(selected_is_pending && !state.pages.bulk.updated_name.is_empty(), add_button)
                         .clicked()
                     {
-                        self.create_pig.request(&state.pages.bulk.updated_name);
+                        self.accept_selected(state);
                     }
 
                     // Upon rejecting the name, submit a patch to remove it from the pending list and add it to the rejected list
                     let reject_button = Button::new("🗑 Reject");
                     if ui.add_enabled(selected_is_pending, reject_button).clicked() {
-                        match state.pages.bulk.selected_pig.as_ref().unwrap() {
-                            SelectedImportedPig::Pending(name) => {
-                                let patch = BulkPatch::new(&state.pages.bulk.selected_import.as_ref().unwrap().id)
-                                    .pending(PatchAction::REMOVE(name.to_owned()))
-                                    .rejected(PatchAction::ADD(name.to_owned()));
-                                self.bulk_api.patch.request(patch);
-                            }
-                            _ => {}
-                        }
+                        self.reject_selected(state);
                     }
 
                     let open_duplicate = Button::new("⮩ Go To Duplicate");
                     if ui.add_enabled(self.selected_duplicate.is_some(), open_duplicate).clicked() {
                         ui.ctx().open_url(OpenUrl::same_tab(
-                            "/pigs#".to_owned() + self.selected_duplicate.as_ref().unwrap().id.to_string().as_str(),
+                            "/pigs#".to_owned() + self.selected_duplicate.as_ref().unwrap().pig.id.to_string().as_str(),
                         ))
                     }
+
+                    // Reverts the last accept or reject
+                    let undo_button = Button::new("⟲ Undo");
+                    if ui.add_enabled(!self.history.is_empty(), undo_button).clicked() {
+                        self.undo(state);
+                    }
+
+                    // changes are shown right away, let the user know they haven't gone through yet
+                    if self.bulk_api.patch.is_pending() || self.undo_patch.is_pending() {
+                        ui.weak("Saving...");
+                    }
                 });
 
                 ui.add_space(SPACE_MEDIUM);
@@ -474,6 +1297,26 @@ impl BulkPageRender {
 
                 ui.add_space(SPACE_MEDIUM);
 
+                // narrow down the pending names to hunt for lookalikes from other alphabets
+                ui.horizontal(|ui| {
+                    ComboBox::from_id_salt("script_filter").selected_text(self.script_filter.label()).show_ui(
+                        ui,
+                        |ui| {
+                            ui.selectable_value(&mut self.script_filter, ScriptFilter::All, ScriptFilter::All.label());
+                            ui.selectable_value(
+                                &mut self.script_filter,
+                                ScriptFilter::Mixed,
+                                ScriptFilter::Mixed.label(),
+                            );
+                            for script in Script::ALL {
+                                ui.selectable_value(&mut self.script_filter, ScriptFilter::Only(script), script.name());
+                            }
+                        },
+                    );
+
+                    ui.checkbox(&mut self.sort_by_script, "Sort by script");
+                });
+
                 // forces the second table to take on a new id. there's an id conflict without this
                 // due to the two tables in the one vertical_centered ui
                 ui.push_id(69, |ui| {
@@ -499,7 +1342,8 @@ impl BulkPageRender {
                 let is_admin = state.has_role(Roles::BulkAdmin);
 
                 // Title
-                spaced_heading(ui, "Import Complete");
+                let closed = state.pages.bulk.selected_import.as_ref().is_some_and(|import| import.closed.is_some());
+                spaced_heading(ui, if closed { "Import Closed" } else { "Import Complete" });
 
                 // navigates to the currently selected pig in the right sidebar, assuming it was added
                 let go_to_selection = Button::new("⮩ Go To Pig");
@@ -514,6 +1358,29 @@ impl BulkPageRender {
                     ui.add_enabled(false, go_to_selection);
                 }
 
+                // moves the currently selected rejected name back to pending, reopening the import
+                if state.can_request(&yuri!(BULK_API_ROOT, "reopen")) {
+                    let reopen_button = Button::new("⟲ Reopen");
+                    if let Some(SelectedImportedPig::Rejected(name)) = state.pages.bulk.selected_pig.as_ref() {
+                        if ui.add(reopen_button).clicked() {
+                            if let Some(import) = state.pages.bulk.selected_import.as_ref() {
+                                self.bulk_api.reopen.request((import.id, &vec![name.to_owned()]));
+                            }
+                        }
+                    } else {
+                        ui.add_enabled(false, reopen_button);
+                    }
+                }
+
+                // picks a closed import back up where it was left off
+                if closed && state.can_request(&yuri!(BULK_API_ROOT, "close")) {
+                    if ui.button("▶ Resume").on_hover_text("Open the import again").clicked() {
+                        if let Some(import) = state.pages.bulk.selected_import.as_ref() {
+                            self.bulk_api.close.request((import.id, false));
+                        }
+                    }
+                }
+
                 // show the import properties
                 self.import_properties_list(ui, state, is_admin);
             });
@@ -523,6 +1390,9 @@ impl BulkPageRender {
     /// Adds a table with the [`BulkImport`] properties to the ui. Hides fields
     /// which the user should not see depending on their permission level
     pub fn import_properties_list(&mut self, ui: &mut Ui, state: &mut ClientState, is_admin: bool) {
+        let can_assign = state.can_request(&yuri!(BULK_API_ROOT, "assign"));
+        let settings = state.settings.clone();
+
         if let Some(import) = state.pages.bulk.selected_import.as_mut() {
             properties_list(ui).body(|mut body| {
                 add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "id", |ui| {
@@ -532,21 +1402,49 @@ impl BulkPageRender {
                 // creator is only relevant if the user can see imports which aren't theirs
                 if is_admin {
                     add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "created by", |ui| {
-                        // TODO actually bother fetching the user data
-                        ui.code(import.creator.to_string());
+                        username_label(ui, &mut state.usernames, &import.creator);
                     });
                 }
 
+                add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "assigned to", |ui| {
+                    let assignee_name = |id: &Uuid| {
+                        self.usernames.as_ref().and_then(|names| names.get(id)).cloned().unwrap_or(id.to_string())
+                    };
+                    let selected_name = import.assignee.as_ref().map(assignee_name).unwrap_or("Nobody".to_owned());
+
+                    // only admins can change who's reviewing an import
+                    if can_assign {
+                        let mut assignee = import.assignee;
+                        ComboBox::from_id_salt("bulk_assignee").selected_text(selected_name).show_ui(ui, |ui| {
+                            ui.selectable_value(&mut assignee, None, "Nobody");
+                            for (id, name) in self.usernames.as_ref().unwrap_or(&BTreeMap::new()) {
+                                ui.selectable_value(&mut assignee, Some(id.to_owned()), name);
+                            }
+                        });
+
+                        if assignee != import.assignee {
+                            self.bulk_api.assign.request((import.id, assignee));
+                        }
+                    } else {
+                        ui.label(selected_name);
+                    }
+                });
+
                 add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "started at", |ui| {
-                    let start_time = import.started.and_utc().with_timezone(&Local);
-                    ui.label(start_time.format(TIME_FMT).to_string());
+                    time_label(ui, import.started, &settings);
                 });
 
                 // only show finished time if we have it
                 if let Some(finished) = import.finished {
                     add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "finished at", |ui| {
-                        let finish_time = finished.and_utc().with_timezone(&Local);
-                        ui.label(finish_time.format(TIME_FMT).to_string());
+                        time_label(ui, finished, &settings);
+                    });
+                }
+
+                // only show closed time if we have it
+                if let Some(closed) = import.closed {
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "closed at", |ui| {
+                        time_label(ui, closed, &settings);
                     });
                 }
 
@@ -558,6 +1456,14 @@ impl BulkPageRender {
                     });
                 }
 
+                // names waiting on a moderator can't be processed here
+                let flagged = import.flagged.len();
+                if flagged > 0 {
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "flagged", |ui| {
+                        ui.label(flagged.to_string()).on_hover_text("Waiting for a moderator to review");
+                    });
+                }
+
                 add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "accepted", |ui| {
                     ui.label(import.accepted.len().to_string());
                 });
@@ -583,85 +1489,58 @@ impl BulkPageRender {
                 .column(Column::remainder())
                 .sense(Sense::click())
                 .cell_layout(Layout::left_to_right(Align::Center))
-                .body(|mut body| {
-                    // add the pending names
-                    import.pending.iter().for_each(|e| {
-                        body.row(TABLE_ROW_HEIGHT_SMALL, |mut row| {
-                            let selected = state.pages.bulk.selected_pig.as_ref().is_some_and(|sel| match sel {
-                                SelectedImportedPig::Pending(name) => name == e,
-                                _ => false,
-                            });
+                .body(|body| {
+                    // the list is pending names, then accepted pigs, then rejected names. only the
+                    // rows which are scrolled into view are laid out, so huge imports stay fast
+                    let pending = self.visible_pending(import);
+                    let accepted = self.accepted_pigs.as_deref().unwrap_or_default();
+                    let total = pending.len() + accepted.len() + import.rejected.len();
+
+                    body.rows(TABLE_ROW_HEIGHT_SMALL, total, |mut row| {
+                        let i = row.index();
+                        let item = if i < pending.len() {
+                            SelectedImportedPig::Pending(pending[i].to_owned())
+                        } else if i < pending.len() + accepted.len() {
+                            SelectedImportedPig::Accepted(accepted[i - pending.len()].to_owned())
+                        } else {
+                            SelectedImportedPig::Rejected(
+                                import.rejected[i - pending.len() - accepted.len()].to_owned(),
+                            )
+                        };
+
+                        let selected = state.pages.bulk.selected_pig.as_ref().is_some_and(|sel| sel.is_same(&item));
+                        row.set_selected(selected);
 
-                            row.set_selected(selected);
+                        // accepted pigs are green and rejected names are red
+                        let text = match &item {
+                            SelectedImportedPig::Pending(name) => RichText::new(name),
+                            SelectedImportedPig::Accepted(pig) => RichText::new(&pig.name).color(COLOR_ACCEPTED),
+                            SelectedImportedPig::Rejected(name) => RichText::new(name).color(COLOR_REJECTED),
+                        };
 
-                            // Make sure we can't select the text or else we can't click the row behind
-                            row.col(|ui| {
-                                Label::new(e).selectable(false).truncate().ui(ui);
-                            });
-
-                            if row.response().clicked() {
-                                if selected {
-                                    clicked = Some(None);
-                                } else {
-                                    clicked = Some(Some(SelectedImportedPig::Pending(e.clone())));
+                        // Make sure we can't select the text or else we can't click the row behind
+                        row.col(|ui| {
+                            match &item {
+                                SelectedImportedPig::Accepted(pig) => label_dot(ui, pig.label),
+                                SelectedImportedPig::Pending(name) if is_mixed_script(name) => {
+                                    Label::new(RichText::new("⚠").color(COLOR_WARNING))
+                                        .selectable(false)
+                                        .ui(ui)
+                                        .on_hover_text("Written in more than one script, this may be a lookalike");
                                 }
+                                _ => {}
                             }
-                        });
-                    });
 
-                    // add the accepted pigs with green name color
-                    if let Some(accepted) = self.accepted_pigs.as_ref() {
-                        accepted.iter().for_each(|e| {
-                            body.row(TABLE_ROW_HEIGHT_SMALL, |mut row| {
-                                let selected = state.pages.bulk.selected_pig.as_ref().is_some_and(|sel| match sel {
-                                    SelectedImportedPig::Accepted(pig) => pig.id == e.id,
-                                    _ => false,
-                                });
-
-                                row.set_selected(selected);
-
-                                // Make sure we can't select the text or else we can't click the row behind
-                                row.col(|ui| {
-                                    Label::new(RichText::new(&e.name).color(COLOR_ACCEPTED))
-                                        .selectable(false)
-                                        .truncate()
-                                        .ui(ui);
-                                });
-
-                                if row.response().clicked() {
-                                    if selected {
-                                        clicked = Some(None);
-                                    } else {
-                                        clicked = Some(Some(SelectedImportedPig::Accepted(e.clone())));
-                                    }
-                                }
-                            });
+                            Label::new(text).selectable(false).truncate().ui(ui);
                         });
-                    }
 
-                    // add the rejected names with red text color
-                    import.rejected.iter().for_each(|e| {
-                        body.row(TABLE_ROW_HEIGHT_SMALL, |mut row| {
-                            let selected = state.pages.bulk.selected_pig.as_ref().is_some_and(|sel| match sel {
-                                SelectedImportedPig::Rejected(name) => name == e,
-                                _ => false,
-                            });
-
-                            row.set_selected(selected);
-
-                            // Make sure we can't select the text or else we can't click the row behind
-                            row.col(|ui| {
-                                Label::new(RichText::new(e).color(COLOR_REJECTED)).selectable(false).truncate().ui(ui);
-                            });
-
-                            if row.response().clicked() {
-                                if selected {
-                                    clicked = Some(None);
-                                } else {
-                                    clicked = Some(Some(SelectedImportedPig::Rejected(e.clone())));
-                                }
+                        if row.response().clicked() {
+                            if selected {
+                                clicked = Some(None);
+                            } else {
+                                clicked = Some(Some(item));
                             }
-                        });
+                        }
                     });
                 });
 
@@ -684,6 +1563,60 @@ impl BulkPageRender {
             }
         }
 
+        if self.duplicate_import_modal {
+            let mut import_anyway = false;
+            let modal = Modal::new("duplicate_import")
+                .with_heading("Already Imported")
+                .with_body("These names were already imported recently. Are you sure you want to import them again?")
+                .show_with_extras(ctx, |ui| {
+                    if ui.button("✔ Import Anyway").clicked() {
+                        import_anyway = true;
+                    }
+                });
+
+            if import_anyway {
+                self.create_import(true);
+            }
+
+            if import_anyway || modal.should_close() {
+                self.duplicate_import_modal = false;
+            }
+        }
+
+        if self.close_modal {
+            let mut submit = false;
+            let pending = state.pages.bulk.selected_import.as_ref().map_or(0, |import| import.pending.len());
+
+            let modal = Modal::new("close_import")
+                .with_heading("Close Import")
+                .with_body(format!(
+                    "Are you sure you want to close this import without finishing it? It won't be listed as in \
+                    progress anymore, but its {} pending names are kept so it can be resumed later.",
+                    pending
+                ))
+                .show_with_extras(ctx, |ui| {
+                    ui.checkbox(&mut self.close_copy_pending, "Copy the pending names");
+                    if ui.button("✔ Yes").clicked() {
+                        submit = true;
+                    }
+                });
+
+            if submit {
+                if let Some(import) = state.pages.bulk.selected_import.as_ref() {
+                    // so they can be pasted into another import
+                    if self.close_copy_pending {
+                        ctx.copy_text(import.pending.join("\n"));
+                    }
+
+                    self.bulk_api.close.request((import.id, true));
+                }
+            }
+
+            if submit || modal.should_close() {
+                self.close_modal = false;
+            }
+        }
+
         if self.not_found_modal {
             if Modal::not_found(ctx) {
                 // Close the modal
@@ -695,18 +1628,237 @@ impl BulkPageRender {
         }
     }
 
-    /// Sends a fetch request for all [`BulkImport`]s the user can see and
-    /// clears the list of current results
-    fn query_imports(&mut self) {
-        self.all_imports = None;
-        self.bulk_api.fetch.request(&BulkQuery::default());
+    /// Handles the keyboard shortcuts for the edit screen. Nothing happens while
+    /// a text box has focus so typing in the name box doesn't trigger them.
+    /// - `Enter` accepts the selected pending name
+    /// - `Delete` rejects the selected pending name
+    /// - `Up`/`Down` move the selection through the list of names
+    /// - `Ctrl+Z` undoes the last action
+    fn handle_shortcuts(&mut self, ctx: &Context, state: &mut ClientState, url: &ParsedURL) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        let (accept, reject, up, down, undo) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Delete),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::COMMAND, Key::Z),
+            )
+        });
+
+        if accept {
+            self.accept_selected(state);
+        } else if reject {
+            self.reject_selected(state);
+        } else if up || down {
+            self.move_selection(ctx, state, url, if down { 1 } else { -1 });
+        } else if undo {
+            self.undo(state);
+        }
+    }
+
+    /// Submits a create request for the selected pending name using what's in
+    /// the edit box. Once the pig is created, the import is patched to match.
+    fn accept_selected(&mut self, state: &mut ClientState) {
+        let selected_is_pending =
+            state.pages.bulk.selected_pig.as_ref().is_some_and(|sel| matches!(sel, SelectedImportedPig::Pending(_)));
+
+        if selected_is_pending && !state.pages.bulk.updated_name.is_empty() {
+            self.create_pig.request(&state.pages.bulk.updated_name);
+        }
+    }
+
+    /// Submits a patch moving the selected pending name to the rejected list
+    fn reject_selected(&mut self, state: &mut ClientState) {
+        if let Some(import) = state.pages.bulk.selected_import.as_ref() {
+            if let Some(SelectedImportedPig::Pending(name)) = state.pages.bulk.selected_pig.as_ref() {
+                let patch = BulkPatch::new(&import.id, import.revision)
+                    .pending(PatchAction::REMOVE(name.to_owned()))
+                    .rejected(PatchAction::ADD(name.to_owned()));
+                let revision = import.revision;
+                self.submit_patch(state, patch, revision, false);
+            }
+        }
+    }
+
+    /// Submits the inverse of the last applied patch. Any pigs created by the
+    /// patch are deleted, since they shouldn't have been accepted.
+    fn undo(&mut self, state: &mut ClientState) {
+        // only undo patches made to the import we're looking at
+        let selected = state.pages.bulk.selected_import.as_ref();
+        if let Some(import) = selected.filter(|import| self.history.last().is_some_and(|patch| patch.id == import.id)) {
+            let patch = self.history.pop().unwrap();
+            let revision = import.revision;
+
+            self.delete_created_pigs(&patch, false);
+            self.submit_patch(state, patch.inverse(), revision, true);
+        }
+    }
+
+    /// Submits the patch against the given revision of its import. The patch
+    /// is kept so it can be reapplied if the import changed in the meantime.
+    fn submit_patch(&mut self, state: &mut ClientState, patch: BulkPatch, revision: i32, undo: bool) {
+        let patch = patch.with_revision(revision);
+        self.apply_patch_locally(state, &patch);
+
+        if undo {
+            self.sent_undo = Some(patch.clone());
+            self.undo_patch.request(patch);
+        } else {
+            self.sent_patch = Some(patch.clone());
+            self.bulk_api.patch.request(patch);
+        }
+    }
+
+    /// Whether any of our changes to the selected import are still on their
+    /// way to the server
+    fn is_patching(&self) -> bool {
+        self.confirmed_import.is_some()
+            || self.stale_patch.is_some()
+            || self.create_pig.is_pending()
+            || self.bulk_api.patch.is_pending()
+            || self.undo_patch.is_pending()
+    }
+
+    /// Fetches the latest version of the import the patch was rejected for, so
+    /// it can be reapplied once it's received
+    fn refetch_stale(&mut self, patch: Option<BulkPatch>, undo: bool) {
+        if let Some(patch) = patch {
+            self.fetch_stale_import.request(&BulkQuery::default().with_id(&patch.id).with_limit(1));
+            self.stale_patch = Some((patch, undo));
+        }
+    }
+
+    /// Moves the selection in the mixed list up or down by the given amount.
+    /// If nothing is selected, starts from the top of the list.
+    fn move_selection(&mut self, ctx: &Context, state: &mut ClientState, url: &ParsedURL, by: isize) {
+        let items = self.mixed_list_items(state);
+        if items.is_empty() {
+            return;
+        }
+
+        // find where the current selection is in the list, if anywhere
+        let current = state.pages.bulk.selected_pig.as_ref().and_then(|sel| items.iter().position(|e| sel.is_same(e)));
+
+        let next = match current {
+            Some(i) => (i as isize + by).clamp(0, items.len() as isize - 1) as usize,
+            None => 0,
+        };
+
+        if current != Some(next) {
+            self.warn_if_dirty(ctx, state, url, BulkPageDirtyAction::SelectPig(items.get(next).cloned()));
+        }
+    }
+
+    /// Lists every name in the selected import in the same order they're shown
+    /// by [`Self::selectable_mixed_list`]. Only pending names are filtered.
+    fn mixed_list_items(&self, state: &ClientState) -> Vec<SelectedImportedPig> {
+        let mut res = Vec::new();
+
+        if let Some(import) = state.pages.bulk.selected_import.as_ref() {
+            res.extend(self.visible_pending(import).into_iter().map(|e| SelectedImportedPig::Pending(e.to_owned())));
+
+            if let Some(accepted) = self.accepted_pigs.as_ref() {
+                res.extend(accepted.iter().map(|e| SelectedImportedPig::Accepted(e.to_owned())));
+            }
+
+            res.extend(import.rejected.iter().map(|e| SelectedImportedPig::Rejected(e.to_owned())));
+        }
+
+        res
+    }
+
+    /// The pending names in the import which match [`Self::script_filter`],
+    /// grouped by script if [`Self::sort_by_script`] is set. Names in the same
+    /// script are left in the order they were imported.
+    fn visible_pending<'a>(&self, import: &'a BulkImport) -> Vec<&'a String> {
+        let mut res: Vec<&String> = import.pending.iter().filter(|name| self.script_filter.matches(name)).collect();
+
+        if self.sort_by_script {
+            res.sort_by_cached_key(|name| dominant_script(name));
+        }
+
+        res
+    }
+
+    /// Sends a fetch request for all [`BulkImport`]s the user can see which
+    /// match the sidebar tab and filter, and replaces the list of current
+    /// results with any cached ones
+    fn query_imports(&mut self, state: &ClientState) {
+        let query = state.pages.bulk.sidebar_query();
+        let key = query.to_yuri();
+
+        // Show the last results for this tab right away, if we have them, while the fresh ones load
+        self.all_imports = state.cache.imports.get(&key).map(|mut imports| {
+            imports.reverse(); // show newest first
+            imports
+        });
+        self.fetched_imports_query = Some(key);
+        self.bulk_api.fetch.request(&query.with_creator_username());
+    }
+
+    /// Applies changes someone else made to the imports shown in the sidebar.
+    /// Only the imports which were created or updated are fetched again
+    /// instead of the whole list.
+    fn reconcile(&mut self, state: &mut ClientState, changes: Reconciliation) {
+        state.cache.imports.reconcile(&changes);
+
+        let imports = match self.all_imports.as_mut() {
+            Some(imports) if !changes.refetch => imports,
+            _ => return self.refresh_imports(state),
+        };
+
+        changes.remove_deleted(imports);
+
+        // anything still being fetched from before is fetched again with these
+        self.changed_imports.extend(changes.changed);
+        if !self.changed_imports.is_empty() {
+            let ids: Vec<Uuid> = self.changed_imports.iter().cloned().collect();
+            let query = state.pages.bulk.sidebar_query().with_ids(&ids).with_offset(0).with_limit(ids.len() as u32);
+            self.fetch_changed_imports.request(&query.with_creator_username());
+        }
+    }
+
+    /// Sends the sidebar query again without replacing the imports shown, for
+    /// when they may have been changed by someone else
+    fn refresh_imports(&mut self, state: &ClientState) {
+        let query = state.pages.bulk.sidebar_query();
+        self.fetched_imports_query = Some(query.to_yuri());
+        self.bulk_api.fetch.request(&query.with_creator_username());
+    }
+
+    /// Finds the other pending names similar to the selected one, if it's
+    /// changed since the last time they were found
+    fn update_similar_pending(&mut self, state: &ClientState) {
+        let selected = match state.pages.bulk.selected_pig.as_ref() {
+            Some(SelectedImportedPig::Pending(name)) => Some(name),
+            _ => None,
+        };
+
+        if self.similar_to.as_ref() != selected {
+            self.similar_to = selected.cloned();
+            self.similar_pending = match (state.pages.bulk.selected_import.as_ref(), selected) {
+                (Some(import), Some(name)) => import.similar_pending(name),
+                _ => Vec::new(),
+            };
+        }
+    }
+
+    /// Submits the pasted names as a new import. If `force` is true, it's
+    /// created even if the same names were imported recently.
+    fn create_import(&mut self, force: bool) {
+        let names = self.raw_names.lines().map(|l: &str| l.to_string()).collect::<Vec<String>>();
+        self.bulk_api.create.request((&names, force));
     }
 
     /// Sends a fetch request for all duplicates of the currently selected
     /// pending name and clears the list of current results
     fn query_duplicates(&mut self, state: &mut ClientState) {
         self.duplicate_pigs = None;
-        self.fetch_duplicate_pigs.request(PigQuery::default().with_name(&state.pages.bulk.updated_name));
+        self.bulk_api.duplicates.request(state.pages.bulk.updated_name.as_str());
     }
 
     /// Clears the list of data for accepted pigs in this [`BulkImport`] and
@@ -720,6 +1872,32 @@ impl BulkPageRender {
         }
     }
 
+    /// Replaces the selected import with a newer version of it. If the
+    /// selected name isn't in the list it was selected from anymore, e.g.
+    /// because someone else took care of it, it's deselected.
+    fn refresh_selected_import(&mut self, state: &mut ClientState, import: BulkImport) {
+        let page = &mut state.pages.bulk;
+        let accepted_changed = page.selected_import.as_ref().is_none_or(|sel| sel.accepted != import.accepted);
+
+        let still_listed = match page.selected_pig.as_ref() {
+            Some(SelectedImportedPig::Pending(name)) => import.pending.contains(name),
+            Some(SelectedImportedPig::Accepted(pig)) => import.accepted.contains(&pig.id),
+            Some(SelectedImportedPig::Rejected(name)) => import.rejected.contains(name),
+            None => true,
+        };
+
+        if !still_listed {
+            page.selected_pig = None;
+            page.updated_name = String::default();
+            page.dirty = false;
+        }
+
+        page.selected_import = Some(import);
+        if accepted_changed {
+            self.update_accepted_pigs(state);
+        }
+    }
+
     /// If the dirty var is true, warn the user with a modal before performing
     /// the given action; otherwise, just do it
     fn warn_if_dirty(&mut self, ctx: &Context, state: &mut ClientState, url: &ParsedURL, action: BulkPageDirtyAction) {
@@ -737,6 +1915,11 @@ impl BulkPageRender {
     fn do_dirty_action(&mut self, ctx: &Context, state: &mut ClientState, url: &ParsedURL) {
         match &self.dirty_modal {
             BulkPageDirtyAction::SelectImport(selection) => {
+                // Undo history only applies to the import it was made in
+                if selection.as_ref().map(|sel| sel.id) != state.pages.bulk.selected_import.as_ref().map(|sel| sel.id) {
+                    self.history.clear();
+                }
+
                 // Change the selection
                 state.pages.bulk.selected_import = selection.clone();
                 state.pages.bulk.selected_pig = None;
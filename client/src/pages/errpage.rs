@@ -4,6 +4,7 @@ use crate::ui::spaced_heading;
 use crate::ui::style::{PANEL_WIDTH_SMALL, SPACE_LARGE, SPACE_MEDIUM};
 use egui::{Button, CentralPanel, OpenUrl, Ui};
 use egui_flex::{item, Flex, FlexJustify};
+use pigweb_common::users::Roles;
 use urlable::ParsedURL;
 
 /// Responsible for rendering [`crate::pages::Routes::NotFound`] (and possibly
@@ -14,6 +15,9 @@ pub struct ErrPageRender {
 
     /// A further explanation of the error
     body: String,
+
+    /// For 403 forbidden errors, the roles which would give the user access
+    roles: Vec<Roles>,
 }
 
 impl Default for ErrPageRender {
@@ -24,6 +28,11 @@ impl Default for ErrPageRender {
 
 impl RenderPage for ErrPageRender {
     fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, _url: &ParsedURL) {
+        // Nobody has any roles until they're signed in, the layout asks them to do that instead
+        if !self.roles.is_empty() && state.authorized.is_none() {
+            return;
+        }
+
         CentralPanel::default().frame(egui::Frame::NONE).show_inside(ui, |ui| {
             ui.vertical_centered(|ui| {
                 ui.set_width(PANEL_WIDTH_SMALL);
@@ -31,6 +40,17 @@ impl RenderPage for ErrPageRender {
                 spaced_heading(ui, self.head.as_str());
 
                 ui.label(self.body.as_str());
+
+                // Tell the user what to ask for
+                if !self.roles.is_empty() {
+                    let roles = self.roles.iter().map(|role| format!("{:?}", role)).collect::<Vec<String>>();
+                    ui.add_space(SPACE_MEDIUM);
+                    ui.weak(format!(
+                        "If you need access, ask an admin to add you to a group with the {} role.",
+                        roles.join(" or ")
+                    ));
+                }
+
                 ui.add_space(SPACE_MEDIUM);
 
                 ui.separator();
@@ -53,7 +73,17 @@ impl RenderPage for ErrPageRender {
 
 impl ErrPageRender {
     /// Creates a renderer for 404 not found errors
-    fn not_found() -> Self {
-        Self { head: "Page Not Found".to_owned(), body: "That pig is in another castle!".to_owned() }
+    pub fn not_found() -> Self {
+        Self { head: "Page Not Found".to_owned(), body: "That pig is in another castle!".to_owned(), roles: Vec::new() }
+    }
+
+    /// Creates a renderer for 403 forbidden errors, where any of the given
+    /// roles would give the user access
+    pub fn forbidden(roles: &[Roles]) -> Self {
+        Self {
+            head: "Forbidden".to_owned(),
+            body: "This pen is off limits, you don't have permission to see this page.".to_owned(),
+            roles: roles.to_vec(),
+        }
     }
 }
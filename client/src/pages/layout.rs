@@ -1,41 +1,228 @@
-use crate::data::api::{ApiError, AuthApi, Status};
+use crate::data::api::{
+    requests_in_flight, take_expired_requests, ApiError, AuthApi, BulkFetchHandler, NotificationApi, PigFetchHandler,
+    ReplayApi, ReplayRequest, Severity, Status, UserFetchHandler,
+};
+use crate::data::persist::Persisted;
 use crate::data::state::ClientState;
+use crate::pages::switcher::SwitcherRender;
 use crate::pages::{RenderPage, Routes};
 use crate::ui::modal::Modal;
-use crate::ui::spaced_heading;
-use crate::ui::style::{COLOR_REJECTED, SPACE_SMALL};
+use crate::ui::style::{
+    COLOR_REJECTED, COLOR_WARNING, PANEL_WIDTH_MEDIUM, PANEL_WIDTH_SMALL, SPACE_MEDIUM, SPACE_SMALL,
+};
+use crate::ui::time::{absolute_time, time_label};
+use chrono::{Local, Utc};
 use eframe::emath::Align;
-use egui::{Button, Context, MenuBar, OpenUrl, Panel, RichText, Ui, ViewportCommand};
-use pigweb_common::users::Roles;
+use egui::{
+    Align2, Area, Button, Color32, Context, Frame, Id, Image, Label, MenuBar, OpenUrl, Order, Panel, RichText,
+    ScrollArea, Sense, Spinner, Ui, ViewportCommand, Window,
+};
+use log::{error, info};
+use pigweb_common::bulk::{BulkQuery, BulkStatus};
+use pigweb_common::errors::ErrorCode;
+use pigweb_common::instance::ProviderHealth;
+use pigweb_common::notifications::{Notification, NotificationKind};
+use pigweb_common::pigs::PigQuery;
+use pigweb_common::users::{Roles, UserQuery};
 use pigweb_common::{yuri, AUTH_API_ROOT};
+use std::collections::{BTreeSet, VecDeque};
+use std::time::Duration;
 use urlable::ParsedURL;
+use uuid::Uuid;
+
+/// How long transient errors are shown before they're dismissed, in seconds
+const TOAST_TIMEOUT: f64 = 6.0;
+
+/// The most errors shown at once, any others are counted instead
+const TOAST_MAX: usize = 3;
+
+/// The most errors kept in the error log, older ones are forgotten
+const ERROR_LOG_MAX: usize = 100;
+
+/// How often to check whether any of the user's imports have finished, in
+/// seconds
+const IMPORT_CHECK_INTERVAL: f64 = 60.0;
+
+/// How often to check for new notifications, in seconds
+const NOTIFICATION_CHECK_INTERVAL: f64 = 60.0;
+
+/// How often to ask whether the OIDC provider can be reached, in seconds. The
+/// server only checks once a minute itself.
+const OIDC_CHECK_INTERVAL: f64 = 60.0;
+
+/// How long before the user's session expires to renew it, in seconds
+const SESSION_RENEW_BEFORE: i64 = 300;
+
+/// How long to wait before trying to renew the session again if it failed, in
+/// seconds
+const SESSION_RENEW_RETRY: f64 = 60.0;
+
+/// The longest we wait to check whether the session should be renewed, in
+/// seconds. Browsers can't wait much longer than this, and the system user's
+/// session doesn't expire for thousands of years.
+const SESSION_CHECK_MAX: i64 = 60 * 60 * 24;
 
 /// Persistent data storage for the common layout
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct Layout {
-    /// The error message currently on display, if any
+    /// The error messages currently on display, both global and those scoped
+    /// to the current page
     #[serde(skip)]
     pub display_error: Vec<ApiError>,
+
+    /// Every recent error, including dismissed ones, so they can be reported
+    #[serde(skip)]
+    pub error_log: VecDeque<ApiError>,
+
+    /// Changes which failed because the user's session expired. These are
+    /// saved so they survive signing back in, then are sent again in order.
+    pub replay: Vec<ReplayRequest>,
+
+    /// The user's own imports which weren't finished when we last checked.
+    /// These are saved so the user is still told about imports which finished
+    /// while they were away.
+    pub unfinished_imports: BTreeSet<Uuid>,
+}
+
+impl Layout {
+    /// Shows the given error. If the same error is already on display, it's
+    /// counted again instead so repeated failures don't pile up.
+    pub fn push_error(&mut self, err: ApiError) {
+        if self.error_log.len() >= ERROR_LOG_MAX {
+            self.error_log.pop_front();
+        }
+        self.error_log.push_back(err.clone());
+
+        match self.display_error.iter_mut().find(|shown| shown.is_same(&err)) {
+            Some(shown) => {
+                shown.repeats += 1;
+                shown.last_seen = err.last_seen;
+                shown.request_id = err.request_id.or(shown.request_id);
+                shown.shown_at = None; // restart the timer for transient errors
+            }
+            None => self.display_error.push(err),
+        }
+    }
+
+    /// Dismisses all errors which came from the given page, any global errors
+    /// are left alone
+    pub fn close_scope(&mut self, scope: Routes) {
+        self.display_error.retain(|err| err.scope != Some(scope));
+    }
 }
 
 impl Default for Layout {
     fn default() -> Self {
-        Self { display_error: Vec::new() }
+        Self {
+            display_error: Vec::new(),
+            error_log: VecDeque::new(),
+            replay: Vec::new(),
+            unfinished_imports: BTreeSet::new(),
+        }
     }
 }
 
+impl Persisted for Layout {
+    const KEY: &'static str = "pigweb.layout";
+    const VERSION: u32 = 1;
+}
+
 /// The renderer for the main layout. This is run before the current route
 /// renderer and holds any elements common to all pages.
 pub struct LayoutRender {
     /// API used to check whether the user is signed in upon first loading the
     /// page.
     auth_api: AuthApi,
+
+    /// API used to resend changes which failed due to an expired session
+    replay_api: ReplayApi,
+
+    /// Whether we're waiting on a replayed change
+    replaying: bool,
+
+    /// Whether the error log window is open
+    show_error_log: bool,
+
+    /// Fetches the pigs page's results ahead of time
+    prefetch_pigs: PigFetchHandler,
+
+    /// Fetches the import page's sidebar ahead of time
+    prefetch_imports: BulkFetchHandler,
+
+    /// Fetches every username ahead of time
+    prefetch_users: UserFetchHandler,
+
+    /// The query the pigs are being prefetched with
+    prefetch_pig_query: Option<PigQuery>,
+
+    /// The query the imports are being prefetched with
+    prefetch_import_query: Option<BulkQuery>,
+
+    /// Whether we've prefetched data since the app was loaded
+    prefetched: bool,
+
+    /// Fetches the user's unfinished imports, to find any which have finished
+    unfinished_imports: BulkFetchHandler,
+
+    /// Fetches the imports which are no longer unfinished, to tell the user
+    /// how they went
+    finished_imports: BulkFetchHandler,
+
+    /// When we last checked whether the user's imports have finished, in
+    /// seconds since the app was loaded
+    last_import_check: Option<f64>,
+
+    /// Whether the OIDC provider could be reached the last time the server
+    /// checked, only fetched for admins
+    oidc_health: Option<ProviderHealth>,
+
+    /// When we last asked whether the OIDC provider can be reached, in
+    /// seconds since the app was loaded
+    last_oidc_check: Option<f64>,
+
+    /// When we last tried to renew the user's session, in seconds since the
+    /// app was loaded
+    last_session_renewal: Option<f64>,
+
+    /// API used to fetch the user's notifications and mark them as read
+    notification_api: NotificationApi,
+
+    /// The user's most recent notifications, newest first
+    notifications: Vec<Notification>,
+
+    /// When we last checked for new notifications, in seconds since the app
+    /// was loaded
+    last_notification_check: Option<f64>,
+
+    /// Searches pigs, imports, and users from any page
+    switcher: SwitcherRender,
 }
 
 impl Default for LayoutRender {
     fn default() -> Self {
-        Self { auth_api: AuthApi::default() }
+        Self {
+            auth_api: AuthApi::default(),
+            replay_api: ReplayApi::default(),
+            replaying: false,
+            show_error_log: false,
+            prefetch_pigs: PigFetchHandler::default(),
+            prefetch_imports: BulkFetchHandler::default(),
+            prefetch_users: UserFetchHandler::default(),
+            prefetch_pig_query: None,
+            prefetch_import_query: None,
+            prefetched: false,
+            unfinished_imports: BulkFetchHandler::default(),
+            finished_imports: BulkFetchHandler::default(),
+            last_import_check: None,
+            oidc_health: None,
+            last_oidc_check: None,
+            last_session_renewal: None,
+            notification_api: NotificationApi::default(),
+            notifications: Vec::new(),
+            last_notification_check: None,
+            switcher: SwitcherRender::default(),
+        }
     }
 }
 
@@ -43,23 +230,63 @@ impl RenderPage for LayoutRender {
     fn open(&mut self, _ctx: &Context, _state: &mut ClientState, _url: &ParsedURL) {
         // Check whether the user is logged in
         self.auth_api.is_authenticated.request(false); // this arg doesn't matter
+        self.auth_api.capabilities.request(false);
+        self.auth_api.branding.request(false);
+        self.auth_api.environment.request(false);
     }
 
     fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, _url: &ParsedURL) {
         // Handle all the incoming data
         self.process_promises(state);
+        self.check_imports(ui, state);
+        self.check_oidc_health(ui, state);
+        self.renew_session(ui, state);
+        self.check_notifications(ui, state);
         state.colorix.draw_background(ui.ctx(), false);
 
+        // make it impossible to miss that this isn't the real pig list
+        if let Some(environment) = state.environment.as_ref().filter(|environment| !environment.production) {
+            let color = environment.color.map(|[r, g, b]| Color32::from_rgb(r, g, b)).unwrap_or(COLOR_WARNING);
+            Panel::top("environment_banner")
+                .resizable(false)
+                .frame(Frame::NONE.fill(color).inner_margin(SPACE_SMALL))
+                .show_inside(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(RichText::new(environment.name.to_uppercase()).strong().color(Color32::BLACK));
+                    });
+                });
+        }
+
+        // tell admins why nobody can sign in before they start getting asked
+        if let Some(health) = self.oidc_health.as_ref().filter(|health| !health.available) {
+            let message = format!(
+                "The sign-in provider couldn't be reached as of {}, users may not be able to sign in. {}",
+                absolute_time(health.checked, &state.settings),
+                health.error.as_deref().unwrap_or_default()
+            );
+
+            Panel::top("oidc_banner")
+                .resizable(false)
+                .frame(Frame::NONE.fill(COLOR_REJECTED).inner_margin(SPACE_SMALL))
+                .show_inside(ui, |ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.label(RichText::new(message).strong().color(Color32::BLACK));
+                    });
+                });
+        }
+
         Panel::top("top_panel").resizable(false).show_inside(ui, |ui| {
             MenuBar::new().ui(ui, |ui| {
                 self.populate_menu(ui, state);
             });
         });
 
-        // show error banner, if we have one
+        // show error toasts, if we have any
         self.display_error(ui, state);
+        self.show_error_log(ui.ctx(), state);
 
         self.show_modals(ui.ctx(), state);
+        self.switcher.show(ui.ctx(), state);
     }
 }
 
@@ -74,10 +301,305 @@ impl LayoutRender {
                     state.pages.layout.display_error.clear();
                 }
 
-                // save the authorized state
+                // save the authorized state, then find out who the user is
+                if authorized.is_some() {
+                    self.auth_api.me.request(false);
+                } else {
+                    state.user = None;
+                }
                 state.authorized = authorized;
+
+                // get a head start on the data other pages need
+                if state.authorized.is_some() && !self.prefetched {
+                    self.prefetch(state);
+                }
+            }
+            Status::Errored(err) => state.pages.layout.push_error(err),
+            Status::Pending => {}
+        }
+
+        if let Some(capabilities) = self.auth_api.capabilities.received(state) {
+            state.capabilities = Some(capabilities);
+        }
+
+        if let Some(branding) = self.auth_api.branding.received(state) {
+            state.branding = Some(branding);
+        }
+
+        if let Some(environment) = self.auth_api.environment.received(state) {
+            state.environment = Some(environment);
+        }
+
+        if let Some(user) = self.auth_api.me.received(state) {
+            state.user = Some(user);
+        }
+
+        self.process_prefetched(state);
+        self.process_imports(state);
+
+        // Save any changes which failed because the session expired
+        state.pages.layout.replay.append(&mut take_expired_requests());
+
+        if self.replaying {
+            match self.replay_api.replay.resolve() {
+                Status::Received(_) => {
+                    info!("Replayed change after signing back in: {:?}", state.pages.layout.replay.first());
+                    state.pages.layout.replay.remove(0);
+                    self.replaying = false;
+                }
+                // the session expired again, the change is already queued again so drop this copy
+                Status::Errored(err) if err.kind == Some(ErrorCode::Unauthorized) => {
+                    state.pages.layout.replay.remove(0);
+                    state.authorized = None;
+                    self.replaying = false;
+                }
+                Status::Errored(err) => {
+                    state.pages.layout.replay.remove(0);
+                    state.pages.layout.push_error(err.with_reason("Unable to replay change".to_owned()));
+                    self.replaying = false;
+                }
+                Status::Pending => {}
+            }
+        }
+
+        // Once signed back in, send the oldest change again
+        if !self.replaying && state.authorized.is_some() {
+            if let Some(req) = state.pages.layout.replay.first() {
+                self.replay_api.replay.request(req);
+                self.replaying = true;
+            }
+        }
+    }
+
+    /// Requests the data each page starts with all at once, so it's ready by
+    /// the time the user opens the page. The current page is skipped since it
+    /// has already requested its own.
+    fn prefetch(&mut self, state: &ClientState) {
+        self.prefetched = true;
+
+        if state.route != Routes::Pigs && state.has_role(Roles::PigViewer) {
+            let query = state.pages.pigs.search_query();
+            self.prefetch_pigs.request(query.clone());
+            self.prefetch_pig_query = Some(query);
+        }
+
+        if state.route != Routes::Bulk && (state.has_role(Roles::BulkEditor) || state.has_role(Roles::BulkAdmin)) {
+            self.prefetch_imports.request(&state.pages.bulk.sidebar_query().with_creator_username());
+            self.prefetch_import_query = Some(state.pages.bulk.sidebar_query());
+        }
+
+        self.prefetch_users.request(UserQuery::default());
+    }
+
+    /// Saves prefetched data for pages to pick up. Errors are only logged
+    /// since the page will just request the data again when it's opened.
+    fn process_prefetched(&mut self, state: &mut ClientState) {
+        match self.prefetch_pigs.resolve() {
+            Status::Received(pigs) => {
+                let pigs = state.usernames.take_creators(pigs, |pig| pig.creator);
+
+                // if the page was opened while we were waiting, it already has its own results
+                let query = self.prefetch_pig_query.take();
+                if state.route != Routes::Pigs {
+                    state.prefetched.pigs = query.map(|query| (query, pigs));
+                }
+            }
+            Status::Errored(err) => error!("Unable to prefetch pigs: {:?}", err),
+            Status::Pending => {}
+        }
+
+        match self.prefetch_imports.resolve() {
+            Status::Received(imports) => {
+                let imports = state.usernames.take_creators(imports, |import| import.creator);
+
+                let query = self.prefetch_import_query.take();
+                if state.route != Routes::Bulk {
+                    state.prefetched.imports = query.map(|query| (query, imports));
+                }
+            }
+            Status::Errored(err) => error!("Unable to prefetch imports: {:?}", err),
+            Status::Pending => {}
+        }
+
+        match self.prefetch_users.resolve() {
+            Status::Received(res) => {
+                if let Some(usernames) = res.usernames {
+                    state.usernames.extend(&usernames);
+                    state.prefetched.usernames = Some(usernames);
+                }
+            }
+            Status::Errored(err) => error!("Unable to prefetch usernames: {:?}", err),
+            Status::Pending => {}
+        }
+    }
+
+    /// Checks whether any of the user's imports have finished every
+    /// [`IMPORT_CHECK_INTERVAL`] seconds, so they don't have to keep the
+    /// import page open to find out
+    fn check_imports(&mut self, ui: &Ui, state: &ClientState) {
+        if state.authorized.is_none() || !(state.has_role(Roles::BulkEditor) || state.has_role(Roles::BulkAdmin)) {
+            return;
+        }
+
+        let now = ui.input(|i| i.time);
+        if let Some(last) = self.last_import_check {
+            let waited = now - last;
+            if waited < IMPORT_CHECK_INTERVAL {
+                // make sure we come back to check even if the user isn't doing anything
+                ui.ctx().request_repaint_after(Duration::from_secs_f64(IMPORT_CHECK_INTERVAL - waited));
+                return;
+            }
+        }
+
+        self.last_import_check = Some(now);
+        self.unfinished_imports.request(&BulkQuery::default().with_mine(true).with_status(BulkStatus::InProgress));
+    }
+
+    /// Asks whether the OIDC provider can be reached every
+    /// [`OIDC_CHECK_INTERVAL`] seconds, if the user is allowed to know
+    fn check_oidc_health(&mut self, ui: &Ui, state: &mut ClientState) {
+        if state.authorized.is_none() || !state.can_request("/api/oidc/health") {
+            self.oidc_health = None;
+            return;
+        }
+
+        match self.auth_api.oidc_health.resolve() {
+            Status::Received(health) => self.oidc_health = health,
+            Status::Errored(err) => error!("Unable to check whether the OIDC provider is up: {:?}", err),
+            Status::Pending => {}
+        }
+
+        let now = ui.input(|i| i.time);
+        if let Some(last) = self.last_oidc_check {
+            let waited = now - last;
+            if waited < OIDC_CHECK_INTERVAL {
+                // make sure we come back to check even if the user isn't doing anything
+                ui.ctx().request_repaint_after(Duration::from_secs_f64(OIDC_CHECK_INTERVAL - waited));
+                return;
+            }
+        }
+
+        self.last_oidc_check = Some(now);
+        self.auth_api.oidc_health.request(false); // this arg doesn't matter
+    }
+
+    /// Renews the user's session [`SESSION_RENEW_BEFORE`] seconds before it
+    /// expires, so they aren't asked to sign in again while they're working.
+    /// Failures are only logged, the user can still sign in again once the
+    /// session does expire.
+    fn renew_session(&mut self, ui: &Ui, state: &mut ClientState) {
+        match self.auth_api.renew.resolve() {
+            Status::Received(session_exp) => {
+                info!("Renewed session, it now expires at {}", session_exp);
+                if let Some(user) = state.user.as_mut() {
+                    user.session_exp = Some(session_exp);
+                }
+            }
+            Status::Errored(err) => error!("Unable to renew session: {:?}", err),
+            Status::Pending => {}
+        }
+
+        let session_exp = state.user.as_ref().and_then(|user| user.session_exp);
+        let Some(session_exp) = session_exp.filter(|_| state.authorized.is_some()) else {
+            return;
+        };
+
+        // an expired session can't be renewed, the user has to sign in again
+        let remaining = (session_exp - Utc::now().naive_utc()).num_seconds();
+        if remaining <= 0 || self.auth_api.renew.is_pending() {
+            return;
+        }
+
+        if remaining > SESSION_RENEW_BEFORE {
+            // make sure we come back to renew it even if the user isn't doing anything
+            let wait = (remaining - SESSION_RENEW_BEFORE).min(SESSION_CHECK_MAX);
+            ui.ctx().request_repaint_after(Duration::from_secs(wait as u64));
+            return;
+        }
+
+        let now = ui.input(|i| i.time);
+        if let Some(last) = self.last_session_renewal {
+            let waited = now - last;
+            if waited < SESSION_RENEW_RETRY {
+                ui.ctx().request_repaint_after(Duration::from_secs_f64(SESSION_RENEW_RETRY - waited));
+                return;
             }
-            Status::Errored(err) => state.pages.layout.display_error.push(err),
+        }
+
+        self.last_session_renewal = Some(now);
+        self.auth_api.renew.request(false); // this arg doesn't matter
+    }
+
+    /// Checks for new notifications every [`NOTIFICATION_CHECK_INTERVAL`]
+    /// seconds, and right after any are marked as read. Errors are only logged
+    /// since we'll just check again later.
+    fn check_notifications(&mut self, ui: &Ui, state: &ClientState) {
+        if state.authorized.is_none() {
+            self.notifications.clear();
+            return;
+        }
+
+        match self.notification_api.list.resolve() {
+            Status::Received(notifications) => self.notifications = notifications,
+            Status::Errored(err) => error!("Unable to fetch notifications: {:?}", err),
+            Status::Pending => {}
+        }
+
+        match self.notification_api.read.resolve() {
+            Status::Received(_) => self.last_notification_check = None,
+            Status::Errored(err) => error!("Unable to mark notifications as read: {:?}", err),
+            Status::Pending => {}
+        }
+
+        let now = ui.input(|i| i.time);
+        if let Some(last) = self.last_notification_check {
+            let waited = now - last;
+            if waited < NOTIFICATION_CHECK_INTERVAL {
+                // make sure we come back to check even if the user isn't doing anything
+                ui.ctx().request_repaint_after(Duration::from_secs_f64(NOTIFICATION_CHECK_INTERVAL - waited));
+                return;
+            }
+        }
+
+        self.last_notification_check = Some(now);
+        self.notification_api.list.request(false); // this arg doesn't matter
+    }
+
+    /// Tells the user about any of their imports which finished since we last
+    /// checked. Errors are only logged since we'll just check again later.
+    fn process_imports(&mut self, state: &mut ClientState) {
+        match self.unfinished_imports.resolve() {
+            Status::Received(imports) => {
+                let unfinished: BTreeSet<Uuid> = imports.into_iter().map(|import| import.item.id).collect();
+                let done: Vec<Uuid> = state.pages.layout.unfinished_imports.difference(&unfinished).copied().collect();
+
+                // anything missing was either finished or deleted, find out which
+                if !done.is_empty() {
+                    self.finished_imports.request(&BulkQuery::default().with_ids(&done));
+                }
+                state.pages.layout.unfinished_imports = unfinished;
+            }
+            Status::Errored(err) => error!("Unable to check for unfinished imports: {:?}", err),
+            Status::Pending => {}
+        }
+
+        match self.finished_imports.resolve() {
+            Status::Received(imports) => {
+                for import in imports.into_iter().map(|import| import.item).filter(|import| import.finished.is_some()) {
+                    let description = format!(
+                        "\"{}\" finished with {} accepted and {} rejected.",
+                        import.name,
+                        import.accepted.len(),
+                        import.rejected.len()
+                    );
+                    state.pages.layout.push_error(
+                        ApiError::new(description)
+                            .with_reason("Import finished".to_owned())
+                            .with_severity(Severity::Info),
+                    );
+                }
+            }
+            Status::Errored(err) => error!("Unable to fetch finished imports: {:?}", err),
             Status::Pending => {}
         }
     }
@@ -86,6 +608,15 @@ impl LayoutRender {
     fn populate_menu(&mut self, ui: &mut Ui, state: &mut ClientState) {
         ui.add_space(SPACE_SMALL);
 
+        // Show which instance this is so test and prod can't be mixed up
+        if let Some(branding) = state.branding.as_ref() {
+            if let Some(logo) = branding.logo.as_ref() {
+                ui.add(Image::new(logo.as_str()).max_height(ui.spacing().interact_size.y));
+            }
+            ui.label(RichText::new(&branding.name).strong());
+            ui.separator();
+        }
+
         // Use the Colorix theme picker instead of egui's
         state.colorix.light_dark_toggle_button(ui, 14.0);
 
@@ -105,6 +636,13 @@ impl LayoutRender {
                     ui.ctx().open_url(OpenUrl::same_tab("/pigs"))
                 }
             }
+
+            let current = state.route == Routes::Tags;
+            if ui.add(Button::selectable(current, " 🏷 Tags ")).clicked() {
+                if !current {
+                    ui.ctx().open_url(OpenUrl::same_tab("/tags"))
+                }
+            }
             show_second_separator = true;
         }
         if state.has_role(Roles::BulkEditor) || state.has_role(Roles::BulkAdmin) {
@@ -116,6 +654,15 @@ impl LayoutRender {
             }
             show_second_separator = true;
         }
+        if state.has_role(Roles::Moderator) {
+            let current = state.route == Routes::Review;
+            if ui.add(Button::selectable(current, " ⚠ Review ")).clicked() {
+                if !current {
+                    ui.ctx().open_url(OpenUrl::same_tab("/review"))
+                }
+            }
+            show_second_separator = true;
+        }
         if state.has_role(Roles::LogViewer) {
             ui.add_enabled(false, Button::selectable(false, " 📄 Logs "));
             show_second_separator = true;
@@ -148,61 +695,303 @@ impl LayoutRender {
                 ui.ctx().send_viewport_cmd(ViewportCommand::Close);
             }
 
-            // Logout
-            if ui.button(" ⎆ ").clicked() {
+            self.populate_user_menu(ui, state);
+            self.populate_notification_menu(ui, state);
+
+            if ui
+                .add(Button::selectable(self.switcher.open, " 🔍 "))
+                .on_hover_text("Search everything (Ctrl+K)")
+                .clicked()
+            {
+                self.switcher.toggle();
+            }
+
+            // Let users on slow connections know we're still working
+            let in_flight = requests_in_flight();
+            if in_flight > 0 {
+                ui.add(Spinner::new()).on_hover_text(format!("Waiting on {} request(s)", in_flight));
+            }
+        });
+    }
+
+    /// Shows who's signed in with a dropdown for everything about their
+    /// account, including signing out
+    fn populate_user_menu(&mut self, ui: &mut Ui, state: &ClientState) {
+        let title = match state.user.as_ref() {
+            Some(user) => format!(" 👤 {} ", user.username),
+            None => " 👤 Account ".to_owned(),
+        };
+
+        ui.menu_button(title, |ui| {
+            // Profile
+            if let Some(user) = state.user.as_ref() {
+                ui.strong(&user.username);
+                let groups = if user.groups.is_empty() { "No groups".to_owned() } else { user.groups.join(", ") };
+                ui.weak(groups);
+
+                if let Some(roles) = state.authorized.as_ref() {
+                    let roles = roles.iter().map(|role| format!("{:?}", role)).collect::<Vec<String>>().join(", ");
+                    ui.weak(roles).on_hover_text("Roles");
+                }
+
+                ui.separator();
+            }
+
+            if ui.button("⛭ Settings").clicked() {
+                ui.ctx().open_url(OpenUrl::same_tab("/settings"));
+                ui.close();
+            }
+
+            if ui.add(Button::selectable(self.show_error_log, "📋 Error log")).clicked() {
+                self.show_error_log = !self.show_error_log;
+                ui.close();
+            }
+
+            ui.separator();
+
+            if ui.button("⎆ Sign out").clicked() {
                 ui.ctx().open_url(OpenUrl::same_tab(yuri!(AUTH_API_ROOT, "/oidc/logout/")));
             }
         });
     }
 
-    /// Display all errors as a banner at the top of the page
-    fn display_error(&mut self, ui: &mut Ui, state: &mut ClientState) {
-        // items which should be removed, borrow check doesn't like it in the for loop
-        let mut remove = Vec::new();
-
-        for (i, err) in state.pages.layout.display_error.iter().enumerate() {
-            let heading = err.reason.as_ref().unwrap_or(&"Error".to_owned()).to_owned();
-            let heading_with_code = match err.code {
-                Some(code) => format!("{} {}", code, heading),
-                None => heading,
-            };
+    /// Shows a bell with how many notifications are unread, with a dropdown
+    /// listing them. Clicking one marks it as read and opens what it's about.
+    fn populate_notification_menu(&mut self, ui: &mut Ui, state: &ClientState) {
+        if state.authorized.is_none() {
+            return;
+        }
+
+        let unread = self.notifications.iter().filter(|notification| notification.read.is_none()).count();
+        let title = match unread {
+            0 => " 🔔 ".to_owned(),
+            count => format!(" 🔔 {} ", count),
+        };
+
+        ui.menu_button(title, |ui| {
+            ui.set_max_width(PANEL_WIDTH_SMALL);
+
+            ui.horizontal(|ui| {
+                ui.strong("Notifications");
+                if ui.add_enabled(unread > 0, Button::new("✔ Mark all read")).clicked() {
+                    let now = Utc::now().naive_utc();
+                    self.notifications.iter_mut().for_each(|notification| {
+                        notification.read.get_or_insert(now);
+                    });
+                    self.notification_api.read.request(None);
+                }
+            });
 
-            Panel::top(format!("error_panel_{:?}", i)).resizable(false).show_inside(ui, |ui| {
-                MenuBar::new().ui(ui, |ui| {
-                    state.colorix.draw_background(ui.ctx(), true);
+            ui.separator();
 
-                    // add error message
-                    spaced_heading(ui, RichText::new(heading_with_code).color(COLOR_REJECTED).strong());
+            if self.notifications.is_empty() {
+                ui.weak("Nothing yet.");
+                return;
+            }
+
+            let mut clicked = None;
+            ScrollArea::vertical().show(ui, |ui| {
+                for (i, notification) in self.notifications.iter().enumerate() {
+                    let text = match notification.read {
+                        Some(_) => RichText::new(&notification.message),
+                        None => RichText::new(&notification.message).strong(),
+                    };
+
+                    if ui.add(Label::new(text).sense(Sense::click())).clicked() {
+                        clicked = Some(i);
+                    }
+                    time_label(ui, notification.created, &state.settings);
                     ui.separator();
-                    ui.label(RichText::new(err.description.as_str()).color(COLOR_REJECTED));
+                }
+            });
+
+            if let Some(notification) = clicked.and_then(|i| self.notifications.get_mut(i)) {
+                if notification.read.is_none() {
+                    notification.read = Some(Utc::now().naive_utc());
+                    self.notification_api.read.request(Some(notification.id));
+                }
+
+                if let Some(url) = notification_url(notification) {
+                    ui.ctx().open_url(OpenUrl::same_tab(url));
+                }
+                ui.close();
+            }
+        });
+    }
+
+    /// Display the newest errors as toasts in the corner of the page
+    fn display_error(&mut self, ui: &mut Ui, state: &mut ClientState) {
+        let now = ui.input(|i| i.time);
+        let errors = &mut state.pages.layout.display_error;
+
+        // dismiss transient errors once they've been on screen long enough
+        errors.retain(|err| !err.is_transient() || err.shown_at.is_none_or(|shown| now - shown < TOAST_TIMEOUT));
+        if errors.is_empty() {
+            return;
+        }
 
-                    // right align dismiss button
-                    ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
-                        if ui.button(" 🗙 ").clicked() {
-                            remove.push(i);
+        // only the newest errors are shown, the rest wait until those are dismissed
+        let hidden = errors.len().saturating_sub(TOAST_MAX);
+        let mut remove = None;
+
+        Area::new(Id::new("toasts"))
+            .anchor(Align2::RIGHT_BOTTOM, [-SPACE_MEDIUM, -SPACE_MEDIUM])
+            .order(Order::Foreground)
+            .show(ui.ctx(), |ui| {
+                ui.set_max_width(PANEL_WIDTH_SMALL);
+
+                if hidden > 0 {
+                    ui.weak(format!("{} more...", hidden));
+                }
+
+                for (i, err) in errors.iter_mut().enumerate().skip(hidden) {
+                    err.shown_at.get_or_insert(now);
+
+                    let color = match err.severity {
+                        Severity::Info => ui.visuals().text_color(),
+                        Severity::Warning => COLOR_WARNING,
+                        Severity::Error => COLOR_REJECTED,
+                    };
+
+                    let heading = err.reason.as_ref().unwrap_or(&"Error".to_owned()).to_owned();
+                    let mut heading_with_code = match err.code {
+                        Some(code) => format!("{} {}", code, heading),
+                        None => heading,
+                    };
+
+                    // count repeats of the same error instead of showing each one
+                    if err.repeats > 0 {
+                        let last_seen = err.last_seen.with_timezone(&Local).format("%T");
+                        heading_with_code =
+                            format!("{} (x{}, last at {})", heading_with_code, err.repeats + 1, last_seen);
+                    }
+
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(heading_with_code).color(color).strong());
+
+                            // right align dismiss button
+                            ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
+                                if ui.small_button("🗙").clicked() {
+                                    remove = Some(i);
+                                }
+                            });
+                        });
+
+                        ui.label(RichText::new(err.description.as_str()).color(color));
+
+                        // suggest what to do next, if there's anything the user can do
+                        let hint = match err.kind {
+                            Some(ErrorCode::BadRequest | ErrorCode::Validation) => {
+                                Some("Check what you entered and try again.")
+                            }
+                            Some(ErrorCode::Forbidden) => Some("Ask an admin if you need access."),
+                            Some(ErrorCode::RateLimited) => Some("Wait a moment before trying again."),
+                            Some(ErrorCode::TooLarge) => Some(
+                                "That's more than the server accepts at once, try splitting it into smaller batches.",
+                            ),
+                            Some(ErrorCode::QuotaExceeded) => {
+                                Some("You've reached your limit for now. Ask an admin if you need more.")
+                            }
+                            Some(ErrorCode::DatabaseUnavailable) => {
+                                Some("The database is down, try again in a few minutes.")
+                            }
+                            Some(ErrorCode::Internal | ErrorCode::Unavailable) => {
+                                Some("Try again later. If this keeps happening, let an admin know.")
+                            }
+                            _ => None,
+                        };
+
+                        if let Some(hint) = hint {
+                            ui.weak(hint);
+                        }
+
+                        // include this in bug reports so the request can be found in the server's logs
+                        if let Some(request_id) = err.request_id {
+                            ui.label(RichText::new(format!("Request ID: {}", request_id)).small().weak());
                         }
                     });
-                });
+
+                    ui.add_space(SPACE_SMALL);
+                }
             });
+
+        if let Some(i) = remove {
+            errors.remove(i);
         }
 
-        // remove the errors which should be dismissed
-        for i in remove.iter() {
-            state.pages.layout.display_error.remove(*i);
+        // keep checking whether anything should be dismissed, even if nothing else changes
+        if errors.iter().any(|err| err.is_transient()) {
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
         }
     }
 
+    /// Shows a window listing every recent error, newest first, so the user
+    /// can report them after they've been dismissed
+    fn show_error_log(&mut self, ctx: &Context, state: &mut ClientState) {
+        let time_fmt = state.settings.time_format.pattern();
+        let log = &mut state.pages.layout.error_log;
+
+        Window::new("Error log")
+            .open(&mut self.show_error_log)
+            .collapsible(true)
+            .default_width(PANEL_WIDTH_MEDIUM)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!log.is_empty(), Button::new("📋 Copy")).clicked() {
+                        let text = log
+                            .iter()
+                            .rev()
+                            .map(|err| describe_error(err, time_fmt))
+                            .collect::<Vec<String>>()
+                            .join("\n");
+                        ui.ctx().copy_text(text);
+                    }
+
+                    if ui.add_enabled(!log.is_empty(), Button::new("🗑 Clear")).clicked() {
+                        log.clear();
+                    }
+                });
+
+                ui.separator();
+
+                if log.is_empty() {
+                    ui.label("No errors yet.");
+                    return;
+                }
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    for err in log.iter().rev() {
+                        ui.label(describe_error(err, time_fmt));
+                        ui.separator();
+                    }
+                });
+            });
+    }
+
     /// Show any page-specific modals which should be visible
     fn show_modals(&mut self, ctx: &Context, state: &mut ClientState) {
         if state.authorized.is_none() {
-            let modal = Modal::new("Login")
-                .with_body("You need to login or renew your session to continue.")
-                .cancellable(false)
-                .show_with_extras(ctx, |ui| {
-                    if ui.button("✔ Ok").clicked() {
-                        ui.ctx().open_url(OpenUrl::same_tab(yuri!(AUTH_API_ROOT, "/oidc/login/")));
-                    }
-                });
+            let body = if state.pages.layout.replay.is_empty() {
+                "You need to login or renew your session to continue."
+            } else {
+                "You need to login or renew your session to continue. Your unsaved changes will be sent again once you're signed in."
+            };
+
+            // greet the user with the instance's own name and announcement, if it has them
+            let mut modal = Modal::new("Login").with_body(body).cancellable(false);
+            if let Some(branding) = state.branding.as_ref() {
+                modal = modal.with_heading(format!("Welcome to {}", branding.name));
+                if let Some(welcome) = branding.welcome.as_ref() {
+                    modal = modal.with_body(format!("{}\n\n{}", welcome, body));
+                }
+            }
+
+            let modal = modal.show_with_extras(ctx, |ui| {
+                if ui.button("✔ Ok").clicked() {
+                    ui.ctx().open_url(OpenUrl::same_tab(yuri!(AUTH_API_ROOT, "/oidc/login/")));
+                }
+            });
 
             if modal.should_close() {
                 ctx.open_url(OpenUrl::same_tab(yuri!(AUTH_API_ROOT, "/oidc/login/")));
@@ -210,3 +999,26 @@ impl LayoutRender {
         }
     }
 }
+
+/// Where to go to see what the notification is about, if anywhere. Deleted
+/// pigs can't be shown.
+fn notification_url(notification: &Notification) -> Option<String> {
+    let target = notification.target?;
+    match notification.kind {
+        NotificationKind::ImportProcessed => Some(format!("/bulk#{}", target)),
+        NotificationKind::PigEdited => Some(format!("/pigs#{}", target)),
+        NotificationKind::PigDeleted | NotificationKind::SessionExpired => None,
+    }
+}
+
+/// Describes the given error on a single line for the error log, with the
+/// time it was last seen in the given format
+fn describe_error(err: &ApiError, time_fmt: &str) -> String {
+    let time = err.last_seen.with_timezone(&Local).format(time_fmt);
+    let code = err.code.map(|code| format!("{} ", code)).unwrap_or_default();
+    let reason = err.reason.as_deref().unwrap_or("Error");
+    let request = err.request.as_ref().map(|req| format!(" ({})", req)).unwrap_or_default();
+    let request_id = err.request_id.map(|id| format!(" [request {}]", id)).unwrap_or_default();
+
+    format!("[{}] {}{}: {}{}{}", time, code, reason, err.description, request, request_id)
+}
@@ -2,6 +2,9 @@ use crate::data::state::ClientState;
 use crate::pages::bulkpage::BulkPageRender;
 use crate::pages::errpage::ErrPageRender;
 use crate::pages::pigpage::PigPageRender;
+use crate::pages::reviewpage::ReviewPageRender;
+use crate::pages::settingspage::SettingsPageRender;
+use crate::pages::tagpage::TagPageRender;
 use crate::pages::userpage::UserPageRender;
 use egui::{Context, Ui};
 use urlable::ParsedURL;
@@ -10,6 +13,10 @@ pub mod bulkpage;
 pub mod errpage;
 pub mod layout;
 pub mod pigpage;
+pub mod reviewpage;
+pub mod settingspage;
+pub mod switcher;
+pub mod tagpage;
 pub mod userpage;
 
 /// The unique page routes users can navigate to
@@ -21,9 +28,18 @@ pub enum Routes {
     /// Import multiple names at once
     Bulk,
 
+    /// Decide whether flagged names are appropriate
+    Review,
+
+    /// Manage the tags given to pigs
+    Tags,
+
     /// Manage app users
     Users,
 
+    /// Change the user's preferences
+    Settings,
+
     /// 404 page
     NotFound,
 }
@@ -34,10 +50,26 @@ impl Routes {
         match self {
             Self::Pigs => Box::new(PigPageRender::default()),
             Self::Bulk => Box::new(BulkPageRender::default()),
+            Self::Review => Box::new(ReviewPageRender::default()),
+            Self::Tags => Box::new(TagPageRender::default()),
             Self::Users => Box::new(UserPageRender::default()),
+            Self::Settings => Box::new(SettingsPageRender::default()),
             Self::NotFound => Box::new(ErrPageRender::default()),
         }
     }
+
+    /// The name of the page, shown in the browser tab's title
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Pigs => "Pigs",
+            Self::Bulk => "Import",
+            Self::Review => "Review",
+            Self::Tags => "Tags",
+            Self::Users => "Users",
+            Self::Settings => "Settings",
+            Self::NotFound => "Not Found",
+        }
+    }
 }
 
 /// Anything responsible for actually rendering a route. You should not expect
@@ -54,4 +86,10 @@ pub trait RenderPage {
 
     /// Runs every frame to render the UI.
     fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL);
+
+    /// The name of whatever is selected on the page, if anything, which goes
+    /// at the start of the browser tab's title.
+    fn selection_title(&self, state: &ClientState) -> Option<String> {
+        None
+    }
 }
@@ -1,19 +1,44 @@
-use crate::data::api::{ApiError, PigApi, PigFetchHandler};
+use crate::data::api::{changes_made, ApiError, AuditApi, FilterApi, PigApi, PigFetchHandler, RetryPolicy, Status};
+use crate::data::persist::Persisted;
+use crate::data::reconcile::Reconciliation;
 use crate::data::state::ClientState;
+use crate::pages::errpage::ErrPageRender;
 use crate::pages::RenderPage;
 use crate::ui::modal::Modal;
-use crate::ui::style::{PANEL_WIDTH_MEDIUM, PANEL_WIDTH_SMALL, SPACE_SMALL, TABLE_ROW_HEIGHT_LARGE, TIME_FMT};
-use crate::ui::{add_properties_row, properties_list, selectable_list, spaced_heading, wrapped_singleline_layouter};
+use crate::ui::style::{label_color, PANEL_WIDTH_MEDIUM, PANEL_WIDTH_SMALL, SPACE_SMALL, TABLE_ROW_HEIGHT_LARGE};
+use crate::ui::time::time_label;
+use crate::ui::{
+    add_properties_row, label_dot, paginator, properties_list, request_spinner, selectable_list, spaced_heading,
+    username_label, wrapped_singleline_layouter, Pagination,
+};
 use crate::update_url_hash;
-use chrono::Local;
-use egui::{Button, CentralPanel, Context, Label, Panel, ScrollArea, TextEdit, Ui, Widget};
+use egui::{
+    Button, CentralPanel, Checkbox, ComboBox, Context, Hyperlink, Label, OpenUrl, Panel, RichText, ScrollArea,
+    TextEdit, Ui, Widget,
+};
 use egui_flex::{item, Flex, FlexJustify};
-use log::{debug, error};
-use pigweb_common::pigs::{Pig, PigQuery};
+use log::{debug, error, info};
+use pigweb_common::audit::{PigChange, PigChangeAction};
+use pigweb_common::changes::ChangeTarget;
+use pigweb_common::errors::ErrorCode;
+use pigweb_common::filters::SavedFilter;
+use pigweb_common::pigs::{Pig, PigLabel, PigLink, PigQuery};
 use pigweb_common::users::Roles;
+use pigweb_common::{yuri, PIG_API_ROOT};
+use std::collections::BTreeSet;
+use std::time::Duration;
 use urlable::ParsedURL;
 use uuid::Uuid;
 
+/// How long to wait after the search bar was last edited before searching, in
+/// seconds, so we don't send a request for every keystroke
+const SEARCH_DEBOUNCE: f64 = 0.25;
+
+/// The only fields of each pig fetched for the sidebar, tags included so
+/// checked pigs can be copied. The rest are fetched once a pig is opened, see
+/// [`PigPageDirtyAction::Open`].
+pub const SIDEBAR_FIELDS: [&str; 5] = ["id", "name", "tags", "archived", "label"];
+
 /// An action which should only be performed when there are no unsaved changes.
 /// When this isn't [PigPageDirtyAction::None], shows a modal with a warning
 /// before performing the action and resetting itself to None.
@@ -26,6 +51,11 @@ enum PigPageDirtyAction {
     /// Select a different pig
     Select(Option<Pig>),
 
+    /// Fetch the pig with the given id and select it once it's received. Pigs
+    /// in the sidebar are missing most of their fields, so they're opened
+    /// with this instead of [`Self::Select`].
+    Open(Uuid),
+
     /// No pending action, don't prompt the user for anything
     None,
 }
@@ -41,13 +71,64 @@ pub struct PigPage {
     /// The currently selected pig
     selection: Option<Pig>,
 
+    /// Only pigs with this tag are listed in the sidebar
+    pub tag: Option<String>,
+
+    /// Only pigs created by this user are listed in the sidebar
+    pub creator: Option<Uuid>,
+
+    /// Which page of results is listed in the sidebar
+    pub pagination: Pagination,
+
     /// Whether we have unsaved changes
     dirty: bool,
+
+    /// Whether the selection was loaded from the user's device and hasn't
+    /// been checked against the server yet, see [`Persisted::restored`]
+    #[serde(skip)]
+    unverified: bool,
 }
 
 impl Default for PigPage {
     fn default() -> Self {
-        Self { query: String::default(), selection: None, dirty: false }
+        Self {
+            query: String::default(),
+            selection: None,
+            tag: None,
+            creator: None,
+            pagination: Pagination::default(),
+            dirty: false,
+            unverified: false,
+        }
+    }
+}
+
+impl Persisted for PigPage {
+    const KEY: &'static str = "pigweb.pigs";
+    const VERSION: u32 = 1;
+
+    fn restored(&mut self) {
+        self.unverified = self.selection.is_some();
+    }
+}
+
+impl PigPage {
+    /// Builds a [`PigQuery`] from the current search params
+    pub fn search_query(&self) -> PigQuery {
+        let mut query = PigQuery::default()
+            .with_name(&self.query)
+            .with_fields(&SIDEBAR_FIELDS)
+            .with_limit(self.pagination.limit())
+            .with_offset(self.pagination.offset());
+
+        if let Some(tag) = self.tag.as_ref() {
+            query = query.with_tag(tag);
+        }
+
+        match self.creator.as_ref() {
+            Some(creator) => query.with_creator(creator),
+            None => query,
+        }
     }
 }
 
@@ -60,28 +141,141 @@ pub struct PigPageRender {
     /// Handles API data specifically when getting the selection from the URL
     fetch_url_selection: PigFetchHandler,
 
+    /// Handles API data when checking a selection loaded from the user's
+    /// device still exists
+    fetch_restored_selection: PigFetchHandler,
+
+    /// The id of the selection loaded from the user's device which we're
+    /// checking
+    restored_selection: Option<Uuid>,
+
+    /// Handles API data when fetching pigs someone else changed, see
+    /// [`Reconciliation`]
+    fetch_changed: PigFetchHandler,
+
+    /// The pigs someone else changed which we're still fetching
+    changed: BTreeSet<Uuid>,
+
+    /// Handles saving and loading filters
+    filter_api: FilterApi,
+
+    /// All filters the user saved or which have been shared with them
+    filters: Option<Vec<SavedFilter>>,
+
+    /// The id of the filter which was last applied
+    selected_filter: Option<Uuid>,
+
+    /// The name to save the current query under
+    new_filter_name: String,
+
+    /// Whether the new filter should be shared with other users
+    new_filter_shared: bool,
+
+    /// The tag to add to the selected pig
+    new_tag: String,
+
+    /// The label of the link to add to the selected pig
+    new_link_label: String,
+
+    /// The url of the link to add to the selected pig
+    new_link_url: String,
+
     /// The current list of search results
     query_results: Option<Vec<Pig>>,
 
+    /// The yuri of the last search sent, which its results are cached under
+    fetched_query: Option<String>,
+
+    /// When the search bar was last edited, if we haven't searched for it yet
+    query_edited: Option<f64>,
+
     /// Modal which warns you when there's unsaved changes
     dirty_modal: PigPageDirtyAction,
 
     /// Whether to show the modal to confirm deleting a pig
     delete_modal: bool,
 
+    /// The pigs checked in the sidebar for batch actions
+    checked: BTreeSet<Uuid>,
+
+    /// The tag to add to every checked pig
+    batch_tag: String,
+
+    /// Whether to show the modal to confirm deleting every checked pig
+    batch_delete_modal: bool,
+
+    /// Only archive pigs created on or after this date, as `YYYY-MM-DD`
+    archive_after: String,
+
+    /// Only archive pigs created before this date, as `YYYY-MM-DD`
+    archive_before: String,
+
+    /// How many pigs will be archived, shown in a modal to confirm archiving
+    /// every pig matching the current search
+    archive_matching_modal: Option<usize>,
+
+    /// Whether the last archive request was confirmed, as opposed to a dry run
+    archive_confirmed: bool,
+
     /// Whether to show the modal for a URL where no pig exists
     pig_not_found_modal: bool,
+
+    /// The id of the pig whose changes were last saved. They're shown as saved
+    /// right away, and rolled back if the server rejects them.
+    saving: Option<Uuid>,
+
+    /// Handles fetching recent changes for the dashboard
+    audit_api: AuditApi,
+
+    /// The most recently created and renamed pigs, shown on the dashboard
+    recently_modified: Option<Vec<PigChange>>,
+
+    /// The most recently deleted pigs, shown on the dashboard
+    recently_deleted: Option<Vec<PigChange>>,
+
+    /// How many changes had been made when the dashboard was last fetched, so
+    /// it's only fetched again once something changed, see [`changes_made`]
+    audit_fetched: Option<u32>,
 }
 
 impl Default for PigPageRender {
     fn default() -> Self {
         Self {
-            pig_api: PigApi::default(),
+            pig_api: PigApi {
+                fetch: PigFetchHandler::default().with_retry(RetryPolicy::default()),
+                ..PigApi::default()
+            },
             fetch_url_selection: PigFetchHandler::default(),
+            fetch_restored_selection: PigFetchHandler::default(),
+            restored_selection: None,
+            fetch_changed: PigFetchHandler::default(),
+            changed: BTreeSet::new(),
+            filter_api: FilterApi::default(),
+            filters: None,
+            selected_filter: None,
+            new_filter_name: String::default(),
+            new_filter_shared: false,
+            new_tag: String::default(),
+            new_link_label: String::default(),
+            new_link_url: String::default(),
             query_results: None,
+            fetched_query: None,
+            query_edited: None,
             dirty_modal: PigPageDirtyAction::None,
             delete_modal: false,
+            checked: BTreeSet::new(),
+            batch_tag: String::default(),
+            batch_delete_modal: false,
+            archive_after: String::default(),
+            archive_before: String::default(),
+            archive_matching_modal: None,
+            archive_confirmed: false,
             pig_not_found_modal: false,
+            saving: None,
+            audit_api: AuditApi::default(),
+            recently_modified: None,
+            recently_deleted: None,
+            audit_fetched: None,
         }
     }
 }
@@ -102,15 +296,12 @@ impl RenderPage for PigPageRender {
                             "The selection has been updated via url! Previous Selection: {:?}",
                             state.pages.pigs.selection.as_ref()
                         );
-                        self.fetch_url_selection.request(PigQuery::default().with_id(&uuid).with_limit(1));
+                        self.fetch_url_selection
+                            .request(PigQuery::default().with_id(&uuid).with_limit(1).with_creator_username());
                     }
                 }
                 Err(err) => {
-                    state
-                        .pages
-                        .layout
-                        .display_error
-                        .push(ApiError::new(err.to_string()).with_reason("Unable to parse UUID.".to_owned()));
+                    state.page_error(ApiError::new(err.to_string()).with_reason("Unable to parse UUID.".to_owned()));
                     update_url_hash(ctx, url, None);
                     error!("Unable to parse hash \"{:?}\", err: {:?}", &stripped_hash, err);
                 }
@@ -123,12 +314,30 @@ impl RenderPage for PigPageRender {
     }
 
     fn open(&mut self, _ctx: &Context, state: &mut ClientState, _url: &ParsedURL) {
-        self.do_query(state)
+        // Use the results fetched after signing in if they're for the same search
+        match state.prefetched.take_pigs(&state.pages.pigs.search_query()) {
+            Some(pigs) => self.query_results = Some(pigs),
+            None => self.do_query(state),
+        }
+        self.filter_api.fetch.request(false); // this arg doesn't matter
+
+        // The selection may have been deleted since it was saved
+        if std::mem::take(&mut state.pages.pigs.unverified) {
+            self.restored_selection = state.pages.pigs.selection.as_ref().map(|pig| pig.id);
+            if let Some(id) = self.restored_selection.as_ref() {
+                self.fetch_restored_selection
+                    .request(PigQuery::default().with_id(id).with_limit(1).with_creator_username());
+            }
+        }
+    }
+
+    fn selection_title(&self, state: &ClientState) -> Option<String> {
+        state.pages.pigs.selection.as_ref().map(|pig| pig.name.to_owned())
     }
 
     fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
         if !state.has_role(Roles::PigViewer) {
-            // TODO 403 Forbidden
+            ErrPageRender::forbidden(&[Roles::PigViewer]).ui(ui, state, url);
             return;
         }
 
@@ -152,6 +361,34 @@ impl RenderPage for PigPageRender {
 impl PigPageRender {
     /// Checks all APIs for data received from previously submitted requests
     fn process_promises(&mut self, ctx: &Context, state: &mut ClientState, url: &ParsedURL) {
+        // Someone else changed the pig list, make sure we're showing the latest of it
+        if let Some(changes) = state.changes.reconcile(ChangeTarget::Pig) {
+            self.reconcile(state, changes);
+            self.audit_fetched = None;
+
+            // Our own unsaved or unconfirmed changes take priority over theirs
+            let busy = state.pages.pigs.dirty || self.saving.is_some() || self.pig_api.delete.is_pending();
+            if let Some(id) = state.pages.pigs.selection.as_ref().map(|pig| pig.id) {
+                if !busy && state.changes.affects_id(ChangeTarget::Pig, &id) {
+                    self.fetch_url_selection
+                        .request(PigQuery::default().with_id(&id).with_limit(1).with_creator_username());
+                }
+            }
+        }
+
+        // Put the latest versions of the changed pigs in place, unless there's new ones we don't know where to put
+        if let Some(pigs) = self.fetch_changed.received(state) {
+            let pigs = state.usernames.take_creators(pigs, |pig| pig.creator);
+            let changed = std::mem::take(&mut self.changed);
+            let merged =
+                self.query_results.as_mut().is_some_and(|results| Reconciliation::merge(&changed, results, pigs));
+
+            match self.query_results.as_ref().filter(|_| merged) {
+                Some(results) => state.cache.pigs.insert(state.pages.pigs.search_query().to_yuri(), results),
+                None => self.refresh_query(state),
+            }
+        }
+
         if let Some(pig) = self.pig_api.create.received(state) {
             state.pages.pigs.dirty = false;
             state.pages.pigs.selection = Some(pig);
@@ -159,9 +396,27 @@ impl PigPageRender {
             self.do_query(state); // Redo the search query so it includes the new pig
         }
 
-        if self.pig_api.update.received(state).is_some() {
-            state.pages.pigs.dirty = false;
-            self.do_query(state); // Redo the search query so it includes any possible changes
+        // Saved changes are already shown, so there's only something to do if they didn't go through
+        match self.pig_api.update.resolve() {
+            Status::Received(_) => {
+                self.saving = None;
+                self.do_query(state); // Redo the search query so it includes any possible changes
+            }
+            Status::Errored(err) if err.kind == Some(ErrorCode::Unauthorized) => state.authorized = None,
+            Status::Errored(err) => {
+                state.page_error(err.with_reason("Changes Not Saved".to_owned()));
+
+                // Roll back to whatever the server has, unless the user already moved on
+                if let Some(id) = self.saving.take() {
+                    if state.pages.pigs.selection.as_ref().is_some_and(|pig| pig.id == id) {
+                        state.pages.pigs.dirty = false;
+                        self.fetch_url_selection
+                            .request(PigQuery::default().with_id(&id).with_limit(1).with_creator_username());
+                    }
+                }
+                self.do_query(state);
+            }
+            Status::Pending => {}
         }
 
         if self.pig_api.delete.received(state).is_some() {
@@ -171,11 +426,101 @@ impl PigPageRender {
             self.do_query(state); // Redo the search query to exclude the deleted pig
         }
 
-        if let Some(pigs) = self.pig_api.fetch.received(state) {
+        if let Some(deleted) = self.pig_api.batch_delete.received(state) {
+            // Deselect the current pig if it was one of them
+            if state.pages.pigs.selection.as_ref().is_some_and(|pig| self.checked.contains(&pig.id)) {
+                state.pages.pigs.dirty = false;
+                state.pages.pigs.selection = None;
+                update_url_hash(ctx, url, None);
+            }
+
+            info!("Deleted {} pigs", deleted);
+            self.checked.clear();
+            self.do_query(state);
+        }
+
+        if let Some(pig) = self.pig_api.archive.received(state) {
+            state.pages.pigs.dirty = false;
+            state.pages.pigs.selection = Some(pig);
+            self.do_query(state); // Redo the search query so the list shows the change
+        }
+
+        // Dry runs come back with how many pigs would be archived, so the user can confirm
+        if let Some(count) = self.pig_api.archive_matching.received(state) {
+            match self.archive_confirmed {
+                true => {
+                    info!("Archived {} pigs", count);
+                    self.archive_confirmed = false;
+                    self.do_query(state);
+                }
+                false => self.archive_matching_modal = Some(count),
+            }
+        }
+
+        if let Some(changed) = self.pig_api.batch_tag.received(state) {
+            info!("Tagged {} pigs", changed);
+            self.checked.clear();
+            self.batch_tag = String::default();
+            self.do_query(state);
+        }
+
+        if let Some(pigs) =
+            self.pig_api.fetch.received(state).map(|pigs| state.usernames.take_creators(pigs, |pig| pig.creator))
+        {
+            if let Some(key) = self.fetched_query.take() {
+                state.cache.pigs.insert(key, &pigs);
+            }
             self.query_results = Some(pigs);
         }
 
-        if let Some(mut pigs) = self.fetch_url_selection.received(state) {
+        if let Some(filters) = self.filter_api.fetch.received(state) {
+            self.filters = Some(filters);
+        }
+
+        if let Some(changes) = self.audit_api.modified.received(state) {
+            self.recently_modified = Some(changes);
+        }
+
+        if let Some(changes) = self.audit_api.deleted.received(state) {
+            self.recently_deleted = Some(changes);
+        }
+
+        if let Some(filter) = self.filter_api.create.received(state) {
+            self.new_filter_name = String::default();
+            self.new_filter_shared = false;
+            self.selected_filter = Some(filter.id);
+            self.filter_api.fetch.request(false);
+        }
+
+        if self.filter_api.delete.received(state).is_some() {
+            self.selected_filter = None;
+            self.filter_api.fetch.request(false);
+        }
+
+        // A selection saved before someone else deleted it can't stay selected
+        if let Some(mut pigs) = self
+            .fetch_restored_selection
+            .received(state)
+            .map(|pigs| state.usernames.take_creators(pigs, |pig| pig.creator))
+        {
+            let restored = self.restored_selection.take();
+            if restored.is_some() && restored == state.pages.pigs.selection.as_ref().map(|pig| pig.id) {
+                match pigs.pop() {
+                    // unsaved changes are kept rather than replaced with the latest version
+                    Some(_) if state.pages.pigs.dirty => {}
+                    Some(pig) => state.pages.pigs.selection = Some(pig),
+                    None => {
+                        info!("Dropping the restored selection {:?}, it no longer exists.", restored);
+                        self.dirty_modal = PigPageDirtyAction::Select(None);
+                        self.do_dirty_action(ctx, state, url);
+                    }
+                }
+            }
+        }
+
+        if let Some(mut pigs) =
+            self.fetch_url_selection.received(state).map(|pigs| state.usernames.take_creators(pigs, |pig| pig.creator))
+        {
             // This request should have been made with limit = 1
             // therefore, the only pig is the one we want
             if let Some(pig) = pigs.pop() {
@@ -192,14 +537,23 @@ impl PigPageRender {
         spaced_heading(ui, "The Pig List");
 
         ui.horizontal(|ui| {
-            // Search bar, perform a search if it's been changed
+            // Search bar, perform a search once the user stops typing
             if ui.add(TextEdit::singleline(&mut state.pages.pigs.query).hint_text("Search")).changed() {
-                self.do_query(state);
+                self.query_edited = Some(ui.input(|input| input.time));
+            }
+
+            if let Some(edited) = self.query_edited {
+                let waited = ui.input(|input| input.time) - edited;
+                if waited >= SEARCH_DEBOUNCE {
+                    self.new_query(state);
+                } else {
+                    ui.ctx().request_repaint_after(Duration::from_secs_f64(SEARCH_DEBOUNCE - waited));
+                }
             }
 
             // Pig create button, it's only enabled when you have something in
             // the search bar and when you have permissions
-            let can_add = state.has_role(Roles::PigEditor) && !state.pages.pigs.query.is_empty();
+            let can_add = state.can_request(&yuri!(PIG_API_ROOT, "create")) && !state.pages.pigs.query.is_empty();
             ui.add_enabled_ui(can_add, |ui| {
                 if ui.button("+ Add").clicked() {
                     // We need to save the name here or else borrow check complains
@@ -209,10 +563,45 @@ impl PigPageRender {
             });
         });
 
+        // Show the tag the list is filtered by, clicking it clears the filter
+        if let Some(tag) = state.pages.pigs.tag.to_owned() {
+            ui.horizontal(|ui| {
+                ui.label("Tagged");
+                if ui.button(format!("🏷 {} 🗙", tag)).clicked() {
+                    state.pages.pigs.tag = None;
+                    self.new_query(state);
+                }
+            });
+        }
+
+        // Only list the user's own pigs, we need to know who they are first
+        let mut mine = state.pages.pigs.creator.is_some();
+        let can_filter_mine = state.user.is_some() || mine;
+        if ui.add_enabled(can_filter_mine, Checkbox::new(&mut mine, "Created by me")).changed() {
+            state.pages.pigs.creator = if mine { state.user.as_ref().map(|user| user.id) } else { None };
+            self.new_query(state);
+        }
+
+        self.populate_filters(ui, state);
+
+        if state.can_request(&yuri!(PIG_API_ROOT, "archive_matching")) {
+            self.populate_archive_matching(ui, state);
+        }
+
         ui.add_space(SPACE_SMALL);
 
+        let shown = self.query_results.as_ref().map(|pigs| pigs.len());
+        let max_limit = state.max_limit();
+        if paginator(ui, "pig_pages", &mut state.pages.pigs.pagination, shown, max_limit) {
+            self.checked.clear();
+            self.do_query(state);
+        }
+
+        if !self.checked.is_empty() {
+            self.populate_batch_actions(ui, state);
+        }
+
         // Only render the results table if we have results to show
-        // TODO add pagination
         if self.query_results.as_ref().is_some_and(|pigs| !pigs.is_empty()) {
             let clicked: Option<Option<Pig>> = selectable_list(ui, self.query_results.as_ref().unwrap(), |row, pig| {
                 let selected = state.pages.pigs.selection.as_ref().is_some_and(|select| select.id == pig.id);
@@ -220,6 +609,22 @@ impl PigPageRender {
 
                 // Make sure we can't select the text or else we can't click the row behind
                 row.col(|ui| {
+                    // Checking a pig doesn't select it, they're only used for batch actions
+                    let mut checked = self.checked.contains(&pig.id);
+                    if ui.checkbox(&mut checked, "").changed() {
+                        if checked {
+                            self.checked.insert(pig.id);
+                        } else {
+                            self.checked.remove(&pig.id);
+                        }
+                    }
+
+                    if pig.archived {
+                        Label::new(RichText::new("🗄").weak()).selectable(false).ui(ui).on_hover_text("Archived");
+                    }
+
+                    label_dot(ui, pig.label);
+
                     Label::new(&pig.name).selectable(false).truncate().ui(ui);
                 });
 
@@ -228,7 +633,11 @@ impl PigPageRender {
 
             // Check if we have an action to do
             if let Some(clicked) = clicked {
-                self.warn_if_dirty(ui.ctx(), state, url, PigPageDirtyAction::Select(clicked));
+                let action = match clicked {
+                    Some(pig) => PigPageDirtyAction::Open(pig.id),
+                    None => PigPageDirtyAction::Select(None),
+                };
+                self.warn_if_dirty(ui.ctx(), state, url, action);
             }
         } else if self.query_results.is_none() {
             // Still waiting on results, this should only happen when waiting
@@ -236,38 +645,218 @@ impl PigPageRender {
 
             // You spin me right 'round, baby, 'right round
             // Like a record, baby, right 'round, 'round, 'round
-            ui.vertical_centered(|ui| ui.spinner());
+            ui.vertical_centered(|ui| request_spinner(ui, self.pig_api.fetch.attempt()));
         }
     }
 
-    /// Adds the pig details/editor to the center panel if a pig is selected
+    /// Adds the actions which can be done to every checked pig at once
+    fn populate_batch_actions(&mut self, ui: &mut Ui, state: &mut ClientState) {
+        let can_tag = state.can_request(&yuri!(PIG_API_ROOT, "batch_tag"));
+        let can_delete = state.can_request(&yuri!(PIG_API_ROOT, "batch_delete"));
+        let ids: Vec<Uuid> = self.checked.iter().copied().collect();
+
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} selected", ids.len()));
+
+                if ui.button("🗙 Clear").clicked() {
+                    self.checked.clear();
+                }
+
+                // Copy the checked pigs as tab-separated values so they can be pasted into a spreadsheet
+                if ui.button("📋 Copy").on_hover_text("Copy the name, id, and tags of each pig").clicked() {
+                    let rows: Vec<String> = self
+                        .query_results
+                        .iter()
+                        .flatten()
+                        .filter(|pig| self.checked.contains(&pig.id))
+                        .map(|pig| format!("{}\t{}\t{}", pig.name, pig.id, pig.tags.join(", ")))
+                        .collect();
+                    ui.ctx().copy_text(rows.join("\n"));
+                }
+
+                if ui.add_enabled(can_delete, Button::new("🗑 Delete")).clicked() {
+                    match state.settings.confirm_delete {
+                        true => self.batch_delete_modal = true,
+                        false => self.pig_api.batch_delete.request(&ids),
+                    }
+                }
+            });
+
+            ui.add_enabled_ui(can_tag, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(TextEdit::singleline(&mut self.batch_tag).hint_text("Tag").desired_width(120.0));
+                    let tag = self.batch_tag.trim();
+                    if ui.add_enabled(!tag.is_empty(), Button::new("🏷 Add to all")).clicked() {
+                        self.pig_api.batch_tag.request((tag, &ids));
+                    }
+                });
+            });
+        });
+    }
+
+    /// Adds a collapsible section to the sidebar for applying, saving, and
+    /// deleting [`SavedFilter`]s
+    fn populate_filters(&mut self, ui: &mut Ui, state: &mut ClientState) {
+        ui.collapsing("Filters", |ui| {
+            let mut apply = None;
+
+            ui.horizontal(|ui| {
+                // Show the name of the filter which was applied last, if we still have it
+                let selected_name = self
+                    .filters
+                    .as_ref()
+                    .and_then(|filters| filters.iter().find(|f| self.selected_filter.is_some_and(|id| id == f.id)))
+                    .map(|f| f.name.to_owned())
+                    .unwrap_or("Saved filters".to_owned());
+
+                ComboBox::from_id_salt("saved_filters").selected_text(selected_name).show_ui(ui, |ui| {
+                    for filter in self.filters.as_ref().unwrap_or(&Vec::new()) {
+                        let selected = self.selected_filter.is_some_and(|id| id == filter.id);
+                        let label =
+                            if filter.shared { format!("👥 {}", filter.name) } else { filter.name.to_owned() };
+
+                        if ui.add(Button::selectable(selected, label)).clicked() {
+                            apply = Some(filter.to_owned());
+                        }
+                    }
+                });
+
+                // The server only lets you delete your own filters
+                if ui.add_enabled(self.selected_filter.is_some(), Button::new("🗑")).clicked() {
+                    self.filter_api.delete.request(self.selected_filter.unwrap());
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.new_filter_name).hint_text("Filter name").desired_width(120.0));
+                ui.checkbox(&mut self.new_filter_shared, "Shared");
+
+                // Saves whatever is currently in the search bar
+                if ui.add_enabled(!self.new_filter_name.is_empty(), Button::new("💾 Save")).clicked() {
+                    let filter = SavedFilter::new(
+                        self.new_filter_name.as_str(),
+                        &Uuid::default(), // the server sets the creator
+                        self.new_filter_shared,
+                        &state.pages.pigs.search_query(),
+                    );
+                    self.filter_api.create.request(&filter);
+                }
+            });
+
+            // Apply the filter's values to the current query
+            if let Some(filter) = apply {
+                self.selected_filter = Some(filter.id);
+                state.pages.pigs.query = filter.query.name.unwrap_or_default();
+                state.pages.pigs.tag = filter.query.tag;
+                state.pages.pigs.creator =
+                    filter.query.creator.and_then(|ids| ids.first().and_then(|id| Uuid::parse_str(id).ok()));
+                self.new_query(state);
+            }
+        });
+    }
+
+    /// Adds a collapsible section to the sidebar for archiving every pig which
+    /// matches the current search, optionally only those created within a
+    /// date range
+    fn populate_archive_matching(&mut self, ui: &mut Ui, state: &mut ClientState) {
+        ui.collapsing("Archive matching", |ui| {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.archive_after).hint_text("From YYYY-MM-DD").desired_width(100.0));
+                ui.add(
+                    TextEdit::singleline(&mut self.archive_before).hint_text("Before YYYY-MM-DD").desired_width(100.0),
+                );
+            });
+
+            // The server won't archive everything at once, so there has to be a search or date to go off of
+            let query = self.archive_query(state);
+            let can_archive = query.has_filters() && query.has_valid_dates();
+            let button = Button::new("🗄 Archive all matching");
+            let hover = "Archive every pig which matches the current search and dates, not just this page";
+
+            if ui.add_enabled(can_archive, button).on_hover_text(hover).clicked() {
+                self.archive_confirmed = false;
+                self.pig_api.archive_matching.request((&query, true));
+            }
+        });
+    }
+
+    /// Builds the query for [`Self::populate_archive_matching`] from the
+    /// current search and dates
+    fn archive_query(&self, state: &ClientState) -> PigQuery {
+        let after = Some(self.archive_after.as_str()).filter(|date| !date.trim().is_empty());
+        let before = Some(self.archive_before.as_str()).filter(|date| !date.trim().is_empty());
+        state.pages.pigs.search_query().with_created(after, before)
+    }
+
+    /// Adds the pig details/editor to the center panel if a pig is selected,
+    /// otherwise the instance's welcome message
     fn populate_center(&mut self, ui: &mut Ui, state: &mut ClientState) {
         ui.set_max_width(PANEL_WIDTH_MEDIUM);
-        let can_edit = state.has_role(Roles::PigEditor);
+        let can_edit = state.can_request(&yuri!(PIG_API_ROOT, "update"));
+        let can_delete = state.can_request(&yuri!(PIG_API_ROOT, "delete"));
+        let can_archive = state.can_request(&yuri!(PIG_API_ROOT, "archive"));
+        let confirm_delete = state.settings.confirm_delete;
+        let dirty = state.pages.pigs.dirty;
+        let settings = state.settings.clone();
+
+        // The tag which was clicked, the pig list gets filtered by it
+        let mut filter_tag = None;
 
         // THIS IS REALLY FUCKING IMPORTANT, LETS US MODIFY THE VALUE INSIDE THE OPTION
         if let Some(pig) = state.pages.pigs.selection.as_mut() {
             spaced_heading(ui, pig.name.to_owned()); // convert to owned since we transfer a mut reference later
 
+            // Archived pigs are read-only until someone unarchives them
+            let can_edit = can_edit && !pig.archived;
+            if pig.archived {
+                ui.label(RichText::new("🗄 Archived").weak())
+                    .on_hover_text("This pig has been retired. It can't be edited unless it's unarchived.");
+                ui.add_space(SPACE_SMALL);
+            }
+
             // Pig action buttons
-            if can_edit {
+            let mut saved = None;
+            if can_edit || can_delete || can_archive {
                 Flex::horizontal().w_full().justify(FlexJustify::SpaceBetween).show(ui, |flex| {
                     let save_button = Button::new("💾 Save");
                     let delete_button = Button::new("🗑 Delete");
+                    let archive_button = Button::new(if pig.archived { "📤 Unarchive" } else { "🗄 Archive" });
 
                     // TODO set as disabled again when not dirty. we just have to live with this until https://github.com/lucasmerlin/hello_egui/pull/50 is done
-                    if flex.add(item().grow(1.0), save_button).clicked() {
+                    if can_edit && flex.add(item().grow(1.0), save_button).clicked() {
                         self.pig_api.update.request(pig);
+                        saved = Some(pig.clone());
                     }
 
-                    if flex.add(item().grow(1.0), delete_button).clicked() {
-                        self.delete_modal = true;
+                    // archiving replaces the selection, so it's hidden while there are unsaved changes to lose
+                    if can_archive && !dirty && flex.add(item().grow(1.0), archive_button).clicked() {
+                        self.pig_api.archive.request((pig.id, !pig.archived));
+                    }
+
+                    // deleting is reserved for admins
+                    if can_delete && flex.add(item().grow(1.0), delete_button).clicked() {
+                        match confirm_delete {
+                            true => self.delete_modal = true,
+                            false => self.pig_api.delete.request(pig.id),
+                        }
                     }
                 });
 
+                if self.pig_api.update.is_pending() {
+                    ui.weak("Saving...");
+                }
+
                 ui.add_space(SPACE_SMALL);
             }
 
+            // Show the changes as saved right away instead of waiting on the server
+            if let Some(saved) = saved {
+                state.pages.pigs.dirty = false;
+                self.saving = Some(saved.id);
+                self.show_saved(&saved);
+            }
+
             // Pig properties table
             properties_list(ui).body(|mut body| {
                 add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "id", |ui| {
@@ -290,14 +879,195 @@ impl PigPageRender {
                     });
                 });
 
+                add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE * 2.0, "tags", |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        let mut remove = None;
+
+                        for (i, tag) in pig.tags.iter().enumerate() {
+                            if ui.button(format!("🏷 {}", tag)).on_hover_text("Show pigs with this tag").clicked() {
+                                filter_tag = Some(tag.to_owned());
+                            }
+
+                            if can_edit && ui.small_button("🗙").clicked() {
+                                remove = Some(i);
+                            }
+                        }
+
+                        if let Some(i) = remove {
+                            pig.tags.remove(i);
+                            state.pages.pigs.dirty = true;
+                        }
+
+                        // Add a new tag, if it's not already there
+                        if can_edit {
+                            ui.add(TextEdit::singleline(&mut self.new_tag).hint_text("New tag").desired_width(80.0));
+
+                            let tag = self.new_tag.trim().to_owned();
+                            let can_add = !tag.is_empty() && !pig.tags.contains(&tag);
+                            if ui.add_enabled(can_add, Button::new("+")).clicked() {
+                                pig.tags.push(tag);
+                                self.new_tag = String::default();
+                                state.pages.pigs.dirty = true;
+                            }
+                        }
+                    });
+                });
+
+                add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE * 2.0, "links", |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        let mut remove = None;
+
+                        for (i, link) in pig.links.iter().enumerate() {
+                            let hyperlink = Hyperlink::from_label_and_url(format!("🔗 {}", link.label), &link.url);
+                            ui.add(hyperlink.open_in_new_tab(true)).on_hover_text(&link.url);
+
+                            if can_edit && ui.small_button("🗙").clicked() {
+                                remove = Some(i);
+                            }
+                        }
+
+                        if let Some(i) = remove {
+                            pig.links.remove(i);
+                            state.pages.pigs.dirty = true;
+                        }
+
+                        // Add a new link, the server rejects any which aren't valid
+                        if can_edit {
+                            ui.add(
+                                TextEdit::singleline(&mut self.new_link_label).hint_text("Label").desired_width(80.0),
+                            );
+                            ui.add(
+                                TextEdit::singleline(&mut self.new_link_url).hint_text("https://").desired_width(120.0),
+                            );
+
+                            let link = PigLink::new(&self.new_link_label, &self.new_link_url);
+                            if ui.add_enabled(link.is_valid(), Button::new("+")).clicked() {
+                                pig.links.push(link);
+                                self.new_link_label = String::default();
+                                self.new_link_url = String::default();
+                                state.pages.pigs.dirty = true;
+                            }
+                        }
+                    });
+                });
+
+                add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "label", |ui| {
+                    ui.horizontal(|ui| {
+                        if !can_edit {
+                            label_dot(ui, pig.label);
+                            ui.label(pig.label.map_or("none", |label| label.name()));
+                            return;
+                        }
+
+                        if ui.add(Button::selectable(pig.label.is_none(), "none")).clicked() && pig.label.is_some() {
+                            pig.label = None;
+                            state.pages.pigs.dirty = true;
+                        }
+
+                        for label in PigLabel::ALL {
+                            let dot = RichText::new("●").color(label_color(label));
+                            let button = ui.add(Button::selectable(pig.label == Some(label), dot));
+                            if button.on_hover_text(label.name()).clicked() && pig.label != Some(label) {
+                                pig.label = Some(label);
+                                state.pages.pigs.dirty = true;
+                            }
+                        }
+                    });
+                });
+
                 add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "created by", |ui| {
-                    // TODO actually bother fetching the user data
-                    ui.code(pig.creator.to_string());
+                    username_label(ui, &mut state.usernames, &pig.creator);
                 });
 
                 add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "created at", |ui| {
-                    let create_time = pig.created.and_utc().with_timezone(&Local);
-                    ui.label(create_time.format(TIME_FMT).to_string());
+                    time_label(ui, pig.created, &settings);
+                });
+            });
+        } else if self.fetch_url_selection.is_pending() {
+            // a pig is being opened from the sidebar
+            ui.add_space(SPACE_SMALL);
+            ui.spinner();
+        } else {
+            // this is the home page, so greet the user when nothing is selected
+            if let Some(branding) = state.branding.as_ref() {
+                if let Some(welcome) = branding.welcome.as_ref() {
+                    spaced_heading(ui, format!("Welcome to {}", branding.name));
+                    ui.label(welcome);
+                }
+            }
+
+            self.populate_dashboard(ui, state);
+        }
+
+        if filter_tag.is_some() {
+            state.pages.pigs.tag = filter_tag;
+            self.new_query(state);
+        }
+    }
+
+    /// The recent activity shown on the home page, for users who can view the
+    /// audit log
+    fn populate_dashboard(&mut self, ui: &mut Ui, state: &mut ClientState) {
+        if !state.has_role(Roles::LogViewer) {
+            return;
+        }
+
+        // Only fetch the changes again once someone's made more of them
+        if self.audit_fetched != Some(changes_made()) {
+            self.audit_fetched = Some(changes_made());
+            self.audit_api.modified.request(false);
+            self.audit_api.deleted.request(true);
+        }
+
+        let settings = state.settings.clone();
+        let lists = [
+            ("Recently changed", "recently_modified", self.recently_modified.as_ref()),
+            ("Recently deleted", "recently_deleted", self.recently_deleted.as_ref()),
+        ];
+
+        for (heading, id, changes) in lists {
+            spaced_heading(ui, heading);
+
+            let changes = match changes {
+                Some(changes) => changes,
+                None => {
+                    ui.spinner();
+                    continue;
+                }
+            };
+
+            if changes.is_empty() {
+                ui.weak("Nothing yet.");
+                continue;
+            }
+
+            ui.push_id(id, |ui| {
+                properties_list(ui).body(|mut body| {
+                    for change in changes {
+                        let what = match change.action {
+                            PigChangeAction::Created => format!("🐖 {}", change.name),
+                            PigChangeAction::Renamed => {
+                                format!("✏ {} → {}", change.previous_name.as_deref().unwrap_or_default(), change.name)
+                            }
+                            PigChangeAction::Deleted => format!("🗑 {}", change.name),
+                        };
+
+                        body.row(TABLE_ROW_HEIGHT_LARGE, |mut row| {
+                            row.col(|ui| {
+                                time_label(ui, change.changed, &settings);
+                            });
+                            row.col(|ui| {
+                                // Deleted pigs can't be opened anymore
+                                if change.action == PigChangeAction::Deleted {
+                                    ui.label(what);
+                                } else if ui.link(what).clicked() {
+                                    ui.ctx().open_url(OpenUrl::same_tab(format!("/pigs#{}", change.pig)));
+                                }
+                                ui.weak("by");
+                                username_label(ui, &mut state.usernames, &change.actor);
+                            });
+                        });
+                    }
                 });
             });
         }
@@ -313,7 +1083,7 @@ impl PigPageRender {
                     if ui.button("✔ Yes").clicked() {
                         match state.pages.pigs.selection.as_ref() {
                             Some(pig) => self.pig_api.delete.request(pig.id),
-                            None => state.pages.layout.display_error.push(ApiError::new("You tried to delete a pig without having one selected, how the fuck did you manage that?".to_owned())),
+                            None => state.page_error(ApiError::new("You tried to delete a pig without having one selected, how the fuck did you manage that?".to_owned())),
                         }
                         self.delete_modal = false;
                     }
@@ -324,6 +1094,48 @@ impl PigPageRender {
             }
         }
 
+        if let Some(count) = self.archive_matching_modal {
+            let body = format!(
+                "This will archive {} pigs matching the current search. They can still be found, but can't be edited until they're unarchived one at a time.",
+                count
+            );
+            let modal = Modal::new("archive_matching")
+                .with_heading("Confirm Archiving")
+                .with_body(body)
+                .show_with_extras(ctx, |ui| {
+                    if ui.add_enabled(count > 0, Button::new("🗄 Archive")).clicked() {
+                        let query = self.archive_query(state);
+                        self.archive_confirmed = true;
+                        self.archive_matching_modal = None;
+                        self.pig_api.archive_matching.request((&query, false));
+                    }
+                });
+
+            if modal.should_close() {
+                self.archive_matching_modal = None;
+            }
+        }
+
+        if self.batch_delete_modal {
+            let body = format!(
+                "Are you sure you want to delete these {} pigs? There's no going back after this!",
+                self.checked.len()
+            );
+            let modal = Modal::new("batch_delete").with_heading("Confirm Deletion").with_body(body).show_with_extras(
+                ctx,
+                |ui| {
+                    if ui.button("✔ Yes").clicked() {
+                        self.pig_api.batch_delete.request(&self.checked.iter().copied().collect());
+                        self.batch_delete_modal = false;
+                    }
+                },
+            );
+
+            if modal.should_close() {
+                self.batch_delete_modal = false;
+            }
+        }
+
         if !matches!(self.dirty_modal, PigPageDirtyAction::None) {
             if let Some(do_action) = Modal::dirty(ctx) {
                 if do_action {
@@ -345,11 +1157,66 @@ impl PigPageRender {
         }
     }
 
-    /// Sends a fetch request for all results of the current query and clears
-    /// the list of current results
+    /// Updates the given pig in the sidebar to match its saved changes, before
+    /// the server confirms them
+    fn show_saved(&mut self, saved: &Pig) {
+        if let Some(pig) = self.query_results.iter_mut().flatten().find(|pig| pig.id == saved.id) {
+            pig.name = saved.name.to_owned();
+            pig.tags = saved.tags.to_owned();
+            pig.label = saved.label;
+        }
+    }
+
+    /// Sends a fetch request for all results of the current query and replaces
+    /// the list of current results with any cached ones
     fn do_query(&mut self, state: &mut ClientState) {
-        self.query_results = None;
-        self.pig_api.fetch.request(PigQuery::default().with_name(&state.pages.pigs.query));
+        let query = state.pages.pigs.search_query();
+        let key = query.to_yuri();
+
+        // Show the last results for this search right away, if we have them, while the fresh ones load
+        self.query_edited = None;
+        self.query_results = state.cache.pigs.get(&key);
+        self.fetched_query = Some(key);
+        self.pig_api.fetch.request(query);
+    }
+
+    /// Applies changes someone else made to the pigs shown in the sidebar.
+    /// Deleted pigs are removed right away, and only the pigs which were
+    /// created or updated are fetched again instead of the whole list.
+    fn reconcile(&mut self, state: &mut ClientState, changes: Reconciliation) {
+        state.cache.pigs.reconcile(&changes);
+
+        let results = match self.query_results.as_mut() {
+            Some(results) if !changes.refetch => results,
+            _ => return self.refresh_query(state),
+        };
+
+        changes.remove_deleted(results);
+        self.checked.retain(|id| !changes.deleted.contains(id));
+
+        // Anything still being fetched from before is fetched again with these
+        self.changed.extend(changes.changed);
+        if !self.changed.is_empty() {
+            let ids: Vec<Uuid> = self.changed.iter().cloned().collect();
+            let query = state.pages.pigs.search_query().with_ids(&ids).with_offset(0).with_limit(ids.len() as u32);
+            self.fetch_changed.request(query);
+        }
+    }
+
+    /// Sends the current query again without replacing the results shown,
+    /// for when they may have been changed by someone else
+    fn refresh_query(&mut self, state: &ClientState) {
+        let query = state.pages.pigs.search_query();
+        self.fetched_query = Some(query.to_yuri());
+        self.pig_api.fetch.request(query);
+    }
+
+    /// Goes back to the first page of results, then sends the query. Use this
+    /// when the search itself changes.
+    fn new_query(&mut self, state: &mut ClientState) {
+        self.checked.clear();
+        state.pages.pigs.pagination.reset();
+        self.do_query(state);
     }
 
     /// If the dirty var is true, warn the user with a modal before performing
@@ -374,6 +1241,11 @@ impl PigPageRender {
                 state.pages.pigs.selection = selection.as_ref().and_then(|pig| Some(pig.to_owned()));
                 update_url_hash(ctx, url, state.pages.pigs.selection.as_ref().and_then(|pig| Some(pig.id)))
             }
+            PigPageDirtyAction::Open(id) => {
+                // Unsaved changes are discarded right away, the new selection is made once it's fetched
+                state.pages.pigs.selection = None;
+                self.fetch_url_selection.request(PigQuery::default().with_id(id).with_limit(1).with_creator_username());
+            }
             PigPageDirtyAction::None => {}
         }
         // Reset dirty state, how tf did i forget this?
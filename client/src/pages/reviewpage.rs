@@ -0,0 +1,153 @@
+use crate::data::api::{BulkApi, Status};
+use crate::data::state::ClientState;
+use crate::pages::errpage::ErrPageRender;
+use crate::pages::RenderPage;
+use crate::ui::style::{
+    PANEL_WIDTH_MEDIUM, SPACE_MEDIUM, TABLE_COLUMN_WIDTH_SMALL, TABLE_ROW_HEIGHT_LARGE, TABLE_ROW_HEIGHT_SMALL,
+};
+use eframe::emath::Align;
+use egui::{Button, CentralPanel, Context, Layout, Ui};
+use egui_extras::{Column, TableBody, TableBuilder};
+use pigweb_common::bulk::{BulkImport, BulkReview};
+use pigweb_common::errors::ErrorCode;
+use pigweb_common::users::Roles;
+use pigweb_common::{yuri, BULK_API_ROOT};
+use urlable::ParsedURL;
+
+/// Responsible for rendering [`crate::pages::Routes::Review`]
+pub struct ReviewPageRender {
+    /// Handles sending and receiving API data
+    bulk_api: BulkApi,
+
+    /// Every import with names flagged for review
+    imports: Option<Vec<BulkImport>>,
+
+    /// Whether we're waiting on the server to apply a review
+    reviewing: bool,
+}
+
+impl Default for ReviewPageRender {
+    fn default() -> Self {
+        Self { bulk_api: BulkApi::default(), imports: None, reviewing: false }
+    }
+}
+
+impl RenderPage for ReviewPageRender {
+    fn open(&mut self, _ctx: &Context, _state: &mut ClientState, _url: &ParsedURL) {
+        self.bulk_api.flagged.request(true);
+    }
+
+    fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
+        if !state.has_role(Roles::Moderator) {
+            ErrPageRender::forbidden(&[Roles::Moderator]).ui(ui, state, url);
+            return;
+        }
+
+        self.process_promises(state);
+
+        CentralPanel::default().frame(egui::Frame::NONE).show_inside(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_max_width(PANEL_WIDTH_MEDIUM);
+                ui.add_space(SPACE_MEDIUM);
+
+                // Only add the table if there's something to review
+                if self.imports.as_ref().is_some_and(|imports| !imports.is_empty()) {
+                    TableBuilder::new(ui)
+                        .striped(true)
+                        .cell_layout(Layout::left_to_right(Align::Center))
+                        .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL))
+                        .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL))
+                        .column(Column::remainder())
+                        .header(TABLE_ROW_HEIGHT_LARGE, |mut header| {
+                            header.col(|ui| {
+                                ui.heading("name");
+                            });
+                            header.col(|ui| {
+                                ui.heading("import");
+                            });
+                            header.col(|_ui| {});
+                        })
+                        .body(|mut body| self.add_flagged_rows(&mut body, state));
+                } else if self.imports.is_none() {
+                    ui.spinner();
+                } else {
+                    ui.label("Nothing needs reviewing right now.");
+                }
+            });
+        });
+    }
+}
+
+impl ReviewPageRender {
+    /// Checks all APIs for data received from previously submitted requests
+    fn process_promises(&mut self, state: &mut ClientState) {
+        if let Some(imports) = self.bulk_api.flagged.received(state) {
+            self.imports = Some(imports);
+        }
+
+        match self.bulk_api.review.resolve() {
+            Status::Received(import) => {
+                self.reviewing = false;
+
+                // Swap in the updated import, dropping it once nothing's left to review
+                if let Some(imports) = self.imports.as_mut() {
+                    imports.retain(|i| i.id != import.id);
+                    if !import.flagged.is_empty() {
+                        imports.push(import);
+                        imports.sort_by_key(|i| i.started);
+                    }
+                }
+            }
+            // someone else already reviewed the name, get the latest list
+            Status::Errored(err) if err.kind == Some(ErrorCode::Conflict) => {
+                self.reviewing = false;
+                self.bulk_api.flagged.request(true);
+            }
+            Status::Errored(err) if err.kind == Some(ErrorCode::Unauthorized) => {
+                self.reviewing = false;
+                state.authorized = None;
+            }
+            Status::Errored(err) => {
+                self.reviewing = false;
+                state.page_error(err);
+            }
+            Status::Pending => {}
+        }
+    }
+
+    /// Populates the given table body with each flagged name
+    fn add_flagged_rows(&mut self, body: &mut TableBody, state: &mut ClientState) {
+        let can_review = !self.reviewing && state.can_request(&yuri!(BULK_API_ROOT, "review"));
+        let mut review = None;
+
+        for import in self.imports.as_ref().unwrap() {
+            for name in &import.flagged {
+                body.row(TABLE_ROW_HEIGHT_SMALL, |mut row| {
+                    row.col(|ui| {
+                        ui.label(name);
+                    });
+
+                    row.col(|ui| {
+                        ui.label(&import.name);
+                    });
+
+                    row.col(|ui| {
+                        let approve = ui.add_enabled(can_review, Button::new("✔ Approve"));
+                        if approve.on_hover_text("Send it back to be imported").clicked() {
+                            review = Some(BulkReview { id: import.id, name: name.to_owned(), approved: true });
+                        }
+
+                        if ui.add_enabled(can_review, Button::new("🗙 Reject")).clicked() {
+                            review = Some(BulkReview { id: import.id, name: name.to_owned(), approved: false });
+                        }
+                    });
+                });
+            }
+        }
+
+        if let Some(review) = review {
+            self.bulk_api.review.request(&review);
+            self.reviewing = true;
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use crate::data::api::TokenApi;
+use crate::data::settings::{Settings, TimeFormat};
+use crate::data::state::ClientState;
+use crate::pages::{RenderPage, Routes};
+use crate::ui::modal::Modal;
+use crate::ui::request_spinner;
+use crate::ui::style::{palette, set_theme, theme, PANEL_WIDTH_MEDIUM, TABLE_ROW_HEIGHT_LARGE, THEME_ACCENT};
+use crate::ui::time::time_label;
+use crate::ui::{add_properties_row, properties_list, spaced_heading, PAGE_SIZES};
+use chrono::{Days, Local, Utc};
+use egui::color_picker::color_edit_button_srgb;
+use egui::{Button, CentralPanel, CollapsingHeader, ComboBox, Context, RichText, TextEdit, Ui};
+use pigweb_common::tokens::{ApiToken, ApiTokenRequest};
+use pigweb_common::users::Roles;
+use urlable::ParsedURL;
+use uuid::Uuid;
+
+/// How long new API tokens can last in days, [`None`] meaning they never expire
+const TOKEN_LIFETIMES: [Option<u64>; 5] = [None, Some(7), Some(30), Some(90), Some(365)];
+
+/// Responsible for rendering [`crate::pages::Routes::Settings`]
+#[derive(Default)]
+pub struct SettingsPageRender {
+    /// Whether to show the modal to confirm clearing local data
+    clear_modal: bool,
+
+    /// Handles sending and receiving API token data
+    token_api: TokenApi,
+
+    /// The user's API tokens, newest first
+    tokens: Option<Vec<ApiToken>>,
+
+    /// The token the user is about to create
+    new_token: ApiTokenRequest,
+
+    /// How many days the new token lasts, see [`TOKEN_LIFETIMES`]
+    new_token_lifetime: Option<u64>,
+
+    /// The secret of a token which was just created or rotated, shown until
+    /// the user closes it since it can't be shown again
+    secret: Option<String>,
+
+    /// The token to revoke, if the user has been asked to confirm it
+    revoke: Option<Uuid>,
+}
+
+impl RenderPage for SettingsPageRender {
+    fn open(&mut self, _ctx: &Context, _state: &mut ClientState, _url: &ParsedURL) {
+        self.token_api.list.request(false); // this arg doesn't matter
+    }
+
+    fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, _url: &ParsedURL) {
+        // The layout asks the user to sign in first
+        if state.authorized.is_none() {
+            return;
+        }
+
+        self.process_promises(state);
+        self.show_token_modals(ui.ctx());
+
+        let before = state.settings.clone();
+
+        CentralPanel::default().frame(egui::Frame::NONE).show_inside(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_max_width(PANEL_WIDTH_MEDIUM);
+                spaced_heading(ui, "Settings");
+
+                let landing_pages = landing_pages(state);
+                let max_limit = state.max_limit();
+                let settings = &mut state.settings;
+                let colorix = &mut state.colorix;
+
+                properties_list(ui).body(|mut body| {
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "time format", |ui| {
+                        // Show the current time in each format so users can tell them apart
+                        let now = Local::now();
+                        ComboBox::from_id_salt("time_format")
+                            .selected_text(now.format(settings.time_format.pattern()).to_string())
+                            .show_ui(ui, |ui| {
+                                for format in TimeFormat::ALL {
+                                    let example = now.format(format.pattern()).to_string();
+                                    ui.selectable_value(&mut settings.time_format, format, example);
+                                }
+                            });
+                    });
+
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "relative times", |ui| {
+                        ui.checkbox(&mut settings.relative_times, "Show how long ago things happened");
+                    });
+
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "page size", |ui| {
+                        ComboBox::from_id_salt("page_size").selected_text(settings.page_size.to_string()).show_ui(
+                            ui,
+                            |ui| {
+                                for size in
+                                    PAGE_SIZES.into_iter().filter(|size| max_limit.is_none_or(|max| *size <= max))
+                                {
+                                    ui.selectable_value(&mut settings.page_size, size, size.to_string());
+                                }
+                            },
+                        );
+                    });
+
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "accent color", |ui| {
+                        // Picking an accent replaces any custom palette, otherwise it wouldn't do anything
+                        let mut color = settings.accent_color.unwrap_or(THEME_ACCENT);
+                        if color_edit_button_srgb(ui, &mut color).changed() {
+                            settings.accent_color = Some(color);
+                            settings.palette = None;
+                        }
+
+                        let custom = settings.accent_color.is_some() || settings.palette.is_some();
+                        if ui.add_enabled(custom, Button::new("⟲ Reset")).clicked() {
+                            settings.accent_color = None;
+                            settings.palette = None;
+                        }
+                    });
+
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "confirm deletion", |ui| {
+                        ui.checkbox(&mut settings.confirm_delete, "Ask before deleting anything");
+                    });
+
+                    add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "landing page", |ui| {
+                        let selected = landing_pages
+                            .iter()
+                            .find(|(route, _)| *route == settings.landing_page)
+                            .map(|(_, label)| *label)
+                            .unwrap_or("Pigs");
+
+                        ComboBox::from_id_salt("landing_page").selected_text(selected).show_ui(ui, |ui| {
+                            for (route, label) in &landing_pages {
+                                ui.selectable_value(&mut settings.landing_page, *route, *label);
+                            }
+                        });
+                    });
+                });
+
+                // Colorix applies changes as they're made, we just have to remember them
+                CollapsingHeader::new("Theme editor").show(ui, |ui| {
+                    colorix.ui_combo_12(ui, false);
+
+                    let palette = palette(colorix);
+                    if palette != current_palette(&before) {
+                        settings.palette = Some(palette);
+                    }
+                });
+
+                if ui.add_enabled(*settings != Settings::default(), Button::new("⟲ Reset all")).clicked() {
+                    *settings = Settings::default();
+                }
+
+                // For when something saved on this device is stuck in a bad state
+                if ui.button("🗑 Clear local data").clicked() {
+                    self.clear_modal = true;
+                }
+
+                CollapsingHeader::new("API tokens").show(ui, |ui| {
+                    self.tokens_ui(ui, state);
+                });
+            });
+        });
+
+        if self.clear_modal {
+            let mut submit = false;
+
+            let modal = Modal::new("clear")
+                .with_heading("Clear Local Data")
+                .with_body(
+                    "Are you sure you want to forget your settings, searches, selections, and unsaved changes on this \
+                    device? Changes waiting to be sent once you sign back in will be lost too.",
+                )
+                .show_with_extras(ui.ctx(), |ui| {
+                    if ui.button("✔ Yes").clicked() {
+                        submit = true;
+                    }
+                });
+
+            if submit {
+                state.clear_local_data();
+                self.clear_modal = false;
+            } else if modal.should_close() {
+                self.clear_modal = false;
+            }
+        }
+
+        apply_changes(ui, state, &before);
+    }
+}
+
+impl SettingsPageRender {
+    /// Checks the token API for data received from previously submitted requests
+    fn process_promises(&mut self, state: &mut ClientState) {
+        if let Some(tokens) = self.token_api.list.received(state) {
+            self.tokens = Some(tokens);
+        }
+
+        if let Some(res) = self.token_api.create.received(state) {
+            self.secret = Some(res.secret);
+            self.new_token = ApiTokenRequest::default();
+            self.token_api.list.request(false);
+        }
+
+        if let Some(res) = self.token_api.rotate.received(state) {
+            self.secret = Some(res.secret);
+            self.token_api.list.request(false);
+        }
+
+        if self.token_api.revoke.received(state).is_some() {
+            self.token_api.list.request(false);
+        }
+    }
+
+    /// Lists the user's API tokens, followed by a form to create another one
+    fn tokens_ui(&mut self, ui: &mut Ui, state: &ClientState) {
+        ui.label(
+            "Tokens let scripts use the API as you by sending an \"Authorization: Bearer\" header. They can only do \
+            what their roles allow, and can't manage tokens themselves.",
+        );
+
+        let now = Utc::now().naive_utc();
+        match self.tokens.as_ref() {
+            None => {
+                request_spinner(ui, self.token_api.list.attempt());
+            }
+            Some(tokens) if tokens.is_empty() => {
+                ui.weak("You don't have any tokens yet.");
+            }
+            Some(tokens) => {
+                for token in tokens {
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.strong(token.name.as_str());
+                            let roles: Vec<&str> = token.roles.iter().map(|role| role.name()).collect();
+                            ui.weak(roles.join(", "));
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.weak("created");
+                            time_label(ui, token.created, &state.settings);
+
+                            ui.weak("expires");
+                            match token.expires {
+                                Some(expires) => {
+                                    time_label(ui, expires, &state.settings);
+                                    if token.is_expired(now) {
+                                        ui.colored_label(ui.visuals().error_fg_color, "(expired)");
+                                    }
+                                }
+                                None => {
+                                    ui.label("never");
+                                }
+                            }
+
+                            ui.weak("last used");
+                            match token.last_used {
+                                Some(last_used) => time_label(ui, last_used, &state.settings),
+                                None => ui.label("never"),
+                            };
+                        });
+
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!token.is_expired(now), Button::new("⟳ Rotate")).clicked() {
+                                self.token_api.rotate.request(token.id);
+                            }
+
+                            if ui.button("🗑 Revoke").clicked() {
+                                match state.settings.confirm_delete {
+                                    true => self.revoke = Some(token.id),
+                                    false => self.token_api.revoke.request(token.id),
+                                }
+                            }
+                        });
+                    });
+                }
+            }
+        }
+
+        ui.separator();
+
+        // Tokens can't have roles the user doesn't
+        properties_list(ui).body(|mut body| {
+            add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "name", |ui| {
+                ui.add(TextEdit::singleline(&mut self.new_token.name).hint_text("What the token is for"));
+            });
+
+            add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "roles", |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for role in Roles::values().filter(|role| state.has_role(*role)) {
+                        let mut checked = self.new_token.roles.contains(&role);
+                        if ui.checkbox(&mut checked, role.name()).changed() {
+                            match checked {
+                                true => self.new_token.roles.insert(role),
+                                false => self.new_token.roles.remove(&role),
+                            };
+                        }
+                    }
+                });
+            });
+
+            add_properties_row(&mut body, TABLE_ROW_HEIGHT_LARGE, "expires", |ui| {
+                ComboBox::from_id_salt("token_lifetime")
+                    .selected_text(lifetime_label(self.new_token_lifetime))
+                    .show_ui(ui, |ui| {
+                        for lifetime in TOKEN_LIFETIMES {
+                            ui.selectable_value(&mut self.new_token_lifetime, lifetime, lifetime_label(lifetime));
+                        }
+                    });
+            });
+        });
+
+        let valid = !self.new_token.name.trim().is_empty() && !self.new_token.roles.is_empty();
+        if ui.add_enabled(valid && !self.token_api.create.is_pending(), Button::new("➕ Create token")).clicked() {
+            // Count the lifetime from when the token is created, not when the form was opened
+            self.new_token.expires = self.new_token_lifetime.and_then(|days| now.checked_add_days(Days::new(days)));
+            self.token_api.create.request(&self.new_token);
+        }
+    }
+
+    /// Shows the secret of a new token and the confirmation to revoke a token
+    fn show_token_modals(&mut self, ctx: &Context) {
+        if let Some(secret) = self.secret.as_ref() {
+            let mut copy = false;
+
+            let modal = Modal::new("token_secret")
+                .with_heading("API Token")
+                .with_body("Copy this token now, it won't be shown again.")
+                .show_with_extras(ctx, |ui| {
+                    ui.label(RichText::new(secret).monospace());
+                    if ui.button("🗐 Copy").clicked() {
+                        copy = true;
+                    }
+                });
+
+            if copy {
+                ctx.copy_text(secret.to_owned());
+            }
+
+            if modal.should_close() {
+                self.secret = None;
+            }
+        }
+
+        if let Some(id) = self.revoke {
+            let mut submit = false;
+
+            let modal = Modal::new("revoke_token")
+                .with_heading("Revoke Token")
+                .with_body("Are you sure you want to revoke this token? Anything using it will stop working.")
+                .show_with_extras(ctx, |ui| {
+                    if ui.button("✔ Yes").clicked() {
+                        submit = true;
+                    }
+                });
+
+            if submit {
+                self.token_api.revoke.request(id);
+                self.revoke = None;
+            } else if modal.should_close() {
+                self.revoke = None;
+            }
+        }
+    }
+}
+
+/// Describes how long a new API token lasts
+fn lifetime_label(lifetime: Option<u64>) -> String {
+    match lifetime {
+        Some(days) => format!("in {} days", days),
+        None => "never".to_owned(),
+    }
+}
+
+/// Applies any settings which changed from `before` to the rest of the app
+fn apply_changes(ui: &Ui, state: &mut ClientState, before: &Settings) {
+    // The theme editor applies its own changes, so only re-theme if something else did
+    if palette(&state.colorix) != current_palette(&state.settings) {
+        state.colorix = set_theme(ui.ctx(), &state.settings);
+    }
+
+    // Lists remember their own page size, so update the ones which are saved
+    if state.settings.page_size != before.page_size {
+        for pagination in [&mut state.pages.pigs.pagination, &mut state.pages.bulk.sidebar_pagination] {
+            pagination.page_size = state.settings.page_size;
+            pagination.reset();
+        }
+    }
+}
+
+/// Gets every color in the theme the given settings would apply as RGB
+fn current_palette(settings: &Settings) -> [[u8; 3]; 12] {
+    settings.palette.unwrap_or_else(|| theme(settings.accent_color.unwrap_or(THEME_ACCENT)).map(|color| color.rgb()))
+}
+
+/// Gets the pages the user can pick to land on, along with their names
+fn landing_pages(state: &ClientState) -> Vec<(Routes, &'static str)> {
+    let mut res = vec![(Routes::Pigs, "Pigs")];
+
+    if state.has_role(Roles::PigViewer) {
+        res.push((Routes::Tags, "Tags"));
+    }
+
+    if state.has_role(Roles::BulkEditor) || state.has_role(Roles::BulkAdmin) {
+        res.push((Routes::Bulk, "Import"));
+    }
+
+    if state.has_role(Roles::Moderator) {
+        res.push((Routes::Review, "Review"));
+    }
+
+    if state.has_role(Roles::UserViewer) {
+        res.push((Routes::Users, "Users"));
+    }
+
+    res
+}
@@ -0,0 +1,261 @@
+use crate::data::api::{BulkFetchHandler, PigFetchHandler, UserFetchHandler};
+use crate::data::state::ClientState;
+use crate::ui::style::{PANEL_WIDTH_MEDIUM, SPACE_SMALL};
+use egui::{Button, Context, Id, Key, Modifiers, OpenUrl, TextEdit};
+use pigweb_common::bulk::BulkQuery;
+use pigweb_common::pigs::PigQuery;
+use pigweb_common::users::{Roles, UserQuery};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long to wait after the search box was last edited before searching, in
+/// seconds, so we don't send a request for every keystroke
+const SEARCH_DEBOUNCE: f64 = 0.25;
+
+/// The most results of each kind to list at once
+const RESULT_LIMIT: u32 = 5;
+
+/// Something the quick switcher found which the user can jump to
+#[derive(Debug, Clone)]
+enum SwitcherResult {
+    /// A pig's id and name
+    Pig(Uuid, String),
+
+    /// A bulk import's id and name
+    Import(Uuid, String),
+
+    /// A user's id and username
+    User(Uuid, String),
+}
+
+impl SwitcherResult {
+    /// What the result is listed as, with an icon for what kind it is
+    fn label(&self) -> String {
+        match self {
+            Self::Pig(_, name) => format!("🐖 {}", name),
+            Self::Import(_, name) => format!("📥 {}", name),
+            Self::User(_, name) => format!("😐 {}", name),
+        }
+    }
+
+    /// The page which shows the result, with it selected
+    fn url(&self) -> String {
+        match self {
+            Self::Pig(id, _) => format!("/pigs#{}", id),
+            Self::Import(id, _) => format!("/bulk#{}", id),
+            Self::User(id, _) => format!("/users#{}", id),
+        }
+    }
+}
+
+/// The app-wide quick switcher, opened with Ctrl+K. Searches pigs, imports,
+/// and users from one box and navigates to whichever result is chosen, so the
+/// user doesn't need to switch pages before searching.
+pub struct SwitcherRender {
+    /// Whether the switcher is showing
+    pub open: bool,
+
+    /// What the user is searching for
+    query: String,
+
+    /// When the search box was last edited, if we haven't searched for it yet
+    query_edited: Option<f64>,
+
+    /// Searches for pigs
+    pigs: PigFetchHandler,
+
+    /// Searches for imports
+    imports: BulkFetchHandler,
+
+    /// Searches for users
+    users: UserFetchHandler,
+
+    /// The pigs which matched the last search
+    pig_results: Vec<SwitcherResult>,
+
+    /// The imports which matched the last search
+    import_results: Vec<SwitcherResult>,
+
+    /// The users which matched the last search
+    user_results: Vec<SwitcherResult>,
+
+    /// The index of the result which is opened by pressing enter
+    highlighted: usize,
+}
+
+impl Default for SwitcherRender {
+    fn default() -> Self {
+        Self {
+            open: false,
+            query: String::default(),
+            query_edited: None,
+            pigs: PigFetchHandler::default(),
+            imports: BulkFetchHandler::default(),
+            users: UserFetchHandler::default(),
+            pig_results: Vec::new(),
+            import_results: Vec::new(),
+            user_results: Vec::new(),
+            highlighted: 0,
+        }
+    }
+}
+
+impl SwitcherRender {
+    /// Toggles the switcher when Ctrl+K is pressed and shows it if it's open
+    pub fn show(&mut self, ctx: &Context, state: &mut ClientState) {
+        if ctx.input_mut(|input| input.consume_key(Modifiers::COMMAND, Key::K)) {
+            self.toggle();
+        }
+
+        if !self.open || state.authorized.is_none() {
+            return;
+        }
+
+        self.process_promises(state);
+
+        // Wait for the user to stop typing before searching
+        if let Some(edited) = self.query_edited {
+            let waited = ctx.input(|input| input.time) - edited;
+            if waited >= SEARCH_DEBOUNCE {
+                self.search(state);
+            } else {
+                ctx.request_repaint_after(Duration::from_secs_f64(SEARCH_DEBOUNCE - waited));
+            }
+        }
+
+        // Keyboard navigation, so the user never has to reach for the mouse
+        let results: Vec<SwitcherResult> = self
+            .pig_results
+            .iter()
+            .chain(self.import_results.iter())
+            .chain(self.user_results.iter())
+            .cloned()
+            .collect();
+        let (up, down, enter) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::Enter),
+            )
+        });
+
+        if up {
+            self.highlighted = self.highlighted.saturating_sub(1);
+        }
+        if down && self.highlighted + 1 < results.len() {
+            self.highlighted += 1;
+        }
+
+        let mut chosen = results.get(self.highlighted).filter(|_| enter).cloned();
+
+        let modal = egui::Modal::new(Id::new("switcher")).show(ctx, |ui| {
+            ui.set_width(PANEL_WIDTH_MEDIUM);
+
+            let search = TextEdit::singleline(&mut self.query)
+                .hint_text("Search pigs, imports, and users")
+                .desired_width(f32::INFINITY);
+            let search = ui.add(search);
+            search.request_focus();
+
+            if search.changed() {
+                self.query_edited = Some(ui.input(|input| input.time));
+            }
+
+            ui.add_space(SPACE_SMALL);
+
+            for (i, result) in results.iter().enumerate() {
+                if ui.add(Button::selectable(i == self.highlighted, result.label())).clicked() {
+                    chosen = Some(result.to_owned());
+                }
+            }
+
+            let searching = self.query_edited.is_some() || (!self.query.trim().is_empty() && self.is_pending());
+            if searching {
+                ui.vertical_centered(|ui| ui.spinner());
+            } else if results.is_empty() && !self.query.trim().is_empty() {
+                ui.weak("Nothing found.");
+            }
+
+            ui.separator();
+            ui.weak("↑↓ to choose, enter to open, esc to close");
+        });
+
+        if let Some(result) = chosen {
+            ctx.open_url(OpenUrl::same_tab(result.url()));
+            self.open = false;
+        } else if modal.should_close() {
+            self.open = false;
+        }
+    }
+
+    /// Opens the switcher with an empty search, or closes it if it's open
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        if self.open {
+            self.query = String::default();
+            self.query_edited = None;
+            self.clear_results();
+        }
+    }
+
+    /// Checks all APIs for data received from previously submitted requests
+    fn process_promises(&mut self, state: &mut ClientState) {
+        if let Some(pigs) = self.pigs.received(state) {
+            self.pig_results = pigs.into_iter().map(|pig| SwitcherResult::Pig(pig.id, pig.name.to_owned())).collect();
+        }
+
+        if let Some(imports) = self.imports.received(state) {
+            self.import_results =
+                imports.into_iter().map(|import| SwitcherResult::Import(import.id, import.name.to_owned())).collect();
+        }
+
+        if let Some(res) = self.users.received(state) {
+            // Only the usernames were asked for, so they're all we get
+            self.user_results = res
+                .usernames
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(id, username)| SwitcherResult::User(id, username))
+                .collect();
+        }
+    }
+
+    /// Searches everything the user is allowed to see for the current query
+    fn search(&mut self, state: &ClientState) {
+        self.query_edited = None;
+        self.clear_results();
+
+        let query = self.query.trim().to_owned();
+        if query.is_empty() {
+            return;
+        }
+
+        if state.has_role(Roles::PigViewer) {
+            self.pigs
+                .request(PigQuery::default().with_name(&query).with_fields(&["id", "name"]).with_limit(RESULT_LIMIT));
+        }
+
+        if state.has_role(Roles::BulkEditor) || state.has_role(Roles::BulkAdmin) {
+            self.imports.request(&BulkQuery::default().with_name(&query).with_limit(RESULT_LIMIT));
+        }
+
+        if state.has_role(Roles::UserViewer) {
+            self.users.request(
+                UserQuery::default().with_username(&query).with_fields(&["id", "username"]).with_limit(RESULT_LIMIT),
+            );
+        }
+    }
+
+    /// Forgets the results of the last search
+    fn clear_results(&mut self) {
+        self.pig_results.clear();
+        self.import_results.clear();
+        self.user_results.clear();
+        self.highlighted = 0;
+    }
+
+    /// Whether any search is still waiting on the server
+    fn is_pending(&self) -> bool {
+        self.pigs.is_pending() || self.imports.is_pending() || self.users.is_pending()
+    }
+}
@@ -0,0 +1,191 @@
+use crate::data::api::{RetryPolicy, TagApi, TagFetchHandler};
+use crate::data::state::ClientState;
+use crate::pages::errpage::ErrPageRender;
+use crate::pages::RenderPage;
+use crate::ui::modal::Modal;
+use crate::ui::request_spinner;
+use crate::ui::style::{
+    PANEL_WIDTH_MEDIUM, SPACE_MEDIUM, TABLE_COLUMN_WIDTH_SMALL, TABLE_ROW_HEIGHT_LARGE, TABLE_ROW_HEIGHT_SMALL,
+};
+use eframe::emath::Align;
+use egui::{Button, CentralPanel, Context, Layout, OpenUrl, TextEdit, Ui};
+use egui_extras::{Column, TableBody, TableBuilder};
+use log::info;
+use pigweb_common::tags::Tag;
+use pigweb_common::users::Roles;
+use pigweb_common::{yuri, TAG_API_ROOT};
+use urlable::ParsedURL;
+
+/// Responsible for rendering [`crate::pages::Routes::Tags`]
+pub struct TagPageRender {
+    /// Handles sending and receiving API data
+    tag_api: TagApi,
+
+    /// Every tag in use, along with how many pigs have it
+    tags: Option<Vec<Tag>>,
+
+    /// The tag being renamed and what to rename it to
+    rename: Option<(String, String)>,
+
+    /// The tag to delete, if the user has been asked to confirm it
+    delete: Option<String>,
+}
+
+impl Default for TagPageRender {
+    fn default() -> Self {
+        let tag_api =
+            TagApi { fetch: TagFetchHandler::default().with_retry(RetryPolicy::default()), ..TagApi::default() };
+        Self { tag_api, tags: None, rename: None, delete: None }
+    }
+}
+
+impl RenderPage for TagPageRender {
+    fn open(&mut self, _ctx: &Context, _state: &mut ClientState, _url: &ParsedURL) {
+        self.tag_api.fetch.request(true);
+    }
+
+    fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
+        if !state.has_role(Roles::PigViewer) {
+            ErrPageRender::forbidden(&[Roles::PigViewer]).ui(ui, state, url);
+            return;
+        }
+
+        self.process_promises(state);
+        self.show_modals(ui.ctx());
+
+        CentralPanel::default().frame(egui::Frame::NONE).show_inside(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.set_max_width(PANEL_WIDTH_MEDIUM);
+                ui.add_space(SPACE_MEDIUM);
+
+                // Only add the table if we have tags loaded
+                if self.tags.as_ref().is_some_and(|tags| !tags.is_empty()) {
+                    TableBuilder::new(ui)
+                        .striped(true)
+                        .cell_layout(Layout::left_to_right(Align::Center))
+                        .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL))
+                        .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL / 2.0))
+                        .column(Column::remainder())
+                        .header(TABLE_ROW_HEIGHT_LARGE, |mut header| {
+                            header.col(|ui| {
+                                ui.heading("tag");
+                            });
+                            header.col(|ui| {
+                                ui.heading("pigs");
+                            });
+                            header.col(|_ui| {});
+                        })
+                        .body(|mut body| self.add_tag_rows(&mut body, state));
+                } else if self.tags.is_none() {
+                    request_spinner(ui, self.tag_api.fetch.attempt());
+                } else {
+                    ui.label("No pigs have been tagged yet.");
+                }
+            });
+        });
+    }
+}
+
+impl TagPageRender {
+    /// Checks all APIs for data received from previously submitted requests
+    fn process_promises(&mut self, state: &mut ClientState) {
+        if let Some(tags) = self.tag_api.fetch.received(state) {
+            self.tags = Some(tags);
+        }
+
+        if let Some(count) = self.tag_api.rename.received(state) {
+            info!("Renamed a tag on {} pigs", count);
+            self.tag_api.fetch.request(true);
+        }
+
+        if let Some(count) = self.tag_api.delete.received(state) {
+            info!("Deleted a tag from {} pigs", count);
+            self.tag_api.fetch.request(true);
+        }
+    }
+
+    /// Populates the given table body with the loaded tags
+    fn add_tag_rows(&mut self, body: &mut TableBody, state: &mut ClientState) {
+        let can_rename = state.can_request(&yuri!(TAG_API_ROOT, "rename"));
+        let can_delete = state.can_request(&yuri!(TAG_API_ROOT, "delete"));
+        let confirm_delete = state.settings.confirm_delete;
+
+        for tag in self.tags.as_ref().unwrap() {
+            body.row(TABLE_ROW_HEIGHT_SMALL, |mut row| {
+                row.col(|ui| {
+                    // Show only the pigs with this tag on the pig page
+                    if ui.button(format!("🏷 {}", tag.name)).on_hover_text("Show pigs with this tag").clicked() {
+                        state.pages.pigs.tag = Some(tag.name.to_owned());
+                        ui.ctx().open_url(OpenUrl::same_tab("/pigs"));
+                    }
+                });
+
+                row.col(|ui| {
+                    ui.label(tag.count.to_string());
+                });
+
+                row.col(|ui| {
+                    if ui.add_enabled(can_rename, Button::new("✏ Rename")).clicked() {
+                        self.rename = Some((tag.name.to_owned(), tag.name.to_owned()));
+                    }
+
+                    if ui.add_enabled(can_delete, Button::new("🗑 Delete")).clicked() {
+                        match confirm_delete {
+                            true => self.delete = Some(tag.name.to_owned()),
+                            false => self.tag_api.delete.request(tag.name.as_str()),
+                        }
+                    }
+                });
+            });
+        }
+    }
+
+    /// Shows the rename and delete modals if they're open
+    fn show_modals(&mut self, ctx: &Context) {
+        if let Some((from, to)) = self.rename.as_mut() {
+            let mut submit = false;
+
+            let modal = Modal::new("rename")
+                .with_heading("Rename Tag")
+                .with_body("If a tag with the new name already exists, the two will be merged.")
+                .show_with_extras(ctx, |ui| {
+                    ui.add(TextEdit::singleline(to).hint_text("New name"));
+
+                    let valid = !to.trim().is_empty() && to.trim() != from.as_str();
+                    if ui.add_enabled(valid, Button::new("✔ Rename")).clicked() {
+                        submit = true;
+                    }
+                });
+
+            if submit {
+                self.tag_api.rename.request((from.as_str(), to.trim()));
+                self.rename = None;
+            } else if modal.should_close() {
+                self.rename = None;
+            }
+        }
+
+        if let Some(tag) = self.delete.as_ref() {
+            let mut submit = false;
+
+            let modal = Modal::new("delete")
+                .with_heading("Confirm Deletion")
+                .with_body(format!(
+                    "Are you sure you want to remove \"{}\" from every pig? There's no going back after this!",
+                    tag
+                ))
+                .show_with_extras(ctx, |ui| {
+                    if ui.button("✔ Yes").clicked() {
+                        submit = true;
+                    }
+                });
+
+            if submit {
+                self.tag_api.delete.request(tag.as_str());
+                self.delete = None;
+            } else if modal.should_close() {
+                self.delete = None;
+            }
+        }
+    }
+}
@@ -1,17 +1,21 @@
-use crate::data::api::{ApiError, UserApi, UserFetchHandler};
+use crate::data::api::{ApiError, UserApi, UserFetchHandler, CLIENT_VERSION};
 use crate::data::state::ClientState;
+use crate::pages::errpage::ErrPageRender;
 use crate::pages::RenderPage;
 use crate::ui::style::{
-    PANEL_WIDTH_LARGE, SPACE_MEDIUM, TABLE_COLUMN_WIDTH_MEDIUM, TABLE_COLUMN_WIDTH_SMALL, TABLE_ROW_HEIGHT_LARGE,
-    TABLE_ROW_HEIGHT_SMALL, TIME_FMT,
+    COLOR_WARNING, PANEL_WIDTH_LARGE, SPACE_MEDIUM, TABLE_COLUMN_WIDTH_MEDIUM, TABLE_COLUMN_WIDTH_SMALL,
+    TABLE_ROW_HEIGHT_LARGE, TABLE_ROW_HEIGHT_SMALL,
 };
+use crate::ui::time::time_label;
+use crate::ui::{paginator, Pagination};
 use crate::update_url_hash;
-use chrono::{Local, Utc};
+use chrono::Utc;
 use eframe::emath::Align;
-use egui::{Button, CentralPanel, Context, Layout, Sense, Ui};
+use egui::{Button, CentralPanel, Context, Layout, RichText, Sense, Ui};
 use egui_extras::{Column, TableBody, TableBuilder};
 use log::{debug, error};
 use pigweb_common::users::{Roles, User, UserQuery};
+use pigweb_common::{yuri, USER_API_ROOT};
 use std::collections::BTreeSet;
 use urlable::ParsedURL;
 use uuid::Uuid;
@@ -29,9 +33,12 @@ pub struct UserPageRender {
     /// Handles API data specifically when getting the selection from the URL
     fetch_url_selection: UserFetchHandler,
 
-    /// The full list of users registered in the app
+    /// The current page of users registered in the app
     users: Option<Vec<User>>,
 
+    /// Which page of users is shown
+    pagination: Pagination,
+
     /// The currently selected user
     selection: Option<User>,
 
@@ -45,6 +52,7 @@ impl Default for UserPageRender {
             user_api: UserApi::default(),
             fetch_url_selection: UserFetchHandler::default(),
             users: None,
+            pagination: Pagination::default(),
             selection: None,
             roles: None,
         }
@@ -71,11 +79,7 @@ impl RenderPage for UserPageRender {
                     }
                 }
                 Err(err) => {
-                    state
-                        .pages
-                        .layout
-                        .display_error
-                        .push(ApiError::new(err.to_string()).with_reason("Unable to parse UUID.".to_owned()));
+                    state.page_error(ApiError::new(err.to_string()).with_reason("Unable to parse UUID.".to_owned()));
                     update_url_hash(ctx, url, None);
                     error!("Unable to parse hash \"{:?}\", err: {:?}", &stripped_hash, err);
                 }
@@ -89,13 +93,18 @@ impl RenderPage for UserPageRender {
         }
     }
 
-    fn open(&mut self, _ctx: &Context, _state: &mut ClientState, _url: &ParsedURL) {
+    fn open(&mut self, _ctx: &Context, state: &mut ClientState, _url: &ParsedURL) {
+        self.pagination.page_size = state.settings.page_size;
         self.fetch_users();
     }
 
+    fn selection_title(&self, _state: &ClientState) -> Option<String> {
+        self.selection.as_ref().map(|user| user.username.to_owned())
+    }
+
     fn ui(&mut self, ui: &mut Ui, state: &mut ClientState, url: &ParsedURL) {
         if !state.has_role(Roles::UserViewer) {
-            // TODO 403 Forbidden
+            ErrPageRender::forbidden(&[Roles::UserViewer]).ui(ui, state, url);
             return;
         }
 
@@ -108,6 +117,11 @@ impl RenderPage for UserPageRender {
                 ui.set_max_width(PANEL_WIDTH_LARGE);
                 ui.add_space(SPACE_MEDIUM);
 
+                let shown = self.users.as_ref().map(|users| users.len());
+                if paginator(ui, "user_pages", &mut self.pagination, shown, state.max_limit()) {
+                    self.fetch_users();
+                }
+
                 // Only add the table if we have users loaded
                 if self.users.as_ref().is_some_and(|users| !users.is_empty()) {
                     TableBuilder::new(ui)
@@ -118,6 +132,7 @@ impl RenderPage for UserPageRender {
                         .column(Column::initial(TABLE_COLUMN_WIDTH_MEDIUM))
                         .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL))
                         .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL))
+                        .column(Column::initial(TABLE_COLUMN_WIDTH_SMALL))
                         .header(TABLE_ROW_HEIGHT_LARGE, |mut header| {
                             header.col(|ui| {
                                 ui.heading("id");
@@ -128,6 +143,9 @@ impl RenderPage for UserPageRender {
                             header.col(|ui| {
                                 ui.heading("last seen");
                             });
+                            header.col(|ui| {
+                                ui.heading("client");
+                            });
                             header.col(|ui| {
                                 ui.heading("session");
                             });
@@ -185,6 +203,9 @@ impl UserPageRender {
     /// all in one shot rather than having a function per user or else borrow
     /// checker complains
     fn add_user_rows(&mut self, body: &mut TableBody, state: &mut ClientState, url: &ParsedURL) {
+        let can_expire = state.can_request(&yuri!(USER_API_ROOT, "expire"));
+        let settings = state.settings.clone();
+
         for user in self.users.as_ref().unwrap() {
             let selected = self.selection.as_ref().is_some_and(|sel| sel.id == user.id);
 
@@ -200,15 +221,28 @@ impl UserPageRender {
                 });
 
                 row.col(|ui| {
-                    let time = user.seen.and_utc().with_timezone(&Local);
-                    ui.label(time.format(TIME_FMT).to_string());
+                    time_label(ui, user.seen, &settings);
+                });
+
+                row.col(|ui| {
+                    // A different version than ours usually means their browser is running a cached build
+                    let res = match user.session_client_version.as_deref() {
+                        Some(CLIENT_VERSION) => ui.label(CLIENT_VERSION),
+                        Some(version) => ui
+                            .label(RichText::new(version).color(COLOR_WARNING))
+                            .on_hover_text(format!("This isn't the current version, {}", CLIENT_VERSION)),
+                        None => ui.weak("unknown"),
+                    };
+
+                    if let Some(user_agent) = user.session_user_agent.as_ref() {
+                        res.on_hover_text(user_agent);
+                    }
                 });
 
                 row.col(|ui| {
                     if ui
                         .add_enabled(
-                            user.session_exp
-                                .is_some_and(|time| state.has_role(Roles::UserAdmin) && time >= Utc::now().naive_utc()),
+                            user.session_exp.is_some_and(|time| can_expire && time >= Utc::now().naive_utc()),
                             Button::new("⌛ Expire"),
                         )
                         .clicked()
@@ -237,8 +271,12 @@ impl UserPageRender {
         }
     }
 
-    /// Sends a fetch request for all [`User`]s in the system
+    /// Sends a fetch request for the current page of [`User`]s in the system
+    /// and clears the current list
     fn fetch_users(&mut self) {
-        self.user_api.fetch.request(UserQuery::default())
+        self.users = None;
+        self.user_api
+            .fetch
+            .request(UserQuery::default().with_limit(self.pagination.limit()).with_offset(self.pagination.offset()))
     }
 }
@@ -1,11 +1,58 @@
-use crate::ui::style::{SPACE_MEDIUM, TABLE_COLUMN_WIDTH_SMALL, TABLE_ROW_HEIGHT_SMALL};
+use crate::data::usernames::UsernameCache;
+use crate::ui::style::{label_color, SPACE_MEDIUM, TABLE_COLUMN_WIDTH_SMALL, TABLE_ROW_HEIGHT_SMALL};
 use egui::text::LayoutJob;
-use egui::{Align, FontSelection, Galley, Layout, RichText, Sense, TextBuffer, Ui, WidgetText};
+use egui::{
+    vec2, Align, Button, ComboBox, FontSelection, Galley, Layout, Response, RichText, Sense, TextBuffer, Ui, WidgetText,
+};
 use egui_extras::{Column, TableBody, TableBuilder, TableRow};
+use pigweb_common::pigs::PigLabel;
+use pigweb_common::DEFAULT_API_RESPONSE_LIMIT;
+use std::hash::Hash;
 use std::sync::Arc;
+use uuid::Uuid;
 
 pub mod modal;
 pub mod style;
+pub mod time;
+
+/// The page sizes users can pick from in a [`paginator`], as long as the
+/// server lets them fetch that many at once
+pub const PAGE_SIZES: [u32; 6] = [25, 50, 100, 250, 500, 1000];
+
+/// Which page of a query's results to show and how many are on each page. Use
+/// [`Self::limit`] and [`Self::offset`] with the `with_limit` and
+/// `with_offset` builders on the query.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Pagination {
+    /// The current page, starting at 0
+    pub page: u32,
+
+    /// The most results shown on each page
+    pub page_size: u32,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self { page: 0, page_size: DEFAULT_API_RESPONSE_LIMIT }
+    }
+}
+
+impl Pagination {
+    /// The most results the query should return
+    pub fn limit(&self) -> u32 {
+        self.page_size
+    }
+
+    /// How many results the query should skip to get to the current page
+    pub fn offset(&self) -> u32 {
+        self.page * self.page_size
+    }
+
+    /// Goes back to the first page, e.g. when the search changes
+    pub fn reset(&mut self) {
+        self.page = 0;
+    }
+}
 
 /// Starts a two-column table meant to show the data in a struct. Start with
 /// this function, then call [`TableBuilder::body`] and use
@@ -85,29 +132,91 @@ pub fn selectable_list<T: Clone>(
         .column(Column::remainder())
         .sense(Sense::click())
         .cell_layout(Layout::left_to_right(Align::Center))
-        .body(|mut body| {
-            // This means we don't have to clone the list every frame
-            items.iter().for_each(|e| {
-                body.row(TABLE_ROW_HEIGHT_SMALL, |mut row| {
-                    let selected = add_row(&mut row, e);
-
-                    if row.response().clicked() {
-                        if selected {
-                            // if this row is selected, deselect it
-                            clicked = Some(None);
-                        } else {
-                            // change the selection
-                            // ...and we clone the clone because of fucking course we do D:<
-                            clicked = Some(Some(e.clone()));
-                        }
+        .body(|body| {
+            // Only the rows which are scrolled into view are laid out, so long lists don't slow down every frame
+            body.rows(TABLE_ROW_HEIGHT_SMALL, items.len(), |mut row| {
+                let e = &items[row.index()];
+                let selected = add_row(&mut row, e);
+
+                if row.response().clicked() {
+                    if selected {
+                        // if this row is selected, deselect it
+                        clicked = Some(None);
+                    } else {
+                        // change the selection
+                        // ...and we clone the clone because of fucking course we do D:<
+                        clicked = Some(Some(e.clone()));
                     }
-                });
+                }
             });
         });
 
     clicked
 }
 
+/// Adds buttons to move between pages, a dropdown for the page size, and which
+/// results are shown to the ui. `shown` is how many results the current page
+/// has, or [None] if they haven't loaded yet. Only page sizes up to
+/// `max_limit` are offered, see [`crate::data::state::ClientState::max_limit`].
+///
+/// The server doesn't say how many results there are in total, so the next
+/// button is enabled whenever the current page is full, and the total is only
+/// shown once the last page is reached.
+///
+/// Returns whether the page or page size changed, meaning the query should be
+/// sent again.
+pub fn paginator(
+    ui: &mut Ui,
+    id_salt: impl Hash,
+    pagination: &mut Pagination,
+    shown: Option<usize>,
+    max_limit: Option<u32>,
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        if ui.add_enabled(pagination.page > 0, Button::new("◀")).clicked() {
+            pagination.page -= 1;
+            changed = true;
+        }
+
+        ui.label(format!("Page {}", pagination.page + 1));
+
+        let full = shown.is_some_and(|shown| shown >= pagination.page_size as usize);
+        if ui.add_enabled(full, Button::new("▶")).clicked() {
+            pagination.page += 1;
+            changed = true;
+        }
+
+        // Changing the page size would put us on a different set of results, so start over
+        ComboBox::from_id_salt(id_salt).selected_text(pagination.page_size.to_string()).width(48.0).show_ui(ui, |ui| {
+            for size in PAGE_SIZES.into_iter().filter(|size| max_limit.is_none_or(|max| *size <= max)) {
+                if ui.add(Button::selectable(pagination.page_size == size, size.to_string())).clicked() {
+                    pagination.page_size = size;
+                    pagination.reset();
+                    changed = true;
+                }
+            }
+        });
+
+        // Show which results these are, we only know the total on the last page
+        match shown {
+            Some(0) | None => {}
+            Some(shown) => {
+                let first = pagination.offset() as usize + 1;
+                let last = pagination.offset() as usize + shown;
+                if shown < pagination.page_size as usize {
+                    ui.weak(format!("{}–{} of {}", first, last, last));
+                } else {
+                    ui.weak(format!("{}–{}", first, last));
+                }
+            }
+        }
+    });
+
+    changed
+}
+
 /// Adds a heading with a [medium space]([SPACE_MEDIUM]) before and after to
 /// the ui
 pub fn spaced_heading(ui: &mut Ui, text: impl Into<RichText>) {
@@ -116,6 +225,36 @@ pub fn spaced_heading(ui: &mut Ui, text: impl Into<RichText>) {
     ui.add_space(SPACE_MEDIUM);
 }
 
+/// Shows the username of the user with the given id, or the id itself until
+/// the username has been fetched. Hovering over it always shows the id.
+pub fn username_label(ui: &mut Ui, usernames: &mut UsernameCache, id: &Uuid) -> Response {
+    match usernames.get(id) {
+        Some(username) => ui.label(username),
+        None => ui.code(id.to_string()),
+    }
+    .on_hover_text(id.to_string())
+}
+
+/// Shows a dot in the color of the given [`PigLabel`], named when hovered.
+/// Nothing is shown for pigs without a label.
+pub fn label_dot(ui: &mut Ui, label: Option<PigLabel>) {
+    if let Some(label) = label {
+        let (rect, res) = ui.allocate_exact_size(vec2(10.0, 10.0), Sense::hover());
+        ui.painter().circle_filled(rect.center(), 4.0, label_color(label));
+        res.on_hover_text(label.name());
+    }
+}
+
+/// Shows a spinner while waiting on a request. If the request is being
+/// retried, also shows which attempt it's on.
+pub fn request_spinner(ui: &mut Ui, attempt: u32) -> Response {
+    let res = ui.spinner();
+    if attempt > 1 {
+        ui.weak(format!("Retrying, attempt {}...", attempt));
+    }
+    res
+}
+
 /// A custom layouter which visually wraps text while still treating it as a
 /// single line. Must be saved as a variable before applying it to a
 /// [`egui::TextEdit::singleline`].
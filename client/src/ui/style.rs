@@ -1,7 +1,9 @@
+use crate::data::settings::Settings;
 use egui::epaint::text::{FontInsert, InsertFontFamily};
-use egui::{Color32, FontData};
+use egui::{Color32, Context, FontData};
 use egui_colors::tokens::ThemeColor;
 use egui_colors::Colorix;
+use pigweb_common::pigs::PigLabel;
 
 /// Theme color for accepted pig names in bulk import
 pub const COLOR_ACCEPTED: Color32 = Color32::from_rgb(85, 187, 85);
@@ -9,6 +11,21 @@ pub const COLOR_ACCEPTED: Color32 = Color32::from_rgb(85, 187, 85);
 /// Theme color for rejected pig names in bulk import
 pub const COLOR_REJECTED: Color32 = Color32::from_rgb(221, 51, 68);
 
+/// Theme color for warnings which don't need the user to do anything
+pub const COLOR_WARNING: Color32 = Color32::from_rgb(221, 153, 51);
+
+/// Returns the color a [`PigLabel`] is shown as
+pub fn label_color(label: PigLabel) -> Color32 {
+    match label {
+        PigLabel::Red => Color32::from_rgb(221, 51, 68),
+        PigLabel::Orange => Color32::from_rgb(238, 136, 34),
+        PigLabel::Yellow => Color32::from_rgb(221, 187, 34),
+        PigLabel::Green => Color32::from_rgb(85, 187, 85),
+        PigLabel::Blue => Color32::from_rgb(68, 136, 221),
+        PigLabel::Purple => Color32::from_rgb(153, 85, 204),
+    }
+}
+
 /// Primary font used by the application.
 const FONT_MAIN: &[u8] = include_bytes!("../../data/ReadexPro-Regular.ttf");
 
@@ -51,43 +68,48 @@ pub const TABLE_ROW_HEIGHT_LARGE: f32 = 36.0;
 /// Greyscale.
 const THEME_PRIMARY: ThemeColor = ThemeColor::Gray;
 
-/// The secondary theme color, accenting the primary. Pink.
-const THEME_ACCENT: ThemeColor = ThemeColor::Custom([255, 137, 172]);
-
-/// The theme scale used by egui_colors.
-const THEME: [ThemeColor; 12] = [
-    THEME_PRIMARY,
-    THEME_PRIMARY,
-    THEME_ACCENT,
-    THEME_ACCENT,
-    THEME_ACCENT,
-    THEME_PRIMARY,
-    THEME_PRIMARY,
-    THEME_ACCENT,
-    THEME_ACCENT,
-    THEME_ACCENT,
-    THEME_PRIMARY,
-    THEME_PRIMARY,
-];
+/// The default secondary theme color as RGB, accenting the primary. Pink.
+pub const THEME_ACCENT: [u8; 3] = [255, 137, 172];
+
+/// Builds the theme scale used by egui_colors with the given accent color
+pub fn theme(accent: [u8; 3]) -> [ThemeColor; 12] {
+    let accent = ThemeColor::Custom(accent);
+    [
+        THEME_PRIMARY,
+        THEME_PRIMARY,
+        accent,
+        accent,
+        accent,
+        THEME_PRIMARY,
+        THEME_PRIMARY,
+        accent,
+        accent,
+        accent,
+        THEME_PRIMARY,
+        THEME_PRIMARY,
+    ]
+}
+
+/// Gets every color in the theme Colorix is using as RGB
+pub fn palette(colorix: &Colorix) -> [[u8; 3]; 12] {
+    std::array::from_fn(|i| colorix.theme()[i].rgb())
+}
 
 /// The standard format timestamps should be displayed in. See
 /// [`chrono::format::strftime`] for options.
 pub const TIME_FMT: &'static str = "%a, %b %e %Y %T";
 
 /// Sets global styles on the given CreationContext and initializes Colorix to
-/// manage it. Returns the Colorix instance
-pub fn set_styles(cc: &eframe::CreationContext<'_>) -> Colorix {
+/// manage it with the theme from the given settings, see [`set_theme`].
+/// Returns the Colorix instance
+pub fn set_styles(cc: &eframe::CreationContext<'_>, settings: &Settings) -> Colorix {
     // This is also where you can customize the look and feel of egui using
     // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
 
     // Set zoom to 110% so everything is slightly easier to see
     cc.egui_ctx.set_zoom_factor(1.1);
 
-    // Initialize Colorix with the global ctx and our theme. We could use
-    // Colorix::local_from_style without the context, but we would also have
-    // to know in advance if dark mode is enabled. It's easier to just let
-    // the widget and egui itself worry about that.
-    let colorix = Colorix::global(&cc.egui_ctx, THEME);
+    let colorix = set_theme(&cc.egui_ctx, settings);
 
     // Add fonts https://github.com/emilk/egui/blob/0db56dc9f1a8459b5b9376159fab7d7048b19b65/examples/custom_font/src/main.rs
     cc.egui_ctx.add_font(FontInsert::new(
@@ -110,3 +132,19 @@ pub fn set_styles(cc: &eframe::CreationContext<'_>) -> Colorix {
 
     colorix
 }
+
+/// Applies the user's custom palette if they have one, otherwise our theme
+/// with their accent color or [`THEME_ACCENT`]. Returns the Colorix instance
+/// managing it
+pub fn set_theme(ctx: &Context, settings: &Settings) -> Colorix {
+    let theme = match settings.palette {
+        Some(palette) => palette.map(ThemeColor::Custom),
+        None => theme(settings.accent_color.unwrap_or(THEME_ACCENT)),
+    };
+
+    // Initialize Colorix with the global ctx and our theme. We could use
+    // Colorix::local_from_style without the context, but we would also have
+    // to know in advance if dark mode is enabled. It's easier to just let
+    // the widget and egui itself worry about that.
+    Colorix::global(ctx, theme)
+}
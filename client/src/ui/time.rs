@@ -0,0 +1,83 @@
+use crate::data::settings::Settings;
+use chrono::{Local, NaiveDateTime, Utc};
+use egui::{Response, Ui};
+use std::time::Duration;
+
+/// The units relative times are shown in, with how many seconds each one is.
+/// The largest unit the time is at least one of is used.
+const UNITS: [(&str, i64); 6] = [
+    ("year", 365 * 24 * 60 * 60),
+    ("month", 30 * 24 * 60 * 60),
+    ("day", 24 * 60 * 60),
+    ("hour", 60 * 60),
+    ("minute", 60),
+    ("second", 1),
+];
+
+/// Shows the given UTC time the way the user prefers, see
+/// [`Settings::relative_times`]. Whichever format isn't shown is put in a
+/// tooltip.
+pub fn time_label(ui: &mut Ui, time: NaiveDateTime, settings: &Settings) -> Response {
+    let absolute = absolute_time(time, settings);
+    let relative = relative_time(time);
+
+    // Relative times go stale, so keep them up to date even if nothing else is happening
+    if settings.relative_times {
+        let recent = (Utc::now().naive_utc() - time).num_seconds().abs() < 60;
+        ui.ctx().request_repaint_after(Duration::from_secs(if recent { 1 } else { 60 }));
+        ui.label(relative).on_hover_text(absolute)
+    } else {
+        ui.label(absolute).on_hover_text(relative)
+    }
+}
+
+/// Formats the given UTC time the way the user prefers, for places which can't
+/// have a tooltip
+pub fn format_time(time: NaiveDateTime, settings: &Settings) -> String {
+    match settings.relative_times {
+        true => relative_time(time),
+        false => absolute_time(time, settings),
+    }
+}
+
+/// Formats the given UTC time in the user's timezone with their preferred
+/// [`crate::data::settings::TimeFormat`]
+pub fn absolute_time(time: NaiveDateTime, settings: &Settings) -> String {
+    time.and_utc().with_timezone(&Local).format(settings.time_format.pattern()).to_string()
+}
+
+/// Formats the given UTC time relative to now, e.g. "3 hours ago"
+pub fn relative_time(time: NaiveDateTime) -> String {
+    let seconds = (time - Utc::now().naive_utc()).num_seconds();
+    let (unit, size) = UNITS.into_iter().find(|(_, size)| seconds.abs() >= *size).unwrap_or(("second", 1));
+    format_relative(seconds / size, unit)
+}
+
+/// Formats the given number of units from now in the browser's language, e.g.
+/// `-3` hours is "3 hours ago"
+#[cfg(target_arch = "wasm32")]
+fn format_relative(value: i64, unit: &str) -> String {
+    thread_local! {
+        // Setting up the formatter isn't free and this is called for every row of a table, so only do it once
+        static FORMATTER: js_sys::Intl::RelativeTimeFormat = {
+            let options = js_sys::Object::new();
+            js_sys::Reflect::set(&options, &js_sys::JsString::from("numeric"), &js_sys::JsString::from("auto"))
+                .unwrap_or_default();
+            js_sys::Intl::RelativeTimeFormat::new(&js_sys::Array::new(), &options)
+        };
+    }
+
+    FORMATTER.with(|formatter| formatter.format(value as f64, unit).into())
+}
+
+/// Formats the given number of units from now in English, e.g. `-3` hours is
+/// "3 hours ago"
+#[cfg(not(target_arch = "wasm32"))]
+fn format_relative(value: i64, unit: &str) -> String {
+    let plural = if value.abs() == 1 { "" } else { "s" };
+    match value {
+        0 => "now".to_owned(),
+        v if v < 0 => format!("{} {}{} ago", -v, unit, plural),
+        v => format!("in {} {}{}", v, unit, plural),
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use diesel::{deserialize, pg, serialize, sql_types};
+
+/// How many changes the dashboard shows in each of its lists by default
+pub const RECENT_CHANGES_LIMIT: u32 = 20;
+
+/// A single change made to the pig list, kept so moderators can see who did
+/// what. Only creations, renames, and deletions are recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::Identifiable, diesel::Insertable, diesel::Queryable, diesel::Selectable))]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::pig_changes))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct PigChange {
+    /// The unique id of this change
+    pub id: Uuid,
+
+    /// The id of the pig which was changed. Deleted pigs no longer exist, so
+    /// this doesn't reference the pigs table.
+    pub pig: Uuid,
+
+    /// The name of the pig after the change, or when it was deleted
+    pub name: String,
+
+    /// The name of the pig before it was renamed
+    pub previous_name: Option<String>,
+
+    /// What was done to the pig
+    pub action: PigChangeAction,
+
+    /// The id of the user who made the change
+    pub actor: Uuid,
+
+    /// When the change was made
+    pub changed: NaiveDateTime,
+}
+
+impl PigChange {
+    /// Creates a new PigChange made at the given time with a random [`Uuid`]
+    pub fn new(action: PigChangeAction, pig: &Uuid, name: &str, actor: &Uuid, now: NaiveDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            pig: pig.to_owned(),
+            name: name.to_owned(),
+            previous_name: None,
+            action,
+            actor: actor.to_owned(),
+            changed: now,
+        }
+    }
+
+    /// Creates a new [`PigChangeAction::Renamed`] change from the pig's old
+    /// name to its new one
+    pub fn renamed(pig: &Uuid, from: &str, to: &str, actor: &Uuid, now: NaiveDateTime) -> Self {
+        Self { previous_name: Some(from.to_owned()), ..Self::new(PigChangeAction::Renamed, pig, to, actor, now) }
+    }
+}
+
+/// What was done to a pig in a [`PigChange`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Text))]
+#[serde(rename_all = "lowercase")]
+pub enum PigChangeAction {
+    Created,
+    Renamed,
+    Deleted,
+}
+
+impl PigChangeAction {
+    /// The name of the action, which is also how it's saved
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Renamed => "renamed",
+            Self::Deleted => "deleted",
+        }
+    }
+}
+
+/// Lets [`PigChangeAction`]s be saved to the DB by name
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Text, pg::Pg> for PigChangeAction {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let name = <String as deserialize::FromSql<sql_types::Text, pg::Pg>>::from_sql(bytes)?;
+        match [Self::Created, Self::Renamed, Self::Deleted].into_iter().find(|action| action.name() == name) {
+            Some(action) => Ok(action),
+            None => Err(format!("Unknown pig change action {:?}", name).into()),
+        }
+    }
+}
+
+/// Lets [`PigChangeAction`]s be saved to the DB by name
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Text, pg::Pg> for PigChangeAction {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        <str as serialize::ToSql<sql_types::Text, pg::Pg>>::to_sql(self.name(), &mut out.reborrow())
+    }
+}
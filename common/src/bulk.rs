@@ -1,6 +1,12 @@
-use crate::{query_limit_offset, query_list, query_to_yuri, BULK_API_ROOT, DEFAULT_API_RESPONSE_LIMIT};
-use chrono::{NaiveDateTime, Utc};
+use crate::pigs::Pig;
+use crate::scripts::skeleton;
+use crate::{
+    format_timestamp, parse_timestamp, query_fields, query_limit_offset, query_list, query_to_yuri, BULK_API_ROOT,
+    DEFAULT_API_RESPONSE_LIMIT,
+};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
@@ -8,7 +14,9 @@ use {crate::schema, diesel::*};
 
 /// A list of pigs names imported at once. Names start in the [`pending`] list
 /// before a pig is generated whose [`Uuid`] is [`accepted`] or the name is
-/// [`rejected`].
+/// [`rejected`]. Names which need a judgment call are [`flagged`] for a
+/// moderator instead, who either sends them back to [`pending`] or rejects
+/// them.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(
     feature = "server",
@@ -28,6 +36,10 @@ pub struct BulkImport {
     /// The id of the user who started importing these names
     pub creator: Uuid,
 
+    /// The id of the user responsible for reviewing the pending names, if
+    /// anyone has been assigned
+    pub assignee: Option<Uuid>,
+
     /// When the import was created
     pub started: NaiveDateTime,
 
@@ -36,6 +48,11 @@ pub struct BulkImport {
     /// still in-progress.
     pub finished: Option<NaiveDateTime>,
 
+    /// When the import was closed without finishing it, leaving its pending
+    /// names unprocessed. Closed imports aren't in-progress anymore, but can be
+    /// opened again.
+    pub closed: Option<NaiveDateTime>,
+
     /// The list of names still waiting to be processed
     pub pending: Vec<String>,
 
@@ -44,6 +61,19 @@ pub struct BulkImport {
 
     /// The names from the import which were not added to the list
     pub rejected: Vec<String>,
+
+    /// Counts how many times the import has been changed. Patches made against
+    /// an older revision are rejected so reviewers can't overwrite each other.
+    pub revision: i32,
+
+    /// Names which need a moderator to decide whether they're appropriate
+    /// before anyone else can process them
+    pub flagged: Vec<String>,
+
+    /// When the import was last changed. The server sets this whenever it
+    /// changes the import.
+    #[serde(default)]
+    pub updated_at: NaiveDateTime,
 }
 
 impl BulkImport {
@@ -55,22 +85,290 @@ impl BulkImport {
         pending: &Vec<String>,
         accepted: &Vec<Uuid>,
         rejected: &Vec<String>,
+        now: NaiveDateTime,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
             name: name.to_owned(),
             creator: creator.to_owned(),
-            started: Utc::now().naive_utc(),
+            assignee: None,
+            started: now,
             finished: None,
+            closed: None,
             pending: pending.to_owned(),
             accepted: accepted.to_owned(),
             rejected: rejected.to_owned(),
+            revision: 0,
+            flagged: Vec::new(),
+            updated_at: now,
+        }
+    }
+
+    /// The number of names which have been either accepted or rejected
+    pub fn reviewed_count(&self) -> usize {
+        self.accepted.len() + self.rejected.len()
+    }
+
+    /// The number of names in this import, whether they've been reviewed or not
+    pub fn total_count(&self) -> usize {
+        self.pending.len() + self.flagged.len() + self.reviewed_count()
+    }
+
+    /// Whether the import can still be worked on, meaning it's neither
+    /// finished nor closed
+    pub fn is_open(&self) -> bool {
+        self.finished.is_none() && self.closed.is_none()
+    }
+
+    /// Whether any names are still pending or flagged. Once there aren't, the
+    /// import is finished.
+    pub fn has_unreviewed(&self) -> bool {
+        !self.pending.is_empty() || !self.flagged.is_empty()
+    }
+
+    /// Returns every other pending name which is similar to the given one, see
+    /// [`names_similar`]
+    pub fn similar_pending(&self, name: &str) -> Vec<String> {
+        self.pending
+            .iter()
+            .filter(|pending| pending.as_str() != name && names_similar(pending, name))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The maximum number of characters which can differ between two names for
+/// them to still be considered similar
+const SIMILAR_NAME_DISTANCE: usize = 2;
+
+/// Names shorter than this are only similar if they match exactly after
+/// ignoring case, spaces, and punctuation. Otherwise, nearly every short name
+/// would be similar to every other.
+const SIMILAR_NAME_MIN_LENGTH: usize = 5;
+
+/// Whether two names are likely to refer to the same pig. Case, spaces,
+/// punctuation, and lookalike letters from other alphabets are ignored, then a
+/// few typos are allowed between longer names.
+pub fn names_similar(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_name(a), normalize_name(b));
+
+    if a == b {
+        return true;
+    }
+
+    if a.len().min(b.len()) < SIMILAR_NAME_MIN_LENGTH || a.len().abs_diff(b.len()) > SIMILAR_NAME_DISTANCE {
+        return false;
+    }
+
+    levenshtein(&a, &b) <= SIMILAR_NAME_DISTANCE
+}
+
+/// How alike two names are, from 0 for nothing in common to 1 for names which
+/// only differ in case, spaces, punctuation, or lookalike letters. This is the
+/// share of characters in the longer name which don't need to change.
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_name(a), normalize_name(b));
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+/// Reduces the name to the letters and numbers of its [`skeleton`], so only
+/// differences which matter are compared
+fn normalize_name(name: &str) -> Vec<char> {
+    skeleton(name).chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+/// The number of single character insertions, deletions, or substitutions
+/// needed to turn one string into the other
+fn levenshtein(a: &Vec<char>, b: &Vec<char>) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + if ca == cb { 0 } else { 1 };
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// A pasted name which may have a typo in it, see [`suggest_spelling`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkSuggestion {
+    /// The name as it was pasted
+    pub name: String,
+
+    /// The name with each likely typo corrected
+    pub suggestion: String,
+}
+
+/// Suggests a correction for the name by replacing each word which isn't
+/// known, but is close to a known word. Known words must be lowercase. Returns
+/// [`None`] if nothing looks misspelled.
+pub fn suggest_spelling(name: &str, known: &BTreeSet<String>) -> Option<String> {
+    let mut changed = false;
+
+    let words: Vec<String> = name
+        .split(' ')
+        .map(|word| {
+            // only check the letters, keep any punctuation around them as-is
+            let start = word.find(|c: char| c.is_alphanumeric());
+            let end = word.char_indices().filter(|(_, c)| c.is_alphanumeric()).last().map(|(i, c)| i + c.len_utf8());
+            let (Some(start), Some(end)) = (start, end) else {
+                return word.to_owned();
+            };
+
+            match suggest_word(&word[start..end], known) {
+                Some(suggestion) => {
+                    changed = true;
+                    format!("{}{}{}", &word[..start], suggestion, &word[end..])
+                }
+                None => word.to_owned(),
+            }
+        })
+        .collect();
+
+    changed.then(|| words.join(" "))
+}
+
+/// Finds the closest known word to the given one, unless it's known already.
+/// Short words and words with numbers are skipped, since nearly anything is
+/// close to them.
+fn suggest_word(word: &str, known: &BTreeSet<String>) -> Option<String> {
+    let lower = word.to_lowercase();
+    let chars: Vec<char> = lower.chars().collect();
+
+    if known.contains(&lower) || chars.len() < SIMILAR_NAME_MIN_LENGTH || chars.iter().any(|c| c.is_numeric()) {
+        return None;
+    }
+
+    let (_, closest) = known
+        .iter()
+        .map(|k| (k.chars().collect::<Vec<char>>(), k))
+        .filter(|(k, _)| k.len().abs_diff(chars.len()) <= SIMILAR_NAME_DISTANCE)
+        .map(|(k, closest)| (levenshtein(&chars, &k), closest))
+        .filter(|(distance, _)| *distance <= SIMILAR_NAME_DISTANCE)
+        .min_by_key(|(distance, _)| *distance)?;
+
+    // keep the word capitalized if it was to begin with
+    if word.chars().next().is_some_and(|c| c.is_uppercase()) {
+        let mut chars = closest.chars();
+        chars.next().map(|first| first.to_uppercase().chain(chars).collect())
+    } else {
+        Some(closest.to_owned())
+    }
+}
+
+/// How a list of names compares to an existing [`BulkImport`], used to only
+/// import what's changed since a list was last submitted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkDiff {
+    /// Names which weren't in the import at all
+    pub added: Vec<String>,
+
+    /// Names which are still pending in the import
+    pub pending: Vec<String>,
+
+    /// Names which were accepted by the import
+    pub accepted: Vec<String>,
+
+    /// Names which were rejected by the import
+    pub rejected: Vec<String>,
+}
+
+impl BulkDiff {
+    /// Whether the name has been sorted into any of the lists
+    pub fn contains(&self, name: &String) -> bool {
+        self.added.contains(name)
+            || self.pending.contains(name)
+            || self.accepted.contains(name)
+            || self.rejected.contains(name)
+    }
+}
+
+/// An existing [`Pig`] which may be a duplicate of a pending name, along with
+/// where it came from so reviewers can judge whether it was legitimate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkDuplicate {
+    /// The possible duplicate
+    pub pig: Pig,
+
+    /// The username of whoever created the pig, if they're still around
+    pub creator_name: Option<String>,
+
+    /// The id of the [`BulkImport`] which accepted the pig. If this is
+    /// [`None`], the pig was added by hand.
+    pub import_id: Option<Uuid>,
+
+    /// The name of the [`BulkImport`] which accepted the pig
+    pub import_name: Option<String>,
+}
+
+/// A moderator's decision on a name flagged in a [`BulkImport`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkReview {
+    /// The id of the [`BulkImport`] the name was flagged in
+    pub id: Uuid,
+
+    /// The flagged name
+    pub name: String,
+
+    /// Whether the name is appropriate. If so, it goes back to pending for
+    /// anyone to process, otherwise it's rejected.
+    pub approved: bool,
+}
+
+/// A line logged while processing a [`BulkImport`], used to figure out what
+/// went wrong when processing fails part way through.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::Insertable, diesel::Queryable, diesel::Selectable))]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::bulk_job_logs))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct BulkJobLog {
+    /// The unique id for this line
+    pub id: Uuid,
+
+    /// The id of the [`BulkImport`] being processed. The import may not exist
+    /// if processing failed before it could be saved.
+    pub import: Uuid,
+
+    /// The id of the request which started the processing
+    pub request: Uuid,
+
+    /// When this line was logged
+    pub logged: NaiveDateTime,
+
+    /// How severe this line is, one of `info`, `warn`, or `error`
+    pub level: String,
+
+    /// What happened
+    pub message: String,
+}
+
+impl BulkJobLog {
+    /// Creates a new BulkJobLog logged at the given time
+    pub fn new(import: &Uuid, request: &Uuid, level: &str, message: String, now: NaiveDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            import: import.to_owned(),
+            request: request.to_owned(),
+            logged: now,
+            level: level.to_owned(),
+            message,
         }
     }
 }
 
 /// A single modification to a BulkImport list.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PatchAction<T> {
     /// Adds the given value to the list
     ADD(T),
@@ -82,10 +380,21 @@ pub enum PatchAction<T> {
     UPDATE(T, T),
 }
 
+impl<T: Clone> PatchAction<T> {
+    /// Returns the action which reverts this one
+    pub fn inverse(&self) -> Self {
+        match self {
+            PatchAction::ADD(e) => PatchAction::REMOVE(e.clone()),
+            PatchAction::REMOVE(e) => PatchAction::ADD(e.clone()),
+            PatchAction::UPDATE(old, new) => PatchAction::UPDATE(new.clone(), old.clone()),
+        }
+    }
+}
+
 /// A request to modify a [`BulkImport`]. Patches are used instead of replacing
 /// the object in-full to hopefully reduce the amount of data transmitted
 /// between client and server.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BulkPatch {
     /// The id of the [`BulkImport`] to modify.
     pub id: Uuid,
@@ -98,12 +407,23 @@ pub struct BulkPatch {
 
     /// Changes to the [`BulkImport`] rejected list
     pub rejected: Option<Vec<PatchAction<String>>>,
+
+    /// The [`BulkImport::revision`] this patch was made against. If the import
+    /// has changed since, the patch is rejected.
+    pub revision: i32,
 }
 
 impl BulkPatch {
-    /// Creates a new BulkPatch to apply to the [`BulkImport`] with the given id
-    pub fn new(id: &Uuid) -> Self {
-        Self { id: id.to_owned(), pending: None, accepted: None, rejected: None }
+    /// Creates a new BulkPatch to apply to the [`BulkImport`] with the given id,
+    /// as long as it's still at the given revision
+    pub fn new(id: &Uuid, revision: i32) -> Self {
+        Self { id: id.to_owned(), pending: None, accepted: None, rejected: None, revision }
+    }
+
+    /// Applies this patch to the given revision of the import instead
+    pub fn with_revision(mut self, revision: i32) -> Self {
+        self.revision = revision;
+        self
     }
 
     /// Adds a change to the [`BulkImport`] pending list
@@ -139,6 +459,23 @@ impl BulkPatch {
         self
     }
 
+    /// Creates the patch which reverts the changes made by this one. Actions are
+    /// inverted and applied in reverse order.
+    pub fn inverse(&self) -> Self {
+        Self {
+            id: self.id,
+            pending: self.pending.as_ref().map(Self::inverse_actions),
+            accepted: self.accepted.as_ref().map(Self::inverse_actions),
+            rejected: self.rejected.as_ref().map(Self::inverse_actions),
+            revision: self.revision,
+        }
+    }
+
+    /// Inverts each item in [`actions`] and reverses their order
+    fn inverse_actions<T: Clone>(actions: &Vec<PatchAction<T>>) -> Vec<PatchAction<T>> {
+        actions.iter().rev().map(|action| action.inverse()).collect()
+    }
+
     /// Applies the changes in this patch to the given BulkImport. This function
     /// is used by the server after all checks have passed and should be used
     /// by the client once the server confirms changes were successful.
@@ -154,6 +491,27 @@ impl BulkPatch {
         if let Some(rejected_actions) = self.rejected.as_ref() {
             Self::perform_actions(rejected_actions, &mut import.rejected);
         }
+
+        import.revision += 1;
+    }
+
+    /// Whether everything this patch removes or updates is still in the
+    /// import. If not, someone else already made a conflicting change and this
+    /// patch shouldn't be reapplied.
+    pub fn applies_to(&self, import: &BulkImport) -> bool {
+        Self::actions_apply(self.pending.as_ref(), &import.pending)
+            && Self::actions_apply(self.accepted.as_ref(), &import.accepted)
+            && Self::actions_apply(self.rejected.as_ref(), &import.rejected)
+    }
+
+    /// Whether every item removed or updated by the actions is in the vec
+    fn actions_apply<T: PartialEq>(actions: Option<&Vec<PatchAction<T>>>, vec: &Vec<T>) -> bool {
+        actions.is_none_or(|actions| {
+            actions.iter().all(|action| match action {
+                PatchAction::ADD(_) => true,
+                PatchAction::REMOVE(e) | PatchAction::UPDATE(e, _) => vec.contains(e),
+            })
+        })
     }
 
     /// Applies each item in [`actions`] to the given [`vec`]
@@ -175,6 +533,20 @@ impl BulkPatch {
     }
 }
 
+/// Whether a [`BulkImport`] still has names waiting to be processed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(rocket::FromFormField))]
+pub enum BulkStatus {
+    /// The import still has pending names
+    InProgress,
+
+    /// Every name in the import has been accepted or rejected
+    Finished,
+
+    /// The import was closed before every name was processed
+    Closed,
+}
+
 /// Represents all possible options in a query to fetch [`BulkImport`]s. Every
 /// possible parameter is an [Option] so all of them aren't absolutely required.
 #[derive(Debug, PartialEq, Serialize)]
@@ -186,6 +558,39 @@ pub struct BulkQuery {
     /// The server should only return [`BulkImport`]s with any of these creators
     pub creator: Option<Vec<String>>,
 
+    /// If true, the server should only return [`BulkImport`]s created by the
+    /// user making the request
+    pub mine: Option<bool>,
+
+    /// The server should only return [`BulkImport`]s assigned to any of these
+    /// users
+    pub assignee: Option<Vec<String>>,
+
+    /// If true, the server should only return [`BulkImport`]s assigned to the
+    /// user making the request
+    pub assigned: Option<bool>,
+
+    /// The server should only return [`BulkImport`]s with this status
+    pub status: Option<BulkStatus>,
+
+    /// The server should only return [`BulkImport`]s whose name contains this
+    pub name: Option<String>,
+
+    /// The server should only return [`BulkImport`]s changed at or after this
+    /// time, formatted like `YYYY-MM-DDTHH:MM:SS.ffffff` in UTC. Results are
+    /// sorted from least to most recently changed, so the last one's
+    /// [`BulkImport::updated_at`] can be used for the next request.
+    pub changed_since: Option<String>,
+
+    /// Extra data the server should add to each [`BulkImport`]. Only `creator`
+    /// is supported, which adds the username of whoever started it.
+    pub include: Option<String>,
+
+    /// A comma-separated list of the only fields the server should send for
+    /// each [`BulkImport`], e.g. `id,name`. Every field is sent if this is
+    /// missing.
+    pub fields: Option<String>,
+
     /// The maximum number of items to return
     pub limit: Option<u32>,
 
@@ -196,16 +601,81 @@ pub struct BulkQuery {
 
 impl Default for BulkQuery {
     fn default() -> Self {
-        Self { id: None, creator: None, limit: Some(DEFAULT_API_RESPONSE_LIMIT), offset: Some(0) }
+        Self {
+            id: None,
+            creator: None,
+            mine: None,
+            assignee: None,
+            assigned: None,
+            status: None,
+            name: None,
+            changed_since: None,
+            include: None,
+            fields: None,
+            limit: Some(DEFAULT_API_RESPONSE_LIMIT),
+            offset: Some(0),
+        }
     }
 }
 
 impl BulkQuery {
     query_list!(id, Uuid);
     query_list!(creator, Uuid);
+    query_list!(assignee, Uuid);
     query_limit_offset!();
+    query_fields!();
     query_to_yuri!(BULK_API_ROOT);
 
+    /// Filters the results to [`BulkImport`]s created by the requester
+    pub fn with_mine(mut self, mine: bool) -> Self {
+        self.mine = Some(mine);
+        self
+    }
+
+    /// Filters the results to [`BulkImport`]s assigned to the requester
+    pub fn with_assigned(mut self, assigned: bool) -> Self {
+        self.assigned = Some(assigned);
+        self
+    }
+
+    /// Filters the results to [`BulkImport`]s with the given status
+    pub fn with_status(mut self, status: BulkStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Filters the results to [`BulkImport`]s whose name contains the given
+    /// String
+    pub fn with_name(mut self, name: &String) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Filters the results to [`BulkImport`]s changed at or after the given
+    /// time, see [`Self::changed_since`]
+    pub fn with_changed_since(mut self, since: NaiveDateTime) -> Self {
+        self.changed_since = Some(format_timestamp(since));
+        self
+    }
+
+    /// Whether the time to filter changes since is either missing or
+    /// formatted correctly
+    pub fn has_valid_dates(&self) -> bool {
+        self.changed_since.as_ref().is_none_or(|since| parse_timestamp(since).is_some())
+    }
+
+    /// Asks the server to add the username of each [`BulkImport`]'s creator
+    pub fn with_creator_username(mut self) -> Self {
+        self.include = Some("creator".to_owned());
+        self
+    }
+
+    /// Whether the server should add the username of each [`BulkImport`]'s
+    /// creator
+    pub fn includes_creator(&self) -> bool {
+        self.include.as_deref() == Some("creator")
+    }
+
     /// Converts query params to DB query
     #[cfg(feature = "server")]
     #[dsl::auto_type(no_type_alias)]
@@ -224,6 +694,34 @@ impl BulkQuery {
             res = res.filter(schema::bulk_imports::creator.eq_any(query_creators));
         }
 
+        // Filter by assignee, if specified
+        if let Some(query_assignees) = self.assignee.as_ref().and_then(|ids| crate::parse_uuids(ids).ok()) {
+            res = res.filter(schema::bulk_imports::assignee.eq_any(query_assignees));
+        }
+
+        // Filter by status, if specified
+        match self.status {
+            Some(BulkStatus::InProgress) => {
+                res =
+                    res.filter(schema::bulk_imports::finished.is_null()).filter(schema::bulk_imports::closed.is_null())
+            }
+            Some(BulkStatus::Finished) => res = res.filter(schema::bulk_imports::finished.is_not_null()),
+            Some(BulkStatus::Closed) => res = res.filter(schema::bulk_imports::closed.is_not_null()),
+            None => {}
+        }
+
+        // Filter by name, if specified
+        if let Some(ref name) = self.name {
+            res = res.filter(schema::bulk_imports::name.ilike(format!("%{}%", name)));
+        }
+
+        // Filter by when the import last changed, if specified
+        if let Some(since) = self.changed_since.as_ref().and_then(|since| parse_timestamp(since)) {
+            res = res
+                .filter(schema::bulk_imports::updated_at.ge(since))
+                .order((schema::bulk_imports::updated_at.asc(), schema::bulk_imports::id.asc()));
+        }
+
         // Set the limit, if present
         res = res.limit(self.limit.unwrap_or_else(|| DEFAULT_API_RESPONSE_LIMIT) as i64);
 
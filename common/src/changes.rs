@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Something which was changed by another request, sent to clients so they
+/// can keep what they're showing up to date without refetching it constantly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Where this event falls in the feed. Each event's cursor is one more
+    /// than the last.
+    pub cursor: u64,
+
+    /// The kind of record which changed
+    pub target: ChangeTarget,
+
+    /// What happened to it
+    pub action: ChangeAction,
+
+    /// The id of the record which changed, or [`None`] if too many changed at
+    /// once to list, e.g. when a tag is renamed. Anything showing this kind of
+    /// record should be refetched.
+    pub id: Option<Uuid>,
+}
+
+/// The kind of record a [`ChangeEvent`] is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeTarget {
+    Pig,
+    Import,
+}
+
+/// What happened to the record in a [`ChangeEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// The events which happened after a given cursor, see [`ChangeEvent`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeBatch {
+    /// The cursor to ask for next time. This is the cursor of the last event,
+    /// or the latest one if there weren't any.
+    pub cursor: u64,
+
+    /// Every event since the given cursor, oldest first
+    pub events: Vec<ChangeEvent>,
+
+    /// Whether some events were missed, either because they're too old to be
+    /// remembered or the server restarted. Everything should be refetched.
+    pub reset: bool,
+}
+
+impl ChangeBatch {
+    /// Whether any of the events, or a reset, could affect records of the
+    /// given kind
+    pub fn affects(&self, target: ChangeTarget) -> bool {
+        self.reset || self.events.iter().any(|event| event.target == target)
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// What went wrong with a request. This is sent with every error from the API
+/// so clients can decide what to do without reading the description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request was malformed and couldn't be understood
+    BadRequest,
+
+    /// The user needs to sign in
+    Unauthorized,
+
+    /// The user doesn't have a role needed for the request
+    Forbidden,
+
+    /// Whatever the request was for doesn't exist
+    NotFound,
+
+    /// The request conflicts with a change someone else already made
+    Conflict,
+
+    /// The request was understood, but something in it isn't allowed
+    Validation,
+
+    /// The request body is larger than the server accepts
+    TooLarge,
+
+    /// The user has made too many requests recently
+    RateLimited,
+
+    /// The user has reached one of their quotas, e.g. how many pigs they can
+    /// create in a day. This isn't sent for any particular status, routes pick
+    /// it themselves.
+    QuotaExceeded,
+
+    /// Something went wrong on the server
+    Internal,
+
+    /// The server is down or couldn't be reached
+    Unavailable,
+
+    /// The server is up, but can't reach its database. This should resolve
+    /// itself once the database is back.
+    DatabaseUnavailable,
+
+    /// Any error not covered above
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    /// Gets the error code which best describes the given HTTP status
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            400 => Self::BadRequest,
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            409 => Self::Conflict,
+            413 => Self::TooLarge,
+            422 => Self::Validation,
+            429 => Self::RateLimited,
+            500 => Self::Internal,
+            502..=504 => Self::Unavailable,
+            _ => Self::Unknown,
+        }
+    }
+}
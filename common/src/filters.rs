@@ -0,0 +1,38 @@
+use crate::pigs::PigQuery;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A [`PigQuery`] saved on the server under a human-friendly name so it can be
+/// reused later. Shared filters are visible to everyone who can view the pig
+/// list, letting the whole team review the same slices of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "server",
+    derive(diesel::AsChangeset, diesel::Identifiable, diesel::Insertable, diesel::Queryable, diesel::Selectable)
+)]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::saved_filters))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct SavedFilter {
+    /// The unique id of this filter
+    pub id: Uuid,
+
+    /// The name shown in the filter list, e.g. "Team: unreviewed 2025"
+    pub name: String,
+
+    /// The id of the user who saved this filter
+    pub creator: Uuid,
+
+    /// Whether other users can see and apply this filter
+    pub shared: bool,
+
+    /// The actual query to perform when this filter is applied. The limit and
+    /// offset are ignored.
+    pub query: PigQuery,
+}
+
+impl SavedFilter {
+    /// Creates a new SavedFilter with a random [`Uuid`] from the given values
+    pub fn new(name: &str, creator: &Uuid, shared: bool, query: &PigQuery) -> Self {
+        Self { id: Uuid::new_v4(), name: name.to_owned(), creator: creator.to_owned(), shared, query: query.to_owned() }
+    }
+}
@@ -0,0 +1,61 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// How this deployment of PigWeb presents itself, so different deployments
+/// (e.g. test and prod) can be told apart at a glance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Branding {
+    /// The name shown in the menu bar and when signing in
+    pub name: String,
+
+    /// The URL of an image shown next to the name, either absolute or
+    /// relative to the client
+    pub logo: Option<String>,
+
+    /// A welcome message or announcement shown when signing in and on the
+    /// home page
+    pub welcome: Option<String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self { name: "PigWeb".to_owned(), logo: None, welcome: None }
+    }
+}
+
+/// Which kind of deployment this is, so users can't mistake a test instance
+/// for the real pig list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Environment {
+    /// The name of the environment, e.g. `"staging"`. This is shown in a
+    /// banner unless this is the production instance.
+    pub name: String,
+
+    /// Whether this is the production instance, which doesn't get a banner
+    pub production: bool,
+
+    /// The color of the banner as RGB, if it shouldn't use the default
+    pub color: Option<[u8; 3]>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self { name: "production".to_owned(), production: true, color: None }
+    }
+}
+
+/// Whether the OIDC provider could be reached the last time the server
+/// checked, so admins can tell when sign-in failures are the provider's fault
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    /// Whether the provider's token endpoint and JWKS responded as expected
+    pub available: bool,
+
+    /// When the provider was last checked, in UTC
+    pub checked: NaiveDateTime,
+
+    /// What went wrong, if the provider couldn't be reached
+    pub error: Option<String>,
+}
@@ -1,5 +1,15 @@
+pub mod audit;
 pub mod bulk;
+pub mod changes;
+pub mod errors;
+pub mod filters;
+pub mod instance;
+pub mod notifications;
 pub mod pigs;
+pub mod scripts;
+pub mod snapshots;
+pub mod tags;
+pub mod tokens;
 pub mod users;
 pub mod yuri;
 
@@ -9,12 +19,27 @@ pub mod schema;
 /// The relative base URL for all authentication API routes
 pub const AUTH_API_ROOT: &str = "/auth/";
 
+/// The relative base URL for all audit log API routes
+pub const AUDIT_API_ROOT: &str = "/api/audit/";
+
 /// The relative base URL for all bulk import API routes
 pub const BULK_API_ROOT: &str = "/api/bulk/";
 
+/// The relative base URL for all saved filter API routes
+pub const FILTER_API_ROOT: &str = "/api/filters/";
+
+/// The relative base URL for all notification API routes
+pub const NOTIFICATION_API_ROOT: &str = "/api/notifications/";
+
 /// The relative base URL for all Pig API routes
 pub const PIG_API_ROOT: &str = "/api/pigs/";
 
+/// The relative base URL for all tag API routes
+pub const TAG_API_ROOT: &str = "/api/tags/";
+
+/// The relative base URL for all API token routes
+pub const TOKEN_API_ROOT: &str = "/api/tokens/";
+
 /// The relative base URL for all User API routes
 pub const USER_API_ROOT: &str = "/api/users/";
 
@@ -26,6 +51,15 @@ pub const COOKIE_JWT: &str = "pigweb_jwt";
 #[cfg(feature = "server")]
 pub const COOKIE_USER: &str = "pigweb_user";
 
+/// The key of the cookie storing the refresh token received from the OIDC
+/// provider, used to renew the session
+#[cfg(feature = "server")]
+pub const COOKIE_REFRESH: &str = "pigweb_refresh";
+
+/// The header the client sends its version in, so the server can tell which
+/// build a session was started from
+pub const CLIENT_VERSION_HEADER: &str = "X-PigWeb-Client-Version";
+
 /// The default maximum number of responses a fetch request will return
 pub const DEFAULT_API_RESPONSE_LIMIT: u32 = 100;
 
@@ -34,6 +68,21 @@ pub const DEFAULT_API_RESPONSE_LIMIT: u32 = 100;
 #[cfg(feature = "server")]
 pub struct OpenIDAuth;
 
+/// The format timestamps are sent in as query params, which is the same one
+/// they're serialized to JSON in
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Formats the UTC timestamp to be sent as a query param, see [`parse_timestamp`]
+pub fn format_timestamp(time: chrono::NaiveDateTime) -> String {
+    time.format(TIMESTAMP_FORMAT).to_string()
+}
+
+/// Parses a UTC timestamp formatted like `YYYY-MM-DDTHH:MM:SS.ffffff`, where
+/// the fractional seconds are optional
+pub fn parse_timestamp(time: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(time.trim(), TIMESTAMP_FORMAT).ok()
+}
+
 /// Attempts to parse a `&str` to a [`uuid::Uuid`], erroring with HTTP status 400
 #[cfg(feature = "server")]
 pub fn parse_uuid(string: &str) -> Result<uuid::Uuid, rocket::http::Status> {
@@ -136,6 +185,52 @@ macro_rules! query_limit_offset {
             self.offset = Some(offset);
             self
         }
+
+        /// Lowers the limit to the given maximum if it's higher, or if there
+        /// isn't one. Does nothing if the maximum is [`None`].
+        pub fn with_max_limit(mut self, max: Option<u32>) -> Self {
+            if let Some(max) = max {
+                self.limit = Some(self.limit.unwrap_or($crate::DEFAULT_API_RESPONSE_LIMIT).min(max));
+            }
+            self
+        }
+    };
+}
+
+/// INTERNAL/COMMON MODULE USE ONLY - generates builder functions for choosing
+/// which fields of each item the server sends back, meant for use when
+/// building structs for querying data. The struct needs a
+/// `fields: Option<String>` member.
+///
+/// Example:
+/// ```rust
+/// use pigweb_common::query_fields;
+///
+/// pub struct FetchQuery {
+///     pub fields: Option<String>
+/// }
+///
+/// impl FetchQuery {
+///     query_fields!();
+/// }
+/// ```
+#[macro_export]
+macro_rules! query_fields {
+    () => {
+        /// Asks the server to only send these fields of each item, leaving
+        /// the rest out of the response
+        pub fn with_fields(mut self, fields: &[&str]) -> Self {
+            self.fields = Some(fields.join(","));
+            self
+        }
+
+        /// The fields the server should send for each item, or [`None`] if it
+        /// should send all of them
+        pub fn field_list(&self) -> Option<Vec<&str>> {
+            self.fields
+                .as_ref()
+                .map(|fields| fields.split(',').map(|field| field.trim()).filter(|field| !field.is_empty()).collect())
+        }
     };
 }
 
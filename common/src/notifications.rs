@@ -0,0 +1,114 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use diesel::{deserialize, pg, serialize, sql_types};
+
+/// How many notifications are listed at once by default
+pub const NOTIFICATION_LIMIT: u32 = 50;
+
+/// A message for a single user about something which happened to them, their
+/// pigs, or their imports, shown under the bell in the top bar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::Identifiable, diesel::Insertable, diesel::Queryable, diesel::Selectable))]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::notifications))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct Notification {
+    /// The unique id of this notification
+    pub id: Uuid,
+
+    /// The id of the user this notification is for
+    pub recipient: Uuid,
+
+    /// What happened
+    pub kind: NotificationKind,
+
+    /// What happened, written for the recipient
+    pub message: String,
+
+    /// The id of the pig or import this is about, if any
+    pub target: Option<Uuid>,
+
+    /// When this notification was sent
+    pub created: NaiveDateTime,
+
+    /// When the recipient marked this notification as read, if they have
+    pub read: Option<NaiveDateTime>,
+}
+
+impl Notification {
+    /// Creates a new unread Notification sent at the given time with a random
+    /// [`Uuid`]
+    pub fn new(
+        recipient: &Uuid,
+        kind: NotificationKind,
+        message: String,
+        target: Option<&Uuid>,
+        now: NaiveDateTime,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            recipient: recipient.to_owned(),
+            kind,
+            message,
+            target: target.copied(),
+            created: now,
+            read: None,
+        }
+    }
+}
+
+/// What a [`Notification`] is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Text))]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The recipient's import finished processing the names it was started
+    /// with. The target is the import.
+    ImportProcessed,
+
+    /// Someone else edited a pig the recipient created. The target is the pig.
+    PigEdited,
+
+    /// Someone else deleted a pig the recipient created. The target is the
+    /// pig, which no longer exists.
+    PigDeleted,
+
+    /// An admin ended the recipient's session
+    SessionExpired,
+}
+
+impl NotificationKind {
+    /// The name of the kind, which is also how it's saved
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ImportProcessed => "import_processed",
+            Self::PigEdited => "pig_edited",
+            Self::PigDeleted => "pig_deleted",
+            Self::SessionExpired => "session_expired",
+        }
+    }
+}
+
+/// Lets [`NotificationKind`]s be saved to the DB by name
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Text, pg::Pg> for NotificationKind {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let name = <String as deserialize::FromSql<sql_types::Text, pg::Pg>>::from_sql(bytes)?;
+        let kinds = [Self::ImportProcessed, Self::PigEdited, Self::PigDeleted, Self::SessionExpired];
+        match kinds.into_iter().find(|kind| kind.name() == name) {
+            Some(kind) => Ok(kind),
+            None => Err(format!("Unknown notification kind {:?}", name).into()),
+        }
+    }
+}
+
+/// Lets [`NotificationKind`]s be saved to the DB by name
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Text, pg::Pg> for NotificationKind {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        <str as serialize::ToSql<sql_types::Text, pg::Pg>>::to_sql(self.name(), &mut out.reborrow())
+    }
+}
@@ -1,5 +1,8 @@
-use crate::{query_limit_offset, query_list, query_to_yuri, DEFAULT_API_RESPONSE_LIMIT, PIG_API_ROOT};
-use chrono::{NaiveDateTime, Utc};
+use crate::{
+    format_timestamp, parse_timestamp, query_fields, query_limit_offset, query_list, query_to_yuri,
+    DEFAULT_API_RESPONSE_LIMIT, PIG_API_ROOT,
+};
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -26,6 +29,8 @@ pub struct Pig {
     pub name: String,
 
     /// When the pig was created
+    // this and the fields below can be left out with PigQuery::fields, so they fall back to their defaults
+    #[serde(default)]
     // skip updating this field in the db as we don't want it to change
     // TODO enable this in diesel 2.3.0
     // https://github.com/diesel-rs/diesel/pull/4364
@@ -33,14 +38,48 @@ pub struct Pig {
     pub created: NaiveDateTime,
 
     /// The id of the user who created this pig
+    #[serde(default)]
     pub creator: Uuid,
+
+    /// Labels used to group related pigs together, see [`crate::tags::Tag`]
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Links to this pig in other places, such as a wiki page or photo album
+    #[serde(default)]
+    pub links: Vec<PigLink>,
+
+    /// Whether this pig has been retired. Archived pigs can still be found,
+    /// but can't be edited until they're unarchived.
+    #[serde(default)]
+    pub archived: bool,
+
+    /// A colored label reviewers can use to triage pigs, shown next to its
+    /// name in lists
+    #[serde(default)]
+    pub label: Option<PigLabel>,
+
+    /// When the pig was last changed. The server sets this whenever it changes
+    /// the pig.
+    #[serde(default)]
+    pub updated_at: NaiveDateTime,
 }
 
 impl Pig {
-    /// Creates a new pig with a random [`Uuid`] and the given name at the
-    /// current timestamp.
-    pub fn new(name: &str, creator: &Uuid) -> Pig {
-        Pig { id: Uuid::new_v4(), name: name.to_owned(), created: Utc::now().naive_utc(), creator: creator.to_owned() }
+    /// Creates a new pig with a random [`Uuid`] and the given name, created at
+    /// the given time.
+    pub fn new(name: &str, creator: &Uuid, now: NaiveDateTime) -> Pig {
+        Pig {
+            id: Uuid::new_v4(),
+            name: name.to_owned(),
+            created: now,
+            creator: creator.to_owned(),
+            tags: Vec::new(),
+            links: Vec::new(),
+            archived: false,
+            label: None,
+            updated_at: now,
+        }
     }
 
     /// Merges this pig and the given one together, using the current pig as a
@@ -53,7 +92,73 @@ impl Pig {
     /// https://stackoverflow.com/a/47748296
     /// https://doc.rust-lang.org/std/cell/struct.Cell.html#examples
     pub fn merge(&self, other: &Pig) -> Pig {
-        Pig { name: other.name.to_owned(), ..*self }
+        Pig {
+            name: other.name.to_owned(),
+            tags: other.tags.to_owned(),
+            links: other.links.to_owned(),
+            label: other.label,
+            ..self.to_owned()
+        }
+    }
+}
+
+/// The colored labels which can be given to a [`Pig`]. What each one means is
+/// up to whoever's reviewing the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Text))]
+#[serde(rename_all = "lowercase")]
+pub enum PigLabel {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl PigLabel {
+    /// Every label, in the order they should be shown
+    pub const ALL: [PigLabel; 6] = [Self::Red, Self::Orange, Self::Yellow, Self::Green, Self::Blue, Self::Purple];
+
+    /// The name of the label, which is also how it's saved
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Red => "red",
+            Self::Orange => "orange",
+            Self::Yellow => "yellow",
+            Self::Green => "green",
+            Self::Blue => "blue",
+            Self::Purple => "purple",
+        }
+    }
+}
+
+/// A link from a [`Pig`] to somewhere outside PigWeb
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Jsonb))]
+pub struct PigLink {
+    /// What the link is shown as
+    pub label: String,
+
+    /// Where the link goes
+    pub url: String,
+}
+
+impl PigLink {
+    /// Creates a new link with the given label and url, trimming both
+    pub fn new(label: &str, url: &str) -> Self {
+        Self { label: label.trim().to_owned(), url: url.trim().to_owned() }
+    }
+
+    /// Whether the link has a label and an http(s) url without any spaces
+    pub fn is_valid(&self) -> bool {
+        let address = self.url.strip_prefix("https://").or_else(|| self.url.strip_prefix("http://"));
+
+        !self.label.trim().is_empty()
+            && address.is_some_and(|address| !address.is_empty())
+            && !self.url.contains(char::is_whitespace)
     }
 }
 
@@ -61,15 +166,48 @@ impl Pig {
 /// parameter is an [Option] so all of them aren't absolutely required.
 // NOTE: all of these MUST be options or else Rocket won't recognize the query params
 // https://stackoverflow.com/a/42551386
-#[derive(Debug, PartialEq, Serialize)]
-#[cfg_attr(feature = "server", derive(rocket::FromForm))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(rocket::FromForm, diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Jsonb))]
 pub struct PigQuery {
     /// The server should only return [`Pig`]s with any of these ids
     pub id: Option<Vec<String>>,
 
+    /// The server should only return [`Pig`]s with any of these creators
+    pub creator: Option<Vec<String>>,
+
     /// Performs a full-text search to only return [`Pig`]s with a similar name
     pub name: Option<String>,
 
+    /// The server should only return [`Pig`]s with this tag
+    pub tag: Option<String>,
+
+    /// The server should only return [`Pig`]s which are archived if true, or
+    /// which aren't if false
+    pub archived: Option<bool>,
+
+    /// The server should only return [`Pig`]s created on or after this date,
+    /// formatted as `YYYY-MM-DD` in UTC
+    pub created_after: Option<String>,
+
+    /// The server should only return [`Pig`]s created before this date,
+    /// formatted as `YYYY-MM-DD` in UTC
+    pub created_before: Option<String>,
+
+    /// The server should only return [`Pig`]s changed at or after this time,
+    /// formatted like `YYYY-MM-DDTHH:MM:SS.ffffff` in UTC. Results are sorted
+    /// from least to most recently changed, so the last one's
+    /// [`Pig::updated_at`] can be used for the next request.
+    pub changed_since: Option<String>,
+
+    /// Extra data the server should add to each [`Pig`]. Only `creator` is
+    /// supported, which adds the username of whoever created it.
+    pub include: Option<String>,
+
+    /// A comma-separated list of the only fields the server should send for
+    /// each [`Pig`], e.g. `id,name`. Every field is sent if this is missing.
+    pub fields: Option<String>,
+
     /// The maximum number of items to return
     pub limit: Option<u32>,
 
@@ -80,13 +218,28 @@ pub struct PigQuery {
 
 impl Default for PigQuery {
     fn default() -> Self {
-        Self { id: None, name: None, limit: Some(DEFAULT_API_RESPONSE_LIMIT), offset: Some(0) }
+        Self {
+            id: None,
+            creator: None,
+            name: None,
+            tag: None,
+            archived: None,
+            created_after: None,
+            created_before: None,
+            changed_since: None,
+            include: None,
+            fields: None,
+            limit: Some(DEFAULT_API_RESPONSE_LIMIT),
+            offset: Some(0),
+        }
     }
 }
 
 impl PigQuery {
     query_list!(id, Uuid);
+    query_list!(creator, Uuid);
     query_limit_offset!();
+    query_fields!();
     query_to_yuri!(PIG_API_ROOT);
 
     /// Filters the results to [`Pig`]s with a name similar to the given String
@@ -95,6 +248,66 @@ impl PigQuery {
         self
     }
 
+    /// Filters the results to [`Pig`]s with the given tag
+    pub fn with_tag(mut self, tag: &String) -> Self {
+        self.tag = Some(tag.to_owned());
+        self
+    }
+
+    /// Filters the results to [`Pig`]s which are or aren't archived
+    pub fn with_archived(mut self, archived: bool) -> Self {
+        self.archived = Some(archived);
+        self
+    }
+
+    /// Filters the results to [`Pig`]s created within the given dates, see
+    /// [`Self::created_after`] and [`Self::created_before`]
+    pub fn with_created(mut self, after: Option<&str>, before: Option<&str>) -> Self {
+        self.created_after = after.map(|date| date.trim().to_owned());
+        self.created_before = before.map(|date| date.trim().to_owned());
+        self
+    }
+
+    /// Filters the results to [`Pig`]s changed at or after the given time, see
+    /// [`Self::changed_since`]
+    pub fn with_changed_since(mut self, since: NaiveDateTime) -> Self {
+        self.changed_since = Some(format_timestamp(since));
+        self
+    }
+
+    /// Whether both creation dates and the time they changed since are either
+    /// missing or formatted correctly
+    pub fn has_valid_dates(&self) -> bool {
+        [&self.created_after, &self.created_before]
+            .iter()
+            .all(|date| date.as_ref().is_none_or(|d| parse_date(d).is_some()))
+            && self.changed_since.as_ref().is_none_or(|since| parse_timestamp(since).is_some())
+    }
+
+    /// Whether this query narrows down which pigs are returned at all, besides
+    /// whether they're archived. Used to make sure actions which affect every
+    /// matching pig aren't run on all of them by accident.
+    pub fn has_filters(&self) -> bool {
+        self.id.is_some()
+            || self.creator.is_some()
+            || self.name.as_ref().is_some_and(|name| !name.trim().is_empty())
+            || self.tag.is_some()
+            || self.created_after.is_some()
+            || self.created_before.is_some()
+            || self.changed_since.is_some()
+    }
+
+    /// Asks the server to add the username of each [`Pig`]'s creator
+    pub fn with_creator_username(mut self) -> Self {
+        self.include = Some("creator".to_owned());
+        self
+    }
+
+    /// Whether the server should add the username of each [`Pig`]'s creator
+    pub fn includes_creator(&self) -> bool {
+        self.include.as_deref() == Some("creator")
+    }
+
     /// Converts query params to DB query
     #[cfg(feature = "server")]
     #[dsl::auto_type(no_type_alias)]
@@ -116,6 +329,37 @@ impl PigQuery {
             res = res.filter(schema::pigs::id.eq_any(query_ids));
         }
 
+        // Filter by creator, if specified
+        if let Some(query_creators) = self.creator.as_ref().and_then(|ids| crate::parse_uuids(ids).ok()) {
+            res = res.filter(schema::pigs::creator.eq_any(query_creators));
+        }
+
+        // Filter by tag, if specified
+        if let Some(ref tag) = self.tag {
+            res = res.filter(schema::pigs::tags.contains(vec![tag.to_owned()]));
+        }
+
+        // Filter by whether the pig is archived, if specified
+        if let Some(archived) = self.archived {
+            res = res.filter(schema::pigs::archived.eq(archived));
+        }
+
+        // Filter by when the pig was created, if specified
+        if let Some(after) = self.created_after.as_ref().and_then(|date| parse_date(date)) {
+            res = res.filter(schema::pigs::created.ge(after));
+        }
+
+        if let Some(before) = self.created_before.as_ref().and_then(|date| parse_date(date)) {
+            res = res.filter(schema::pigs::created.lt(before));
+        }
+
+        // Filter by when the pig last changed, if specified
+        if let Some(since) = self.changed_since.as_ref().and_then(|since| parse_timestamp(since)) {
+            res = res
+                .filter(schema::pigs::updated_at.ge(since))
+                .order((schema::pigs::updated_at.asc(), schema::pigs::id.asc()));
+        }
+
         // Set the limit, if present
         res = res.limit(self.limit.unwrap_or_else(|| DEFAULT_API_RESPONSE_LIMIT) as i64);
 
@@ -129,3 +373,116 @@ impl PigQuery {
         res
     }
 }
+
+/// What checking a name against the existing pigs found, see
+/// `/api/pigs/check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PigCheck {
+    /// The name which was checked, after trimming it and replacing fancy
+    /// punctuation the same way a bulk import does
+    pub name: String,
+
+    /// Whether the name is taken, close to one which is, or unused
+    pub verdict: PigVerdict,
+
+    /// The pigs whose names matched, most similar first. Empty if the name is
+    /// [`PigVerdict::Unique`] or [`PigVerdict::Blocked`].
+    pub matches: Vec<PigMatch>,
+}
+
+/// How a name compares to the existing pigs. Each matches what a bulk import
+/// would do with the name, other than quotas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PigVerdict {
+    /// A pig already has this name, ignoring case. A bulk import would reject
+    /// it.
+    Exact,
+
+    /// Pigs with similar names exist. A bulk import would leave it pending for
+    /// someone to review.
+    Similar,
+
+    /// Nothing like the name exists yet. A bulk import would accept it.
+    Unique,
+
+    /// The name matches the blocklist, so it isn't compared to the existing
+    /// pigs. A bulk import would reject it or flag it for review, depending on
+    /// the server's config.
+    Blocked,
+}
+
+/// An existing [`Pig`] found when checking a name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PigMatch {
+    /// The pig with a matching name
+    pub pig: Pig,
+
+    /// How alike its name is to the one checked, from 0 to 1, see
+    /// [`crate::bulk::name_similarity`]
+    pub score: f64,
+}
+
+/// Parses a date formatted as `YYYY-MM-DD` to midnight UTC on that day, see
+/// [`PigQuery::created_after`]
+pub fn parse_date(date: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+/// Lets [`PigLabel`]s be saved to the DB by name
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Text, pg::Pg> for PigLabel {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let name = <String as deserialize::FromSql<sql_types::Text, pg::Pg>>::from_sql(bytes)?;
+        match PigLabel::ALL.into_iter().find(|label| label.name() == name) {
+            Some(label) => Ok(label),
+            None => Err(format!("Unknown pig label {:?}", name).into()),
+        }
+    }
+}
+
+/// Lets [`PigLabel`]s be saved to the DB by name
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Text, pg::Pg> for PigLabel {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        <str as serialize::ToSql<sql_types::Text, pg::Pg>>::to_sql(self.name(), &mut out.reborrow())
+    }
+}
+
+/// Lets [`PigLink`]s be saved to the DB as JSON
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Jsonb, pg::Pg> for PigLink {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as deserialize::FromSql<sql_types::Jsonb, pg::Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Lets [`PigLink`]s be saved to the DB as JSON
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Jsonb, pg::Pg> for PigLink {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as serialize::ToSql<sql_types::Jsonb, pg::Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
+
+/// Lets [`PigQuery`]s be saved to the DB as JSON, see
+/// [`crate::filters::SavedFilter`]
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Jsonb, pg::Pg> for PigQuery {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as deserialize::FromSql<sql_types::Jsonb, pg::Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Lets [`PigQuery`]s be saved to the DB as JSON, see
+/// [`crate::filters::SavedFilter`]
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Jsonb, pg::Pg> for PigQuery {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as serialize::ToSql<sql_types::Jsonb, pg::Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
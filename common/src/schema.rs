@@ -1,5 +1,18 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_tokens (id) {
+        id -> Uuid,
+        name -> Text,
+        owner -> Uuid,
+        roles -> Array<Text>,
+        created -> Timestamp,
+        expires -> Nullable<Timestamp>,
+        last_used -> Nullable<Timestamp>,
+        hash -> Text,
+    }
+}
+
 diesel::table! {
     bulk_imports (id) {
         id -> Uuid,
@@ -10,6 +23,59 @@ diesel::table! {
         pending -> Array<Text>,
         accepted -> Array<Uuid>,
         rejected -> Array<Text>,
+        processing -> Bool,
+        assignee -> Nullable<Uuid>,
+        hash -> Nullable<Text>,
+        revision -> Int4,
+        flagged -> Array<Text>,
+        closed -> Nullable<Timestamp>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    bulk_job_logs (id) {
+        id -> Uuid,
+        import -> Uuid,
+        request -> Uuid,
+        logged -> Timestamp,
+        level -> Text,
+        message -> Text,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Uuid,
+        recipient -> Uuid,
+        kind -> Text,
+        message -> Text,
+        target -> Nullable<Uuid>,
+        created -> Timestamp,
+        read -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    pig_changes (id) {
+        id -> Uuid,
+        pig -> Uuid,
+        name -> Text,
+        previous_name -> Nullable<Text>,
+        action -> Text,
+        actor -> Uuid,
+        changed -> Timestamp,
+    }
+}
+
+diesel::table! {
+    pig_snapshots (id) {
+        id -> Uuid,
+        name -> Text,
+        creator -> Uuid,
+        created -> Timestamp,
+        pig_ids -> Array<Uuid>,
+        pig_names -> Array<Text>,
     }
 }
 
@@ -19,6 +85,31 @@ diesel::table! {
         name -> Text,
         created -> Timestamp,
         creator -> Uuid,
+        tags -> Array<Text>,
+        links -> Array<Jsonb>,
+        archived -> Bool,
+        label -> Nullable<Text>,
+        updated_at -> Timestamp,
+        name_skeleton -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    role_imports (id) {
+        id -> Uuid,
+        importer -> Uuid,
+        imported -> Timestamp,
+        roles -> Jsonb,
+    }
+}
+
+diesel::table! {
+    saved_filters (id) {
+        id -> Uuid,
+        name -> Text,
+        creator -> Uuid,
+        shared -> Bool,
+        query -> Jsonb,
     }
 }
 
@@ -32,7 +123,20 @@ diesel::table! {
         sso_subject -> Text,
         sso_issuer -> Text,
         session_exp -> Nullable<Timestamp>,
+        session_user_agent -> Nullable<Text>,
+        session_client_version -> Nullable<Text>,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(bulk_imports, pigs, users);
+diesel::allow_tables_to_appear_in_same_query!(
+    api_tokens,
+    bulk_imports,
+    bulk_job_logs,
+    notifications,
+    pig_changes,
+    pig_snapshots,
+    pigs,
+    role_imports,
+    saved_filters,
+    users,
+);
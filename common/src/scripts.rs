@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The writing system a letter belongs to. Only scripts people are likely to
+/// name pigs in are told apart, the rest are [`Script::Other`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+    Other,
+}
+
+impl Script {
+    /// Every script, in the order they should be shown
+    pub const ALL: [Script; 12] = [
+        Self::Latin,
+        Self::Greek,
+        Self::Cyrillic,
+        Self::Armenian,
+        Self::Hebrew,
+        Self::Arabic,
+        Self::Devanagari,
+        Self::Thai,
+        Self::Hangul,
+        Self::Kana,
+        Self::Han,
+        Self::Other,
+    ];
+
+    /// The name of the script as it's shown to users
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Latin => "Latin",
+            Self::Greek => "Greek",
+            Self::Cyrillic => "Cyrillic",
+            Self::Armenian => "Armenian",
+            Self::Hebrew => "Hebrew",
+            Self::Arabic => "Arabic",
+            Self::Devanagari => "Devanagari",
+            Self::Thai => "Thai",
+            Self::Hangul => "Hangul",
+            Self::Kana => "Kana",
+            Self::Han => "CJK",
+            Self::Other => "Other",
+        }
+    }
+
+    /// The script the given character is written in. Returns [`None`] for
+    /// anything which isn't a letter, e.g. digits, spaces, and punctuation,
+    /// since those are shared by every script.
+    pub fn of(c: char) -> Option<Script> {
+        if !c.is_alphabetic() {
+            return None;
+        }
+
+        let script = match c as u32 {
+            0x0041..=0x005A | 0x0061..=0x007A | 0x00AA | 0x00BA | 0x00C0..=0x024F | 0x0250..=0x02AF => Self::Latin,
+            0x1D00..=0x1D7F | 0x1E00..=0x1EFF | 0x2C60..=0x2C7F | 0xA720..=0xA7FF | 0xAB30..=0xAB6F => Self::Latin,
+            0xFF21..=0xFF3A | 0xFF41..=0xFF5A => Self::Latin,
+            0x0370..=0x03FF | 0x1F00..=0x1FFF => Self::Greek,
+            0x0400..=0x052F | 0x1C80..=0x1C8F | 0x2DE0..=0x2DFF | 0xA640..=0xA69F => Self::Cyrillic,
+            0x0530..=0x058F | 0xFB13..=0xFB17 => Self::Armenian,
+            0x0590..=0x05FF | 0xFB1D..=0xFB4F => Self::Hebrew,
+            0x0600..=0x06FF | 0x0750..=0x077F | 0x08A0..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Self::Arabic,
+            0x0900..=0x097F | 0xA8E0..=0xA8FF => Self::Devanagari,
+            0x0E00..=0x0E7F => Self::Thai,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xA960..=0xA97F | 0xAC00..=0xD7FF | 0xFFA0..=0xFFDC => Self::Hangul,
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F | 0x1B000..=0x1B16F => Self::Kana,
+            0x3005..=0x3007 | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x323AF => Self::Han,
+            _ => Self::Other,
+        };
+
+        Some(script)
+    }
+}
+
+/// Counts how many letters of the name are written in each script
+fn count_scripts(name: &str) -> BTreeMap<Script, usize> {
+    let mut res = BTreeMap::new();
+    for script in name.chars().filter_map(Script::of) {
+        *res.entry(script).or_default() += 1;
+    }
+
+    res
+}
+
+/// The script most of the name's letters are written in. Ties go to whichever
+/// comes first in [`Script::ALL`]. Returns [`None`] if the name has no letters.
+pub fn dominant_script(name: &str) -> Option<Script> {
+    count_scripts(name).into_iter().rev().max_by_key(|(_, count)| *count).map(|(script, _)| script)
+}
+
+/// Whether the name's letters are written in more than one script, which
+/// usually means a letter was swapped for a lookalike from another alphabet.
+/// Chinese characters mixed with kana or hangul are still counted as one
+/// script, since that's how Japanese and Korean are written.
+pub fn is_mixed_script(name: &str) -> bool {
+    let mut scripts = count_scripts(name);
+    if scripts.contains_key(&Script::Kana) || scripts.contains_key(&Script::Hangul) {
+        scripts.remove(&Script::Han);
+    }
+
+    scripts.len() > 1
+}
+
+/// What the given character is commonly mistaken for, taken from the
+/// confusables in [UTS #39](https://www.unicode.org/reports/tr39/). Only
+/// lookalikes of Latin letters are listed, since those are what end up in pig
+/// names.
+fn prototype(c: char) -> Option<&'static str> {
+    let res = match c {
+        // Cyrillic
+        'а' => "a",
+        'с' => "c",
+        'ԁ' => "d",
+        'е' => "e",
+        'һ' => "h",
+        'і' => "i",
+        'ј' => "j",
+        'ӏ' => "l",
+        'о' => "o",
+        'р' => "p",
+        'ԛ' => "q",
+        'ѕ' => "s",
+        'ԝ' => "w",
+        'х' => "x",
+        'у' | 'ү' => "y",
+        'А' => "A",
+        'В' => "B",
+        'С' => "C",
+        'Е' => "E",
+        'Н' => "H",
+        'Ј' => "J",
+        'К' => "K",
+        'М' => "M",
+        'О' => "O",
+        'Р' => "P",
+        'Ԛ' => "Q",
+        'Ѕ' => "S",
+        'Т' => "T",
+        'Ԝ' => "W",
+        'Х' => "X",
+        'У' | 'Ү' => "Y",
+        // Greek
+        'α' => "a",
+        'ι' => "i",
+        'ϳ' => "j",
+        'ν' => "v",
+        'ο' => "o",
+        'ρ' => "p",
+        'ϲ' => "c",
+        'υ' => "u",
+        'χ' => "x",
+        'Α' => "A",
+        'Β' => "B",
+        'Ε' => "E",
+        'Η' => "H",
+        'Κ' => "K",
+        'Μ' => "M",
+        'Ν' => "N",
+        'Ο' => "O",
+        'Ρ' => "P",
+        'Τ' => "T",
+        'Χ' => "X",
+        'Υ' => "Y",
+        'Ζ' => "Z",
+        // Armenian
+        'հ' => "h",
+        'ո' => "n",
+        'օ' => "o",
+        'ս' => "u",
+        // Latin and digits
+        'ɑ' => "a",
+        'ɡ' => "g",
+        'ı' => "i",
+        'm' => "rn",
+        '0' => "O",
+        _ => return None,
+    };
+
+    Some(res)
+}
+
+/// Reduces the name to its skeleton, following the algorithm in
+/// [UTS #39](https://www.unicode.org/reports/tr39/#Confusable_Detection):
+/// each character is replaced with what it's commonly mistaken for, so names
+/// which look the same have the same skeleton. Fullwidth letters are treated
+/// as their ASCII equivalents. The result is lowercased, since names are
+/// compared ignoring case anyway. Accented letters aren't decomposed first.
+pub fn skeleton(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+
+    // ASCII is lowercased first so uppercase letters are checked as lowercase
+    for c in name.chars().map(|c| c.to_ascii_lowercase()) {
+        match c as u32 {
+            // fullwidth ASCII
+            0xFF01..=0xFF5E => res.push(char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)),
+            _ => match prototype(c) {
+                Some(prototype) => res.push_str(prototype),
+                None => res.push(c),
+            },
+        }
+    }
+
+    res.to_lowercase()
+}
+
+/// Whether the two names look the same despite being written differently,
+/// e.g. "Pаprika" with a Cyrillic "а" and "Paprika" in Latin, see
+/// [`skeleton`]. Names which only differ by case don't count.
+pub fn names_confusable(a: &str, b: &str) -> bool {
+    !a.eq_ignore_ascii_case(b) && skeleton(a) == skeleton(b)
+}
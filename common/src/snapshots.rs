@@ -0,0 +1,120 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// The id and name of every pig at a point in time, saved under a name so it
+/// can be compared against later, e.g. for a monthly changelog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::Identifiable, diesel::Insertable, diesel::Queryable, diesel::Selectable))]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::pig_snapshots))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct PigSnapshot {
+    /// The unique id of this snapshot
+    pub id: Uuid,
+
+    /// The unique name used to refer to this snapshot, e.g. "2025-04"
+    pub name: String,
+
+    /// The id of the user who took this snapshot
+    pub creator: Uuid,
+
+    /// When the snapshot was taken
+    pub created: NaiveDateTime,
+
+    /// The id of each pig, in the same order as [`Self::pig_names`]
+    pub pig_ids: Vec<Uuid>,
+
+    /// The name of each pig, in the same order as [`Self::pig_ids`]
+    pub pig_names: Vec<String>,
+}
+
+impl PigSnapshot {
+    /// Creates a new PigSnapshot taken at the given time with a random [`Uuid`]
+    /// from the given `(id, name)` pairs
+    pub fn new(name: &str, creator: &Uuid, pigs: Vec<(Uuid, String)>, now: NaiveDateTime) -> Self {
+        let (pig_ids, pig_names) = pigs.into_iter().unzip();
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_owned(),
+            creator: creator.to_owned(),
+            created: now,
+            pig_ids,
+            pig_names,
+        }
+    }
+
+    /// Maps the id of each pig to its name
+    pub fn to_map(&self) -> BTreeMap<Uuid, String> {
+        self.pig_ids.iter().copied().zip(self.pig_names.iter().cloned()).collect()
+    }
+}
+
+/// A pig as it was in a [`PigSnapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotPig {
+    /// The id of the pig
+    pub id: Uuid,
+
+    /// The name of the pig when the snapshot was taken
+    pub name: String,
+}
+
+/// A pig which was renamed between two [`PigSnapshot`]s
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotRename {
+    /// The id of the pig
+    pub id: Uuid,
+
+    /// The name in the older snapshot
+    pub from: String,
+
+    /// The name in the newer snapshot
+    pub to: String,
+}
+
+/// What changed in the pig list between two [`PigSnapshot`]s. Each list is
+/// sorted by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    /// Pigs which are only in the newer snapshot
+    pub added: Vec<SnapshotPig>,
+
+    /// Pigs which are in both snapshots under different names
+    pub renamed: Vec<SnapshotRename>,
+
+    /// Pigs which are only in the older snapshot
+    pub deleted: Vec<SnapshotPig>,
+}
+
+impl SnapshotDiff {
+    /// Compares the pigs in the older snapshot `from` to the newer one `to`
+    pub fn between(from: &PigSnapshot, to: &PigSnapshot) -> Self {
+        let from = from.to_map();
+        let to = to.to_map();
+        let mut res = Self::default();
+
+        for (id, name) in &to {
+            match from.get(id) {
+                None => res.added.push(SnapshotPig { id: id.to_owned(), name: name.to_owned() }),
+                Some(old) if old != name => {
+                    res.renamed.push(SnapshotRename { id: id.to_owned(), from: old.to_owned(), to: name.to_owned() })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (id, name) in &from {
+            if !to.contains_key(id) {
+                res.deleted.push(SnapshotPig { id: id.to_owned(), name: name.to_owned() });
+            }
+        }
+
+        // Sort by name since that's how people will read the changelog
+        res.added.sort_by(|a, b| a.name.cmp(&b.name));
+        res.renamed.sort_by(|a, b| a.to.cmp(&b.to));
+        res.deleted.sort_by(|a, b| a.name.cmp(&b.name));
+
+        res
+    }
+}
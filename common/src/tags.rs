@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// A tag given to at least one [`crate::pigs::Pig`], along with how many pigs
+/// have it. Tags don't exist on their own, they're simply the distinct values
+/// of every pig's tag list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::QueryableByName))]
+pub struct Tag {
+    /// The tag itself
+    #[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Text))]
+    pub name: String,
+
+    /// The number of pigs with this tag
+    #[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::BigInt))]
+    pub count: i64,
+}
+
+/// Trims each tag, removing any which are empty or duplicated
+pub fn clean_tags(tags: &Vec<String>) -> Vec<String> {
+    let mut res: Vec<String> = Vec::new();
+
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !res.iter().any(|e| e == tag) {
+            res.push(tag.to_owned());
+        }
+    }
+
+    res
+}
@@ -0,0 +1,88 @@
+use crate::users::Roles;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// A token automation can use to call the API as the user who created it, by
+/// sending `Authorization: Bearer <secret>`. The token can only use the roles
+/// it was created with, and only while its owner still has them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "server",
+    derive(diesel::AsChangeset, diesel::Identifiable, diesel::Insertable, diesel::Queryable, diesel::Selectable)
+)]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::api_tokens))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct ApiToken {
+    /// The unique id of this token
+    pub id: Uuid,
+
+    /// What the token is for, e.g. "Nightly export"
+    pub name: String,
+
+    /// The id of the user who created this token
+    pub owner: Uuid,
+
+    /// The roles this token is limited to
+    pub roles: Vec<Roles>,
+
+    /// When this token was created, or last rotated
+    pub created: NaiveDateTime,
+
+    /// When this token stops working, if ever
+    pub expires: Option<NaiveDateTime>,
+
+    /// The last time a request was made with this token
+    pub last_used: Option<NaiveDateTime>,
+
+    /// The SHA-256 hash of the secret. This never leaves the server.
+    #[serde(skip)]
+    pub hash: String,
+}
+
+impl ApiToken {
+    /// Creates a new ApiToken with a random [`Uuid`] created at the given time,
+    /// which has never been used
+    pub fn new(request: &ApiTokenRequest, owner: &Uuid, hash: String, now: NaiveDateTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: request.name.trim().to_owned(),
+            owner: owner.to_owned(),
+            roles: request.roles.iter().copied().collect(),
+            created: now,
+            expires: request.expires,
+            last_used: None,
+            hash,
+        }
+    }
+
+    /// Whether this token has expired as of the given time
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// What to create an [`ApiToken`] with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiTokenRequest {
+    /// What the token is for
+    pub name: String,
+
+    /// The roles the token is limited to, which the user must have themselves
+    pub roles: BTreeSet<Roles>,
+
+    /// When the token stops working, or [`None`] for never
+    pub expires: Option<NaiveDateTime>,
+}
+
+/// A newly created or rotated [`ApiToken`] along with its secret. This is the
+/// only time the secret is ever sent, since only its hash is saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenSecret {
+    /// The token the secret is for
+    pub token: ApiToken,
+
+    /// The secret to send as `Authorization: Bearer <secret>`
+    pub secret: String,
+}
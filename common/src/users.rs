@@ -1,8 +1,8 @@
-use crate::{query_limit_offset, query_list, query_to_yuri, DEFAULT_API_RESPONSE_LIMIT, USER_API_ROOT};
-use chrono::{NaiveDate, NaiveDateTime, Utc};
+use crate::{query_fields, query_limit_offset, query_list, query_to_yuri, DEFAULT_API_RESPONSE_LIMIT, USER_API_ROOT};
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use std::borrow::ToOwned;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
@@ -52,20 +52,48 @@ pub struct User {
     /// When the user's current session will expire. The session should be
     /// considered expired if this is [`None`] or the timestamp is in the past.
     pub session_exp: Option<NaiveDateTime>,
+
+    /// The user agent of the browser the user's current session was started
+    /// from, if it sent one
+    pub session_user_agent: Option<String>,
+
+    /// The version of the client the user's current session was started from,
+    /// if it sent one. An older version than the server's usually means the
+    /// browser is still running a cached build.
+    pub session_client_version: Option<String>,
 }
 
 impl User {
     /// Creates a new User from the given values with a random [`Uuid`] and the
-    /// current time as [`created`].
+    /// given time as [`created`].
     pub fn new(
         username: String,
         groups: Vec<String>,
         sso_subject: String,
         sso_issuer: String,
         session_exp: Option<NaiveDateTime>,
+        now: NaiveDateTime,
     ) -> User {
-        let now = Utc::now().naive_utc();
-        User { id: Uuid::new_v4(), username, groups, created: now, seen: now, sso_subject, sso_issuer, session_exp }
+        User {
+            id: Uuid::new_v4(),
+            username,
+            groups,
+            created: now,
+            seen: now,
+            sso_subject,
+            sso_issuer,
+            session_exp,
+            session_user_agent: None,
+            session_client_version: None,
+        }
+    }
+
+    /// Removes the data which only matters to the server, i.e. how the user is
+    /// identified by the OIDC provider
+    pub fn sanitized(mut self) -> Self {
+        self.sso_subject = String::default();
+        self.sso_issuer = String::default();
+        self
     }
 
     /// When OIDC and groups aren't properly setup, this returns a generic user
@@ -83,6 +111,8 @@ impl User {
             session_exp: Some(
                 NaiveDate::from_ymd_opt(9999, 12, 31).unwrap_or_default().and_hms_opt(23, 59, 59).unwrap(),
             ),
+            session_user_agent: None,
+            session_client_version: None,
         }
     }
 }
@@ -96,6 +126,11 @@ pub struct UserQuery {
     /// Performs a full-text search to only return [`User`]s with a similar name
     pub username: Option<String>,
 
+    /// A comma-separated list of the only fields the server should send for
+    /// each [`User`], e.g. `id,username`. Every field is sent if this is
+    /// missing.
+    pub fields: Option<String>,
+
     /// The maximum number of items to return
     pub limit: Option<u32>,
 
@@ -106,13 +141,14 @@ pub struct UserQuery {
 
 impl Default for UserQuery {
     fn default() -> Self {
-        Self { id: None, username: None, limit: Some(DEFAULT_API_RESPONSE_LIMIT), offset: Some(0) }
+        Self { id: None, username: None, fields: None, limit: Some(DEFAULT_API_RESPONSE_LIMIT), offset: Some(0) }
     }
 }
 
 impl UserQuery {
     query_list!(id, Uuid);
     query_limit_offset!();
+    query_fields!();
     query_to_yuri!(USER_API_ROOT);
 
     /// Filters the results to [`User`]s with a name similar to the given String
@@ -156,6 +192,27 @@ impl UserQuery {
     }
 }
 
+/// An item along with the username of the user who created it. The username
+/// is only sent when requested with `include=creator`, otherwise this
+/// serializes exactly like the item itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WithCreator<T> {
+    /// The item itself
+    #[serde(flatten)]
+    pub item: T,
+
+    /// The username of the user who created the item, if requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_username: Option<String>,
+}
+
+impl<T> WithCreator<T> {
+    /// Wraps the item without a username
+    pub fn new(item: T) -> Self {
+        Self { item, creator_username: None }
+    }
+}
+
 /// A response to a user fetch request. If the requester has
 /// [`Roles::UserViewer`], they will be sent the full data for each user.
 /// Otherwise, only a mapping of ids to usernames will be returned.
@@ -192,16 +249,37 @@ impl UserFetchResponse {
     }
 }
 
+/// The result of pre-creating users from a CSV, so roles can be granted to
+/// them before they first sign in. Each line of the CSV is sorted into one of
+/// these lists.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserProvision {
+    /// The users which were created, or would be on a dry run
+    pub created: Vec<User>,
+
+    /// The users which already existed with the same subject and issuer and
+    /// were left untouched
+    pub existing: Vec<User>,
+
+    /// Each line which couldn't be read, along with why
+    pub invalid: Vec<String>,
+}
+
 /// Each action a user is allowed to take. The groups assigned to [`User`]s
 /// directly are simply a list of roles which they grant the user.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Text))]
 pub enum Roles {
     /// Lets a user view the pig list
     PigViewer,
 
-    /// Lets a user edit the pig list (create, update, delete pigs)
+    /// Lets a user edit the pig list (create and update pigs)
     PigEditor,
 
+    /// Lets a user perform destructive actions on the pig list (delete pigs)
+    PigAdmin,
+
     /// Lets a user create and process [`crate::bulk::BulkImport`]s
     BulkEditor,
 
@@ -217,6 +295,10 @@ pub enum Roles {
 
     /// Lets a user view the audit log
     LogViewer,
+
+    /// Lets a user decide whether names flagged in a
+    /// [`crate::bulk::BulkImport`] are appropriate
+    Moderator,
 }
 
 impl Roles {
@@ -226,13 +308,169 @@ impl Roles {
         [
             Self::PigViewer,
             Self::PigEditor,
+            Self::PigAdmin,
             Self::BulkEditor,
             Self::BulkAdmin,
             Self::UserViewer,
             Self::UserAdmin,
             Self::LogViewer,
+            Self::Moderator,
         ]
         .iter()
         .copied()
     }
+
+    /// The name of the role, which is also how it's saved
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PigViewer => "PigViewer",
+            Self::PigEditor => "PigEditor",
+            Self::PigAdmin => "PigAdmin",
+            Self::BulkEditor => "BulkEditor",
+            Self::BulkAdmin => "BulkAdmin",
+            Self::UserViewer => "UserViewer",
+            Self::UserAdmin => "UserAdmin",
+            Self::LogViewer => "LogViewer",
+            Self::Moderator => "Moderator",
+        }
+    }
+}
+
+/// Lets [`Roles`] be saved to the DB by name, see
+/// [`crate::tokens::ApiToken::roles`]
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Text, pg::Pg> for Roles {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let name = <String as deserialize::FromSql<sql_types::Text, pg::Pg>>::from_sql(bytes)?;
+        match Self::values().find(|role| role.name() == name) {
+            Some(role) => Ok(role),
+            None => Err(format!("Unknown role {:?}", name).into()),
+        }
+    }
+}
+
+/// Lets [`Roles`] be saved to the DB by name, see
+/// [`crate::tokens::ApiToken::roles`]
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Text, pg::Pg> for Roles {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        <str as serialize::ToSql<sql_types::Text, pg::Pg>>::to_sql(self.name(), &mut out.reborrow())
+    }
+}
+
+/// Which [`Roles`] are needed to use each API endpoint, so clients can tell
+/// which actions will be forbidden before trying them. Endpoints are keyed by
+/// their path without any query params, e.g. `/api/users/expire`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Each endpoint and the roles which can use it, any one of them is
+    /// enough. Endpoints which aren't listed only require signing in.
+    pub endpoints: BTreeMap<String, BTreeSet<Roles>>,
+
+    /// The most items anyone can fetch at once, unless one of their roles
+    /// allows more. 0 means there's no limit.
+    #[serde(default)]
+    pub max_limit: u32,
+
+    /// The most items users with each role can fetch at once, the highest of
+    /// a user's roles applies. 0 means there's no limit.
+    #[serde(default)]
+    pub role_limits: BTreeMap<Roles, u32>,
+}
+
+impl Capabilities {
+    /// Lets users with any of the given roles use the endpoint
+    pub fn with_endpoint(mut self, endpoint: String, roles: &[Roles]) -> Self {
+        self.endpoints.entry(endpoint).or_default().extend(roles.iter().copied());
+        self
+    }
+
+    /// Whether a user with the given roles can use the endpoint
+    pub fn allows(&self, endpoint: &str, roles: &BTreeSet<Roles>) -> bool {
+        self.endpoints.get(endpoint).is_none_or(|required| !required.is_disjoint(roles))
+    }
+
+    /// Sets the most items anyone can fetch at once, and how many users with
+    /// each role can fetch instead
+    pub fn with_limits(mut self, max_limit: u32, role_limits: &BTreeMap<Roles, u32>) -> Self {
+        self.max_limit = max_limit;
+        self.role_limits = role_limits.to_owned();
+        self
+    }
+
+    /// The most items a user with the given roles can fetch at once, or
+    /// [`None`] if there's no limit
+    pub fn limit_for(&self, roles: &BTreeSet<Roles>) -> Option<u32> {
+        let limits: Vec<u32> =
+            roles.iter().filter_map(|role| self.role_limits.get(role).copied()).chain([self.max_limit]).collect();
+
+        // no limit beats any limit
+        match limits.contains(&0) {
+            true => None,
+            false => limits.into_iter().max(),
+        }
+    }
+}
+
+/// Which roles are provided to which users. This can be exported and imported
+/// through the API so permission changes can be reviewed before they're
+/// applied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::AsExpression, diesel::FromSqlRow))]
+#[cfg_attr(feature = "server", diesel(sql_type = diesel::sql_types::Jsonb))]
+pub struct RoleConfig {
+    /// The roles provided by each group a [`User`] can be a member of
+    #[serde(default)]
+    pub groups: BTreeMap<String, BTreeSet<Roles>>,
+
+    /// Roles provided to individual [`User`]s by id, in addition to the ones
+    /// they get from their groups
+    #[serde(default)]
+    pub users: BTreeMap<Uuid, BTreeSet<Roles>>,
+}
+
+/// Lets [`RoleConfig`]s be saved to the DB as JSON, see [`RoleImport`]
+#[cfg(feature = "server")]
+impl deserialize::FromSql<sql_types::Jsonb, pg::Pg> for RoleConfig {
+    fn from_sql(bytes: pg::PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as deserialize::FromSql<sql_types::Jsonb, pg::Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Lets [`RoleConfig`]s be saved to the DB as JSON, see [`RoleImport`]
+#[cfg(feature = "server")]
+impl serialize::ToSql<sql_types::Jsonb, pg::Pg> for RoleConfig {
+    fn to_sql<'b>(&'b self, out: &mut serialize::Output<'b, '_, pg::Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as serialize::ToSql<sql_types::Jsonb, pg::Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
+
+/// A [`RoleConfig`] which was imported through the API. The most recent one
+/// overrides the groups set in the server config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(diesel::Insertable, diesel::Queryable, diesel::Selectable))]
+#[cfg_attr(feature = "server", diesel(table_name = crate::schema::role_imports))]
+#[cfg_attr(feature = "server", diesel(check_for_backend(diesel::pg::Pg)))]
+pub struct RoleImport {
+    /// The unique id of this import
+    pub id: Uuid,
+
+    /// The id of the user who imported this config
+    pub importer: Uuid,
+
+    /// When this config was imported
+    pub imported: NaiveDateTime,
+
+    /// The imported config
+    pub roles: RoleConfig,
+}
+
+impl RoleImport {
+    /// Creates a new RoleImport with a random [`Uuid`] imported at the given
+    /// time
+    pub fn new(importer: &Uuid, roles: &RoleConfig, now: NaiveDateTime) -> Self {
+        Self { id: Uuid::new_v4(), importer: importer.to_owned(), imported: now, roles: roles.to_owned() }
+    }
 }
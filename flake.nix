@@ -294,6 +294,7 @@
                                     type = lib.types.attrsOf (lib.types.listOf (lib.types.enum [
                                         "PigViewer"
                                         "PigEditor"
+                                        "PigAdmin"
                                         "BulkEditor"
                                         "BulkAdmin"
                                         "UserViewer"
@@ -310,7 +311,7 @@
                                     '';
                                     example = {
                                         user = [ "PigViewer" "PigEditor" "BulkEditor" ];
-                                        admin = [ "BulkAdmin" "UserViewer" "UserAdmin" "LogViewer" ];
+                                        admin = [ "PigAdmin" "BulkAdmin" "UserViewer" "UserAdmin" "LogViewer" ];
                                     };
                                 };
                             };
@@ -0,0 +1,10 @@
+-- Named pig queries saved by users. The query itself is stored as JSON since
+-- its structure will keep changing as more filters are added.
+CREATE TABLE saved_filters
+(
+    id      uuid PRIMARY KEY,
+    name    text    NOT NULL,
+    creator uuid    NOT NULL,
+    shared  boolean NOT NULL default false,
+    query   jsonb   NOT NULL
+);
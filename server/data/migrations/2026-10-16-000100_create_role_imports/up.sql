@@ -0,0 +1,10 @@
+-- Every role configuration imported through the API. The most recent import
+-- takes precedence over the groups defined in the config file. Older imports
+-- are kept so there's a record of who changed permissions and when.
+CREATE TABLE role_imports
+(
+    id       uuid PRIMARY KEY,
+    importer uuid      NOT NULL,
+    imported timestamp NOT NULL,
+    roles    jsonb     NOT NULL
+);
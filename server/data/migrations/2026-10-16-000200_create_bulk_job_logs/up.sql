@@ -0,0 +1,14 @@
+-- Log lines written while processing bulk imports, tied to the request which
+-- started the processing. There's intentionally no foreign key on the import
+-- since logs should be kept even if the import itself never got saved.
+CREATE TABLE bulk_job_logs
+(
+    id      uuid PRIMARY KEY,
+    import  uuid      NOT NULL,
+    request uuid      NOT NULL,
+    logged  timestamp NOT NULL,
+    level   text      NOT NULL,
+    message text      NOT NULL
+);
+
+CREATE INDEX bulk_job_logs_import_idx ON bulk_job_logs (import);
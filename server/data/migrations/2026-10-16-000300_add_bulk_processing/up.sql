@@ -0,0 +1,5 @@
+-- Imports are saved before their names are processed so pigs created for them
+-- can be recovered if the server stops part way through. This marks the ones
+-- which haven't finished processing yet.
+ALTER TABLE bulk_imports
+    ADD COLUMN processing boolean NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+ALTER TABLE bulk_imports
+    ADD COLUMN assignee uuid;
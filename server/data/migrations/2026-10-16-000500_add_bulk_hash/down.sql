@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX bulk_imports_hash_idx;
+
+ALTER TABLE bulk_imports
+    DROP COLUMN hash;
@@ -0,0 +1,6 @@
+-- A hash of the names in each import, used to find identical lists which were
+-- pasted more than once. Imports created before this was added don't have one.
+ALTER TABLE bulk_imports
+    ADD COLUMN hash text;
+
+CREATE INDEX bulk_imports_hash_idx ON bulk_imports (hash);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX pigs_tags_idx;
+
+ALTER TABLE pigs
+    DROP COLUMN tags;
@@ -0,0 +1,6 @@
+-- See create_bulk for why nulls are checked
+ALTER TABLE pigs
+    ADD COLUMN tags text[] NOT NULL DEFAULT '{}' check (array_position(tags, null) is null);
+
+-- Lets the pig list be filtered by tag without scanning every pig
+CREATE INDEX pigs_tags_idx ON pigs USING gin (tags);
@@ -0,0 +1,2 @@
+ALTER TABLE bulk_imports
+    ADD COLUMN revision integer NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE pigs
+    ADD COLUMN links jsonb[] NOT NULL DEFAULT '{}';
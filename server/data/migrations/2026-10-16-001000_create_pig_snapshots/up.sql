@@ -0,0 +1,11 @@
+-- Point-in-time copies of the pig list, used to find out what changed between
+-- them. Only the ids and names are kept, in matching order.
+CREATE TABLE pig_snapshots
+(
+    id        uuid PRIMARY KEY,
+    name      text UNIQUE NOT NULL,
+    creator   uuid        NOT NULL,
+    created   timestamp   NOT NULL,
+    pig_ids   uuid[]      NOT NULL,
+    pig_names text[]      NOT NULL
+);
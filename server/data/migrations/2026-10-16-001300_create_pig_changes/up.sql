@@ -0,0 +1,14 @@
+-- Every creation, rename, and deletion of a pig, along with who did it. The pig
+-- isn't referenced so its changes outlive it.
+CREATE TABLE pig_changes
+(
+    id            uuid PRIMARY KEY,
+    pig           uuid      NOT NULL,
+    name          text      NOT NULL,
+    previous_name text,
+    action        text      NOT NULL,
+    actor         uuid      NOT NULL,
+    changed       timestamp NOT NULL
+);
+
+CREATE INDEX pig_changes_changed_idx ON pig_changes (changed);
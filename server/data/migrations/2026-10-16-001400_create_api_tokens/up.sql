@@ -0,0 +1,15 @@
+-- Tokens automation can use to call the API as the user who created them. Only
+-- a hash of each secret is stored, so a leaked database doesn't leak them.
+CREATE TABLE api_tokens
+(
+    id        uuid PRIMARY KEY,
+    name      text      NOT NULL,
+    owner     uuid      NOT NULL,
+    roles     text[]    NOT NULL,
+    created   timestamp NOT NULL,
+    expires   timestamp,
+    last_used timestamp,
+    hash      text      NOT NULL UNIQUE
+);
+
+CREATE INDEX api_tokens_owner_idx ON api_tokens (owner);
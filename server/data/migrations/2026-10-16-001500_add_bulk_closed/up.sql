@@ -0,0 +1,4 @@
+-- Imports which were given up on before every name was processed. Their
+-- pending names are kept so they can be picked back up later.
+ALTER TABLE bulk_imports
+    ADD COLUMN closed timestamp;
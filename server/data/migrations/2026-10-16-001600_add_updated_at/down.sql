@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER set_updated_at ON bulk_imports;
+ALTER TABLE bulk_imports
+    DROP COLUMN updated_at;
+
+DROP TRIGGER set_updated_at ON pigs;
+ALTER TABLE pigs
+    DROP COLUMN updated_at;
+
+DROP FUNCTION set_updated_at_utc();
@@ -0,0 +1,35 @@
+-- When each pig and import last changed, so clients can fetch only what
+-- changed since they last checked. A trigger keeps it up to date, so nothing
+-- which changes them can forget to. Existing rows count as changed when they
+-- were created.
+CREATE OR REPLACE FUNCTION set_updated_at_utc() RETURNS trigger AS $$
+BEGIN
+    -- every other timestamp is saved in UTC, whatever timezone the db is in
+    IF NEW IS DISTINCT FROM OLD THEN
+        NEW.updated_at := now() AT TIME ZONE 'utc';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE pigs
+    ADD COLUMN updated_at timestamp;
+UPDATE pigs
+    SET updated_at = created;
+ALTER TABLE pigs
+    ALTER COLUMN updated_at SET NOT NULL,
+    ALTER COLUMN updated_at SET DEFAULT (now() AT TIME ZONE 'utc');
+CREATE INDEX pigs_updated_at_idx ON pigs (updated_at);
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON pigs
+    FOR EACH ROW EXECUTE PROCEDURE set_updated_at_utc();
+
+ALTER TABLE bulk_imports
+    ADD COLUMN updated_at timestamp;
+UPDATE bulk_imports
+    SET updated_at = coalesce(greatest(finished, closed), started);
+ALTER TABLE bulk_imports
+    ALTER COLUMN updated_at SET NOT NULL,
+    ALTER COLUMN updated_at SET DEFAULT (now() AT TIME ZONE 'utc');
+CREATE INDEX bulk_imports_updated_at_idx ON bulk_imports (updated_at);
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON bulk_imports
+    FOR EACH ROW EXECUTE PROCEDURE set_updated_at_utc();
@@ -0,0 +1,15 @@
+-- Messages for a single user about something which happened to them, their
+-- pigs, or their imports. The recipient isn't referenced so deleting a user
+-- doesn't have to clean these up first.
+CREATE TABLE notifications
+(
+    id        uuid PRIMARY KEY,
+    recipient uuid      NOT NULL,
+    kind      text      NOT NULL,
+    message   text      NOT NULL,
+    target    uuid,
+    created   timestamp NOT NULL,
+    read      timestamp
+);
+
+CREATE INDEX notifications_recipient_idx ON notifications (recipient, created);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE users
+    DROP COLUMN session_user_agent,
+    DROP COLUMN session_client_version;
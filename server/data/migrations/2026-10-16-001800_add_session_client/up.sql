@@ -0,0 +1,5 @@
+-- Which browser and client build each user's current session was started
+-- from, so support can tell an old cached client apart from a server bug
+ALTER TABLE users
+    ADD COLUMN session_user_agent TEXT,
+    ADD COLUMN session_client_version TEXT;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX pigs_name_skeleton_idx;
+ALTER TABLE pigs
+    DROP COLUMN name_skeleton;
@@ -0,0 +1,6 @@
+-- The skeleton of each pig's name, so names which only look like an existing
+-- one can be found without reading every pig. It's computed by the server, so
+-- existing pigs are filled in when it starts rather than here.
+ALTER TABLE pigs
+    ADD COLUMN name_skeleton TEXT;
+CREATE INDEX pigs_name_skeleton_idx ON pigs (name_skeleton);
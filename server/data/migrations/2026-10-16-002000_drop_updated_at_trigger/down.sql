@@ -0,0 +1,15 @@
+-- This file should undo anything in `up.sql`
+CREATE OR REPLACE FUNCTION set_updated_at_utc() RETURNS trigger AS $$
+BEGIN
+    -- every other timestamp is saved in UTC, whatever timezone the db is in
+    IF NEW IS DISTINCT FROM OLD THEN
+        NEW.updated_at := now() AT TIME ZONE 'utc';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON pigs
+    FOR EACH ROW EXECUTE PROCEDURE set_updated_at_utc();
+CREATE TRIGGER set_updated_at BEFORE UPDATE ON bulk_imports
+    FOR EACH ROW EXECUTE PROCEDURE set_updated_at_utc();
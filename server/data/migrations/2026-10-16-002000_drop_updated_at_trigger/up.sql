@@ -0,0 +1,6 @@
+-- The server sets updated_at from its own clock whenever it changes a pig or
+-- import, so it agrees with every other timestamp, including under a test
+-- clock.
+DROP TRIGGER set_updated_at ON bulk_imports;
+DROP TRIGGER set_updated_at ON pigs;
+DROP FUNCTION set_updated_at_utc();
@@ -0,0 +1,72 @@
+use crate::auth::AuthenticatedUser;
+use crate::config::Config;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+use pigweb_common::audit::{PigChange, PigChangeAction, RECENT_CHANGES_LIMIT};
+use pigweb_common::schema;
+use pigweb_common::users::Roles;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+
+/// The most changes which can be fetched at once
+const MAX_RECENT_CHANGES: u32 = 100;
+
+/// Returns a list of all audit log api routes
+pub fn get_audit_api_routes() -> Vec<Route> {
+    routes![api_audit_recent]
+}
+
+/// Saves the given changes to the audit log. The change itself has already
+/// gone through by the time it's recorded, so failing to record it is only
+/// logged instead of failing the whole request.
+pub fn record_changes(db_connection: &mut PgConnection, changes: &Vec<PigChange>) {
+    if changes.is_empty() {
+        return;
+    }
+
+    let sql_res = diesel::insert_into(schema::pig_changes::table).values(changes).execute(db_connection);
+    if let Err(err) = sql_res {
+        error!("Unable to record pig changes {:?}: {:?}", changes, err);
+    }
+}
+
+/// Returns the most recent changes to the pig list, newest first. With
+/// `deleted`, only deletions are returned, otherwise only creations and
+/// renames are.
+#[get("/recent?<deleted>&<limit>")]
+async fn api_audit_recent(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    deleted: Option<bool>,
+    limit: Option<u32>,
+) -> Result<Json<Vec<PigChange>>, Status> {
+    if !auth_user.has_role(config, Roles::LogViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let limit = limit.unwrap_or(RECENT_CHANGES_LIMIT).min(MAX_RECENT_CHANGES);
+    let mut query = schema::pig_changes::table.into_boxed();
+    query = if deleted.unwrap_or(false) {
+        query.filter(schema::pig_changes::action.eq(PigChangeAction::Deleted))
+    } else {
+        query.filter(schema::pig_changes::action.ne(PigChangeAction::Deleted))
+    };
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = query
+        .order(schema::pig_changes::changed.desc())
+        .limit(limit as i64)
+        .select(PigChange::as_select())
+        .load(db_connection.deref_mut());
+
+    match sql_res {
+        Ok(changes) => Ok(Json(changes)),
+        Err(err) => {
+            error!("Unable to load recent pig changes: {:?}", err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
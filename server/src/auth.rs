@@ -1,13 +1,18 @@
-use crate::config::Config;
+use crate::clock::Clock;
+use crate::config::{Config, OpenIDConfig};
+use crate::requestlog::RequestUser;
+use crate::search::{UserCache, UsernameCache};
+use crate::tokenapi::authenticate_token;
 use crate::userapi::{get_user_roles, user_has_role};
-use chrono::{DateTime, Utc};
+use chrono::DateTime;
 use diesel::internal::derives::multiconnection::chrono::NaiveDateTime;
 use diesel::{
     ExpressionMethods, NullableExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper,
 };
 use jsonwebtoken::{DecodingKey, Validation};
+use pigweb_common::tokens::ApiToken;
 use pigweb_common::users::{Roles, User};
-use pigweb_common::{schema, OpenIDAuth, COOKIE_JWT, COOKIE_USER};
+use pigweb_common::{schema, OpenIDAuth, CLIENT_VERSION_HEADER, COOKIE_JWT, COOKIE_REFRESH, COOKIE_USER};
 use rocket::http::{Cookie, CookieJar, SameSite, Status};
 use rocket::outcome::try_outcome;
 use rocket::outcome::Outcome::{Error, Success};
@@ -21,6 +26,10 @@ use std::collections::BTreeSet;
 use std::ops::DerefMut;
 use std::sync::Mutex;
 
+/// The most characters of the user agent and client version which are saved
+/// with a session, since they're whatever the browser says they are
+const MAX_CLIENT_INFO_LENGTH: usize = 256;
+
 /// A [Request Guard](FromRequest) which requires the user be signed in with an
 /// active session before accessing the given route.
 pub struct AuthenticatedUser {
@@ -29,6 +38,10 @@ pub struct AuthenticatedUser {
 
     /// The user data backing this AuthenticatedUser
     pub user: User,
+
+    /// The API token the user signed in with, if they used one instead of a
+    /// session. This limits which roles they have.
+    pub token: Option<ApiToken>,
 }
 
 impl AuthenticatedUser {
@@ -36,41 +49,43 @@ impl AuthenticatedUser {
     fn invalidate_session(cookies: &CookieJar) -> Outcome<AuthenticatedUser, ()> {
         cookies.remove_private(COOKIE_JWT);
         cookies.remove_private(COOKIE_USER);
+        cookies.remove_private(COOKIE_REFRESH);
         Error((Status::Unauthorized, ()))
     }
 
-    /// Whether this user is in a group which provides the given Role.
-    ///
-    /// ***Always returns true if OIDC or groups are not configured.***
-    pub fn has_role(&self, config: &Config, role: Roles) -> bool {
-        user_has_role(config, &self.user, role)
-    }
-
-    /// Gets all roles this user has been provided by their groups.
-    ///
-    /// ***Returns a set of all roles if the OIDC or groups are not configured.***
-    pub fn get_roles(&self, config: &Config) -> BTreeSet<Roles> {
-        get_user_roles(config, &self.user)
-    }
-}
-
-// adding async_trait resolves E0195, somehow... https://stackoverflow.com/a/69271844
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for AuthenticatedUser {
-    // This must be nothing for try_outcome!() to work
-    type Error = ();
-
+    /// Checks the session cookies and finds or creates the user they belong
+    /// to, see [`FromRequest`]
     // see https://github.com/jebrosen/rocket_oauth2/blob/b0971d6d6e0e1422306e397bc3e018c1ec822013/examples/user_info/src/main.rs#L18-L30
-    async fn from_request(request: &'r Request<'_>) -> Outcome<AuthenticatedUser, ()> {
+    async fn authenticate(request: &Request<'_>) -> Outcome<AuthenticatedUser, ()> {
         // Get the request guards we need
         let config = try_outcome!(request.guard::<&State<Config>>().await);
         let cookies = request.cookies();
         let db_connection = try_outcome!(request.guard::<&State<Mutex<PgConnection>>>().await);
+        let user_cache = try_outcome!(request.guard::<&State<UserCache>>().await);
+        let username_cache = try_outcome!(request.guard::<&State<UsernameCache>>().await);
+        let clock = try_outcome!(request.guard::<&State<Clock>>().await);
+
+        // Automation signs in with an API token instead of a session
+        if let Some(authorization) = request.headers().get_one("Authorization") {
+            let Some(secret) = authorization.strip_prefix("Bearer ") else {
+                return Error((Status::Unauthorized, ()));
+            };
+
+            let mut db_connection = db_connection.lock().unwrap();
+            return match authenticate_token(db_connection.deref_mut(), clock, secret.trim()) {
+                Ok(Some((user, token))) => Success(AuthenticatedUser { jwt: None, user, token: Some(token) }),
+                Ok(None) => Error((Status::Unauthorized, ())),
+                Err(err) => {
+                    error!("Unable to check API token: {:?}", err);
+                    Error((Status::InternalServerError, ()))
+                }
+            };
+        }
 
         // First, check the config to see if authentication is actually configured
         // If authentication isn't configured, pass the challenge and return the system user
         if config.oidc.as_ref().is_none() {
-            return Success(AuthenticatedUser { jwt: None, user: User::get_system_user() });
+            return Success(AuthenticatedUser { jwt: None, user: User::get_system_user(), token: None });
         }
 
         // Get the JWT cookie and attempt to parse it to a Value
@@ -79,7 +94,7 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
             let jwt_opt: Option<Claims> = serde_json::from_str(jwt_cookie.value()).ok();
             if let Some(jwt) = jwt_opt {
                 // Check if the JWT expired, or if we couldn't get it, expire it anyway
-                if jwt.exp * 1000 <= Utc::now().timestamp_millis() {
+                if jwt.exp * 1000 <= clock.now().timestamp_millis() {
                     // Invalidate the session
                     return AuthenticatedUser::invalidate_session(cookies);
                 }
@@ -108,7 +123,7 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                         if res.len() > 0 {
                             if let Some(db_exp) = res[0] {
                                 // If the expiration as per the db has passed, invalidate the session
-                                if db_exp.to_owned() <= Utc::now().naive_utc() {
+                                if db_exp.to_owned() <= clock.now_naive() {
                                     return AuthenticatedUser::invalidate_session(cookies);
                                 }
                             }
@@ -142,9 +157,10 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                             let mut user = user_vec[0].to_owned();
 
                             // Update our user info from the new JWT info
-                            user.seen = Utc::now().naive_utc();
+                            user.seen = clock.now_naive();
                             user.session_exp =
                                 Some(DateTime::from_timestamp(jwt.exp, 0).unwrap_or_default().naive_utc());
+                            (user.session_user_agent, user.session_client_version) = client_info(request);
 
                             if let Some(preferred_username) = jwt.preferred_username.as_ref() {
                                 user.username = preferred_username.to_owned();
@@ -161,6 +177,8 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
 
                             if sql_res.is_ok() {
                                 // Save the user result
+                                user_cache.clear();
+                                username_cache.clear();
                                 user_res = Some(user);
                                 create_new_user = false;
                             } else {
@@ -176,13 +194,15 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                             // Create a new user
                             let session_exp =
                                 DateTime::from_timestamp(jwt.exp.to_owned(), 0).unwrap_or_default().naive_utc();
-                            let user = User::new(
+                            let mut user = User::new(
                                 preferred_username.to_owned(),
                                 jwt.groups.as_ref().unwrap_or(&Vec::new()).to_owned(), // &Vec doesn't implement default()
                                 jwt.sub.to_owned(),
                                 jwt.iss.to_owned(),
                                 Some(session_exp),
+                                clock.now_naive(),
                             );
+                            (user.session_user_agent, user.session_client_version) = client_info(request);
 
                             // ...and save it to the DB
                             let sql_res = diesel::insert_into(schema::users::table)
@@ -190,6 +210,7 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                                 .execute(db_connection.deref_mut());
 
                             if sql_res.is_ok() {
+                                user_cache.clear();
                                 user_res = Some(user);
                             } else {
                                 error!("Unable to save new user {:?}: {:?}", user, sql_res.unwrap_err());
@@ -200,21 +221,9 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 }
 
                 // Return the user if we have it
-                if user_res.is_some() {
-                    // Save the user cookie
-                    cookies.add_private(
-                        Cookie::build((
-                            COOKIE_USER,
-                            serde_json::to_string(&user_res).unwrap_or_else(|e| {
-                                error!("Unable to convert User struct into JSON: {:?}", e);
-                                "{}".to_owned()
-                            }),
-                        ))
-                        .same_site(SameSite::Lax)
-                        .build(),
-                    );
-
-                    return Success(AuthenticatedUser { jwt: Some(jwt), user: user_res.unwrap() });
+                if let Some(user) = user_res {
+                    save_user_cookie(cookies, &user);
+                    return Success(AuthenticatedUser { jwt: Some(jwt), user, token: None });
                 }
             }
         }
@@ -222,6 +231,45 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
         // If there are any errors, you're probably unauthorized
         AuthenticatedUser::invalidate_session(cookies)
     }
+
+    /// Whether this user is in a group which provides the given Role, and the
+    /// API token they signed in with allows it, if any.
+    ///
+    /// ***Always returns true if OIDC or groups are not configured and no
+    /// token was used.***
+    pub fn has_role(&self, config: &Config, role: Roles) -> bool {
+        user_has_role(config, &self.user, role) && self.token.as_ref().is_none_or(|token| token.roles.contains(&role))
+    }
+
+    /// Gets all roles this user has been provided by their groups, limited to
+    /// the ones the API token they signed in with allows, if any.
+    ///
+    /// ***Returns a set of all roles if the OIDC or groups are not configured
+    /// and no token was used.***
+    pub fn get_roles(&self, config: &Config) -> BTreeSet<Roles> {
+        let mut roles = get_user_roles(config, &self.user);
+        if let Some(token) = self.token.as_ref() {
+            roles.retain(|role| token.roles.contains(role));
+        }
+
+        roles
+    }
+}
+
+// adding async_trait resolves E0195, somehow... https://stackoverflow.com/a/69271844
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedUser {
+    // This must be nothing for try_outcome!() to work
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<AuthenticatedUser, ()> {
+        let res = AuthenticatedUser::authenticate(request).await;
+        if let Success(auth_user) = &res {
+            RequestUser::record(request, auth_user.user.id);
+        }
+
+        res
+    }
 }
 
 /// Represents the claims returned by a JWT response. Includes all [mandatory
@@ -263,7 +311,7 @@ pub struct Claims {
 
 /// Returns a list of all auth api routes
 pub fn get_auth_api_routes() -> Vec<Route> {
-    routes![is_authenticated, oidc_login, oidc_response, oidc_logout]
+    routes![is_authenticated, current_user, oidc_login, oidc_response, renew_session, oidc_logout]
 }
 
 /// Checks whether the user has a valid session.
@@ -275,6 +323,14 @@ async fn is_authenticated(user: AuthenticatedUser, config: &State<Config>) -> Js
     Json(user.get_roles(config))
 }
 
+/// Returns the signed in user, so the client knows who it is. Anything only
+/// the server needs is removed. If the user isn't signed in, returns status
+/// 401 unauthorized.
+#[get("/me")]
+async fn current_user(user: AuthenticatedUser) -> Json<User> {
+    Json(user.user.sanitized())
+}
+
 /// Redirects users to the configured OIDC login page
 #[get("/oidc/login")]
 async fn oidc_login(oauth2: OAuth2<OpenIDAuth>, config: &State<Config>, cookies: &CookieJar<'_>) -> Redirect {
@@ -298,13 +354,87 @@ async fn oidc_response(
     cookies: &CookieJar<'_>,
 ) -> Result<Redirect, Status> {
     // Only force the user to login if it's actually configured
-    if config.oidc.as_ref().is_none() {
+    let Some(oidc_config) = config.oidc.as_ref() else {
         return Ok(Redirect::to("/"));
+    };
+
+    match save_session(oidc_config, &token_response, cookies) {
+        Some(_) => Ok(Redirect::to("/")),
+        None => Err(Status::InternalServerError),
     }
+}
 
-    // Get the OIDC config and response JSON values
+/// Renews the user's session with the refresh token the OIDC provider gave
+/// them at login, so they don't have to sign in again. Returns the new expiry
+/// timestamp of the session.
+/// - If the user signed in with an API token, returns status 403 forbidden
+/// - If there's no refresh token for the session, returns status 422
+///   unprocessable entity. The provider only gives one out if it's been
+///   configured to, usually with the `offline_access` scope.
+/// - If the provider refuses the refresh token, returns status 502 bad gateway
+#[post("/renew")]
+async fn renew_session(
+    auth_user: AuthenticatedUser,
+    oauth2: Option<OAuth2<OpenIDAuth>>,
+    config: &State<Config>,
+    cookies: &CookieJar<'_>,
+    db_connection: &State<Mutex<PgConnection>>,
+    user_cache: &State<UserCache>,
+) -> Result<Json<NaiveDateTime>, Status> {
+    if auth_user.token.is_some() {
+        return Err(Status::Forbidden);
+    }
+
+    // Without OIDC, there's only the system user, whose session never expires
+    let (Some(oidc_config), Some(oauth2)) = (config.oidc.as_ref(), oauth2) else {
+        return Ok(Json(auth_user.user.session_exp.unwrap_or_default()));
+    };
+
+    let Some(refresh_token) = cookies.get_private(COOKIE_REFRESH) else {
+        return Err(Status::UnprocessableEntity);
+    };
+
+    let token_response = match oauth2.refresh(refresh_token.value()).await {
+        Ok(token_response) => token_response,
+        Err(e) => {
+            warn!("Unable to renew the session of user {:?}: {:?}", auth_user.user.id, e);
+            return Err(Status::BadGateway);
+        }
+    };
+
+    let Some(jwt) = save_session(oidc_config, &token_response, cookies) else {
+        return Err(Status::BadGateway);
+    };
+
+    let mut user = auth_user.user;
+    let session_exp = DateTime::from_timestamp(jwt.exp, 0).unwrap_or_default().naive_utc();
+    user.session_exp = Some(session_exp);
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::update(schema::users::table)
+        .filter(schema::users::id.eq(user.id))
+        .set(schema::users::session_exp.eq(session_exp))
+        .execute(db_connection.deref_mut());
+
+    if let Err(e) = sql_res {
+        error!("Unable to save the renewed session of user {:?}: {:?}", user.id, e);
+        return Err(Status::InternalServerError);
+    }
+
+    user_cache.clear();
+    save_user_cookie(cookies, &user);
+    Ok(Json(session_exp))
+}
+
+/// Validates the id_token on the OIDC provider's response and saves its claims
+/// as the session cookie, along with the refresh token if there is one.
+/// Returns the claims, or [`None`] if the id_token is missing or invalid.
+fn save_session(
+    oidc_config: &OpenIDConfig,
+    token_response: &TokenResponse<OpenIDAuth>,
+    cookies: &CookieJar<'_>,
+) -> Option<Claims> {
     // What the token response should look like: https://openid.net/specs/openid-connect-core-1_0.html#TokenResponse
-    let oidc_config = config.oidc.as_ref().unwrap();
     let response_values = token_response.as_value();
 
     // yes, this is nested quite a bit, but autism has forced my hand in covering error cases
@@ -337,8 +467,16 @@ async fn oidc_response(
                     .build(),
                 );
 
-                // FINALLY return our OK case
-                return Ok(Redirect::to("/"));
+                // Keep the refresh token for renewing the session later. Some
+                // providers don't send a new one on renewal, so the old one
+                // is kept in that case.
+                if let Some(refresh_token) = token_response.refresh_token() {
+                    cookies.add_private(
+                        Cookie::build((COOKIE_REFRESH, refresh_token.to_owned())).same_site(SameSite::Lax).build(),
+                    );
+                }
+
+                return Some(jwt.claims);
             } else if let Err(e) = decode_result {
                 error!("Unable to parse or validate JWT: {:?}", e);
             }
@@ -349,17 +487,45 @@ async fn oidc_response(
         error!("Unable to find id_token on OIDC response: {:?}", response_values.as_str());
     }
 
-    // Putting this at the end so we don't have to duplicate the return statement
-    Err(Status::InternalServerError)
+    None
+}
+
+/// The user agent and client version the request was sent with, if any, cut
+/// down to [`MAX_CLIENT_INFO_LENGTH`]. These are saved with the session when
+/// the user signs in.
+fn client_info(request: &Request<'_>) -> (Option<String>, Option<String>) {
+    let header = |name: &str| {
+        let value = request.headers().get_one(name)?.trim();
+        (!value.is_empty()).then(|| value.chars().take(MAX_CLIENT_INFO_LENGTH).collect())
+    };
+
+    (header("User-Agent"), header(CLIENT_VERSION_HEADER))
+}
+
+/// Saves the user's info in a cookie, so it doesn't have to be looked up on
+/// every request
+fn save_user_cookie(cookies: &CookieJar<'_>, user: &User) {
+    cookies.add_private(
+        Cookie::build((
+            COOKIE_USER,
+            serde_json::to_string(user).unwrap_or_else(|e| {
+                error!("Unable to convert User struct into JSON: {:?}", e);
+                "{}".to_owned()
+            }),
+        ))
+        .same_site(SameSite::Lax)
+        .build(),
+    );
 }
 
 /// Removes the user's current session cookies and redirects them to the OIDC
 /// provider logout page (if present) or the root page
 #[get("/oidc/logout")]
 async fn oidc_logout(config: &State<Config>, cookies: &CookieJar<'_>) -> Redirect {
-    // Remove the current JWT and USER cookies
+    // Remove the current JWT, USER, and REFRESH cookies
     cookies.remove_private(COOKIE_JWT);
     cookies.remove_private(COOKIE_USER);
+    cookies.remove_private(COOKIE_REFRESH);
 
     // TODO update session exp in db?
 
@@ -1,31 +1,90 @@
+use crate::auditapi::record_changes;
 use crate::auth::AuthenticatedUser;
-use crate::config::Config;
-use chrono::Utc;
-use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
-use pigweb_common::bulk::{BulkImport, BulkPatch, BulkQuery};
+use crate::catchers::ErrorHint;
+use crate::changes::ChangeFeed;
+use crate::clock::Clock;
+use crate::config::{BlocklistAction, Config, WebhookEvent};
+use crate::hooks::ImportHooks;
+use crate::notificationapi::send_notifications;
+use crate::quotas::{can_open_import, pigs_left};
+use crate::requestid::RequestId;
+use crate::screening::Blocklist;
+use crate::search::{max_limit, select_fields, PigCache, SearchLimiter};
+use crate::userapi::with_creators;
+use crate::webhooks::{notify_import_finished, notify_pigs};
+use chrono::{Duration, NaiveDateTime};
+use diesel::{
+    Connection, ExpressionMethods, OptionalExtension, PgArrayExpressionMethods, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl, SelectableHelper,
+};
+use pigweb_common::audit::{PigChange, PigChangeAction};
+use pigweb_common::bulk::{
+    suggest_spelling, BulkDiff, BulkDuplicate, BulkImport, BulkJobLog, BulkPatch, BulkQuery, BulkReview,
+    BulkSuggestion, PatchAction,
+};
+use pigweb_common::changes::{ChangeAction, ChangeTarget};
+use pigweb_common::errors::ErrorCode;
+use pigweb_common::notifications::{Notification, NotificationKind};
 use pigweb_common::pigs::{Pig, PigQuery};
-use pigweb_common::schema;
+use pigweb_common::scripts::skeleton;
 use pigweb_common::users::Roles;
+use pigweb_common::{parse_uuid, schema, DEFAULT_API_RESPONSE_LIMIT};
 use rocket::http::Status;
 use rocket::response::status::Created;
-use rocket::serde::json::Json;
-use rocket::{Route, State};
+use rocket::serde::json::{Json, Value};
+use rocket::{Build, Rocket, Route, State};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::DerefMut;
 use std::sync::Mutex;
 use uuid::Uuid;
 
 /// Returns a list of all bulk api routes
 pub fn get_bulk_api_routes() -> Vec<Route> {
-    routes![api_bulk_create, api_bulk_patch, api_bulk_fetch]
+    routes![
+        api_bulk_create,
+        api_bulk_patch,
+        api_bulk_reopen,
+        api_bulk_assign,
+        api_bulk_close,
+        api_bulk_flagged,
+        api_bulk_review,
+        api_bulk_split,
+        api_bulk_diff,
+        api_bulk_duplicates,
+        api_bulk_spellcheck,
+        api_bulk_fetch,
+        api_bulk_job_log
+    ]
 }
 
 /// Starts a bulk import from the JSON list of pig names given in the request
-/// body. Returns the BulkImport as JSON.
-#[post("/create", data = "<names>")]
+/// body. Returns the BulkImport as JSON. Progress is written to the import's
+/// job log, see [`api_bulk_job_log`].
+///
+/// Returns HTTP status 409 if an import with the same names was started
+/// recently, unless `force` is true.
+///
+/// Names which match the blocklist are rejected or left pending for review,
+/// depending on the config. Each one is noted in the job log.
+///
+/// Returns HTTP status 403 if the user already has as many imports in progress
+/// as [`crate::config::QuotaConfig::open_imports`] allows. Once they reach
+/// [`crate::config::QuotaConfig::pigs_per_day`], the rest of the names are left
+/// pending instead of being created.
+#[post("/create?<force>", data = "<names>")]
 async fn api_bulk_create(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
+    clock: &State<Clock>,
     db_connection: &State<Mutex<PgConnection>>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    blocklist: &State<Blocklist>,
+    hooks: &State<ImportHooks>,
+    request_id: RequestId,
+    error_hint: ErrorHint<'_>,
+    force: Option<bool>,
     names: Json<Vec<String>>,
 ) -> Result<Created<Json<BulkImport>>, Status> {
     if !auth_user.has_role(config, Roles::BulkEditor) {
@@ -35,108 +94,261 @@ async fn api_bulk_create(
     let inputs = names.into_inner();
     let mut db_connection = db_connection.lock().unwrap();
 
-    // Actual values for the BulkImport struct
-    let mut import_name = None;
-    let started = Utc::now().naive_utc();
-    let mut finished = None;
+    match can_open_import(db_connection.deref_mut(), config, &auth_user) {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("User {:?} tried to start a bulk import with too many already in progress", auth_user.user.id);
+            error_hint.set(ErrorCode::QuotaExceeded);
+            return Err(Status::Forbidden);
+        }
+        Err(err) => {
+            error!("Unable to count bulk imports in progress for user {:?}: {:?}", auth_user.user.id, err);
+            return Err(Status::InternalServerError);
+        }
+    }
+
+    // Start with initial cleanup
+    let names: Vec<String> = inputs.into_iter().map(|input| clean_name(&hooks.pre_normalize(input))).collect();
+
+    // Save the import with every name pending before processing anything, so
+    // the pigs we create can be recovered if the server dies part way through,
+    // see [`recover_interrupted_imports`]
+    let mut res = BulkImport {
+        id: config.new_id(),
+        name: names.first().cloned().unwrap_or_default(),
+        creator: auth_user.user.id,
+        assignee: None,
+        started: clock.now_naive(),
+        finished: None,
+        closed: None,
+        pending: names.iter().fold(Vec::new(), |mut pending, name| {
+            if !pending.contains(name) {
+                pending.push(name.to_owned());
+            }
+            pending
+        }),
+        accepted: Vec::new(),
+        rejected: Vec::new(),
+        revision: 0,
+        flagged: Vec::new(),
+        updated_at: clock.now_naive(),
+    };
+    hooks.post_dedup(&mut res.pending);
+
+    // People sometimes paste the same list twice, check whether that's happened before generating a pile of pending
+    // names which were already dealt with
+    let hash = hash_names(&res.pending);
+    if !force.unwrap_or(false) && config.bulk.duplicate_window > 0 {
+        let since = res.started - Duration::seconds(config.bulk.duplicate_window as i64);
+        let sql_res = schema::bulk_imports::table
+            .filter(schema::bulk_imports::hash.eq(&hash))
+            .filter(schema::bulk_imports::started.ge(since))
+            .count()
+            .get_result::<i64>(db_connection.deref_mut());
+
+        match sql_res {
+            Ok(0) => {}
+            Ok(_) => return Err(Status::Conflict),
+            Err(err) => {
+                error!("Unable to check for duplicate bulk imports: {:?}", err);
+                return Err(Status::InternalServerError);
+            }
+        }
+    }
+
+    let message = format!("User {} started processing {} names", auth_user.user.id, names.len());
+    log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "info", message);
+
+    if !hooks.names().is_empty() {
+        let message = format!("Running import hooks {:?}", hooks.names());
+        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "info", message);
+    }
+
+    let sql_res = diesel::insert_into(schema::bulk_imports::table)
+        .values((&res, schema::bulk_imports::processing.eq(true), schema::bulk_imports::hash.eq(&hash)))
+        .execute(db_connection.deref_mut());
+
+    if let Err(err) = sql_res {
+        let message = format!("Unable to save new bulk import {:?}: {:?}", res, err);
+        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "error", message);
+        return Err(Status::InternalServerError);
+    }
+
     let mut pending = Vec::new();
     let mut accepted = Vec::new();
     let mut rejected = Vec::new();
+    let mut flagged = Vec::new();
 
-    // for each input name
-    // TODO can we run this concurrently?
-    for input in inputs {
-        // Start with initial cleanup
-        let mut name = String::new();
-        input.trim().chars().for_each(|c| {
-            name.push(match c {
-                '“' | '”' => '"',
-                '‘' | '’' => '\'',
-                '‒' | '–' | '—' | '⸺' | '⸻' => '-',
-                _ => c,
-            })
-        });
+    // the ids and names of new pigs, for webhooks
+    let mut created = Vec::new();
 
-        // set the import name, if not set already
-        if import_name.is_none() {
-            import_name = Some(name.to_owned());
-        }
+    // the search can't find names which only look like an existing one, those are checked separately
+    let mut skeletons = load_skeletons(db_connection.deref_mut(), config, &res.pending).unwrap_or_else(|err| {
+        let message = format!("Unable to load pig names to check for lookalikes: {:?}", err);
+        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "warn", message);
+        BTreeMap::new()
+    });
+
+    // once the user can't create any more pigs today, everything else is left for them to accept later
+    let mut quota = pigs_left(db_connection.deref_mut(), config, &auth_user, clock.now_naive()).unwrap_or_else(|err| {
+        let message = format!("Unable to check the creator's pig quota, leaving every name pending: {:?}", err);
+        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "warn", message);
+        Some(0)
+    });
+    if quota == Some(0) {
+        let message = "The creator has reached their daily pig quota, leaving every name pending".to_owned();
+        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "info", message);
+    }
 
+    // for each name left after deduplicating
+    // TODO can we run this concurrently?
+    for name in res.pending.clone() {
         // if this name is a duplicate of an already pending pig, skip it entirely
-        if pending.contains(&name) {
+        if pending.contains(&name) || flagged.contains(&name) {
+            continue;
+        }
+
+        // Screen out inappropriate names before anything else
+        if let Some(entry) = blocklist.check(name.as_str()) {
+            let outcome = match config.blocklist.action {
+                BlocklistAction::Reject => "rejecting it",
+                BlocklistAction::Review => "flagging it for review",
+            };
+
+            let message = format!("Name {:?} matches blocklist entry {:?}, {}", name, entry, outcome);
+            log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "warn", message);
+
+            match config.blocklist.action {
+                BlocklistAction::Reject => rejected.push(name),
+                BlocklistAction::Review => flagged.push(name),
+            }
             continue;
         }
 
         // Search for duplicates
-        let query = PigQuery::default().with_name(&name).with_limit(10);
+        let mut query = PigQuery::default().with_name(&name).with_limit(10);
+        if !config.pigs.archived_duplicates {
+            query = query.with_archived(false);
+        }
         let duplicates_sql_query = query.to_db_select();
         let duplicates_sql_res = duplicates_sql_query.select(Pig::as_select()).load(db_connection.deref_mut());
 
         if let Ok(duplicates) = duplicates_sql_res {
-            // if we have duplicates and the first one is an exact duplicate, reject it
+            // if we have duplicates and any of them is an exact duplicate, reject it
             if duplicates.len() > 0 {
-                if duplicates.get(1).is_some_and(|pig| pig.name.eq_ignore_ascii_case(name.as_str())) {
+                if duplicates.iter().any(|pig| pig.name.eq_ignore_ascii_case(name.as_str())) {
                     // we have an exact duplicate, add to rejected
                     rejected.push(name);
                 } else {
                     // duplicate isn't exact, looking into it
                     pending.push(name);
                 }
+            } else if let Some((_, lookalike)) = skeletons.get(&skeleton(&name)).and_then(|pigs| pigs.first()) {
+                // e.g. a Cyrillic letter swapped in for a Latin one, someone needs to look at it
+                let message = format!("Name {:?} looks like existing pig {:?}, leaving it pending", name, lookalike);
+                log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "warn", message);
+                pending.push(name);
+            } else if quota == Some(0) {
+                pending.push(name);
             } else {
                 // we should only get to this case if we have no duplicates, in which case add the pig
-                let pig = Pig::new(name.as_str(), auth_user.user.id.as_ref());
-                let create_sql_res =
-                    diesel::insert_into(schema::pigs::table).values(&pig).execute(db_connection.deref_mut());
+                let now = clock.now_naive();
+                let pig = Pig { id: config.new_id(), ..Pig::new(name.as_str(), auth_user.user.id.as_ref(), now) };
+                let create_sql_res = diesel::insert_into(schema::pigs::table)
+                    .values((&pig, schema::pigs::name_skeleton.eq(skeleton(&pig.name))))
+                    .execute(db_connection.deref_mut());
 
-                if create_sql_res.is_ok() {
-                    // create went through successfully
-                    accepted.push(pig.id);
-                } else {
+                if let Err(err) = create_sql_res {
                     // the create request didn't go through, add to pending
+                    let message = format!("Unable to create pig {:?}, leaving it pending: {:?}", name, err);
+                    log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "warn", message);
                     pending.push(name);
+                } else {
+                    // create went through successfully
+                    hooks.post_accept(&pig);
+                    skeletons.entry(skeleton(&pig.name)).or_default().push((pig.id, pig.name.to_owned()));
+
+                    quota = quota.map(|left| left - 1);
+                    if quota == Some(0) {
+                        let message = "The creator reached their daily pig quota, leaving the rest pending".to_owned();
+                        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "info", message);
+                    }
+                    let change = PigChange::new(PigChangeAction::Created, &pig.id, &pig.name, &auth_user.user.id, now);
+                    record_changes(db_connection.deref_mut(), &vec![change]);
+                    accepted.push(pig.id);
+                    created.push((pig.id, pig.name));
                 }
             }
         } else {
+            let message = format!(
+                "Unable to search for duplicates of {:?}, leaving it pending: {:?}",
+                name,
+                duplicates_sql_res.unwrap_err()
+            );
+            log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "warn", message);
             pending.push(name);
         }
     }
 
-    // if there are no pending pigs left we're done here
-    if pending.len() == 0 {
-        finished = Some(Utc::now().naive_utc());
+    let message = format!(
+        "Finished processing with {} pending, {} accepted, {} rejected, and {} flagged",
+        pending.len(),
+        accepted.len(),
+        rejected.len(),
+        flagged.len()
+    );
+    log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "info", message);
+
+    // new pigs were added, previous search results are out of date
+    if !accepted.is_empty() {
+        pig_cache.clear();
+        changes.publish(ChangeTarget::Pig, ChangeAction::Created, &accepted);
+        notify_pigs(config, WebhookEvent::PigCreated, &created, &auth_user.user.id);
     }
 
-    // create the response struct
-    let res = BulkImport {
-        id: Uuid::new_v4(),
-        name: import_name.unwrap_or_default(),
-        creator: auth_user.user.id,
-        started,
-        finished,
-        pending,
-        accepted,
-        rejected,
-    };
+    res.pending = pending;
+    res.accepted = accepted;
+    res.rejected = rejected;
+    res.flagged = flagged;
+
+    // if there are no pending or flagged pigs left we're done here
+    if !res.has_unreviewed() {
+        res.finished = Some(clock.now_naive());
+    }
 
-    // Save it to the DB
-    let sql_res = diesel::insert_into(schema::bulk_imports::table).values(&res).execute(db_connection.deref_mut());
+    // Save the results
+    res.updated_at = clock.now_naive();
+    let sql_res = diesel::update(schema::bulk_imports::table)
+        .filter(schema::bulk_imports::id.eq(&res.id))
+        .set((&res, schema::bulk_imports::processing.eq(false)))
+        .execute(db_connection.deref_mut());
 
     if sql_res.is_ok() {
+        changes.publish(ChangeTarget::Import, ChangeAction::Created, &[res.id]);
+        send_notifications(db_connection.deref_mut(), &vec![processed_notification(&res, clock.now_naive())]);
+        if res.finished.is_some() {
+            notify_import_finished(config, &res);
+        }
+
         let params = BulkQuery::default().with_id(&res.id);
         Ok(Created::new(params.to_yuri()).body(Json(res)))
     } else {
-        error!("Unable to save new bulk import {:?}: {:?}", res, sql_res.unwrap_err());
+        let message = format!("Unable to save processed bulk import {:?}: {:?}", res, sql_res.unwrap_err());
+        log_job(db_connection.deref_mut(), clock, &res.id, &request_id, "error", message);
         Err(Status::InternalServerError)
     }
 }
 
 /// Updates a BulkImport with the actions in the request body. Returns HTTP
-/// status code 200 if changes are successful.
+/// status code 200 if changes are successful, or 409 if the patch was made
+/// against an older revision of the import or the import was closed.
 #[patch("/patch", data = "<actions>")]
 async fn api_bulk_patch(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
+    clock: &State<Clock>,
     db_connection: &State<Mutex<PgConnection>>,
+    changes: &State<ChangeFeed>,
     actions: Json<BulkPatch>,
 ) -> Status {
     if !auth_user.has_role(config, Roles::BulkEditor) {
@@ -159,24 +371,43 @@ async fn api_bulk_patch(
             return Status::InternalServerError;
         }
 
-        // Perform updates
+        // Someone else changed the import since the patch was made
         let mut import = imports.pop().unwrap();
+        if import.closed.is_some() || actions.revision != import.revision {
+            return Status::Conflict;
+        }
+
+        // Perform updates
+        let revision = import.revision;
         actions.update_import(&mut import);
 
-        // if there are no pending pigs left we're done here
-        if import.pending.len() == 0 {
-            import.finished = Some(Utc::now().naive_utc());
+        // if there are no pending or flagged pigs left we're done here
+        let finishing = import.finished.is_none() && !import.has_unreviewed();
+        if !import.has_unreviewed() {
+            import.finished = Some(clock.now_naive());
         }
 
         // Save changes
         // we need to manually filter the id out here, whereas it just works when updating the pigs table (well, it did)
         // why did they break it? no fucking clue.
+        import.updated_at = clock.now_naive();
         let sql_res = diesel::update(schema::bulk_imports::table)
             .filter(schema::bulk_imports::id.eq(&import.id))
+            .filter(schema::bulk_imports::revision.eq(revision))
             .set(&import)
             .execute(db_connection.deref_mut());
 
-        if sql_res.is_ok() {
+        if let Ok(updated) = sql_res {
+            // nothing matched the revision, it must have changed since we loaded it
+            if updated == 0 {
+                return Status::Conflict;
+            }
+
+            changes.publish(ChangeTarget::Import, ChangeAction::Updated, &[import.id]);
+            if finishing {
+                notify_import_finished(config, &import);
+            }
+
             Status::Ok
         } else {
             error!("Unable to save BulkImport patch changes! err: {:?}", sql_res.unwrap_err());
@@ -192,6 +423,669 @@ async fn api_bulk_patch(
     }
 }
 
+/// Moves the JSON list of names in the request body from the rejected list of
+/// the finished BulkImport with the given id back to pending, marking it as
+/// in-progress again. Returns the updated BulkImport as JSON, or HTTP status
+/// 409 if the import isn't finished or was changed while it was reopened.
+#[post("/reopen?<id>", data = "<names>")]
+async fn api_bulk_reopen(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    changes: &State<ChangeFeed>,
+    id: &str,
+    names: Json<Vec<String>>,
+) -> Result<Json<BulkImport>, Status> {
+    if !auth_user.has_role(config, Roles::BulkAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+    let names = names.into_inner();
+
+    // Get object from the DB
+    let mut db_connection = db_connection.lock().unwrap();
+    let query = BulkQuery::default().with_id(&uuid).with_limit(1);
+    let sql_req_res = query.to_db_select().select(BulkImport::as_select()).load(db_connection.deref_mut());
+
+    let mut import = match sql_req_res {
+        Ok(mut imports) => imports.pop().ok_or(Status::NotFound)?,
+        Err(err) => {
+            error!("Unable to load SQL result for BulkImport reopen! query: {:?}, err: {:?}", query, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    // Only finished imports can be reopened. Closed ones go through api_bulk_close instead.
+    if import.finished.is_none() {
+        return Err(Status::Conflict);
+    }
+
+    // Move each name back to pending, ignoring any which weren't rejected
+    let mut patch = BulkPatch::new(&import.id, import.revision);
+    for name in names.iter().filter(|name| import.rejected.contains(name)) {
+        patch = patch.rejected(PatchAction::REMOVE(name.to_owned())).pending(PatchAction::ADD(name.to_owned()));
+    }
+
+    // There's no point reopening the import if nothing would be pending
+    if patch.pending.is_none() {
+        return Err(Status::BadRequest);
+    }
+
+    let revision = import.revision;
+    patch.update_import(&mut import);
+    import.finished = None;
+    import.updated_at = clock.now_naive();
+
+    // Save changes
+    let sql_res = diesel::update(schema::bulk_imports::table)
+        .filter(schema::bulk_imports::id.eq(&import.id))
+        .filter(schema::bulk_imports::revision.eq(revision))
+        .set(&import)
+        .execute(db_connection.deref_mut());
+
+    match sql_res {
+        // nothing matched the revision, it must have changed since we loaded it
+        Ok(0) => Err(Status::Conflict),
+        Ok(_) => {
+            changes.publish(ChangeTarget::Import, ChangeAction::Updated, &[import.id]);
+            Ok(Json(import))
+        }
+        Err(err) => {
+            error!("Unable to save reopened BulkImport {:?}: {:?}", import.id, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Assigns the BulkImport with the given id to the user with the given id for
+/// review, or unassigns it if no user is given. Returns the updated BulkImport
+/// as JSON.
+#[patch("/assign?<id>&<assignee>")]
+async fn api_bulk_assign(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    changes: &State<ChangeFeed>,
+    id: &str,
+    assignee: Option<&str>,
+) -> Result<Json<BulkImport>, Status> {
+    if !auth_user.has_role(config, Roles::BulkAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+    let assignee = assignee.map(parse_uuid).transpose()?;
+    let mut db_connection = db_connection.lock().unwrap();
+
+    // Make sure whoever we're assigning it to actually exists
+    if let Some(assignee) = assignee.as_ref() {
+        let sql_res = schema::users::table
+            .filter(schema::users::id.eq(assignee))
+            .count()
+            .get_result::<i64>(db_connection.deref_mut());
+
+        match sql_res {
+            Ok(0) => return Err(Status::BadRequest),
+            Ok(_) => {}
+            Err(err) => {
+                error!("Unable to look up assignee {:?}: {:?}", assignee, err);
+                return Err(Status::InternalServerError);
+            }
+        }
+    }
+
+    // Tell the DB to change the assignee, returning the updated import
+    let sql_res = diesel::update(schema::bulk_imports::table)
+        .filter(schema::bulk_imports::id.eq(&uuid))
+        .set((
+            schema::bulk_imports::assignee.eq(&assignee),
+            schema::bulk_imports::revision.eq(schema::bulk_imports::revision + 1),
+            schema::bulk_imports::updated_at.eq(clock.now_naive()),
+        ))
+        .returning(BulkImport::as_returning())
+        .get_result(db_connection.deref_mut())
+        .optional();
+
+    match sql_res {
+        Ok(Some(import)) => {
+            changes.publish(ChangeTarget::Import, ChangeAction::Updated, &[import.id]);
+            Ok(Json(import))
+        }
+        Ok(None) => Err(Status::NotFound),
+        Err(err) => {
+            error!("Unable to assign BulkImport {:?} to {:?}: {:?}", uuid, assignee, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Closes the BulkImport with the given id without finishing it, leaving its
+/// pending names unprocessed. Closed imports can still be fetched, but aren't
+/// in-progress and can't be patched. If `closed` is false, the import is opened
+/// again instead. Returns the updated BulkImport as JSON.
+///
+/// Users can only close their own imports unless they're a BulkAdmin. Returns
+/// HTTP status 400 if the import is already finished, or 403 if opening it
+/// would put the user over [`crate::config::QuotaConfig::open_imports`].
+#[patch("/close?<id>&<closed>")]
+async fn api_bulk_close(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    clock: &State<Clock>,
+    db_connection: &State<Mutex<PgConnection>>,
+    changes: &State<ChangeFeed>,
+    error_hint: ErrorHint<'_>,
+    id: &str,
+    closed: Option<bool>,
+) -> Result<Json<BulkImport>, Status> {
+    let bulk_admin = auth_user.has_role(config, Roles::BulkAdmin);
+    if !(bulk_admin || auth_user.has_role(config, Roles::BulkEditor)) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+    let closed = closed.unwrap_or(true);
+
+    // Get object from the DB
+    let mut db_connection = db_connection.lock().unwrap();
+    let query = BulkQuery::default().with_id(&uuid).with_limit(1);
+    let sql_req_res = query.to_db_select().select(BulkImport::as_select()).load(db_connection.deref_mut());
+
+    let import = match sql_req_res {
+        Ok(mut imports) => imports.pop().ok_or(Status::NotFound)?,
+        Err(err) => {
+            error!("Unable to load SQL result for BulkImport close! query: {:?}, err: {:?}", query, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    if !bulk_admin && import.creator != auth_user.user.id {
+        return Err(Status::Forbidden);
+    }
+
+    // There's nothing left to give up on once every name is processed
+    if import.finished.is_some() {
+        return Err(Status::BadRequest);
+    }
+
+    // Nothing to change
+    if import.closed.is_some() == closed {
+        return Ok(Json(import));
+    }
+
+    // Opening the import again counts against the creator's quota like starting a new one
+    if !closed {
+        match can_open_import(db_connection.deref_mut(), config, &auth_user) {
+            Ok(true) => {}
+            Ok(false) => {
+                error_hint.set(ErrorCode::QuotaExceeded);
+                return Err(Status::Forbidden);
+            }
+            Err(err) => {
+                error!("Unable to count bulk imports in progress for user {:?}: {:?}", auth_user.user.id, err);
+                return Err(Status::InternalServerError);
+            }
+        }
+    }
+
+    // Tell the DB to change when it was closed, returning the updated import
+    let closed_at = closed.then(|| clock.now_naive());
+    let sql_res = diesel::update(schema::bulk_imports::table)
+        .filter(schema::bulk_imports::id.eq(&uuid))
+        .set((
+            schema::bulk_imports::closed.eq(closed_at),
+            schema::bulk_imports::revision.eq(schema::bulk_imports::revision + 1),
+            schema::bulk_imports::updated_at.eq(clock.now_naive()),
+        ))
+        .returning(BulkImport::as_returning())
+        .get_result(db_connection.deref_mut());
+
+    match sql_res {
+        Ok(import) => {
+            info!("User {:?} set BulkImport {:?} closed: {:?}", auth_user.user.id, uuid, closed);
+            changes.publish(ChangeTarget::Import, ChangeAction::Updated, &[import.id]);
+            Ok(Json(import))
+        }
+        Err(err) => {
+            error!("Unable to close BulkImport {:?}: {:?}", uuid, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Returns a JSON list of every BulkImport with names flagged for a moderator
+/// to review, oldest first.
+#[get("/flagged")]
+async fn api_bulk_flagged(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+) -> Result<Json<Vec<BulkImport>>, Status> {
+    if !auth_user.has_role(config, Roles::Moderator) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = schema::bulk_imports::table
+        .filter(schema::bulk_imports::flagged.ne(Vec::<String>::new()))
+        .order(schema::bulk_imports::started.asc())
+        .limit(DEFAULT_API_RESPONSE_LIMIT as i64)
+        .select(BulkImport::as_select())
+        .load(db_connection.deref_mut());
+
+    sql_res.map(Json).map_err(|err| {
+        error!("Unable to load flagged BulkImports: {:?}", err);
+        Status::InternalServerError
+    })
+}
+
+/// Applies a moderator's decision on a flagged name from the request body.
+/// Approved names go back to pending for anyone to process, others are
+/// rejected. Returns the updated BulkImport as JSON, or HTTP status 409 if the
+/// name isn't flagged anymore.
+#[patch("/review", data = "<review>")]
+async fn api_bulk_review(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    clock: &State<Clock>,
+    db_connection: &State<Mutex<PgConnection>>,
+    changes: &State<ChangeFeed>,
+    review: Json<BulkReview>,
+) -> Result<Json<BulkImport>, Status> {
+    if !auth_user.has_role(config, Roles::Moderator) {
+        return Err(Status::Forbidden);
+    }
+
+    let review = review.into_inner();
+    let mut db_connection = db_connection.lock().unwrap();
+
+    let query = BulkQuery::default().with_id(&review.id).with_limit(1);
+    let sql_res = query.to_db_select().select(BulkImport::as_select()).load(db_connection.deref_mut());
+
+    let mut import = match sql_res {
+        Ok(mut imports) => imports.pop().ok_or(Status::NotFound)?,
+        Err(err) => {
+            error!("Unable to load BulkImport {:?} for review: {:?}", review.id, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    // Another moderator already got to it
+    let Some(i) = import.flagged.iter().position(|name| name.eq(&review.name)) else {
+        return Err(Status::Conflict);
+    };
+
+    let name = import.flagged.remove(i);
+    if review.approved {
+        import.pending.push(name);
+    } else {
+        import.rejected.push(name);
+    }
+
+    let revision = import.revision;
+    import.revision += 1;
+    let finishing = import.finished.is_none() && !import.has_unreviewed();
+    if !import.has_unreviewed() {
+        import.finished = Some(clock.now_naive());
+    }
+    import.updated_at = clock.now_naive();
+
+    let sql_res = diesel::update(schema::bulk_imports::table)
+        .filter(schema::bulk_imports::id.eq(&import.id))
+        .filter(schema::bulk_imports::revision.eq(revision))
+        .set(&import)
+        .execute(db_connection.deref_mut());
+
+    match sql_res {
+        // nothing matched the revision, it must have changed since we loaded it
+        Ok(0) => Err(Status::Conflict),
+        Ok(_) => {
+            info!(
+                "User {:?} reviewed {:?} in BulkImport {:?}: {:?}",
+                auth_user.user.id, review.name, import.id, review.approved
+            );
+
+            changes.publish(ChangeTarget::Import, ChangeAction::Updated, &[import.id]);
+
+            if finishing {
+                notify_import_finished(config, &import);
+            }
+
+            Ok(Json(import))
+        }
+        Err(err) => {
+            error!("Unable to save review of BulkImport {:?}: {:?}", import.id, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Moves pending names out of the BulkImport with the given id into new
+/// imports so they can be reviewed by different people. If the JSON list of
+/// names in the request body isn't empty, those names are moved into a single
+/// new import. Otherwise, the pending names are split evenly into the given
+/// number of parts, with the first part staying in the original import.
+/// Returns the JSON list of new BulkImports, or HTTP status 409 if the import
+/// is closed, still processing, or was changed while it was being split.
+#[post("/split?<id>&<parts>", data = "<names>")]
+async fn api_bulk_split(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    clock: &State<Clock>,
+    db_connection: &State<Mutex<PgConnection>>,
+    changes: &State<ChangeFeed>,
+    id: &str,
+    parts: Option<usize>,
+    names: Json<Vec<String>>,
+) -> Result<Json<Vec<BulkImport>>, Status> {
+    if !auth_user.has_role(config, Roles::BulkAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+    let names = names.into_inner();
+
+    // Get object from the DB
+    let mut db_connection = db_connection.lock().unwrap();
+    let query = BulkQuery::default().with_id(&uuid).with_limit(1);
+    let sql_req_res =
+        query
+            .to_db_select()
+            .select((BulkImport::as_select(), schema::bulk_imports::processing))
+            .load::<(BulkImport, bool)>(db_connection.deref_mut());
+
+    let (mut import, processing) = match sql_req_res {
+        Ok(mut imports) => imports.pop().ok_or(Status::NotFound)?,
+        Err(err) => {
+            error!("Unable to load SQL result for BulkImport split! query: {:?}, err: {:?}", query, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    // The names still being processed would overwrite the split when it's done
+    if import.closed.is_some() || processing {
+        return Err(Status::Conflict);
+    }
+
+    // Figure out which names go into each new import
+    let groups: Vec<Vec<String>> = if !names.is_empty() {
+        let mut subset: Vec<String> = Vec::new();
+        for name in names.into_iter().filter(|name| import.pending.contains(name)) {
+            if !subset.contains(&name) {
+                subset.push(name);
+            }
+        }
+
+        vec![subset]
+    } else {
+        let parts = parts.unwrap_or(2);
+        if parts < 2 || parts > import.pending.len() {
+            return Err(Status::BadRequest);
+        }
+
+        // The first few parts take one of the leftover names each, so there's exactly as many as requested
+        let (size, extra) = (import.pending.len() / parts, import.pending.len() % parts);
+        let mut rest = import.pending.as_slice();
+        let mut groups = Vec::new();
+        for i in 0..parts {
+            let (group, tail) = rest.split_at(size + usize::from(i < extra));
+            groups.push(group.to_vec());
+            rest = tail;
+        }
+
+        groups.into_iter().skip(1).collect()
+    };
+
+    // There's no point making empty imports
+    if groups.iter().all(|group| group.is_empty()) {
+        return Err(Status::BadRequest);
+    }
+
+    let total = groups.len() + 1;
+    let new_imports: Vec<BulkImport> = groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let name = format!("{} ({}/{})", import.name, i + 2, total);
+            BulkImport {
+                id: config.new_id(),
+                ..BulkImport::new(&name, &import.creator, group, &Vec::new(), &Vec::new(), clock.now_naive())
+            }
+        })
+        .collect();
+
+    // The moved names aren't pending here anymore
+    import.pending.retain(|name| !groups.iter().any(|group| group.contains(name)));
+    let revision = import.revision;
+    import.revision += 1;
+    let finishing = import.finished.is_none() && !import.has_unreviewed();
+    if !import.has_unreviewed() {
+        import.finished = Some(clock.now_naive());
+    }
+    import.updated_at = clock.now_naive();
+
+    // Either everything gets moved or nothing does
+    let sql_res = db_connection.transaction(|db_connection| {
+        let updated = diesel::update(schema::bulk_imports::table)
+            .filter(schema::bulk_imports::id.eq(&import.id))
+            .filter(schema::bulk_imports::revision.eq(revision))
+            .filter(schema::bulk_imports::processing.eq(false))
+            .set(&import)
+            .execute(db_connection)?;
+
+        // nothing matched the revision, it must have changed since we loaded it
+        if updated == 0 {
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        diesel::insert_into(schema::bulk_imports::table).values(&new_imports).execute(db_connection)
+    });
+
+    if let Err(diesel::result::Error::RollbackTransaction) = sql_res {
+        Err(Status::Conflict)
+    } else if sql_res.is_ok() {
+        info!("User {:?} split {} new imports off of BulkImport {:?}", auth_user.user.id, new_imports.len(), import.id);
+        let new_ids: Vec<Uuid> = new_imports.iter().map(|new_import| new_import.id).collect();
+        changes.publish(ChangeTarget::Import, ChangeAction::Created, &new_ids);
+        changes.publish(ChangeTarget::Import, ChangeAction::Updated, &[import.id]);
+        if finishing {
+            notify_import_finished(config, &import);
+        }
+
+        Ok(Json(new_imports))
+    } else {
+        error!("Unable to save split BulkImport {:?}: {:?}", import.id, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Compares the JSON list of pig names in the request body to the BulkImport
+/// with the given id. Returns a [`BulkDiff`] as JSON sorting each name by
+/// whether it's new or what happened to it in that import.
+#[post("/diff?<id>", data = "<names>")]
+async fn api_bulk_diff(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    id: &str,
+    names: Json<Vec<String>>,
+) -> Result<Json<BulkDiff>, Status> {
+    let bulk_admin = auth_user.has_role(config, Roles::BulkAdmin);
+    if !(bulk_admin || auth_user.has_role(config, Roles::BulkEditor)) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+
+    // Get object from the DB
+    let mut db_connection = db_connection.lock().unwrap();
+    let query = BulkQuery::default().with_id(&uuid).with_limit(1);
+    let sql_req_res = query.to_db_select().select(BulkImport::as_select()).load(db_connection.deref_mut());
+
+    let import = match sql_req_res {
+        Ok(mut imports) => imports.pop().ok_or(Status::NotFound)?,
+        Err(err) => {
+            error!("Unable to load SQL result for BulkImport diff! query: {:?}, err: {:?}", query, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    // Only admins can see imports which aren't theirs or assigned to them
+    let user_id = auth_user.user.id;
+    if !(bulk_admin || import.creator == user_id || import.assignee.is_some_and(|assignee| assignee == user_id)) {
+        return Err(Status::Forbidden);
+    }
+
+    // The import only has ids for accepted pigs, we need their names
+    let sql_res = schema::pigs::table
+        .filter(schema::pigs::id.eq_any(&import.accepted))
+        .select(schema::pigs::name)
+        .load::<String>(db_connection.deref_mut());
+
+    let accepted = match sql_res {
+        Ok(accepted) => accepted,
+        Err(err) => {
+            error!("Unable to load accepted pigs for BulkImport diff {:?}: {:?}", import.id, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    // Sort each name by whichever list it showed up in, ignoring case
+    let contains = |list: &Vec<String>, name: &String| list.iter().any(|e| e.eq_ignore_ascii_case(name.as_str()));
+    let mut res = BulkDiff::default();
+    for name in names.iter().map(|input| clean_name(input)) {
+        if name.is_empty() || res.contains(&name) {
+            continue;
+        }
+
+        if contains(&accepted, &name) {
+            res.accepted.push(name);
+        } else if contains(&import.rejected, &name) {
+            res.rejected.push(name);
+        } else if contains(&import.pending, &name) {
+            res.pending.push(name);
+        } else {
+            res.added.push(name);
+        }
+    }
+
+    Ok(Json(res))
+}
+
+/// Returns a JSON list of [`BulkDuplicate`]s for every pig with a name similar
+/// to the given one, including who created each pig and which import it was
+/// accepted from, if any. Archived pigs are left out unless
+/// [`crate::config::PigConfig::archived_duplicates`] is set.
+#[get("/duplicates?<name>")]
+async fn api_bulk_duplicates(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    search_limiter: &State<SearchLimiter>,
+    name: &str,
+) -> Result<Json<Vec<BulkDuplicate>>, Status> {
+    if !(auth_user.has_role(config, Roles::BulkAdmin) || auth_user.has_role(config, Roles::BulkEditor)) {
+        return Err(Status::Forbidden);
+    }
+
+    let _permit = search_limiter.acquire(&auth_user.user.id)?;
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let pigs = find_duplicates(db_connection.deref_mut(), config, name, DEFAULT_API_RESPONSE_LIMIT).map_err(|err| {
+        error!("Unable to find duplicates of {:?}: {:?}", name, err);
+        Status::InternalServerError
+    })?;
+
+    let ids: Vec<Uuid> = pigs.iter().map(|pig| pig.id).collect();
+    let creators: Vec<Uuid> = pigs.iter().map(|pig| pig.creator).collect();
+
+    // Find which imports accepted these pigs
+    let imports = schema::bulk_imports::table
+        .filter(schema::bulk_imports::accepted.overlaps_with(&ids))
+        .select((schema::bulk_imports::id, schema::bulk_imports::name, schema::bulk_imports::accepted))
+        .load::<(Uuid, String, Vec<Uuid>)>(db_connection.deref_mut())
+        .map_err(|err| {
+            error!("Unable to load imports for duplicates of {:?}: {:?}", name, err);
+            Status::InternalServerError
+        })?;
+
+    // Find who created each pig
+    let usernames = schema::users::table
+        .filter(schema::users::id.eq_any(&creators))
+        .select((schema::users::id, schema::users::username))
+        .load::<(Uuid, String)>(db_connection.deref_mut())
+        .map_err(|err| {
+            error!("Unable to load creators for duplicates of {:?}: {:?}", name, err);
+            Status::InternalServerError
+        })?;
+
+    let res = pigs
+        .into_iter()
+        .map(|pig| {
+            let import = imports.iter().find(|(_, _, accepted)| accepted.contains(&pig.id));
+            let creator_name = usernames.iter().find(|(id, _)| *id == pig.creator).map(|(_, name)| name.to_owned());
+
+            BulkDuplicate {
+                creator_name,
+                import_id: import.map(|(id, _, _)| id.to_owned()),
+                import_name: import.map(|(_, name, _)| name.to_owned()),
+                pig,
+            }
+        })
+        .collect();
+
+    Ok(Json(res))
+}
+
+/// Checks the JSON list of pig names in the request body for typos before
+/// they're imported. Returns a JSON list of [`BulkSuggestion`]s for each name
+/// with a word which isn't known, but is close to one. Known words are those in
+/// the config and every word already used in a pig name.
+#[post("/spellcheck", data = "<names>")]
+async fn api_bulk_spellcheck(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    names: Json<Vec<String>>,
+) -> Result<Json<Vec<BulkSuggestion>>, Status> {
+    if !auth_user.has_role(config, Roles::BulkEditor) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = schema::pigs::table.select(schema::pigs::name).load::<String>(db_connection.deref_mut());
+
+    let pig_names = sql_res.map_err(|err| {
+        error!("Unable to load pig names for spellcheck: {:?}", err);
+        Status::InternalServerError
+    })?;
+
+    // Every word we already know, ignoring case and punctuation
+    let known: BTreeSet<String> = pig_names
+        .iter()
+        .chain(config.bulk.known_words.iter())
+        .flat_map(|name| name.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+
+    let mut res: Vec<BulkSuggestion> = Vec::new();
+    for name in names.iter().map(|input| clean_name(input)) {
+        if res.iter().any(|e| e.name == name) {
+            continue;
+        }
+
+        if let Some(suggestion) = suggest_spelling(&name, &known) {
+            res.push(BulkSuggestion { name, suggestion });
+        }
+    }
+
+    Ok(Json(res))
+}
+
 /// Returns a JSON list of BulkImports which match the given query.
 #[get("/fetch?<query..>")]
 async fn api_bulk_fetch(
@@ -199,7 +1093,7 @@ async fn api_bulk_fetch(
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
     query: BulkQuery,
-) -> Result<Json<Vec<BulkImport>>, Status> {
+) -> Result<Json<Value>, Status> {
     let mut query = query;
     let bulk_admin = auth_user.has_role(config, Roles::BulkAdmin);
 
@@ -208,20 +1102,319 @@ async fn api_bulk_fetch(
         return Err(Status::Forbidden);
     }
 
-    // If the user is not a BulkAdmin, only let them see their own
-    if !bulk_admin {
+    // If the user only wants the ones assigned to them, they can see those regardless of who made them
+    // otherwise, if the user is not a BulkAdmin or only wants their own, only let them see their own
+    if query.assigned.unwrap_or(false) {
+        query = BulkQuery { assignee: Some(vec![auth_user.user.id.to_string()]), ..query }
+    } else if !bulk_admin || query.mine.unwrap_or(false) {
         query = BulkQuery { creator: Some(vec![auth_user.user.id.to_string()]), ..query }
     }
 
+    if !query.has_valid_dates() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    let max_limit = max_limit(config, &auth_user, query.id.as_ref());
+    query = query.with_max_limit(max_limit);
+
     // Fetch from the DB
     let sql_query = query.to_db_select();
-    let mut db_connection = db_connection.lock().unwrap();
-    let sql_res = sql_query.select(BulkImport::as_select()).load(db_connection.deref_mut());
+    let sql_res = sql_query.select(BulkImport::as_select()).load(db_connection.lock().unwrap().deref_mut());
 
     if let Ok(imports) = sql_res {
-        Ok(Json(imports))
+        let res = with_creators(db_connection, imports, query.includes_creator(), |import| import.creator)?;
+        Ok(Json(select_fields(&res, query.field_list())?))
     } else {
         error!("Unable to load SQL result for query {:?}: {:?}", query, sql_res.unwrap_err());
         Err(Status::InternalServerError)
     }
 }
+
+/// Returns the JSON list of lines logged while processing the BulkImport with
+/// the given id, oldest first. This works even if the import itself was never
+/// saved.
+#[get("/job_log?<id>")]
+async fn api_bulk_job_log(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    id: &str,
+) -> Result<Json<Vec<BulkJobLog>>, Status> {
+    if !auth_user.has_role(config, Roles::BulkAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = schema::bulk_job_logs::table
+        .filter(schema::bulk_job_logs::import.eq(uuid))
+        .order(schema::bulk_job_logs::logged.asc())
+        .select(BulkJobLog::as_select())
+        .load(db_connection.deref_mut());
+
+    if let Ok(logs) = sql_res {
+        Ok(Json(logs))
+    } else {
+        error!("Unable to load job log for BulkImport {:?}: {:?}", uuid, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Finds every [`BulkImport`] which was still being processed when the server
+/// last stopped. Pigs which were already created for it are moved to the
+/// accepted list, and the import is left in-progress so its creator can finish
+/// reviewing the remaining names.
+pub async fn recover_interrupted_imports(rocket: Rocket<Build>) -> Rocket<Build> {
+    let (config, clock) = (rocket.state::<Config>(), rocket.state::<Clock>());
+    if let (Some(config), Some(clock), Some(db_connection)) = (config, clock, rocket.state::<Mutex<PgConnection>>()) {
+        let mut db_connection = db_connection.lock().unwrap();
+        let sql_res = schema::bulk_imports::table
+            .filter(schema::bulk_imports::processing.eq(true))
+            .select(BulkImport::as_select())
+            .load(db_connection.deref_mut());
+
+        match sql_res {
+            Ok(imports) => {
+                for import in imports {
+                    recover_import(config, clock, db_connection.deref_mut(), import);
+                }
+            }
+            Err(err) => error!("Unable to load interrupted bulk imports: {:?}", err),
+        }
+    }
+
+    rocket
+}
+
+/// Rolls the pigs created for an interrupted [`BulkImport`] into it and marks
+/// it as no longer processing. The import is left untouched if anything fails
+/// so recovery can be attempted again on the next startup.
+fn recover_import(config: &Config, clock: &Clock, db_connection: &mut PgConnection, mut import: BulkImport) {
+    // Log under the request which started the job so everything stays together
+    let request_id = schema::bulk_job_logs::table
+        .filter(schema::bulk_job_logs::import.eq(&import.id))
+        .order(schema::bulk_job_logs::logged.asc())
+        .select(schema::bulk_job_logs::request)
+        .first(db_connection)
+        .map(RequestId)
+        .unwrap_or_else(|_| RequestId(Uuid::new_v4()));
+
+    // Every name starts pending, so any pig the creator made from one since the import started came from it
+    let sql_res = schema::pigs::table
+        .filter(schema::pigs::creator.eq(&import.creator))
+        .filter(schema::pigs::created.ge(&import.started))
+        .filter(schema::pigs::name.eq_any(&import.pending))
+        .select(Pig::as_select())
+        .load(db_connection);
+
+    let pigs = match sql_res {
+        Ok(pigs) => pigs,
+        Err(err) => {
+            let message = format!("Unable to find pigs created before processing was interrupted: {:?}", err);
+            log_job(db_connection, clock, &import.id, &request_id, "error", message);
+            return;
+        }
+    };
+
+    for pig in pigs {
+        if let Some(i) = import.pending.iter().position(|name| name.eq(&pig.name)) {
+            import.pending.remove(i);
+            import.accepted.push(pig.id);
+        }
+    }
+
+    if !import.has_unreviewed() {
+        import.finished = Some(clock.now_naive());
+    }
+    import.revision += 1;
+    import.updated_at = clock.now_naive();
+
+    let sql_res = diesel::update(schema::bulk_imports::table)
+        .filter(schema::bulk_imports::id.eq(&import.id))
+        .set((&import, schema::bulk_imports::processing.eq(false)))
+        .execute(db_connection);
+
+    if sql_res.is_ok() {
+        send_notifications(db_connection, &vec![processed_notification(&import, clock.now_naive())]);
+        if import.finished.is_some() {
+            notify_import_finished(config, &import);
+        }
+    }
+
+    let (level, message) = match sql_res {
+        Ok(_) => (
+            "warn",
+            format!(
+                "Recovered after processing was interrupted with {} pending and {} accepted",
+                import.pending.len(),
+                import.accepted.len()
+            ),
+        ),
+        Err(err) => ("error", format!("Unable to save recovered bulk import: {:?}", err)),
+    };
+
+    log_job(db_connection, clock, &import.id, &request_id, level, message);
+}
+
+/// Tells the creator of the given import that it finished processing the
+/// names it was started with, and how they were sorted. It's sent at the given
+/// time.
+fn processed_notification(import: &BulkImport, now: NaiveDateTime) -> Notification {
+    let message = format!(
+        "Your import \"{}\" finished processing with {} accepted, {} pending, {} rejected, and {} flagged",
+        import.name,
+        import.accepted.len(),
+        import.pending.len(),
+        import.rejected.len(),
+        import.flagged.len()
+    );
+    Notification::new(&import.creator, NotificationKind::ImportProcessed, message, Some(&import.id), now)
+}
+
+/// Trims the given name and replaces fancy punctuation, such as curly quotes
+/// and em dashes, with their plain equivalents.
+pub fn clean_name(input: &str) -> String {
+    input
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '“' | '”' => '"',
+            '‘' | '’' => '\'',
+            '‒' | '–' | '—' | '⸺' | '⸻' => '-',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Groups the id and name of every pig which looks like any of the given names
+/// by the [`skeleton`] of its name, so names which only look like an existing
+/// one can be found. Archived pigs are left out unless
+/// [`crate::config::PigConfig::archived_duplicates`] is set.
+fn load_skeletons(
+    db_connection: &mut PgConnection,
+    config: &Config,
+    names: &[String],
+) -> QueryResult<BTreeMap<String, Vec<(Uuid, String)>>> {
+    let skeletons: BTreeSet<String> = names.iter().map(|name| skeleton(name)).collect();
+    let mut query = schema::pigs::table
+        .filter(schema::pigs::name_skeleton.eq_any(skeletons))
+        .select((schema::pigs::id, schema::pigs::name))
+        .into_boxed();
+    if !config.pigs.archived_duplicates {
+        query = query.filter(schema::pigs::archived.eq(false));
+    }
+
+    let mut res: BTreeMap<String, Vec<(Uuid, String)>> = BTreeMap::new();
+    for (id, name) in query.load::<(Uuid, String)>(db_connection)? {
+        res.entry(skeleton(&name)).or_default().push((id, name));
+    }
+
+    Ok(res)
+}
+
+/// Finds up to `limit` pigs the way the pig list searches for the given name,
+/// followed by any it can't find because their names only look like it, e.g.
+/// with a Cyrillic letter swapped in. Archived pigs are left out unless
+/// [`crate::config::PigConfig::archived_duplicates`] is set.
+pub fn find_duplicates(
+    db_connection: &mut PgConnection,
+    config: &Config,
+    name: &str,
+    limit: u32,
+) -> QueryResult<Vec<Pig>> {
+    let mut query = PigQuery::default().with_name(&name.to_owned()).with_limit(limit);
+    if !config.pigs.archived_duplicates {
+        query = query.with_archived(false);
+    }
+
+    let mut pigs: Vec<Pig> = query.to_db_select().select(Pig::as_select()).load(db_connection)?;
+    let lookalikes = load_lookalikes(db_connection, config, name, &pigs)?;
+    pigs.extend(lookalikes);
+    Ok(pigs)
+}
+
+/// Loads every pig whose name has the same [`skeleton`] as the given one,
+/// other than those already found. Archived pigs are left out unless
+/// [`crate::config::PigConfig::archived_duplicates`] is set.
+fn load_lookalikes(
+    db_connection: &mut PgConnection,
+    config: &Config,
+    name: &str,
+    found: &[Pig],
+) -> QueryResult<Vec<Pig>> {
+    let mut query = schema::pigs::table.filter(schema::pigs::name_skeleton.eq(skeleton(name))).into_boxed();
+    if !config.pigs.archived_duplicates {
+        query = query.filter(schema::pigs::archived.eq(false));
+    }
+
+    let mut lookalikes = query.select(Pig::as_select()).load(db_connection)?;
+    lookalikes.retain(|pig| !found.iter().any(|found| found.id == pig.id));
+    Ok(lookalikes)
+}
+
+/// Fills in the [`skeleton`] of each pig's name which hasn't been saved yet,
+/// since the database can't work it out itself when the column is added. Their
+/// `updated_at` is left alone, so clients syncing changes don't fetch every pig
+/// again. This must be attached after the database.
+pub async fn backfill_name_skeletons(rocket: Rocket<Build>) -> Rocket<Build> {
+    if let Some(db_connection) = rocket.state::<Mutex<PgConnection>>() {
+        let sql_res = db_connection.lock().unwrap().transaction(|db_connection| {
+            let pigs = schema::pigs::table
+                .filter(schema::pigs::name_skeleton.is_null())
+                .select((schema::pigs::id, schema::pigs::name))
+                .load::<(Uuid, String)>(db_connection)?;
+
+            for (id, name) in pigs.iter() {
+                diesel::update(schema::pigs::table)
+                    .filter(schema::pigs::id.eq(id))
+                    .set(schema::pigs::name_skeleton.eq(skeleton(name)))
+                    .execute(db_connection)?;
+            }
+
+            QueryResult::Ok(pigs.len())
+        });
+
+        match sql_res {
+            Ok(0) => {}
+            Ok(filled) => info!("Filled in the name skeletons of {} pigs", filled),
+            Err(err) => error!("Unable to fill in the name skeletons of pigs: {:?}", err),
+        }
+    }
+
+    rocket
+}
+
+/// Hashes the given names regardless of their order or case, so identical lists
+/// of names can be found without comparing every name.
+fn hash_names(names: &Vec<String>) -> String {
+    let mut normalized: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+    normalized.sort();
+    normalized.dedup();
+
+    format!("{:x}", Sha256::digest(normalized.join("\n")))
+}
+
+/// Saves a line to the job log of the given import and prints it to the server
+/// log with the id of the request which started the job.
+fn log_job(
+    db_connection: &mut PgConnection,
+    clock: &Clock,
+    import: &Uuid,
+    request_id: &RequestId,
+    level: &str,
+    message: String,
+) {
+    let line = BulkJobLog::new(import, &request_id.0, level, message, clock.now_naive());
+
+    match level {
+        "error" => error!("[{}] BulkImport {}: {}", line.request, line.import, line.message),
+        "warn" => warn!("[{}] BulkImport {}: {}", line.request, line.import, line.message),
+        _ => info!("[{}] BulkImport {}: {}", line.request, line.import, line.message),
+    }
+
+    let sql_res = diesel::insert_into(schema::bulk_job_logs::table).values(&line).execute(db_connection);
+    if let Err(err) = sql_res {
+        error!("Unable to save job log line {:?}: {:?}", line, err);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::database::DatabaseHealth;
+use crate::requestid::RequestId;
+use pigweb_common::errors::ErrorCode;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::serde::json::Json;
+use rocket::{Catcher, Request};
+use serde::Serialize;
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+/// Returns a list of catchers which respond to API errors with JSON
+pub fn get_api_catchers() -> Vec<Catcher> {
+    catchers![api_default_catcher]
+}
+
+/// The body of an error response. The error itself is wrapped in an "error"
+/// tag, matching what Rocket sends when JSON is requested.
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+/// The details of an error response
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    /// The HTTP status code
+    code: u16,
+
+    /// The HTTP reason phrase, e.g. "Not Found"
+    reason: &'static str,
+
+    /// A brief description of what the error is
+    description: &'static str,
+
+    /// What went wrong, so clients don't need to read the description
+    kind: ErrorCode,
+
+    /// The id of the request which failed, also sent in the `X-Request-Id`
+    /// header. Shown to users so their reports can be found in the logs.
+    request_id: Uuid,
+}
+
+/// The [`ErrorCode`] a route picked for its error, see [`ErrorHint`]
+#[derive(Default)]
+struct HintedErrorCode(OnceLock<ErrorCode>);
+
+/// A [Request Guard](FromRequest) which lets a route send a more specific
+/// [`ErrorCode`] than its status would, e.g. [`ErrorCode::QuotaExceeded`]
+/// instead of [`ErrorCode::RateLimited`] for HTTP status 429.
+pub struct ErrorHint<'r>(&'r OnceLock<ErrorCode>);
+
+impl ErrorHint<'_> {
+    /// Sends the given code with the error the route is about to respond with
+    pub fn set(&self, kind: ErrorCode) {
+        let _ = self.0.set(kind);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ErrorHint<'r> {
+    // This can't fail
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<ErrorHint<'r>, ()> {
+        Outcome::Success(ErrorHint(&request.local_cache(HintedErrorCode::default).0))
+    }
+}
+
+/// Responds to any error on an API route with JSON, so the client can always
+/// parse it, regardless of what the request said it would accept.
+#[catch(default)]
+fn api_default_catcher(status: Status, req: &Request) -> (Status, Json<ErrorResponse>) {
+    let database_down = req.rocket().state::<DatabaseHealth>().is_some_and(|health| !health.is_available());
+    let hinted = req.local_cache(HintedErrorCode::default).0.get().copied();
+    let kind = match status.code {
+        503 if database_down => ErrorCode::DatabaseUnavailable,
+        _ => hinted.unwrap_or(ErrorCode::from_status(status.code)),
+    };
+
+    (status, Json(error_response(status, kind, RequestId::of(req))))
+}
+
+/// Serializes the same error response [`api_default_catcher`] would send, for
+/// responses which need to be changed after a catcher already ran
+pub fn error_body(status: Status, kind: ErrorCode, request_id: RequestId) -> String {
+    rocket::serde::json::to_string(&error_response(status, kind, request_id)).unwrap_or_default()
+}
+
+/// Builds the error response for the given status and request
+fn error_response(status: Status, kind: ErrorCode, request_id: RequestId) -> ErrorResponse {
+    let reason = status.reason_lossy();
+    let description = match kind {
+        ErrorCode::DatabaseUnavailable => "The server is unable to reach its database, please try again later.",
+        ErrorCode::QuotaExceeded => "The user has reached their quota for this, please try again later.",
+        _ => description(status),
+    };
+
+    ErrorResponse { error: ErrorBody { code: status.code, reason, description, kind, request_id: request_id.0 } }
+}
+
+/// Describes what the given status means
+fn description(status: Status) -> &'static str {
+    match status.code {
+        400 => "The request could not be understood by the server due to malformed syntax.",
+        401 => "The request requires user authentication.",
+        403 => "The server refused to authorize the request.",
+        404 => "The requested resource could not be found.",
+        409 => "The request could not be completed because of a conflict in the request.",
+        413 => "The request body is larger than the server is configured to accept.",
+        422 => "The request was well-formed but was unable to be followed due to semantic errors.",
+        429 => "The user has sent too many requests in a given amount of time.",
+        500 => "The server encountered an internal error while processing this request.",
+        503 => "The server is temporarily unable to handle the request.",
+        _ => "The server could not handle the request.",
+    }
+}
@@ -0,0 +1,217 @@
+use crate::auth::AuthenticatedUser;
+use crate::clock::Clock;
+use crate::config::Config;
+use chrono::Utc;
+use pigweb_common::changes::{ChangeAction, ChangeBatch, ChangeEvent, ChangeTarget};
+use pigweb_common::users::Roles;
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::http::Status;
+use rocket::serde::json::{serde_json, Json};
+use rocket::tokio::select;
+use rocket::tokio::sync::Notify;
+use rocket::tokio::time::{timeout_at, Instant};
+use rocket::{Route, State};
+use rocket_ws::{Channel, Message, WebSocket};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// The most events remembered at once. Clients which fall further behind than
+/// this have to refetch everything.
+const FEED_SIZE: usize = 1000;
+
+/// How long a request for changes waits for something to happen before
+/// responding with nothing, short enough that proxies don't time it out first
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// Returns a list of all change feed api routes
+pub fn get_change_api_routes() -> Vec<Route> {
+    routes![api_changes, api_changes_ws]
+}
+
+/// Remembers the most recent changes made to pigs and imports so clients can
+/// ask for everything since the last time they checked, see [`ChangeEvent`].
+/// This is only kept in memory, so clients are told to refetch everything
+/// after the server restarts.
+pub struct ChangeFeed {
+    /// The cursor of the latest event and every event still remembered,
+    /// oldest first
+    events: Mutex<(u64, VecDeque<ChangeEvent>)>,
+
+    /// Wakes up requests waiting on new events
+    notify: Notify,
+}
+
+impl ChangeFeed {
+    /// Creates a new empty ChangeFeed. Cursors start at the current time in
+    /// milliseconds so ones from before a restart are always too old. This is
+    /// the real time rather than the [`crate::clock::Clock`], since a test
+    /// clock starts at the same time after every restart.
+    pub fn new() -> Self {
+        let start = Utc::now().timestamp_millis().max(0) as u64;
+        Self { events: Mutex::new((start, VecDeque::new())), notify: Notify::new() }
+    }
+
+    /// Adds an event for each of the given records to the feed
+    pub fn publish(&self, target: ChangeTarget, action: ChangeAction, ids: &[Uuid]) {
+        self.push(target, action, ids.iter().map(|id| Some(id.to_owned())).collect());
+    }
+
+    /// Adds an event to the feed saying too many records of the given kind
+    /// changed to list them
+    pub fn publish_many(&self, target: ChangeTarget, action: ChangeAction) {
+        self.push(target, action, vec![None]);
+    }
+
+    /// Adds an event with each given id to the feed and wakes up anyone
+    /// waiting on them
+    fn push(&self, target: ChangeTarget, action: ChangeAction, ids: Vec<Option<Uuid>>) {
+        if ids.is_empty() {
+            return;
+        }
+
+        let mut events = self.events.lock().unwrap();
+        let (latest, remembered) = &mut *events;
+        for id in ids {
+            *latest += 1;
+            remembered.push_back(ChangeEvent { cursor: *latest, target, action, id });
+        }
+
+        while remembered.len() > FEED_SIZE {
+            remembered.pop_front();
+        }
+
+        self.notify.notify_waiters();
+    }
+
+    /// Returns every event after the given cursor about one of the given
+    /// targets. If events after the cursor were already forgotten, or the
+    /// cursor is from before a restart, the batch is a reset instead.
+    pub fn since(&self, cursor: u64, targets: &[ChangeTarget]) -> ChangeBatch {
+        let events = self.events.lock().unwrap();
+        let (latest, remembered) = (events.0, &events.1);
+        let oldest = remembered.front().map_or(latest, |event| event.cursor - 1);
+
+        if cursor > latest || cursor < oldest {
+            return ChangeBatch { cursor: latest, events: Vec::new(), reset: true };
+        }
+
+        let events = remembered
+            .iter()
+            .filter(|event| event.cursor > cursor && targets.contains(&event.target))
+            .cloned()
+            .collect();
+
+        ChangeBatch { cursor: latest, events, reset: false }
+    }
+
+    /// Like [`ChangeFeed::since`], but if nothing has happened yet, waits up
+    /// to the given amount of time for something to
+    pub async fn wait(&self, cursor: u64, targets: &[ChangeTarget], timeout: Duration) -> ChangeBatch {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            // Start listening before checking so nothing published in between is missed
+            let notified = self.notify.notified();
+            let batch = self.since(cursor, targets);
+
+            if batch.reset || !batch.events.is_empty() || timeout_at(deadline, notified).await.is_err() {
+                return batch;
+            }
+        }
+    }
+
+    /// Returns an empty batch with the latest cursor, where a client should
+    /// start from
+    pub fn latest(&self) -> ChangeBatch {
+        let cursor = self.events.lock().unwrap().0;
+        ChangeBatch { cursor, events: Vec::new(), reset: false }
+    }
+}
+
+/// Responds with every change made since the given cursor as a
+/// [`ChangeBatch`], waiting until something changes if nothing has yet. Only
+/// changes the user is allowed to hear about are included, see
+/// [`change_targets`]. Without a cursor, responds right away with the latest
+/// one.
+#[get("/changes?<since>")]
+async fn api_changes(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    feed: &State<ChangeFeed>,
+    since: Option<u64>,
+) -> Result<Json<ChangeBatch>, Status> {
+    let targets = change_targets(&auth_user, config)?;
+    match since {
+        Some(cursor) => Ok(Json(feed.wait(cursor, &targets, LONG_POLL_TIMEOUT).await)),
+        None => Ok(Json(feed.latest())),
+    }
+}
+
+/// Sends each [`ChangeBatch`] over a WebSocket as soon as it happens, so
+/// clients don't need to keep polling [`api_changes`]. The first batch is
+/// sent right away, with every change since the given cursor or just the
+/// latest cursor if there isn't one. After that, an empty batch is sent every
+/// [`LONG_POLL_TIMEOUT`] if nothing changes, so proxies don't close the
+/// socket. The socket is closed once the user's session expires.
+#[get("/changes/ws?<since>")]
+async fn api_changes_ws<'r>(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    feed: &'r State<ChangeFeed>,
+    clock: &'r State<Clock>,
+    ws: WebSocket,
+    since: Option<u64>,
+) -> Result<Channel<'r>, Status> {
+    let targets = change_targets(&auth_user, config)?;
+    let session_exp = auth_user.user.session_exp;
+
+    Ok(ws.channel(move |mut stream| {
+        Box::pin(async move {
+            let mut batch = match since {
+                Some(cursor) => feed.since(cursor, &targets),
+                None => feed.latest(),
+            };
+
+            loop {
+                let cursor = batch.cursor;
+                stream.send(Message::Text(serde_json::to_string(&batch).unwrap_or_default())).await?;
+
+                if session_exp.is_some_and(|exp| exp <= clock.now_naive()) {
+                    break;
+                }
+
+                // Anything the client sends is ignored, we only need to know when it leaves
+                batch = select! {
+                    batch = feed.wait(cursor, &targets, LONG_POLL_TIMEOUT) => batch,
+                    message = stream.next() => match message {
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                        Some(Ok(_)) => feed.since(cursor, &targets),
+                    },
+                };
+            }
+
+            stream.close().await
+        })
+    }))
+}
+
+/// Which kinds of changes the user is allowed to hear about. Pig changes are
+/// only for a [`Roles::PigViewer`], and import changes for a
+/// [`Roles::BulkEditor`] or [`Roles::BulkAdmin`]. Returns HTTP status 403 if
+/// the user can't hear about either.
+fn change_targets(auth_user: &AuthenticatedUser, config: &Config) -> Result<Vec<ChangeTarget>, Status> {
+    let mut targets = Vec::new();
+    if auth_user.has_role(config, Roles::PigViewer) {
+        targets.push(ChangeTarget::Pig);
+    }
+    if auth_user.has_role(config, Roles::BulkEditor) || auth_user.has_role(config, Roles::BulkAdmin) {
+        targets.push(ChangeTarget::Import);
+    }
+
+    match targets.is_empty() {
+        true => Err(Status::Forbidden),
+        false => Ok(targets),
+    }
+}
@@ -0,0 +1,93 @@
+use crate::auth::AuthenticatedUser;
+use crate::config::Config;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use pigweb_common::users::Roles;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Build, Rocket, Route, State};
+use std::sync::{Arc, RwLock};
+
+/// Returns a list of all clock api routes
+pub fn get_clock_api_routes() -> Vec<Route> {
+    routes![api_clock_travel]
+}
+
+/// Where the server gets the current time from. Anything which depends on the
+/// time, such as sessions expiring or imports finishing, should ask this
+/// instead of calling [`Utc::now`] so it can be tested without waiting.
+/// Clones share the same time, so background tasks can keep their own.
+#[derive(Debug, Clone)]
+pub enum Clock {
+    /// The real time, used unless [`Config::test_clock`] is set
+    System,
+
+    /// A clock which only moves when it's told to, see [`api_clock_travel`]
+    Mock(Arc<RwLock<DateTime<Utc>>>),
+}
+
+impl Clock {
+    /// Gets the current time
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Self::System => Utc::now(),
+            Self::Mock(now) => *now.read().unwrap(),
+        }
+    }
+
+    /// Gets the current time without a timezone, the way it's stored in the
+    /// database
+    pub fn now_naive(&self) -> NaiveDateTime {
+        self.now().naive_utc()
+    }
+
+    /// Moves a mock clock forward by the given amount, or backward if it's
+    /// negative. Returns the new time, or [`None`] if this is the real clock.
+    pub fn travel(&self, by: Duration) -> Option<DateTime<Utc>> {
+        match self {
+            Self::System => None,
+            Self::Mock(now) => {
+                let mut now = now.write().unwrap();
+                *now += by;
+                Some(*now)
+            }
+        }
+    }
+}
+
+/// Manages the [`Clock`], which is mocked if [`Config::test_clock`] is set.
+/// This must be attached after the config.
+pub async fn load_clock(rocket: Rocket<Build>) -> Rocket<Build> {
+    let clock = match rocket.state::<Config>().and_then(|config| config.test_clock) {
+        Some(start) => {
+            warn!("Using a test clock starting at {}, this should never happen in production!", start);
+            Clock::Mock(Arc::new(RwLock::new(start.and_utc())))
+        }
+        None => Clock::System,
+    };
+
+    rocket.manage(clock)
+}
+
+/// Moves the test clock by the given number of seconds and returns the new
+/// time. Requires [`Roles::UserAdmin`] since it can expire every session.
+/// This is only mounted when [`Config::test_clock`] is set, so it returns HTTP
+/// status 404 if the server is using the real clock.
+#[post("/travel?<seconds>")]
+async fn api_clock_travel(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    clock: &State<Clock>,
+    seconds: i64,
+) -> Result<Json<NaiveDateTime>, Status> {
+    if !auth_user.has_role(config, Roles::UserAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    match clock.travel(Duration::seconds(seconds)) {
+        Some(now) => {
+            info!("User {:?} moved the test clock {} seconds to {}", auth_user.user.id, seconds, now);
+            Ok(Json(now.naive_utc()))
+        }
+        None => Err(Status::NotFound),
+    }
+}
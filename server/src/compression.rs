@@ -0,0 +1,270 @@
+use crate::config::Config;
+use crate::preload::PreloadHints;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::fs::NamedFile;
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::tokio::task::spawn_blocking;
+use rocket::{Request, Response};
+use std::io;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// How many hex digits Trunk adds to the names of the files it builds, e.g.
+/// `pigweb_client-0123456789abcdef_bg.wasm`
+const FILE_HASH_LENGTH: usize = 16;
+
+/// Files with a hash in their name never change, since a new build gets a new
+/// name. Caches can keep them for a year without asking again.
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Any other file has to be checked with the server before it's reused, so
+/// `index.html` always points to the latest build. Thanks to the [`ETag`],
+/// that's usually just a 304.
+///
+/// [`ETag`]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/ETag
+const CACHE_REVALIDATE: &str = "no-cache";
+
+/// The encodings the client accepts, read from its `Accept-Encoding` header.
+/// Encodings with a quality of 0 are left out, since the client refuses them.
+pub struct AcceptEncoding(Vec<String>);
+
+impl AcceptEncoding {
+    /// Reads the encodings the given request accepts
+    pub fn of(request: &Request<'_>) -> Self {
+        let encodings = request
+            .headers()
+            .get("Accept-Encoding")
+            .flat_map(|header| header.split(','))
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let name = parts.next()?.trim().to_ascii_lowercase();
+                let refused = parts.any(|param| param.trim().strip_prefix("q=").is_some_and(|q| q.parse() == Ok(0.0)));
+
+                (!name.is_empty() && !refused).then_some(name)
+            })
+            .collect();
+
+        Self(encodings)
+    }
+
+    /// Whether the client accepts responses in the given encoding
+    pub fn accepts(&self, encoding: &str) -> bool {
+        self.0.iter().any(|accepted| accepted == encoding || accepted == "*")
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptEncoding {
+    // This can't fail
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<AcceptEncoding, ()> {
+        Outcome::Success(AcceptEncoding::of(request))
+    }
+}
+
+/// A file from the compiled client, which may have been compressed ahead of
+/// time. The content type is still that of the original file.
+pub struct ClientFile {
+    /// The file being sent
+    file: NamedFile,
+
+    /// The content type of the original file, if it's known
+    content_type: Option<ContentType>,
+
+    /// How the file was compressed, if it was
+    encoding: Option<&'static str>,
+
+    /// How long the file can be cached for, see [`is_hashed`]
+    cache_control: &'static str,
+
+    /// Identifies this version of the file, if its metadata could be read
+    etag: Option<String>,
+}
+
+impl ClientFile {
+    /// Opens the file at the path. If the client accepts them, a copy
+    /// compressed with brotli or gzip next to the file is sent instead, e.g.
+    /// `app.wasm.br` for `app.wasm`.
+    pub async fn open(path: &Path, accept: &AcceptEncoding) -> io::Result<Self> {
+        let content_type = path.extension().and_then(|ext| ContentType::from_extension(&ext.to_string_lossy()));
+        let cache_control = match is_hashed(path) {
+            true => CACHE_IMMUTABLE,
+            false => CACHE_REVALIDATE,
+        };
+
+        let mut opened = None;
+        for (encoding, ext) in [("br", "br"), ("gzip", "gz")] {
+            if !accept.accepts(encoding) {
+                continue;
+            }
+
+            let mut compressed = path.as_os_str().to_owned();
+            compressed.push(".");
+            compressed.push(ext);
+
+            if let Ok(file) = NamedFile::open(PathBuf::from(compressed)).await {
+                opened = Some((file, Some(encoding)));
+                break;
+            }
+        }
+
+        let (file, encoding) = match opened {
+            Some(opened) => opened,
+            None => (NamedFile::open(path).await?, None),
+        };
+
+        let etag = etag(&file).await;
+        Ok(Self { file, content_type, encoding, cache_control, etag })
+    }
+
+    /// Whether the client already has this version of the file, according to
+    /// its `If-None-Match` header
+    fn is_cached_by(&self, request: &Request<'_>) -> bool {
+        let Some(etag) = self.etag.as_ref() else {
+            return false;
+        };
+
+        // If-None-Match always uses weak comparison
+        request
+            .headers()
+            .get("If-None-Match")
+            .flat_map(|header| header.split(','))
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag.trim_start_matches("W/"))
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ClientFile {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        // only index.html is HTML, so the preload hints aren't sent with anything else
+        let is_html = self.content_type == Some(ContentType::HTML);
+        let preload = request.rocket().state::<PreloadHints>().filter(|_| is_html);
+
+        // The client can reuse its copy, so only the headers are needed
+        let mut res = match self.is_cached_by(request) {
+            true => Response::build().status(Status::NotModified).finalize(),
+            false => {
+                let mut res = self.file.respond_to(request)?;
+
+                if let Some(content_type) = self.content_type {
+                    res.set_header(content_type);
+                }
+
+                if let Some(encoding) = self.encoding {
+                    res.set_raw_header("Content-Encoding", encoding);
+                }
+
+                if let Some(hints) = preload {
+                    res.set_raw_header("Link", hints.link().to_owned());
+                }
+
+                res
+            }
+        };
+
+        if let Some(etag) = self.etag {
+            res.set_raw_header("ETag", etag);
+        }
+
+        res.set_raw_header("Cache-Control", self.cache_control);
+        res.set_raw_header("Vary", "Accept-Encoding");
+        Ok(res)
+    }
+}
+
+/// Whether the file's name has a hash of its contents in it, the way Trunk
+/// names the files it builds
+fn is_hashed(path: &Path) -> bool {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+        return false;
+    };
+
+    // The hash comes after a dash and before the extension or a suffix like `_bg`
+    name.split('-').skip(1).any(|part| {
+        let hash = part.split(['.', '_']).next().unwrap_or_default();
+        hash.len() == FILE_HASH_LENGTH && hash.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// Generates a weak [`ETag`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/ETag)
+/// for the file from its size and when it was last modified, so the file
+/// doesn't have to be read to tell whether it changed. Returns [`None`] if
+/// that can't be read.
+async fn etag(file: &NamedFile) -> Option<String> {
+    let metadata = file.file().metadata().await.ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!("W/\"{:x}-{:x}\"", metadata.len(), modified.as_nanos()))
+}
+
+/// Compresses JSON responses with gzip if the client accepts it. Small
+/// responses are left alone, as are any which don't get smaller.
+pub struct ResponseCompression;
+
+#[rocket::async_trait]
+impl Fairing for ResponseCompression {
+    fn info(&self) -> Info {
+        Info { name: "Response Compression", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(config) = request.rocket().state::<Config>().map(|config| &config.compression) else {
+            return;
+        };
+
+        if !config.enabled
+            || response.content_type() != Some(ContentType::JSON)
+            || response.headers().contains("Content-Encoding")
+            || !AcceptEncoding::of(request).accepts("gzip")
+        {
+            return;
+        }
+
+        // Only bodies whose size is known up front, so streams aren't held back
+        if response.body_mut().size().await.is_none_or(|size| size < config.min_size) {
+            return;
+        }
+
+        let body = match response.body_mut().to_bytes().await {
+            Ok(body) => body,
+            Err(e) => {
+                error!("Unable to read response body to compress it: {:?}", e);
+                return;
+            }
+        };
+
+        let Ok((body, compressed)) = spawn_blocking(move || {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            let compressed = encoder.write_all(&body).and_then(|_| encoder.finish());
+            (body, compressed)
+        })
+        .await
+        else {
+            error!("Unable to compress response to {}", request.uri());
+            return;
+        };
+
+        let compressed = match compressed {
+            Ok(compressed) => compressed,
+            Err(e) => {
+                error!("Unable to compress response to {}: {:?}", request.uri(), e);
+                response.set_sized_body(body.len(), Cursor::new(body));
+                return;
+            }
+        };
+
+        if compressed.len() < body.len() {
+            response.set_raw_header("Content-Encoding", "gzip");
+            response.set_sized_body(compressed.len(), Cursor::new(compressed));
+        } else {
+            response.set_sized_body(body.len(), Cursor::new(body));
+        }
+
+        response.set_raw_header("Vary", "Accept-Encoding");
+    }
+}
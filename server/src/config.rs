@@ -1,37 +1,151 @@
-use pigweb_common::users::Roles;
+use chrono::NaiveDateTime;
+use pigweb_common::instance::{Branding, Environment};
+use pigweb_common::users::{RoleConfig, Roles};
+use rocket::data::ByteUnit;
 use rocket::figment::providers::{Env, Format, Serialized, Toml};
 use rocket::figment::Figment;
 use rocket_oauth2::{OAuthConfig, StaticProvider};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+use uuid::Uuid;
 
 /// The config for the PigWeb server.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
+    /// Names which aren't allowed for pigs
+    pub blocklist: BlocklistConfig,
+
+    /// How this instance presents itself to users
+    pub branding: Branding,
+
+    /// Options for importing pigs in bulk
+    pub bulk: BulkConfig,
+
+    /// Whether to send a Content-Security-Policy with the client which only
+    /// allows scripts matching the hashes in its index.html
+    pub client_csp: bool,
+
     /// The path to the compiled client files
     pub client_path: String,
 
+    /// Whether to send `Link` headers with the client's index.html so
+    /// browsers start downloading the wasm and its JS glue right away
+    pub client_preload: bool,
+
+    /// Which responses are compressed
+    pub compression: CompressionConfig,
+
     /// Config for connecting to the Postgres database
     pub database: DatabaseConfig,
 
+    /// The list of recent pigs other sites can embed
+    pub embed: EmbedConfig,
+
+    /// Which kind of deployment this is, shown to users unless it's production
+    pub environment: Environment,
+
+    /// The Atom feed of new pigs
+    pub feeds: FeedConfig,
+
     /// The permission groups the server should recognize.
     ///
     /// The server will read each user's groups when signing in with OIDC and
     /// grant the corresponding roles defined in each group here.
     pub groups: BTreeMap<String, BTreeSet<Roles>>,
 
+    /// The largest request bodies the server accepts. Rocket reads these from
+    /// the same key, so any of its other limits can be set here too.
+    pub limits: LimitsConfig,
+
+    /// How much traffic each route has handled, served at `/metrics`
+    pub metrics: MetricsConfig,
+
     /// Config for the OIDC SSO provider
     pub oidc: Option<OpenIDConfig>,
+
+    /// Restrictions on how pigs can be modified
+    pub pigs: PigConfig,
+
+    /// Limits on how much each user can add
+    pub quotas: QuotaConfig,
+
+    /// How each request is logged
+    pub request_log: RequestLogConfig,
+
+    /// Limits on the searches users can perform
+    pub search: SearchConfig,
+
+    /// Checks the server runs on startup
+    pub self_test: SelfTestConfig,
+
+    /// Starts the server's clock at this time, and only moves it when asked
+    /// through the API. For testing anything which depends on time passing.
+    pub test_clock: Option<NaiveDateTime>,
+
+    /// Where to send traces of requests and database queries
+    pub tracing: TracingConfig,
+
+    /// Extra roles to grant individual users by id, regardless of their groups
+    pub users: BTreeMap<Uuid, BTreeSet<Roles>>,
+
+    /// Whether new pigs and imports get time-ordered UUIDv7 ids instead of
+    /// random UUIDv4s. Existing ids are left alone either way.
+    pub uuid_v7: bool,
+
+    /// Where to send events such as new and deleted pigs as they happen
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// The most recent role config imported through the API, which takes
+    /// precedence over [`groups`] and [`users`]. This is loaded from the DB
+    /// when the server starts.
+    #[serde(skip)]
+    pub imported_roles: RwLock<Option<RoleConfig>>,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Config { client_path: "dist".to_owned(), database: Default::default(), groups: BTreeMap::new(), oidc: None }
+        Config {
+            blocklist: Default::default(),
+            branding: Default::default(),
+            bulk: Default::default(),
+            client_csp: true,
+            client_path: "dist".to_owned(),
+            client_preload: true,
+            compression: Default::default(),
+            database: Default::default(),
+            embed: Default::default(),
+            feeds: Default::default(),
+            environment: Default::default(),
+            groups: BTreeMap::new(),
+            limits: Default::default(),
+            metrics: Default::default(),
+            oidc: None,
+            pigs: Default::default(),
+            quotas: Default::default(),
+            request_log: Default::default(),
+            search: Default::default(),
+            self_test: Default::default(),
+            test_clock: None,
+            tracing: Default::default(),
+            users: BTreeMap::new(),
+            uuid_v7: false,
+            webhooks: Vec::new(),
+            imported_roles: RwLock::new(None),
+        }
     }
 }
 
 impl Config {
+    /// Generates an id for a new pig or import, see [`Self::uuid_v7`]
+    pub fn new_id(&self) -> Uuid {
+        match self.uuid_v7 {
+            true => Uuid::now_v7(),
+            false => Uuid::new_v4(),
+        }
+    }
+
     /// Loads data from [the default Figment provider](Self::load_figment).
     pub fn load() -> Config {
         Self::load_from_figment(&Self::load_figment())
@@ -45,6 +159,16 @@ impl Config {
         })
     }
 
+    /// Returns the role config currently in effect. This is the most recently
+    /// imported one if present, otherwise the one in this config.
+    pub fn role_config(&self) -> RoleConfig {
+        if let Some(imported) = self.imported_roles.read().unwrap().as_ref() {
+            return imported.to_owned();
+        }
+
+        RoleConfig { groups: self.groups.to_owned(), users: self.users.to_owned() }
+    }
+
     /// Creates a Figment provider with the default PigWeb and Rocket config
     /// values as a base. Proceeds to load values from the config file (as
     /// defined by the `PIGWEB_CONFIG` env variable, defaults to `PigWeb.toml`)
@@ -57,6 +181,85 @@ impl Config {
     }
 }
 
+/// Names which aren't allowed for pigs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlocklistConfig {
+    /// What to do with names in a bulk import which match the blocklist
+    pub action: BlocklistAction,
+
+    /// Regular expressions which names can't match anywhere, ignoring case
+    pub patterns: Vec<String>,
+
+    /// Words which names can't contain, ignoring case. Only whole words match,
+    /// so blocking "ham" still allows "Hamlet".
+    pub words: Vec<String>,
+}
+
+impl Default for BlocklistConfig {
+    fn default() -> Self {
+        BlocklistConfig { action: BlocklistAction::Reject, patterns: Vec::new(), words: Vec::new() }
+    }
+}
+
+/// What to do with names which match the blocklist
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlocklistAction {
+    /// Names in bulk imports are rejected, and creating a pig with one
+    /// responds with HTTP status 422
+    Reject,
+
+    /// Names in bulk imports are flagged for a
+    /// [`pigweb_common::users::Roles::Moderator`] to review. Pigs can still be
+    /// created with them once approved, since that's how they're accepted, or
+    /// by moderators themselves.
+    Review,
+}
+
+/// Options for importing pigs in bulk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkConfig {
+    /// How long after an import is started an identical list of names is
+    /// considered a duplicate, in seconds. Duplicates are rejected with HTTP
+    /// status 409 unless the user chooses to import them anyway. Set to 0 to
+    /// disable.
+    pub duplicate_window: u64,
+
+    /// Words which are spelled correctly, in addition to every word already
+    /// used in a pig name. Pasted names are checked against these for typos.
+    pub known_words: Vec<String>,
+
+    /// A URL to POST to as JSON whenever an import is finished, with how many
+    /// names were accepted and rejected. Nothing is sent if this isn't set.
+    /// Prefer [`Config::webhooks`], which can sign what it sends.
+    pub webhook: Option<String>,
+}
+
+impl Default for BulkConfig {
+    fn default() -> Self {
+        BulkConfig { duplicate_window: 86400, known_words: Vec::new(), webhook: None }
+    }
+}
+
+/// Which responses are compressed. Client files compressed ahead of time,
+/// e.g. `app.wasm.br` next to `app.wasm`, are always sent to clients which
+/// accept them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    /// Whether to compress JSON responses with gzip for clients which accept it
+    pub enabled: bool,
+
+    /// The smallest response to compress, in bytes. Smaller ones aren't worth
+    /// the time it takes.
+    pub min_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        CompressionConfig { enabled: true, min_size: 1024 }
+    }
+}
+
 /// Params for connecting to the Postgres database
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DatabaseConfig {
@@ -132,6 +335,260 @@ impl DatabaseConfig {
     }
 }
 
+/// The list of recent pigs other sites can embed, served at `/embed/pigs`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbedConfig {
+    /// Whether the list is served at all
+    pub enabled: bool,
+
+    /// Whether people who aren't signed in can see the list. Otherwise, it
+    /// needs [`Roles::PigViewer`].
+    pub anonymous: bool,
+
+    /// How many of the most recently created pigs to list
+    pub limit: u32,
+
+    /// How many seconds browsers and proxies can cache the list for
+    pub max_age: u32,
+
+    /// The origins allowed to show the list in an iframe, e.g.
+    /// `https://community.example.com`. Any site can if this is empty.
+    pub frame_ancestors: Vec<String>,
+}
+
+impl Default for EmbedConfig {
+    fn default() -> Self {
+        EmbedConfig { enabled: false, anonymous: true, limit: 10, max_age: 300, frame_ancestors: Vec::new() }
+    }
+}
+
+/// The Atom feed of new pigs, served at `/api/feeds/pigs.atom`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// The token which must be given to read the feed. The feed is disabled if
+    /// this isn't set.
+    pub token: Option<String>,
+
+    /// How many entries to list
+    pub limit: u32,
+
+    /// Whether to list finished bulk imports along with new pigs
+    pub imports: bool,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        FeedConfig { token: None, limit: 50, imports: false }
+    }
+}
+
+/// The largest request bodies the server accepts, either in bytes or with a
+/// unit such as `"8 MiB"`. Larger requests are rejected with HTTP status 413.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// JSON bodies, such as the names in a bulk import
+    pub json: ByteUnit,
+
+    /// URL-encoded forms
+    pub form: ByteUnit,
+
+    /// Uploaded files
+    pub file: ByteUnit,
+
+    /// Plain text bodies, such as role imports and CSVs of users
+    pub string: ByteUnit,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        LimitsConfig {
+            json: ByteUnit::Mebibyte(8),
+            form: ByteUnit::Kibibyte(64),
+            file: ByteUnit::Mebibyte(8),
+            string: ByteUnit::Mebibyte(1),
+        }
+    }
+}
+
+/// How much traffic each route has handled, served at `/metrics` for
+/// Prometheus to scrape
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// The token which must be given to read the metrics. They aren't served
+    /// if this isn't set.
+    pub token: Option<String>,
+}
+
+/// Restrictions on how pigs can be modified
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PigConfig {
+    /// Whether archived pigs count as duplicates when checking new names, such
+    /// as during a bulk import. Otherwise, a retired name can be used again.
+    pub archived_duplicates: bool,
+
+    /// Whether users with [`pigweb_common::users::Roles::PigEditor`] can delete
+    /// pigs they created themselves, such as to undo a mistaken bulk import.
+    /// Otherwise, only [`pigweb_common::users::Roles::PigAdmin`]s can delete
+    /// pigs. Still read from `creator_only_delete`, which allowed the same.
+    #[serde(alias = "creator_only_delete")]
+    pub creator_can_delete: bool,
+}
+
+/// How each request is logged
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestLogConfig {
+    /// Whether to log a line for each request once it's been responded to
+    pub enabled: bool,
+
+    /// Whether to log each request as a JSON object instead of plain text,
+    /// for log collectors which can parse it
+    pub json: bool,
+}
+
+impl Default for RequestLogConfig {
+    fn default() -> Self {
+        RequestLogConfig { enabled: true, json: false }
+    }
+}
+
+/// Limits on how much each user can add, so one user can't flood the list
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// The most pigs each user can create in 24 hours, including through bulk
+    /// imports. Users with [`Roles::PigAdmin`] aren't limited. Set to 0 to
+    /// disable.
+    pub pigs_per_day: u32,
+
+    /// The most bulk imports each user can have in progress at once. Users
+    /// with [`Roles::BulkAdmin`] aren't limited. Set to 0 to disable.
+    pub open_imports: u32,
+}
+
+/// Limits on the searches users can perform
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchConfig {
+    /// The maximum number of searches each user can have in progress at once.
+    /// Any more are rejected with HTTP status 429. Set to 0 to disable.
+    pub max_concurrent: u32,
+
+    /// The maximum number of query results to cache for each of pigs and
+    /// users, as well as the number of usernames. Set to 0 to disable.
+    pub cache_size: usize,
+
+    /// How long the results of a query are reused for identical queries, in
+    /// milliseconds. Results are also dropped as soon as the data changes.
+    /// Set to 0 to disable.
+    pub cache_ttl: u64,
+
+    /// The most pigs, imports, or users anyone can fetch in one query, unless
+    /// one of their roles allows more. Larger limits are lowered to this. Set
+    /// to 0 to disable.
+    pub max_limit: u32,
+
+    /// Higher limits for users with each role, such as a
+    /// [`Roles::BulkAdmin`] reconciling the whole list. The highest of a
+    /// user's roles applies, and 0 means there's no limit.
+    pub role_limits: BTreeMap<Roles, u32>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            max_concurrent: 4,
+            cache_size: 256,
+            cache_ttl: 60000,
+            max_limit: 250,
+            role_limits: BTreeMap::new(),
+        }
+    }
+}
+
+/// Checks the server runs on startup
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfTestConfig {
+    /// Whether to check everything the server needs on startup and log the
+    /// results
+    pub enabled: bool,
+
+    /// Whether to refuse to start if the database, migrations, or client
+    /// files fail their check
+    pub strict: bool,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        SelfTestConfig { enabled: true, strict: false }
+    }
+}
+
+/// Where to send traces of requests and database queries
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TracingConfig {
+    /// The OTLP/HTTP endpoint to export spans to, e.g.
+    /// `http://localhost:4318/v1/traces`. Tracing is disabled if this isn't
+    /// set.
+    pub endpoint: Option<String>,
+
+    /// The service name spans are reported under
+    pub service_name: String,
+
+    /// The fraction of requests to trace, from 0 to 1
+    pub sample_ratio: f64,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        TracingConfig { endpoint: None, service_name: "pigweb".to_owned(), sample_ratio: 1.0 }
+    }
+}
+
+/// An endpoint which events are sent to as they happen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// The URL to POST each event to as JSON
+    pub url: String,
+
+    /// If set, each request is signed with an HMAC-SHA256 of its body using
+    /// this as the key, so the receiver can tell it came from this server
+    pub secret: Option<String>,
+
+    /// Which events to send. Every event is sent if this is empty.
+    #[serde(default)]
+    pub events: BTreeSet<WebhookEvent>,
+}
+
+impl WebhookConfig {
+    /// Whether the given event should be sent to this endpoint
+    pub fn receives(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// The events which can be sent to a [`WebhookConfig`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// One or more pigs were created, either directly or by a bulk import
+    PigCreated,
+
+    /// One or more pigs were deleted
+    PigDeleted,
+
+    /// A bulk import was finished
+    BulkImportFinished,
+}
+
+impl WebhookEvent {
+    /// The name of the event, as it's sent to receivers
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::PigCreated => "pig_created",
+            WebhookEvent::PigDeleted => "pig_deleted",
+            WebhookEvent::BulkImportFinished => "bulk_import_finished",
+        }
+    }
+}
+
 /// Config for the OIDC SSO provider
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenIDConfig {
@@ -159,6 +616,10 @@ pub struct OpenIDConfig {
     /// The list of scopes to request from the OIDC provider. Usually just
     /// `openid` and `profile`
     pub scopes: Vec<String>,
+
+    /// The provider's JSON Web Key Set, which is checked along with the token
+    /// endpoint to make sure the provider is up
+    pub jwks_uri: Option<String>,
 }
 
 impl OpenIDConfig {
@@ -0,0 +1,89 @@
+use crate::catchers::error_body;
+use crate::config::Config;
+use crate::requestid::RequestId;
+use crate::telemetry::QueryTracing;
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use pigweb_common::errors::ErrorCode;
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Status};
+use std::io::Cursor;
+use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Tracks whether the database can be reached, so requests which failed
+/// because it went away can be told apart from other errors
+#[derive(Debug)]
+pub struct DatabaseHealth {
+    available: AtomicBool,
+}
+
+impl DatabaseHealth {
+    /// Starts off assuming the database is available, since the server can't
+    /// start without connecting to it
+    pub fn new() -> Self {
+        Self { available: AtomicBool::new(true) }
+    }
+
+    /// Whether the database could be reached the last time it was checked
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
+    }
+
+    /// Checks whether the connection still works, trying to reconnect if it
+    /// doesn't. Returns whether the database is available afterwards.
+    pub fn check(&self, db_connection: &Mutex<PgConnection>, config: &Config) -> bool {
+        // a route which panicked while holding the lock doesn't mean the connection is bad
+        let mut db_connection = db_connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut available = diesel::sql_query("SELECT 1").execute(db_connection.deref_mut()).is_ok();
+        if !available {
+            match PgConnection::establish(config.database.to_pg_connection_string().as_str()) {
+                Ok(mut new_connection) => {
+                    new_connection.set_instrumentation(QueryTracing::default());
+                    *db_connection = new_connection;
+                    available = true;
+                }
+                Err(e) => warn!("Unable to reconnect to the database: {:?}", e),
+            }
+        }
+
+        // only log when this changes so an outage doesn't flood the logs
+        let was_available = self.available.swap(available, Ordering::Relaxed);
+        if was_available && !available {
+            error!("Lost connection to the database, responding with HTTP status 503 until it's back.");
+        } else if !was_available && available {
+            info!("Reconnected to the database.");
+        }
+
+        available
+    }
+}
+
+/// Turns internal server errors into HTTP status 503 with
+/// [`ErrorCode::DatabaseUnavailable`] when they happened because the database
+/// couldn't be reached. Each of these tries to reconnect, so the server
+/// recovers on its own once the database is back.
+pub fn database_outage_fairing() -> AdHoc {
+    AdHoc::on_response("Database Outages", |req, res| {
+        Box::pin(async move {
+            if res.status() != Status::InternalServerError {
+                return;
+            }
+
+            let rocket = req.rocket();
+            let (Some(health), Some(db_connection), Some(config)) =
+                (rocket.state::<DatabaseHealth>(), rocket.state::<Mutex<PgConnection>>(), rocket.state::<Config>())
+            else {
+                return;
+            };
+
+            if !health.check(db_connection, config) {
+                let body = error_body(Status::ServiceUnavailable, ErrorCode::DatabaseUnavailable, RequestId::of(req));
+                res.set_status(Status::ServiceUnavailable);
+                res.set_header(ContentType::JSON);
+                res.set_sized_body(body.len(), Cursor::new(body));
+            }
+        })
+    })
+}
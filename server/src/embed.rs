@@ -0,0 +1,165 @@
+use crate::auth::AuthenticatedUser;
+use crate::config::Config;
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use pigweb_common::schema;
+use pigweb_common::users::Roles;
+use rocket::http::{Header, Status};
+use rocket::response::content::RawHtml;
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+use rocket::{Route, State};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Returns a list of all embed routes
+pub fn get_embed_routes() -> Vec<Route> {
+    routes![embed_pigs_html, embed_pigs_json]
+}
+
+/// The parts of a pig which are shown in the embed. Creators are left out
+/// since the list may be public.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EmbedPig {
+    /// The unique id of the pig
+    pub id: Uuid,
+
+    /// The name of the pig
+    pub name: String,
+
+    /// When the pig was created
+    pub created: NaiveDateTime,
+}
+
+/// A response with a Cache-Control header, so the embed isn't requested from
+/// the server on every page view
+#[derive(Responder)]
+pub struct Cached<R> {
+    inner: R,
+    cache_control: Header<'static>,
+}
+
+/// An HTML page other sites are allowed to show in an iframe. The
+/// Content-Security-Policy here takes precedence over the X-Frame-Options
+/// header Rocket adds to every response.
+#[derive(Responder)]
+pub struct Framable {
+    inner: RawHtml<String>,
+    content_security_policy: Header<'static>,
+}
+
+/// Returns the most recently created pigs as JSON, for requests which don't
+/// accept HTML
+#[get("/pigs", rank = 2)]
+async fn embed_pigs_json(
+    auth_user: Option<AuthenticatedUser>,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+) -> Result<Cached<Json<Vec<EmbedPig>>>, Status> {
+    let pigs = load_recent_pigs(auth_user, config, db_connection)?;
+    Ok(Cached { inner: Json(pigs), cache_control: cache_control(config) })
+}
+
+/// Returns the most recently created pigs as a standalone HTML page, so other
+/// sites can show them in an iframe without loading the client
+#[get("/pigs", format = "html", rank = 1)]
+async fn embed_pigs_html(
+    auth_user: Option<AuthenticatedUser>,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+) -> Result<Cached<Framable>, Status> {
+    let pigs = load_recent_pigs(auth_user, config, db_connection)?;
+    let items: String = pigs
+        .iter()
+        .map(|pig| {
+            format!(
+                "<li><a href=\"/pigs#{}\" target=\"_blank\" rel=\"noopener\">{}</a> <time>{}</time></li>",
+                pig.id,
+                escape_html(&pig.name),
+                pig.created.format("%Y-%m-%d")
+            )
+        })
+        .collect();
+
+    let html = format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>{0}</title>
+<style>
+body {{ font-family: sans-serif; margin: 0.5em; }}
+ul {{ list-style: none; padding: 0; margin: 0; }}
+li {{ padding: 0.25em 0; }}
+time {{ color: gray; font-size: 0.8em; }}
+</style>
+</head>
+<body>
+<h3>New on {0}</h3>
+<ul>{1}</ul>
+</body>
+</html>",
+        escape_html(&config.branding.name),
+        items
+    );
+
+    // The page doesn't need scripts or anything besides its own styles
+    let frame_ancestors = match config.embed.frame_ancestors.is_empty() {
+        true => "*".to_owned(),
+        false => config.embed.frame_ancestors.join(" "),
+    };
+    let policy = format!("default-src 'none'; style-src 'unsafe-inline'; frame-ancestors {}", frame_ancestors);
+
+    let page =
+        Framable { inner: RawHtml(html), content_security_policy: Header::new("Content-Security-Policy", policy) };
+    Ok(Cached { inner: page, cache_control: cache_control(config) })
+}
+
+/// Loads the pigs to show in the embed, newest first. Returns HTTP status 404
+/// if the embed is disabled, or 401/403 if it needs a user who can view pigs.
+fn load_recent_pigs(
+    auth_user: Option<AuthenticatedUser>,
+    config: &Config,
+    db_connection: &Mutex<PgConnection>,
+) -> Result<Vec<EmbedPig>, Status> {
+    if !config.embed.enabled {
+        return Err(Status::NotFound);
+    }
+
+    if !config.embed.anonymous {
+        match auth_user {
+            Some(auth_user) if auth_user.has_role(config, Roles::PigViewer) => {}
+            Some(_) => return Err(Status::Forbidden),
+            None => return Err(Status::Unauthorized),
+        }
+    }
+
+    let sql_res = schema::pigs::table
+        .select((schema::pigs::id, schema::pigs::name, schema::pigs::created))
+        .order(schema::pigs::created.desc())
+        .limit(config.embed.limit as i64)
+        .load::<(Uuid, String, NaiveDateTime)>(db_connection.lock().unwrap().deref_mut());
+
+    match sql_res {
+        Ok(pigs) => Ok(pigs.into_iter().map(|(id, name, created)| EmbedPig { id, name, created }).collect()),
+        Err(err) => {
+            error!("Unable to load recent pigs for the embed: {:?}", err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Lets shared caches keep the embed if anyone can see it, otherwise only the
+/// user's browser
+fn cache_control(config: &Config) -> Header<'static> {
+    let scope = if config.embed.anonymous { "public" } else { "private" };
+    Header::new("Cache-Control", format!("{}, max-age={}", scope, config.embed.max_age))
+}
+
+/// Escapes the characters which have special meaning in HTML, so pig names
+/// can't inject markup
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
@@ -0,0 +1,144 @@
+use crate::config::Config;
+use crate::embed::escape_html;
+use chrono::NaiveDateTime;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+use pigweb_common::bulk::BulkImport;
+use pigweb_common::schema;
+use rocket::http::{ContentType, Status};
+use rocket::{Route, State};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Returns a list of all feed api routes
+pub fn get_feed_api_routes() -> Vec<Route> {
+    routes![api_feed_pigs]
+}
+
+/// A single entry in an Atom feed
+struct FeedEntry {
+    /// The unique id of the pig or import, used for the entry's id
+    id: Uuid,
+
+    /// The title of the entry
+    title: String,
+
+    /// Where the entry can be seen in the client
+    link: String,
+
+    /// When the pig was created or the import finished
+    updated: NaiveDateTime,
+}
+
+/// Returns an Atom feed of the most recently created pigs, plus finished bulk
+/// imports if they're enabled. Returns HTTP status 404 if there's no feed
+/// token in the config, or 401 if the given token doesn't match it.
+#[get("/pigs.atom?<token>")]
+async fn api_feed_pigs(
+    token: Option<&str>,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+) -> Result<(ContentType, String), Status> {
+    match &config.feeds.token {
+        None => return Err(Status::NotFound),
+        Some(expected) if token != Some(expected.as_str()) => return Err(Status::Unauthorized),
+        Some(_) => {}
+    }
+
+    let limit = config.feeds.limit as i64;
+    let mut db_connection = db_connection.lock().unwrap();
+
+    let pigs_sql_res = schema::pigs::table
+        .select((schema::pigs::id, schema::pigs::name, schema::pigs::created))
+        .order(schema::pigs::created.desc())
+        .limit(limit)
+        .load::<(Uuid, String, NaiveDateTime)>(db_connection.deref_mut());
+
+    let mut entries: Vec<FeedEntry> = match pigs_sql_res {
+        Ok(pigs) => pigs
+            .into_iter()
+            .map(|(id, name, created)| FeedEntry { id, title: name, link: format!("/pigs#{}", id), updated: created })
+            .collect(),
+        Err(err) => {
+            error!("Unable to load recent pigs for the feed: {:?}", err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    if config.feeds.imports {
+        let imports_sql_res = schema::bulk_imports::table
+            .filter(schema::bulk_imports::finished.is_not_null())
+            .order(schema::bulk_imports::finished.desc())
+            .limit(limit)
+            .select(BulkImport::as_select())
+            .load(db_connection.deref_mut());
+
+        match imports_sql_res {
+            Ok(imports) => entries.extend(imports.into_iter().filter_map(|import| {
+                Some(FeedEntry {
+                    id: import.id,
+                    title: format!(
+                        "Import \"{}\" finished: {} accepted, {} rejected",
+                        import.name,
+                        import.accepted.len(),
+                        import.rejected.len()
+                    ),
+                    link: format!("/bulk#{}", import.id),
+                    updated: import.finished?,
+                })
+            })),
+            Err(err) => {
+                error!("Unable to load finished bulk imports for the feed: {:?}", err);
+                return Err(Status::InternalServerError);
+            }
+        }
+
+        // Both lists are sorted on their own, merge them and keep the newest
+        entries.sort_by(|a, b| b.updated.cmp(&a.updated));
+        entries.truncate(config.feeds.limit as usize);
+    }
+
+    Ok((ContentType::new("application", "atom+xml"), to_atom(config, &entries)))
+}
+
+/// Renders the given entries as an Atom feed. Links are relative to the feed,
+/// since the server doesn't know the public URL it's served at.
+fn to_atom(config: &Config, entries: &[FeedEntry]) -> String {
+    // The feed was last updated when its newest entry was, the Unix epoch is as good as anything if it's empty
+    let updated = entries.first().map(|entry| entry.updated).unwrap_or_default();
+
+    let items: String = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "<entry>
+<id>urn:uuid:{}</id>
+<title>{}</title>
+<link href=\"{}\"/>
+<updated>{}</updated>
+</entry>
+",
+                entry.id,
+                escape_html(&entry.title),
+                entry.link,
+                entry.updated.and_utc().to_rfc3339()
+            )
+        })
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+<id>urn:pigweb:feeds:pigs</id>
+<title>New on {}</title>
+<author><name>{}</name></author>
+<link href=\"/pigs\"/>
+<updated>{}</updated>
+{}</feed>
+",
+        escape_html(&config.branding.name),
+        escape_html(&config.branding.name),
+        updated.and_utc().to_rfc3339(),
+        items
+    )
+}
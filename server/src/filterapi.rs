@@ -0,0 +1,105 @@
+use crate::auth::AuthenticatedUser;
+use crate::config::Config;
+use diesel::{BoolExpressionMethods, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+use pigweb_common::filters::SavedFilter;
+use pigweb_common::users::Roles;
+use pigweb_common::{parse_uuid, schema, FILTER_API_ROOT};
+use rocket::http::Status;
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+
+/// Returns a list of all saved filter api routes
+pub fn get_filter_api_routes() -> Vec<Route> {
+    routes![api_filter_create, api_filter_delete, api_filter_fetch]
+}
+
+/// Saves the filter in the request body, responding with it if successful.
+/// The id and creator of the given filter are replaced by the server.
+#[post("/create", data = "<filter>")]
+async fn api_filter_create(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    filter: Json<SavedFilter>,
+) -> Result<Created<Json<SavedFilter>>, Status> {
+    if !auth_user.has_role(config, Roles::PigViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let filter = filter.into_inner();
+    let filter = SavedFilter::new(filter.name.as_str(), &auth_user.user.id, filter.shared, &filter.query);
+
+    // Save it to the DB
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::insert_into(schema::saved_filters::table).values(&filter).execute(db_connection.deref_mut());
+
+    if sql_res.is_ok() {
+        Ok(Created::new(FILTER_API_ROOT.to_owned() + "fetch").body(Json(filter)))
+    } else {
+        error!("Unable to save new filter {:?}: {:?}", filter, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Deletes the filter with the given [`uuid::Uuid`], returning HTTP status 204
+/// if successful. Users can only delete the filters they saved themselves.
+#[delete("/delete?<id>")]
+async fn api_filter_delete(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    id: &str,
+) -> Result<Status, Status> {
+    if !auth_user.has_role(config, Roles::PigViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::delete(
+        schema::saved_filters::table
+            .filter(schema::saved_filters::id.eq(uuid))
+            .filter(schema::saved_filters::creator.eq(auth_user.user.id)),
+    )
+    .execute(db_connection.deref_mut());
+
+    match sql_res {
+        Ok(0) => Err(Status::NotFound),
+        Ok(_) => Ok(Status::NoContent),
+        Err(e) => {
+            error!("Unable to delete filter {:?}: {:?}", id, e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Returns a JSON list of all filters the user saved along with every filter
+/// shared by other users.
+#[get("/fetch")]
+async fn api_filter_fetch(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+) -> Result<Json<Vec<SavedFilter>>, Status> {
+    if !auth_user.has_role(config, Roles::PigViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = schema::saved_filters::table
+        .filter(schema::saved_filters::creator.eq(auth_user.user.id).or(schema::saved_filters::shared.eq(true)))
+        .order(schema::saved_filters::name.asc())
+        .select(SavedFilter::as_select())
+        .load(db_connection.deref_mut());
+
+    if let Ok(filters) = sql_res {
+        Ok(Json(filters))
+    } else {
+        error!("Unable to load saved filters: {:?}", sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
@@ -0,0 +1,110 @@
+use pigweb_common::pigs::Pig;
+
+/// A step deployments can add to the bulk import pipeline for rules specific to
+/// their site, without forking [`crate::bulkapi`]. Every method does nothing
+/// by default, so hooks only need to implement the points they care about.
+///
+/// Hooks are compiled in with cargo features, see [`ImportHooks::new`].
+pub trait ImportHook: Send + Sync {
+    /// The name of the hook, used in the job log
+    fn name(&self) -> &str;
+
+    /// Changes a name exactly as it was submitted, before it's trimmed and its
+    /// punctuation is cleaned up
+    fn pre_normalize(&self, name: String) -> String {
+        name
+    }
+
+    /// Changes the cleaned up list of names after duplicates are removed, but
+    /// before any of them are screened or checked against existing pigs. Names
+    /// can be added, removed, or reordered.
+    fn post_dedup(&self, _names: &mut Vec<String>) {}
+
+    /// Called after a name is accepted and its pig has been saved
+    fn post_accept(&self, _pig: &Pig) {}
+}
+
+/// Every [`ImportHook`] enabled for this build, run in the order they were
+/// added
+pub struct ImportHooks {
+    hooks: Vec<Box<dyn ImportHook>>,
+}
+
+impl ImportHooks {
+    /// Loads the hooks enabled by cargo features. To add your own, put it in
+    /// its own module behind a feature and push it here.
+    pub fn new() -> Self {
+        #[allow(unused_mut)]
+        let mut hooks: Vec<Box<dyn ImportHook>> = Vec::new();
+
+        #[cfg(feature = "transliterate")]
+        hooks.push(Box::new(Transliterate));
+
+        for hook in hooks.iter() {
+            info!("Enabled bulk import hook {:?}", hook.name());
+        }
+
+        Self { hooks }
+    }
+
+    /// The names of every enabled hook
+    pub fn names(&self) -> Vec<&str> {
+        self.hooks.iter().map(|hook| hook.name()).collect()
+    }
+
+    /// Runs [`ImportHook::pre_normalize`] for every hook
+    pub fn pre_normalize(&self, name: String) -> String {
+        self.hooks.iter().fold(name, |name, hook| hook.pre_normalize(name))
+    }
+
+    /// Runs [`ImportHook::post_dedup`] for every hook
+    pub fn post_dedup(&self, names: &mut Vec<String>) {
+        self.hooks.iter().for_each(|hook| hook.post_dedup(names));
+    }
+
+    /// Runs [`ImportHook::post_accept`] for every hook
+    pub fn post_accept(&self, pig: &Pig) {
+        self.hooks.iter().for_each(|hook| hook.post_accept(pig));
+    }
+}
+
+/// Replaces accented Latin letters with their plain ASCII equivalents, e.g.
+/// "Crème Brûlée" becomes "Creme Brulee". Enabled with the `transliterate`
+/// feature.
+#[cfg(feature = "transliterate")]
+pub struct Transliterate;
+
+#[cfg(feature = "transliterate")]
+impl ImportHook for Transliterate {
+    fn name(&self) -> &str {
+        "transliterate"
+    }
+
+    fn pre_normalize(&self, name: String) -> String {
+        name.chars().fold(String::with_capacity(name.len()), |mut res, c| {
+            match c {
+                'À'..='Å' => res.push('A'),
+                'à'..='å' => res.push('a'),
+                'Æ' => res.push_str("AE"),
+                'æ' => res.push_str("ae"),
+                'Ç' => res.push('C'),
+                'ç' => res.push('c'),
+                'È'..='Ë' => res.push('E'),
+                'è'..='ë' => res.push('e'),
+                'Ì'..='Ï' => res.push('I'),
+                'ì'..='ï' => res.push('i'),
+                'Ñ' => res.push('N'),
+                'ñ' => res.push('n'),
+                'Ò'..='Ö' | 'Ø' => res.push('O'),
+                'ò'..='ö' | 'ø' => res.push('o'),
+                'Ù'..='Ü' => res.push('U'),
+                'ù'..='ü' => res.push('u'),
+                'Ý' => res.push('Y'),
+                'ý' | 'ÿ' => res.push('y'),
+                'ß' => res.push_str("ss"),
+                _ => res.push(c),
+            }
+            res
+        })
+    }
+}
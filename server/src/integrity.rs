@@ -0,0 +1,84 @@
+use crate::config::Config;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use regex::Regex;
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Header};
+use rocket::{Build, Rocket};
+use sha2::{Digest, Sha384};
+use std::fs;
+use std::path::PathBuf;
+
+/// The Content-Security-Policy sent with every page of the client. Scripts are
+/// only allowed to run if they're served from the same origin or match one of
+/// the hashes in the compiled client's index.html, so a CDN serving the client
+/// can't swap out the wasm/JS bundle.
+#[derive(Debug)]
+pub struct ContentSecurityPolicy(String);
+
+impl ContentSecurityPolicy {
+    /// Builds the policy from the Subresource Integrity hashes Trunk added to
+    /// the compiled index.html, plus a hash of each inline script in it.
+    /// Returns [`None`] if index.html can't be read.
+    pub fn from_client(client_path: &str) -> Option<Self> {
+        let path = PathBuf::from(client_path).join("index.html");
+        let html = fs::read_to_string(&path)
+            .map_err(|e| warn!("Unable to read {:?} to build the Content-Security-Policy: {:?}", path, e))
+            .ok()?;
+
+        let integrity = Regex::new(r#"integrity="([^"]+)""#).unwrap();
+        let inline = Regex::new(r#"(?s)<script[^>]*>(.*?)</script>"#).unwrap();
+
+        let mut hashes: Vec<String> =
+            integrity.captures_iter(&html).map(|captures| format!("'{}'", &captures[1])).collect();
+
+        // scripts with a src are empty, those are covered by their integrity attribute
+        hashes.extend(
+            inline
+                .captures_iter(&html)
+                .filter(|captures| !captures[1].trim().is_empty())
+                .map(|captures| format!("'sha384-{}'", BASE64_STANDARD.encode(Sha384::digest(captures[1].as_bytes())))),
+        );
+
+        if hashes.is_empty() {
+            warn!("Found no integrity hashes in {:?}, was the client built with data-integrity?", path);
+        }
+
+        // wasm needs wasm-unsafe-eval to be compiled, and egui sets inline styles on the canvas
+        Some(Self(format!(
+            "default-src 'self'; script-src 'self' 'wasm-unsafe-eval' {}; style-src 'self' 'unsafe-inline'; \
+             img-src 'self' data: https:; connect-src 'self' https:; object-src 'none'; base-uri 'self'",
+            hashes.join(" ")
+        )))
+    }
+}
+
+/// Builds the [`ContentSecurityPolicy`] from the compiled client and manages
+/// it, if [`Config::client_csp`] is enabled. This must be attached after the
+/// config.
+pub async fn load_content_security_policy(rocket: Rocket<Build>) -> Rocket<Build> {
+    let policy = rocket
+        .state::<Config>()
+        .filter(|config| config.client_csp)
+        .and_then(|config| ContentSecurityPolicy::from_client(&config.client_path));
+
+    match policy {
+        Some(policy) => rocket.manage(policy),
+        None => rocket,
+    }
+}
+
+/// Adds the [`ContentSecurityPolicy`] to every HTML response which doesn't
+/// already have its own, if there is one
+pub fn content_security_policy_fairing() -> AdHoc {
+    AdHoc::on_response("Content-Security-Policy", |req, res| {
+        Box::pin(async move {
+            if res.content_type() != Some(ContentType::HTML) || res.headers().contains("Content-Security-Policy") {
+                return;
+            }
+
+            if let Some(policy) = req.rocket().state::<ContentSecurityPolicy>() {
+                res.set_header(Header::new("Content-Security-Policy", policy.0.to_owned()));
+            }
+        })
+    })
+}
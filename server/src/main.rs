@@ -1,27 +1,84 @@
 #[macro_use]
 extern crate rocket;
 
+mod auditapi;
 mod auth;
 mod bulkapi;
+mod catchers;
+mod changes;
+mod clock;
+mod compression;
 mod config;
+mod database;
+mod embed;
+mod feeds;
+mod filterapi;
+mod hooks;
+mod integrity;
+mod metrics;
+mod notificationapi;
+mod oidchealth;
 mod pigapi;
+mod preload;
+mod quotas;
+mod requestid;
+mod requestlog;
+mod screening;
+mod search;
+mod selftest;
+mod tagapi;
+mod telemetry;
+mod tokenapi;
+mod transfer;
 mod userapi;
+mod webhooks;
 
+use crate::auditapi::get_audit_api_routes;
 use crate::auth::get_auth_api_routes;
-use crate::bulkapi::get_bulk_api_routes;
+use crate::bulkapi::{backfill_name_skeletons, get_bulk_api_routes, recover_interrupted_imports};
+use crate::catchers::get_api_catchers;
+use crate::changes::{get_change_api_routes, ChangeFeed};
+use crate::clock::{get_clock_api_routes, load_clock};
+use crate::compression::{AcceptEncoding, ClientFile, ResponseCompression};
 use crate::config::Config;
+use crate::database::{database_outage_fairing, DatabaseHealth};
+use crate::embed::get_embed_routes;
+use crate::feeds::get_feed_api_routes;
+use crate::filterapi::get_filter_api_routes;
+use crate::hooks::ImportHooks;
+use crate::integrity::{content_security_policy_fairing, load_content_security_policy};
+use crate::metrics::{get_metrics_routes, Metrics, RequestMetrics};
+use crate::notificationapi::get_notification_api_routes;
+use crate::oidchealth::{get_oidc_health_api_routes, watch_oidc_health, OidcHealth};
 use crate::pigapi::get_pig_api_routes;
-use crate::userapi::get_user_api_routes;
+use crate::preload::load_preload_hints;
+use crate::requestid::RequestIdHeader;
+use crate::requestlog::RequestLogging;
+use crate::screening::Blocklist;
+use crate::search::{PigCache, SearchLimiter, UserCache, UsernameCache};
+use crate::selftest::run_self_test;
+use crate::tagapi::get_tag_api_routes;
+use crate::telemetry::{init_tracing, traced, tracing_shutdown_fairing, QueryTracing, RequestTracing};
+use crate::tokenapi::get_token_api_routes;
+use crate::transfer::Command;
+use crate::userapi::{get_user_api_routes, load_imported_roles};
 use diesel::{Connection, PgConnection};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-use pigweb_common::{OpenIDAuth, AUTH_API_ROOT, BULK_API_ROOT, PIG_API_ROOT, USER_API_ROOT};
+use pigweb_common::instance::{Branding, Environment};
+use pigweb_common::users::{Capabilities, Roles};
+use pigweb_common::{
+    OpenIDAuth, AUDIT_API_ROOT, AUTH_API_ROOT, BULK_API_ROOT, FILTER_API_ROOT, NOTIFICATION_API_ROOT, PIG_API_ROOT,
+    TAG_API_ROOT, TOKEN_API_ROOT, USER_API_ROOT,
+};
 use rocket::fairing::AdHoc;
-use rocket::fs::NamedFile;
+use rocket::http::Status;
 use rocket::response::status::NotFound;
+use rocket::serde::json::Json;
 use rocket::State;
 use rocket_oauth2::{HyperRustlsAdapter, OAuth2, OAuthConfig, StaticProvider};
 use std::path::PathBuf;
 use std::sync::Mutex;
+use std::time::Duration;
 
 /// Embeds all migrations to set up the Postgres database in the app binary
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("data/migrations");
@@ -30,19 +87,20 @@ pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("data/migrations");
 /// redirect to index. Rank must be higher than the index route.
 /// from https://theadventuresofaliceandbob.com/posts/rust_rocket_yew_part1.md
 #[get("/<path..>", rank = 1001)]
-async fn files(config: &State<Config>, path: PathBuf) -> Result<NamedFile, NotFound<String>> {
+async fn files(config: &State<Config>, accept: AcceptEncoding, path: PathBuf) -> Result<ClientFile, NotFound<String>> {
     let path = PathBuf::from(&config.client_path).join(path);
-    match NamedFile::open(path).await {
+    match ClientFile::open(&path, &accept).await {
         Ok(f) => Ok(f),
-        Err(_) => index(config).await, // If no file is found, route to index
+        Err(_) => index(config, accept).await, // If no file is found, route to index
     }
 }
 
 /// Serve the index file
 /// from https://theadventuresofaliceandbob.com/posts/rust_rocket_yew_part1.md
 #[get("/", rank = 1000)]
-async fn index(config: &State<Config>) -> Result<NamedFile, NotFound<String>> {
-    NamedFile::open(PathBuf::from(&config.client_path).join("index.html")).await.map_err(|e| NotFound(e.to_string()))
+async fn index(config: &State<Config>, accept: AcceptEncoding) -> Result<ClientFile, NotFound<String>> {
+    let path = PathBuf::from(&config.client_path).join("index.html");
+    ClientFile::open(&path, &accept).await.map_err(|e| NotFound(e.to_string()))
 }
 
 /// /api root path just to verify the backend is online
@@ -59,24 +117,134 @@ async fn api_root() -> &'static str {
 "
 }
 
-/// Starts the web sever
+/// Describes how this instance presents itself. This doesn't require signing
+/// in so it can be shown on the login prompt.
+#[get("/branding")]
+async fn api_branding(config: &State<Config>) -> Json<Branding> {
+    Json(config.branding.clone())
+}
+
+/// Describes which kind of deployment this is, so the client can warn users
+/// when they aren't using production. This doesn't require signing in.
+#[get("/environment")]
+async fn api_environment(config: &State<Config>) -> Json<Environment> {
+    Json(config.environment.clone())
+}
+
+/// Checks whether the server is ready to handle requests, responding with HTTP
+/// status 204 if so or 503 if the database can't be reached or the OIDC
+/// provider couldn't be the last time it was checked. This doesn't require
+/// signing in so it can be used for health checks.
+#[get("/ready")]
+async fn api_ready(
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    health: &State<DatabaseHealth>,
+    oidc_health: &State<OidcHealth>,
+) -> Status {
+    if health.check(db_connection, config) && oidc_health.is_available() {
+        Status::NoContent
+    } else {
+        Status::ServiceUnavailable
+    }
+}
+
+/// Lists the roles needed to use each endpoint, so the client can disable
+/// anything it knows will be forbidden. This must be kept up to date with the
+/// role checks in each route.
+#[get("/capabilities")]
+async fn api_capabilities(config: &State<Config>) -> Json<Capabilities> {
+    // Editors can delete their own pigs if the config allows it
+    let pig_delete: &[Roles] =
+        if config.pigs.creator_can_delete { &[Roles::PigAdmin, Roles::PigEditor] } else { &[Roles::PigAdmin] };
+
+    let path = |root: &str, endpoint: &str| format!("{}{}", root, endpoint);
+    let mut res = Capabilities::default()
+        .with_endpoint(path(AUDIT_API_ROOT, "recent"), &[Roles::LogViewer])
+        .with_endpoint(path(BULK_API_ROOT, "create"), &[Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "patch"), &[Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "reopen"), &[Roles::BulkAdmin])
+        .with_endpoint(path(BULK_API_ROOT, "assign"), &[Roles::BulkAdmin])
+        .with_endpoint(path(BULK_API_ROOT, "close"), &[Roles::BulkAdmin, Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "split"), &[Roles::BulkAdmin])
+        .with_endpoint(path(BULK_API_ROOT, "flagged"), &[Roles::Moderator])
+        .with_endpoint(path(BULK_API_ROOT, "review"), &[Roles::Moderator])
+        .with_endpoint(path(BULK_API_ROOT, "diff"), &[Roles::BulkAdmin, Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "duplicates"), &[Roles::BulkAdmin, Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "spellcheck"), &[Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "fetch"), &[Roles::BulkAdmin, Roles::BulkEditor])
+        .with_endpoint(path(BULK_API_ROOT, "job_log"), &[Roles::BulkAdmin])
+        .with_endpoint(path(FILTER_API_ROOT, "create"), &[Roles::PigViewer])
+        .with_endpoint(path(FILTER_API_ROOT, "delete"), &[Roles::PigViewer])
+        .with_endpoint(path(FILTER_API_ROOT, "fetch"), &[Roles::PigViewer])
+        .with_endpoint(path(PIG_API_ROOT, "create"), &[Roles::PigEditor])
+        .with_endpoint(path(PIG_API_ROOT, "update"), &[Roles::PigEditor])
+        .with_endpoint(path(PIG_API_ROOT, "delete"), pig_delete)
+        .with_endpoint(path(PIG_API_ROOT, "batch_delete"), pig_delete)
+        .with_endpoint(path(PIG_API_ROOT, "batch_tag"), &[Roles::PigEditor])
+        .with_endpoint(path(PIG_API_ROOT, "archive"), &[Roles::PigAdmin])
+        .with_endpoint(path(PIG_API_ROOT, "archive_matching"), &[Roles::PigAdmin])
+        .with_endpoint(path(PIG_API_ROOT, "fetch"), &[Roles::PigViewer])
+        .with_endpoint(path(PIG_API_ROOT, "check"), &[Roles::PigViewer])
+        .with_endpoint(path(PIG_API_ROOT, "snapshot"), &[Roles::PigEditor])
+        .with_endpoint(path(PIG_API_ROOT, "diff"), &[Roles::PigViewer])
+        .with_endpoint(path(TAG_API_ROOT, "rename"), &[Roles::PigAdmin])
+        .with_endpoint(path(TAG_API_ROOT, "delete"), &[Roles::PigAdmin])
+        .with_endpoint(path(TAG_API_ROOT, "fetch"), &[Roles::PigViewer])
+        .with_endpoint(path(USER_API_ROOT, "roles"), &[Roles::UserViewer])
+        .with_endpoint(path(USER_API_ROOT, "roles/export"), &[Roles::UserViewer])
+        .with_endpoint(path(USER_API_ROOT, "roles/import"), &[Roles::UserAdmin])
+        .with_endpoint(path(USER_API_ROOT, "provision"), &[Roles::UserAdmin])
+        .with_endpoint(path(USER_API_ROOT, "expire"), &[Roles::UserAdmin])
+        .with_endpoint("/api/changes".to_owned(), &[Roles::PigViewer, Roles::BulkEditor, Roles::BulkAdmin])
+        .with_endpoint("/api/changes/ws".to_owned(), &[Roles::PigViewer, Roles::BulkEditor, Roles::BulkAdmin])
+        .with_endpoint("/api/oidc/health".to_owned(), &[Roles::UserAdmin])
+        .with_limits(config.search.max_limit, &config.search.role_limits);
+
+    // The clock can only be moved when it's a test clock
+    if config.test_clock.is_some() {
+        res = res.with_endpoint("/api/clock/travel".to_owned(), &[Roles::UserAdmin]);
+    }
+
+    Json(res)
+}
+
+/// Starts the web sever, or runs the admin command it was started with
 #[launch]
 async fn rocket() -> _ {
     // Load the config here for the db connection and client path
     let figment = Config::load_figment();
     let config = Config::load_from_figment(&figment);
     let oidc_config = config.oidc.as_ref();
+    let tracer_provider = init_tracing(&config.tracing);
 
     // Init DB connection
     let connection_str = config.database.to_pg_connection_string();
     let mut db_connection = PgConnection::establish(connection_str.as_str())
         .unwrap_or_else(|e| panic!("Unable to connect to PostgreSQL database {:?}: {:?}", connection_str, e));
+    db_connection.set_instrumentation(QueryTracing::default());
 
     // Run DB migrations, path relative to Cargo.toml
     if db_connection.run_pending_migrations(MIGRATIONS).is_err() {
         panic!("Unable to migrate database to the latest schema.");
     };
 
+    // Run any admin command instead of starting the server
+    match Command::from_args(std::env::args().skip(1)) {
+        Ok(Some(command)) => match command.run(&mut db_connection) {
+            Ok(()) => std::process::exit(0),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        },
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    }
+
     // warn if groups are not configured
     if config.groups.is_empty() {
         warn!("No permission groups have been configured. All users will have all permissions, I hope you know what you're doing!!!")
@@ -85,13 +253,60 @@ async fn rocket() -> _ {
     // Init Rocket
     let mut rocket = rocket::custom(figment)
         .manage(Mutex::new(db_connection))
+        .manage(DatabaseHealth::new())
+        .manage(OidcHealth::new())
+        .manage(Metrics::new())
+        .manage(SearchLimiter::new(config.search.max_concurrent))
+        .manage(Blocklist::new(&config.blocklist))
+        .manage(ImportHooks::new())
+        .manage(ChangeFeed::new())
+        .manage(PigCache::new(config.search.cache_size, Duration::from_millis(config.search.cache_ttl)))
+        .manage(UserCache::new(config.search.cache_size, Duration::from_millis(config.search.cache_ttl)))
+        .manage(UsernameCache::new(config.search.cache_size, Duration::from_millis(config.search.cache_ttl)))
         .attach(AdHoc::config::<Config>())
-        .mount("/", routes![index, files])
-        .mount("/api", routes![api_root])
-        .mount(AUTH_API_ROOT, get_auth_api_routes())
-        .mount(BULK_API_ROOT, get_bulk_api_routes())
-        .mount(PIG_API_ROOT, get_pig_api_routes())
-        .mount(USER_API_ROOT, get_user_api_routes());
+        .attach(RequestIdHeader)
+        .attach(RequestTracing)
+        .attach(RequestLogging)
+        .attach(AdHoc::on_ignite("Clock", load_clock))
+        .attach(AdHoc::try_on_ignite("Self-Test", run_self_test))
+        .attach(database_outage_fairing())
+        // Compress after any fairing which changes the body, and before metrics so they count what's sent
+        .attach(ResponseCompression)
+        .attach(RequestMetrics)
+        .attach(AdHoc::on_ignite("Content-Security-Policy", load_content_security_policy))
+        .attach(content_security_policy_fairing())
+        .attach(AdHoc::on_ignite("Preload Hints", load_preload_hints))
+        .attach(AdHoc::on_ignite("Imported Roles", load_imported_roles))
+        .attach(AdHoc::on_ignite("Name Skeletons", backfill_name_skeletons))
+        .attach(AdHoc::on_ignite("Interrupted Bulk Imports", recover_interrupted_imports))
+        .attach(AdHoc::on_liftoff("OIDC Health", |rocket| Box::pin(watch_oidc_health(rocket))))
+        .mount("/", traced(routes![index, files]))
+        .mount("/", traced(get_metrics_routes()))
+        .mount("/api", traced(routes![api_root, api_branding, api_capabilities, api_environment, api_ready]))
+        .mount("/api", traced(get_change_api_routes()))
+        .mount("/api", traced(get_oidc_health_api_routes()))
+        .mount("/embed", traced(get_embed_routes()))
+        .mount("/api/feeds", traced(get_feed_api_routes()))
+        .mount(AUDIT_API_ROOT, traced(get_audit_api_routes()))
+        .mount(AUTH_API_ROOT, traced(get_auth_api_routes()))
+        .mount(BULK_API_ROOT, traced(get_bulk_api_routes()))
+        .mount(FILTER_API_ROOT, traced(get_filter_api_routes()))
+        .mount(NOTIFICATION_API_ROOT, traced(get_notification_api_routes()))
+        .mount(PIG_API_ROOT, traced(get_pig_api_routes()))
+        .mount(TAG_API_ROOT, traced(get_tag_api_routes()))
+        .mount(TOKEN_API_ROOT, traced(get_token_api_routes()))
+        .mount(USER_API_ROOT, traced(get_user_api_routes()))
+        .register("/api", get_api_catchers())
+        .register(AUTH_API_ROOT, get_api_catchers());
+
+    // Only tests should be able to move the clock
+    if config.test_clock.is_some() {
+        rocket = rocket.mount("/api/clock", traced(get_clock_api_routes()));
+    }
+
+    if let Some(provider) = tracer_provider {
+        rocket = rocket.attach(tracing_shutdown_fairing(provider));
+    }
 
     // Make sure OAuth2 uses custom config, if defined
     if let Some(oidc_config) = oidc_config {
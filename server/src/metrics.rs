@@ -0,0 +1,138 @@
+use crate::config::Config;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::{ContentType, Status};
+use rocket::{Request, Response, Route, State};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Returns a list of all metrics routes
+pub fn get_metrics_routes() -> Vec<Route> {
+    routes![metrics]
+}
+
+/// How much traffic a single route has handled since the server started
+#[derive(Debug, Clone, Copy, Default)]
+struct RouteTraffic {
+    /// How many requests were made to the route
+    requests: u64,
+
+    /// The total size of all request bodies, going by their `Content-Length`
+    bytes_in: u64,
+
+    /// The total size of all response bodies whose size is known up front
+    bytes_out: u64,
+}
+
+/// Counts the traffic each route has handled, keyed by method and route path.
+/// Requests which didn't match any route are counted together under
+/// `unmatched` so random paths can't flood the list.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    routes: Mutex<BTreeMap<(String, String), RouteTraffic>>,
+}
+
+impl Metrics {
+    /// Creates a new Metrics which hasn't counted anything yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a request with the given body sizes to the route's totals
+    fn record(&self, method: String, route: String, bytes_in: u64, bytes_out: u64) {
+        let mut routes = self.routes.lock().unwrap();
+        let traffic = routes.entry((method, route)).or_default();
+
+        traffic.requests += 1;
+        traffic.bytes_in += bytes_in;
+        traffic.bytes_out += bytes_out;
+    }
+
+    /// Writes the totals for every route in the Prometheus text format
+    fn render(&self) -> String {
+        let routes = self.routes.lock().unwrap();
+        let mut res = String::new();
+
+        write_counter(&mut res, &routes, "pigweb_http_requests_total", "Requests handled by each route.", |t| {
+            t.requests
+        });
+        write_counter(
+            &mut res,
+            &routes,
+            "pigweb_http_request_bytes_total",
+            "Bytes received in request bodies by each route.",
+            |t| t.bytes_in,
+        );
+        write_counter(
+            &mut res,
+            &routes,
+            "pigweb_http_response_bytes_total",
+            "Bytes sent in response bodies by each route.",
+            |t| t.bytes_out,
+        );
+
+        res
+    }
+}
+
+/// Writes a single counter with one line for each route
+fn write_counter(
+    res: &mut String,
+    routes: &BTreeMap<(String, String), RouteTraffic>,
+    name: &str,
+    help: &str,
+    value: impl Fn(&RouteTraffic) -> u64,
+) {
+    let _ = writeln!(res, "# HELP {} {}", name, help);
+    let _ = writeln!(res, "# TYPE {} counter", name);
+
+    for ((method, route), traffic) in routes {
+        let _ = writeln!(res, "{}{{method=\"{}\",route=\"{}\"}} {}", name, method, escape_label(route), value(traffic));
+    }
+}
+
+/// Escapes a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Counts the size of each request and response against the route which
+/// handled it, see [`Metrics`]
+pub struct RequestMetrics;
+
+#[rocket::async_trait]
+impl Fairing for RequestMetrics {
+    fn info(&self) -> Info {
+        Info { name: "Request Metrics", kind: Kind::Response }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(metrics) = request.rocket().state::<Metrics>() else {
+            return;
+        };
+
+        let route = request.route().map_or("unmatched".to_owned(), |route| route.uri.path().to_owned());
+        let bytes_in = request.headers().get_one("Content-Length").and_then(|len| len.parse().ok()).unwrap_or(0);
+        let bytes_out = response.body_mut().size().await.unwrap_or(0) as u64;
+
+        metrics.record(request.method().as_str().to_owned(), route, bytes_in, bytes_out);
+    }
+}
+
+/// Responds with the traffic counted for each route in the Prometheus text
+/// format. Returns HTTP status 404 if there's no metrics token in the config,
+/// or 401 if the given token doesn't match it.
+#[get("/metrics?<token>")]
+async fn metrics(
+    token: Option<&str>,
+    config: &State<Config>,
+    metrics: &State<Metrics>,
+) -> Result<(ContentType, String), Status> {
+    match &config.metrics.token {
+        None => return Err(Status::NotFound),
+        Some(expected) if token != Some(expected.as_str()) => return Err(Status::Unauthorized),
+        Some(_) => {}
+    }
+
+    Ok((ContentType::Plain, metrics.render()))
+}
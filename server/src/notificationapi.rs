@@ -0,0 +1,93 @@
+use crate::auth::AuthenticatedUser;
+use crate::clock::Clock;
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
+use pigweb_common::notifications::{Notification, NOTIFICATION_LIMIT};
+use pigweb_common::{parse_uuid, schema};
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+
+/// The most notifications which can be listed at once
+const MAX_NOTIFICATIONS: u32 = 200;
+
+/// Returns a list of all notification api routes
+pub fn get_notification_api_routes() -> Vec<Route> {
+    routes![api_notification_list, api_notification_read]
+}
+
+/// Saves the given notifications for their recipients to see. Whatever they're
+/// about has already happened by the time they're sent, so failing to save
+/// them is only logged instead of failing the whole request.
+pub fn send_notifications(db_connection: &mut PgConnection, notifications: &Vec<Notification>) {
+    if notifications.is_empty() {
+        return;
+    }
+
+    let sql_res = diesel::insert_into(schema::notifications::table).values(notifications).execute(db_connection);
+    if let Err(err) = sql_res {
+        error!("Unable to send notifications {:?}: {:?}", notifications, err);
+    }
+}
+
+/// Returns a JSON list of the user's notifications, newest first. With
+/// `unread`, only those which haven't been marked as read are returned.
+#[get("/list?<unread>&<limit>")]
+async fn api_notification_list(
+    auth_user: AuthenticatedUser,
+    db_connection: &State<Mutex<PgConnection>>,
+    unread: Option<bool>,
+    limit: Option<u32>,
+) -> Result<Json<Vec<Notification>>, Status> {
+    let limit = limit.unwrap_or(NOTIFICATION_LIMIT).min(MAX_NOTIFICATIONS);
+    let mut query = schema::notifications::table
+        .filter(schema::notifications::recipient.eq(auth_user.user.id))
+        .order(schema::notifications::created.desc())
+        .limit(limit as i64)
+        .into_boxed();
+
+    if unread.unwrap_or(false) {
+        query = query.filter(schema::notifications::read.is_null());
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    match query.select(Notification::as_select()).load(db_connection.deref_mut()) {
+        Ok(notifications) => Ok(Json(notifications)),
+        Err(err) => {
+            error!("Unable to load notifications for user {:?}: {:?}", auth_user.user.id, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Marks the user's notification with the given id as read, or every unread
+/// one if no id is given, responding with how many were marked. Returns HTTP
+/// status 404 if the user has no unread notification with the id.
+#[patch("/read?<id>")]
+async fn api_notification_read(
+    auth_user: AuthenticatedUser,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    id: Option<&str>,
+) -> Result<Json<usize>, Status> {
+    let uuid = id.map(parse_uuid).transpose()?;
+
+    let mut sql_query = diesel::update(schema::notifications::table)
+        .filter(schema::notifications::recipient.eq(auth_user.user.id))
+        .filter(schema::notifications::read.is_null())
+        .into_boxed();
+    if let Some(uuid) = uuid {
+        sql_query = sql_query.filter(schema::notifications::id.eq(uuid));
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    match sql_query.set(schema::notifications::read.eq(clock.now_naive())).execute(db_connection.deref_mut()) {
+        Ok(0) if uuid.is_some() => Err(Status::NotFound),
+        Ok(marked) => Ok(Json(marked)),
+        Err(err) => {
+            error!("Unable to mark notifications for user {:?} as read: {:?}", auth_user.user.id, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
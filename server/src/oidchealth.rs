@@ -0,0 +1,141 @@
+use crate::auth::AuthenticatedUser;
+use crate::clock::Clock;
+use crate::config::Config;
+use chrono::NaiveDateTime;
+use pigweb_common::instance::ProviderHealth;
+use pigweb_common::users::Roles;
+use rocket::http::Status;
+use rocket::serde::json::{serde_json, Json};
+use rocket::tokio::task::spawn_blocking;
+use rocket::tokio::time::sleep;
+use rocket::{Orbit, Rocket, Route, State};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// How often to check whether the OIDC provider can be reached
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long to wait for the provider to respond to each request
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Returns a list of all OIDC health api routes
+pub fn get_oidc_health_api_routes() -> Vec<Route> {
+    routes![api_oidc_health]
+}
+
+/// Remembers whether the OIDC provider could be reached the last time it was
+/// checked, see [`watch_oidc_health`]. This is cloned into the task doing the
+/// checking, so every clone shares the same result.
+#[derive(Debug, Clone, Default)]
+pub struct OidcHealth {
+    /// The result of the last check, or [`None`] if there hasn't been one
+    status: Arc<RwLock<Option<ProviderHealth>>>,
+}
+
+impl OidcHealth {
+    /// Creates a new OidcHealth which hasn't checked anything yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the provider could be reached the last time it was checked.
+    /// Returns `true` if it hasn't been checked yet or OIDC isn't configured.
+    pub fn is_available(&self) -> bool {
+        self.status.read().unwrap().as_ref().is_none_or(|status| status.available)
+    }
+
+    /// The result of the last check, if there's been one
+    pub fn status(&self) -> Option<ProviderHealth> {
+        self.status.read().unwrap().clone()
+    }
+
+    /// Saves the result of a check made at the given time, logging only when
+    /// the provider goes down or comes back so an outage doesn't flood the logs
+    fn update(&self, result: Result<(), String>, checked: NaiveDateTime) {
+        let status = ProviderHealth { available: result.is_ok(), checked, error: result.err() };
+
+        let was_available = self.is_available();
+        if was_available && !status.available {
+            error!("Unable to reach the OIDC provider, users may not be able to sign in: {:?}", status.error);
+        } else if !was_available && status.available {
+            info!("The OIDC provider can be reached again.");
+        }
+
+        *self.status.write().unwrap() = Some(status);
+    }
+}
+
+/// Starts checking whether the OIDC provider's token endpoint and JWKS can be
+/// reached every [`CHECK_INTERVAL`] in the background. Nothing is checked if
+/// OIDC isn't configured. This must be attached after the config and clock.
+pub async fn watch_oidc_health(rocket: &Rocket<Orbit>) {
+    let (Some(config), Some(health), Some(clock)) =
+        (rocket.state::<Config>(), rocket.state::<OidcHealth>(), rocket.state::<Clock>())
+    else {
+        return;
+    };
+
+    let Some(oidc) = config.oidc.as_ref() else {
+        return;
+    };
+
+    let (token_uri, jwks_uri) = (oidc.token_uri.to_owned(), oidc.jwks_uri.to_owned());
+    let (health, clock) = (health.clone(), clock.clone());
+
+    rocket::tokio::spawn(async move {
+        loop {
+            let (token_uri, jwks_uri) = (token_uri.to_owned(), jwks_uri.to_owned());
+            let result = spawn_blocking(move || check_provider(&token_uri, jwks_uri.as_deref()))
+                .await
+                .unwrap_or_else(|e| Err(format!("The check didn't finish: {:?}", e)));
+
+            health.update(result, clock.now_naive());
+            sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Makes sure the provider's token endpoint responds and, if configured, its
+/// JWKS has keys in it. The token endpoint only accepts signed requests, so
+/// any response other than a server error means it's up.
+fn check_provider(token_uri: &str, jwks_uri: Option<&str>) -> Result<(), String> {
+    let agent = ureq::AgentBuilder::new().timeout(CHECK_TIMEOUT).build();
+
+    match agent.get(token_uri).call() {
+        Ok(_) => {}
+        Err(ureq::Error::Status(code, _)) if code < 500 => {}
+        Err(ureq::Error::Status(code, _)) => return Err(format!("The token endpoint responded with HTTP {}", code)),
+        Err(e) => return Err(format!("Unable to reach the token endpoint: {}", e)),
+    }
+
+    if let Some(jwks_uri) = jwks_uri {
+        let jwks: serde_json::Value = agent
+            .get(jwks_uri)
+            .call()
+            .map_err(|e| format!("Unable to fetch the JWKS: {}", e))?
+            .into_json()
+            .map_err(|e| format!("Unable to read the JWKS: {}", e))?;
+
+        if jwks.get("keys").and_then(|keys| keys.as_array()).is_none_or(|keys| keys.is_empty()) {
+            return Err("The JWKS doesn't have any keys".to_owned());
+        }
+    }
+
+    Ok(())
+}
+
+/// Responds with whether the OIDC provider could be reached the last time it
+/// was checked, or `null` if it hasn't been checked or OIDC isn't configured.
+/// Requires [`Roles::UserAdmin`].
+#[get("/oidc/health")]
+async fn api_oidc_health(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    health: &State<OidcHealth>,
+) -> Result<Json<Option<ProviderHealth>>, Status> {
+    if !auth_user.has_role(config, Roles::UserAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    Ok(Json(health.status()))
+}
@@ -1,42 +1,128 @@
+use crate::auditapi::record_changes;
 use crate::auth::AuthenticatedUser;
-use crate::config::Config;
-use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
-use pigweb_common::pigs::{Pig, PigQuery};
-use pigweb_common::users::Roles;
-use pigweb_common::{parse_uuid, schema};
+use crate::bulkapi::{clean_name, find_duplicates};
+use crate::catchers::ErrorHint;
+use crate::changes::ChangeFeed;
+use crate::clock::Clock;
+use crate::config::{BlocklistAction, Config, WebhookEvent};
+use crate::notificationapi::send_notifications;
+use crate::quotas::pigs_left;
+use crate::screening::Blocklist;
+use crate::search::{max_limit, select_fields, PigCache, SearchLimiter};
+use crate::userapi::with_creators;
+use crate::webhooks::notify_pigs;
+use chrono::NaiveDateTime;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::sql_types::{self, Array, Text, Timestamp};
+use diesel::{
+    Connection, ExpressionMethods, OptionalExtension, PgArrayExpressionMethods, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl, SelectableHelper,
+};
+use pigweb_common::audit::{PigChange, PigChangeAction};
+use pigweb_common::bulk::name_similarity;
+use pigweb_common::changes::{ChangeAction, ChangeTarget};
+use pigweb_common::errors::ErrorCode;
+use pigweb_common::notifications::{Notification, NotificationKind};
+use pigweb_common::pigs::{Pig, PigCheck, PigLink, PigMatch, PigQuery, PigVerdict};
+use pigweb_common::scripts::skeleton;
+use pigweb_common::snapshots::{PigSnapshot, SnapshotDiff};
+use pigweb_common::tags::clean_tags;
+use pigweb_common::users::{Roles, User};
+use pigweb_common::{parse_timestamp, parse_uuid, schema, PIG_API_ROOT};
 use rocket::http::Status;
 use rocket::response::status::Created;
-use rocket::serde::json::Json;
+use rocket::serde::json::{Json, Value};
 use rocket::{Route, State};
 use std::ops::DerefMut;
 use std::sync::Mutex;
+use uuid::Uuid;
 
 /// Returns a list of all pig api routes
 pub fn get_pig_api_routes() -> Vec<Route> {
-    routes![api_pig_create, api_pig_update, api_pig_delete, api_pig_fetch]
+    routes![
+        api_pig_create,
+        api_pig_update,
+        api_pig_delete,
+        api_pig_batch_delete,
+        api_pig_batch_tag,
+        api_pig_archive,
+        api_pig_archive_matching,
+        api_pig_fetch,
+        api_pig_check,
+        api_pig_snapshot,
+        api_pig_diff
+    ]
 }
 
 /// Creates a new pig with the given name, responding with it if successful.
+/// Returns HTTP status 422 if the name matches the blocklist, unless names
+/// which match it are only flagged for review and either the user is a
+/// [`Roles::Moderator`] or a moderator already approved it in a bulk import.
+/// Returns 429 if the user has reached
+/// [`crate::config::QuotaConfig::pigs_per_day`].
 #[post("/create?<name>")]
 async fn api_pig_create(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    blocklist: &State<Blocklist>,
+    error_hint: ErrorHint<'_>,
     name: &str,
 ) -> Result<Created<Json<Pig>>, Status> {
     if !auth_user.has_role(config, Roles::PigEditor) {
         return Err(Status::Forbidden);
     }
 
+    let mut db_connection = db_connection.lock().unwrap();
+
+    // When names are only flagged for review, creating the pig is how approved ones are accepted
+    if let Some(entry) = blocklist.check(name) {
+        let approved = config.blocklist.action == BlocklistAction::Review
+            && (auth_user.has_role(config, Roles::Moderator)
+                || was_approved(db_connection.deref_mut(), name).map_err(|err| {
+                    error!("Unable to check whether pig {:?} was approved for review: {:?}", name, err);
+                    Status::InternalServerError
+                })?);
+
+        if !approved {
+            warn!("User {:?} tried to create pig {:?} matching blocklist entry {:?}", auth_user.user.id, name, entry);
+            return Err(Status::UnprocessableEntity);
+        }
+    }
+
+    match pigs_left(db_connection.deref_mut(), config, &auth_user, clock.now_naive()) {
+        Ok(Some(0)) => {
+            warn!("User {:?} tried to create pig {:?} after reaching their daily quota", auth_user.user.id, name);
+            error_hint.set(ErrorCode::QuotaExceeded);
+            return Err(Status::TooManyRequests);
+        }
+        Ok(_) => {}
+        Err(err) => {
+            error!("Unable to check how many pigs user {:?} created today: {:?}", auth_user.user.id, err);
+            return Err(Status::InternalServerError);
+        }
+    }
+
     // Create the new pig
     // TODO deduplicate uuids and names
-    let pig = Pig::new(name, auth_user.user.id.as_ref());
+    let now = clock.now_naive();
+    let pig = Pig { id: config.new_id(), ..Pig::new(name, auth_user.user.id.as_ref(), now) };
 
     // Save it to the DB
-    let mut db_connection = db_connection.lock().unwrap();
-    let sql_res = diesel::insert_into(schema::pigs::table).values(&pig).execute(db_connection.deref_mut());
+    let sql_res = diesel::insert_into(schema::pigs::table)
+        .values((&pig, schema::pigs::name_skeleton.eq(skeleton(&pig.name))))
+        .execute(db_connection.deref_mut());
 
     if sql_res.is_ok() {
+        pig_cache.clear();
+        changes.publish(ChangeTarget::Pig, ChangeAction::Created, &[pig.id]);
+        let change = PigChange::new(PigChangeAction::Created, &pig.id, &pig.name, &auth_user.user.id, now);
+        record_changes(db_connection.deref_mut(), &vec![change]);
+        notify_pigs(config, WebhookEvent::PigCreated, &[(pig.id, pig.name.to_owned())], &auth_user.user.id);
+
         // Respond with a path to the pig and the object itself, unfortunately the location path is mandatory
         let params = PigQuery { id: Some(Vec::from([pig.id.to_string()])), ..Default::default() };
         Ok(Created::new(params.to_yuri()).body(Json(pig)))
@@ -46,31 +132,96 @@ async fn api_pig_create(
     }
 }
 
+/// Whether a moderator approved the given name in a bulk import which is still
+/// open. Flagged names only go back to pending once they're approved.
+fn was_approved(db_connection: &mut PgConnection, name: &str) -> QueryResult<bool> {
+    let imports: i64 = schema::bulk_imports::table
+        .filter(schema::bulk_imports::pending.contains(vec![name]))
+        .filter(schema::bulk_imports::closed.is_null())
+        .count()
+        .get_result(db_connection)?;
+
+    Ok(imports > 0)
+}
+
 /// Performs an in-place update, replacing all mutable fields for the given pig.
-/// Responds with the updated pig if successful.
+/// Responds with the updated pig if successful, HTTP status 422 if any of its
+/// links aren't valid, or 409 if it's archived.
 #[put("/update", data = "<pig>")]
 async fn api_pig_update(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
     pig: Json<Pig>,
 ) -> Result<Json<Pig>, Status> {
     if !auth_user.has_role(config, Roles::PigEditor) {
         return Err(Status::Forbidden);
     }
 
-    let pig = pig.into_inner();
+    let mut pig = pig.into_inner();
+    pig.tags = clean_tags(&pig.tags);
+
+    // Links are shown to everyone as-is, so make sure they're actually links
+    pig.links = pig.links.iter().map(|link| PigLink::new(&link.label, &link.url)).collect();
+    if let Some(link) = pig.links.iter().find(|link| !link.is_valid()) {
+        warn!("User {:?} tried to save invalid link {:?} on pig {:?}", auth_user.user.id, link, pig.id);
+        return Err(Status::UnprocessableEntity);
+    }
     let mut db_connection = db_connection.lock().unwrap();
 
+    // Archived pigs are read-only, they have to be unarchived through api_pig_archive first
+    let archived_res = schema::pigs::table
+        .filter(schema::pigs::id.eq(&pig.id))
+        .select((schema::pigs::archived, schema::pigs::name))
+        .first::<(bool, String)>(db_connection.deref_mut());
+
+    // Keep the old name around so renames can be recorded
+    let old_name = match archived_res {
+        Ok((true, _)) => return Err(Status::Conflict),
+        Ok((false, name)) => {
+            pig.archived = false;
+            name
+        }
+        Err(diesel::NotFound) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Unable to check whether pig {:?} is archived: {:?}", pig.id, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
     // Because Pig derives Identifiable and AsChangeset it just kinda knows what needs to be updated
+    pig.updated_at = clock.now_naive();
     let sql_res = diesel::update(schema::pigs::table)
         .filter(schema::pigs::id.eq(&pig.id))
-        .set(&pig)
+        .set((&pig, schema::pigs::name_skeleton.eq(skeleton(&pig.name))))
+        .returning(Pig::as_returning())
         .get_result(db_connection.deref_mut());
 
     if sql_res.is_ok() {
+        pig_cache.clear();
+        changes.publish(ChangeTarget::Pig, ChangeAction::Updated, &[pig.id]);
+        if old_name != pig.name {
+            let change = PigChange::renamed(&pig.id, &old_name, &pig.name, &auth_user.user.id, clock.now_naive());
+            record_changes(db_connection.deref_mut(), &vec![change]);
+        }
+
+        let pig: Pig = sql_res.unwrap();
+        let edited = [(pig.id, pig.name.to_owned(), pig.creator)];
+        let now = clock.now_naive();
+        notify_creators(
+            db_connection.deref_mut(),
+            NotificationKind::PigEdited,
+            "edited",
+            &edited,
+            &auth_user.user,
+            now,
+        );
+
         // Return the updated pig
-        Ok(Json(sql_res.unwrap()))
+        Ok(Json(pig))
     } else {
         error!("Unable to update pig {:?}: {:?}", pig, sql_res.unwrap_err());
         Err(Status::InternalServerError)
@@ -78,25 +229,64 @@ async fn api_pig_update(
 }
 
 /// Deletes the pig with the given [`Uuid`], returning HTTP status 204 if
-/// successful
+/// successful. Only a [`Roles::PigAdmin`] can delete any pig, though if
+/// [`crate::config::PigConfig::creator_can_delete`] is set, a
+/// [`Roles::PigEditor`] can delete pigs they created.
 #[delete("/delete?<id>")]
 async fn api_pig_delete(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
     id: &str,
 ) -> Result<Status, Status> {
-    if !auth_user.has_role(config, Roles::PigEditor) {
+    let is_admin = auth_user.has_role(config, Roles::PigAdmin);
+    if !(is_admin || (config.pigs.creator_can_delete && auth_user.has_role(config, Roles::PigEditor))) {
         return Err(Status::Forbidden);
     }
 
     let uuid = parse_uuid(id)?;
-
     let mut db_connection = db_connection.lock().unwrap();
-    let sql_res =
-        diesel::delete(schema::pigs::table.filter(schema::pigs::id.eq(uuid))).execute(db_connection.deref_mut());
 
-    if sql_res.is_ok() {
+    // Editors can only delete their own pigs
+    if !is_admin {
+        let creator_res = schema::pigs::table
+            .filter(schema::pigs::id.eq(uuid))
+            .select(schema::pigs::creator)
+            .first::<Uuid>(db_connection.deref_mut());
+
+        match creator_res {
+            Ok(creator) if creator != auth_user.user.id => return Err(Status::Forbidden),
+            Ok(_) => {}
+            Err(diesel::NotFound) => return Err(Status::NotFound),
+            Err(err) => {
+                error!("Unable to load creator of pig {:?}: {:?}", uuid, err);
+                return Err(Status::InternalServerError);
+            }
+        }
+    }
+
+    let sql_res = diesel::delete(schema::pigs::table.filter(schema::pigs::id.eq(uuid)))
+        .returning((schema::pigs::id, schema::pigs::name, schema::pigs::creator))
+        .load::<(Uuid, String, Uuid)>(db_connection.deref_mut());
+
+    if let Ok(deleted) = sql_res {
+        let now = clock.now_naive();
+        notify_creators(
+            db_connection.deref_mut(),
+            NotificationKind::PigDeleted,
+            "deleted",
+            &deleted,
+            &auth_user.user,
+            now,
+        );
+        let deleted = deleted.into_iter().map(|(id, name, _)| (id, name)).collect();
+        pig_cache.clear();
+        publish_deletions(changes, &deleted);
+        record_deletions(db_connection.deref_mut(), &deleted, &auth_user.user.id, now);
+        notify_pigs(config, WebhookEvent::PigDeleted, &deleted, &auth_user.user.id);
         Ok(Status::NoContent)
     } else {
         error!("Unable to delete pig {:?}: {:?}", id, sql_res.unwrap_err());
@@ -104,27 +294,459 @@ async fn api_pig_delete(
     }
 }
 
-/// Returns a JSON list of pigs which match the given query.
+/// Deletes every pig with one of the given [`Uuid`]s, responding with the
+/// number of pigs which were deleted. The same roles are needed as
+/// [`api_pig_delete`], and if an editor includes any pig they didn't create,
+/// nothing is deleted.
+#[delete("/batch_delete", data = "<ids>")]
+async fn api_pig_batch_delete(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    ids: Json<Vec<Uuid>>,
+) -> Result<Json<usize>, Status> {
+    let is_admin = auth_user.has_role(config, Roles::PigAdmin);
+    if !(is_admin || (config.pigs.creator_can_delete && auth_user.has_role(config, Roles::PigEditor))) {
+        return Err(Status::Forbidden);
+    }
+
+    let ids = ids.into_inner();
+    let mut db_connection = db_connection.lock().unwrap();
+
+    // Editors can only delete their own pigs
+    if !is_admin {
+        let others_res = schema::pigs::table
+            .filter(schema::pigs::id.eq_any(&ids))
+            .filter(schema::pigs::creator.ne(auth_user.user.id))
+            .count()
+            .get_result::<i64>(db_connection.deref_mut());
+
+        match others_res {
+            Ok(0) => {}
+            Ok(_) => return Err(Status::Forbidden),
+            Err(err) => {
+                error!("Unable to load creators of pigs {:?}: {:?}", ids, err);
+                return Err(Status::InternalServerError);
+            }
+        }
+    }
+
+    let sql_res = diesel::delete(schema::pigs::table.filter(schema::pigs::id.eq_any(&ids)))
+        .returning((schema::pigs::id, schema::pigs::name, schema::pigs::creator))
+        .load::<(Uuid, String, Uuid)>(db_connection.deref_mut());
+
+    if let Ok(deleted) = sql_res {
+        let now = clock.now_naive();
+        notify_creators(
+            db_connection.deref_mut(),
+            NotificationKind::PigDeleted,
+            "deleted",
+            &deleted,
+            &auth_user.user,
+            now,
+        );
+        let deleted = deleted.into_iter().map(|(id, name, _)| (id, name)).collect();
+        pig_cache.clear();
+        publish_deletions(changes, &deleted);
+        record_deletions(db_connection.deref_mut(), &deleted, &auth_user.user.id, now);
+        notify_pigs(config, WebhookEvent::PigDeleted, &deleted, &auth_user.user.id);
+        info!("User {:?} deleted {} pigs at once", auth_user.user.id, deleted.len());
+        Ok(Json(deleted.len()))
+    } else {
+        error!("Unable to delete pigs {:?}: {:?}", ids, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Adds the given tag to every pig with one of the given [`Uuid`]s, skipping
+/// any which already have it or are archived. Responds with the number of pigs
+/// which were changed.
+#[patch("/batch_tag?<tag>", data = "<ids>")]
+async fn api_pig_batch_tag(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    tag: &str,
+    ids: Json<Vec<Uuid>>,
+) -> Result<Json<usize>, Status> {
+    if !auth_user.has_role(config, Roles::PigEditor) {
+        return Err(Status::Forbidden);
+    }
+
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    let ids = ids.into_inner();
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::sql_query(
+        "UPDATE pigs SET tags = array_append(tags, $1), updated_at = $3 \
+        WHERE id = ANY($2) AND NOT $1 = ANY(tags) AND NOT archived",
+    )
+    .bind::<Text, _>(tag)
+    .bind::<Array<sql_types::Uuid>, _>(&ids)
+    .bind::<Timestamp, _>(clock.now_naive())
+    .execute(db_connection.deref_mut());
+
+    if let Ok(changed) = sql_res {
+        pig_cache.clear();
+        if changed > 0 {
+            changes.publish(ChangeTarget::Pig, ChangeAction::Updated, &ids);
+        }
+        info!("User {:?} added tag {:?} to {} pigs", auth_user.user.id, tag, changed);
+        Ok(Json(changed))
+    } else {
+        error!("Unable to add tag {:?} to pigs {:?}: {:?}", tag, ids, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Archives or unarchives the pig with the given [`Uuid`], responding with the
+/// updated pig. Archived pigs still show up in searches, but can't be edited.
+/// Returns HTTP status 404 if there's no pig with that id.
+#[patch("/archive?<id>&<archived>")]
+async fn api_pig_archive(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    id: &str,
+    archived: bool,
+) -> Result<Json<Pig>, Status> {
+    if !auth_user.has_role(config, Roles::PigAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::update(schema::pigs::table)
+        .filter(schema::pigs::id.eq(uuid))
+        .set((schema::pigs::archived.eq(archived), schema::pigs::updated_at.eq(clock.now_naive())))
+        .returning(Pig::as_returning())
+        .get_result::<Pig>(db_connection.deref_mut());
+
+    match sql_res {
+        Ok(pig) => {
+            pig_cache.clear();
+            changes.publish(ChangeTarget::Pig, ChangeAction::Updated, &[pig.id]);
+
+            let action = if archived { "archived" } else { "unarchived" };
+            let edited = [(pig.id, pig.name.to_owned(), pig.creator)];
+            let now = clock.now_naive();
+            notify_creators(
+                db_connection.deref_mut(),
+                NotificationKind::PigEdited,
+                action,
+                &edited,
+                &auth_user.user,
+                now,
+            );
+            info!("User {:?} set pig {:?} archived to {}", auth_user.user.id, uuid, archived);
+            Ok(Json(pig))
+        }
+        Err(diesel::NotFound) => Err(Status::NotFound),
+        Err(err) => {
+            error!("Unable to set pig {:?} archived to {}: {:?}", uuid, archived, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Archives every pig which matches the given query and isn't archived yet in
+/// one transaction, responding with the number of pigs archived. If `dry_run`
+/// is true, nothing is changed and the response is how many would've been.
+/// Returns HTTP status 400 if the query has an invalid date or doesn't filter
+/// anything, so every pig can't be archived by accident.
+#[patch("/archive_matching?<dry_run>&<query..>")]
+async fn api_pig_archive_matching(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    dry_run: Option<bool>,
+    query: PigQuery,
+) -> Result<Json<usize>, Status> {
+    if !auth_user.has_role(config, Roles::PigAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    if !query.has_filters() || !query.has_valid_dates() {
+        return Err(Status::BadRequest);
+    }
+
+    // Every match is archived, not just the current page of them
+    let query = PigQuery { limit: Some(u32::MAX), offset: Some(0), ..query }.with_archived(false);
+    let dry_run = dry_run.unwrap_or(false);
+
+    let sql_res = db_connection.lock().unwrap().transaction::<_, DieselError, _>(|db_connection| {
+        let ids = query.to_db_select().select(schema::pigs::id).load::<Uuid>(db_connection)?;
+        if dry_run {
+            return Ok(ids.len());
+        }
+
+        diesel::update(schema::pigs::table)
+            .filter(schema::pigs::id.eq_any(&ids))
+            .set((schema::pigs::archived.eq(true), schema::pigs::updated_at.eq(clock.now_naive())))
+            .execute(db_connection)
+    });
+
+    match sql_res {
+        Ok(archived) if dry_run => Ok(Json(archived)),
+        Ok(archived) => {
+            pig_cache.clear();
+            if archived > 0 {
+                changes.publish_many(ChangeTarget::Pig, ChangeAction::Updated);
+            }
+            info!("User {:?} archived {} pigs matching {:?}", auth_user.user.id, archived, query);
+            Ok(Json(archived))
+        }
+        Err(err) => {
+            error!("Unable to archive pigs matching {:?}: {:?}", query, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Returns a JSON list of pigs which match the given query. Results are cached
+/// until any pig is modified.
 #[get("/fetch?<query..>")]
 async fn api_pig_fetch(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    search_limiter: &State<SearchLimiter>,
+    pig_cache: &State<PigCache>,
     query: PigQuery,
-) -> Result<Json<Vec<Pig>>, Status> {
+) -> Result<Json<Value>, Status> {
     if !auth_user.has_role(config, Roles::PigViewer) {
         return Err(Status::Forbidden);
     }
 
-    // Construct the SQL query and submit it to the DB
-    let sql_query = query.to_db_select();
-    let mut db_connection = db_connection.lock().unwrap();
-    let sql_res = sql_query.select(Pig::as_select()).load(db_connection.deref_mut());
+    // Invalid creation dates are ignored here, so the list still loads while they're being typed
+    if query.changed_since.as_ref().is_some_and(|since| parse_timestamp(since).is_none()) {
+        return Err(Status::UnprocessableEntity);
+    }
 
-    if sql_res.is_ok() {
-        Ok(Json(sql_res.unwrap()))
+    let _permit = search_limiter.acquire(&auth_user.user.id)?;
+    let max_limit = max_limit(config, &auth_user, query.id.as_ref());
+    let query = query.with_max_limit(max_limit);
+
+    // Check if we've recently run this exact query, otherwise submit it to the DB
+    let cache_key = query.to_yuri();
+    let pigs = match pig_cache.get(cache_key.as_str()) {
+        Some(pigs) => pigs,
+        None => {
+            let sql_query = query.to_db_select();
+            let sql_res = sql_query.select(Pig::as_select()).load(db_connection.lock().unwrap().deref_mut());
+
+            match sql_res {
+                Ok(pigs) => {
+                    pig_cache.insert(cache_key, &pigs);
+                    pigs
+                }
+                Err(err) => {
+                    error!("Unable to load SQL result for query {:?}: {:?}", query, err);
+                    return Err(Status::InternalServerError);
+                }
+            }
+        }
+    };
+
+    let res = with_creators(db_connection, pigs, query.includes_creator(), |pig| pig.creator)?;
+    Ok(Json(select_fields(&res, query.field_list())?))
+}
+
+/// Checks whether the given name could be used for a new pig without creating
+/// anything, responding with a [`PigCheck`]. The name is screened and compared
+/// to existing pigs the same way a bulk import does with each of its names, so
+/// the verdict is what an import would do with it, other than quotas. Returns
+/// HTTP status 422 if the name is empty.
+#[get("/check?<name>")]
+async fn api_pig_check(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    search_limiter: &State<SearchLimiter>,
+    blocklist: &State<Blocklist>,
+    name: &str,
+) -> Result<Json<PigCheck>, Status> {
+    if !auth_user.has_role(config, Roles::PigViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let name = clean_name(name);
+    if name.is_empty() {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    // An import doesn't look for duplicates of blocked names either
+    if blocklist.check(&name).is_some() {
+        return Ok(Json(PigCheck { name, verdict: PigVerdict::Blocked, matches: Vec::new() }));
+    }
+
+    let _permit = search_limiter.acquire(&auth_user.user.id)?;
+
+    let pigs = find_duplicates(db_connection.lock().unwrap().deref_mut(), config, &name, 10).map_err(|err| {
+        error!("Unable to find duplicates of {:?}: {:?}", name, err);
+        Status::InternalServerError
+    })?;
+
+    let verdict = if pigs.iter().any(|pig| pig.name.eq_ignore_ascii_case(&name)) {
+        PigVerdict::Exact
+    } else if pigs.is_empty() {
+        PigVerdict::Unique
     } else {
-        error!("Unable to load SQL result for query {:?}: {:?}", query, sql_res.unwrap_err());
-        Err(Status::InternalServerError)
+        PigVerdict::Similar
+    };
+
+    let mut matches: Vec<PigMatch> =
+        pigs.into_iter().map(|pig| PigMatch { score: name_similarity(&name, &pig.name), pig }).collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(Json(PigCheck { name, verdict, matches }))
+}
+
+/// Saves the id and name of every pig under the given name, responding with
+/// the snapshot if successful. Returns HTTP status 409 if there's already a
+/// snapshot with that name.
+#[post("/snapshot?<name>")]
+async fn api_pig_snapshot(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    name: &str,
+) -> Result<Created<Json<PigSnapshot>>, Status> {
+    if !auth_user.has_role(config, Roles::PigEditor) {
+        return Err(Status::Forbidden);
+    }
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let snapshot =
+        PigSnapshot::new(name, &auth_user.user.id, load_pig_names(db_connection.deref_mut())?, clock.now_naive());
+    let sql_res =
+        diesel::insert_into(schema::pig_snapshots::table).values(&snapshot).execute(db_connection.deref_mut());
+
+    match sql_res {
+        Ok(_) => Ok(Created::new(PIG_API_ROOT.to_owned() + "diff").body(Json(snapshot))),
+        Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(Status::Conflict),
+        Err(err) => {
+            error!("Unable to save snapshot {:?}: {:?}", name, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Compares the snapshots with the given names, responding with the pigs which
+/// were added, renamed, and deleted between them. If `to` isn't given, `from`
+/// is compared to the current list. Returns HTTP status 404 if either snapshot
+/// doesn't exist.
+#[get("/diff?<from>&<to>")]
+async fn api_pig_diff(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    from: &str,
+    to: Option<&str>,
+) -> Result<Json<SnapshotDiff>, Status> {
+    if !auth_user.has_role(config, Roles::PigViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let from = load_snapshot(db_connection.deref_mut(), from)?;
+    let to = match to {
+        Some(to) => load_snapshot(db_connection.deref_mut(), to)?,
+        None => {
+            let pigs = load_pig_names(db_connection.deref_mut())?;
+            PigSnapshot::new("current", &auth_user.user.id, pigs, clock.now_naive())
+        }
+    };
+
+    Ok(Json(SnapshotDiff::between(&from, &to)))
+}
+
+/// Loads the id and name of every pig, sorted by name
+fn load_pig_names(db_connection: &mut PgConnection) -> Result<Vec<(Uuid, String)>, Status> {
+    schema::pigs::table
+        .select((schema::pigs::id, schema::pigs::name))
+        .order(schema::pigs::name)
+        .load(db_connection)
+        .map_err(|err| {
+            error!("Unable to load pig names for a snapshot: {:?}", err);
+            Status::InternalServerError
+        })
+}
+
+/// Records the deletion of each of the given `(id, name)` pairs by the given
+/// user at the given time in the audit log
+fn record_deletions(db_connection: &mut PgConnection, deleted: &Vec<(Uuid, String)>, actor: &Uuid, now: NaiveDateTime) {
+    let changes =
+        deleted.iter().map(|(id, name)| PigChange::new(PigChangeAction::Deleted, id, name, actor, now)).collect();
+    record_changes(db_connection, &changes);
+}
+
+/// Tells the creator of each of the given `(id, name, creator)` pigs that the
+/// given user did the action to it at the given time, e.g. "edited". Nobody is
+/// told about what they did to their own pigs.
+fn notify_creators(
+    db_connection: &mut PgConnection,
+    kind: NotificationKind,
+    action: &str,
+    pigs: &[(Uuid, String, Uuid)],
+    actor: &User,
+    now: NaiveDateTime,
+) {
+    let notifications = pigs
+        .iter()
+        .filter(|(_, _, creator)| *creator != actor.id)
+        .map(|(id, name, creator)| {
+            let message = format!("{} {} your pig \"{}\"", actor.username, action, name);
+            Notification::new(creator, kind, message, Some(id), now)
+        })
+        .collect();
+    send_notifications(db_connection, &notifications);
+}
+
+/// Tells clients following the change feed about each of the given deleted
+/// `(id, name)` pairs
+fn publish_deletions(changes: &ChangeFeed, deleted: &Vec<(Uuid, String)>) {
+    let ids: Vec<Uuid> = deleted.iter().map(|(id, _)| id.to_owned()).collect();
+    changes.publish(ChangeTarget::Pig, ChangeAction::Deleted, &ids);
+}
+
+/// Loads the snapshot with the given name, or HTTP status 404 if there isn't
+/// one
+fn load_snapshot(db_connection: &mut PgConnection, name: &str) -> Result<PigSnapshot, Status> {
+    let sql_res = schema::pig_snapshots::table
+        .filter(schema::pig_snapshots::name.eq(name))
+        .select(PigSnapshot::as_select())
+        .first(db_connection)
+        .optional();
+
+    match sql_res {
+        Ok(Some(snapshot)) => Ok(snapshot),
+        Ok(None) => Err(Status::NotFound),
+        Err(err) => {
+            error!("Unable to load snapshot {:?}: {:?}", name, err);
+            Err(Status::InternalServerError)
+        }
     }
 }
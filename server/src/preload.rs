@@ -0,0 +1,106 @@
+use crate::config::Config;
+use regex::Regex;
+use rocket::{Build, Rocket};
+use std::fs;
+use std::path::PathBuf;
+
+/// The `Link` headers sent with the client's index.html, so the browser starts
+/// downloading the wasm, its JS glue, and any fonts before it's read the page.
+/// Reverse proxies which support it, such as nginx, Cloudflare, and Fastly,
+/// can send these as [103 Early Hints](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status/103)
+/// while the page itself is still on its way. Rocket can't send those itself.
+#[derive(Debug)]
+pub struct PreloadHints(String);
+
+impl PreloadHints {
+    /// Builds the hints from the preload links Trunk added to the compiled
+    /// index.html, plus any fonts its styles use. The page itself is left as
+    /// it was built so its integrity hashes still match. Returns [`None`] if
+    /// index.html can't be read or has nothing to preload.
+    pub fn from_client(client_path: &str) -> Option<Self> {
+        let path = PathBuf::from(client_path).join("index.html");
+        let html = fs::read_to_string(&path)
+            .map_err(|e| warn!("Unable to read {:?} to find what to preload: {:?}", path, e))
+            .ok()?;
+
+        let link = Regex::new(r#"(?s)<link\s([^>]*)>"#).unwrap();
+        let attribute = Regex::new(r#"([a-zA-Z-]+)(?:\s*=\s*"([^"]*)")?"#).unwrap();
+        let font = Regex::new(r#"url\(\s*['"]?([^'")]+\.(?:woff2?|ttf|otf))['"]?\s*\)"#).unwrap();
+
+        let mut hints = Vec::new();
+        for captures in link.captures_iter(&html) {
+            let attributes: Vec<(&str, Option<&str>)> = attribute
+                .captures_iter(&captures[1])
+                .map(|attr| (attr.get(1).unwrap().as_str(), attr.get(2).map(|value| value.as_str())))
+                .collect();
+            let get = |name: &str| attributes.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, v)| *v);
+
+            let (Some(Some(rel)), Some(Some(href))) = (get("rel"), get("href")) else {
+                continue;
+            };
+
+            if rel != "preload" && rel != "modulepreload" {
+                continue;
+            }
+
+            // the header has to say everything the tag does, or the browser
+            // downloads the file again when the page asks for it
+            let mut hint = format!("<{}>; rel={}", absolute(href), rel);
+            for key in ["as", "type", "integrity"] {
+                if let Some(Some(value)) = get(key) {
+                    hint.push_str(&format!("; {}=\"{}\"", key, value));
+                }
+            }
+
+            if let Some(crossorigin) = get("crossorigin") {
+                hint.push_str(&format!("; crossorigin=\"{}\"", crossorigin.unwrap_or("anonymous")));
+            }
+
+            hints.push(hint);
+        }
+
+        // fonts are always fetched in CORS mode
+        for captures in font.captures_iter(&html) {
+            let hint = format!("<{}>; rel=preload; as=\"font\"; crossorigin=\"anonymous\"", absolute(&captures[1]));
+            if !hints.contains(&hint) {
+                hints.push(hint);
+            }
+        }
+
+        if hints.is_empty() {
+            info!("Found nothing to preload in {:?}", path);
+            return None;
+        }
+
+        Some(Self(hints.join(", ")))
+    }
+
+    /// The value of the `Link` header
+    pub fn link(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Makes a relative path from index.html relative to the root instead, since
+/// index.html is also sent for any page which isn't a file
+fn absolute(href: &str) -> String {
+    match href.starts_with('/') || href.contains("://") {
+        true => href.to_owned(),
+        false => format!("/{}", href.trim_start_matches("./")),
+    }
+}
+
+/// Builds the [`PreloadHints`] from the compiled client and manages them, if
+/// [`Config::client_preload`] is enabled. This must be attached after the
+/// config.
+pub async fn load_preload_hints(rocket: Rocket<Build>) -> Rocket<Build> {
+    let hints = rocket
+        .state::<Config>()
+        .filter(|config| config.client_preload)
+        .and_then(|config| PreloadHints::from_client(&config.client_path));
+
+    match hints {
+        Some(hints) => rocket.manage(hints),
+        None => rocket,
+    }
+}
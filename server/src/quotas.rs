@@ -0,0 +1,55 @@
+use crate::auth::AuthenticatedUser;
+use crate::config::Config;
+use chrono::{Duration, NaiveDateTime};
+use diesel::{ExpressionMethods, PgConnection, QueryDsl, QueryResult, RunQueryDsl};
+use pigweb_common::schema;
+use pigweb_common::users::Roles;
+
+/// How many more pigs the user can create before they reach
+/// [`crate::config::QuotaConfig::pigs_per_day`]. Pigs created in the last 24
+/// hours before `now` count against it, including those created by bulk
+/// imports. Returns [`None`] if there's no quota or the user is a
+/// [`Roles::PigAdmin`].
+pub fn pigs_left(
+    db_connection: &mut PgConnection,
+    config: &Config,
+    user: &AuthenticatedUser,
+    now: NaiveDateTime,
+) -> QueryResult<Option<u32>> {
+    let quota = config.quotas.pigs_per_day;
+    if quota == 0 || user.has_role(config, Roles::PigAdmin) {
+        return Ok(None);
+    }
+
+    let since = now - Duration::days(1);
+    let created: i64 = schema::pigs::table
+        .filter(schema::pigs::creator.eq(&user.user.id))
+        .filter(schema::pigs::created.ge(since))
+        .count()
+        .get_result(db_connection)?;
+
+    Ok(Some(quota.saturating_sub(created as u32)))
+}
+
+/// Whether the user can start another bulk import without going over
+/// [`crate::config::QuotaConfig::open_imports`]. Always true if there's no
+/// quota or the user is a [`Roles::BulkAdmin`].
+pub fn can_open_import(
+    db_connection: &mut PgConnection,
+    config: &Config,
+    user: &AuthenticatedUser,
+) -> QueryResult<bool> {
+    let quota = config.quotas.open_imports;
+    if quota == 0 || user.has_role(config, Roles::BulkAdmin) {
+        return Ok(true);
+    }
+
+    let open: i64 = schema::bulk_imports::table
+        .filter(schema::bulk_imports::creator.eq(&user.user.id))
+        .filter(schema::bulk_imports::finished.is_null())
+        .filter(schema::bulk_imports::closed.is_null())
+        .count()
+        .get_result(db_connection)?;
+
+    Ok(open < quota as i64)
+}
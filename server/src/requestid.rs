@@ -0,0 +1,57 @@
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Data, Request, Response};
+use std::fmt::{Display, Formatter};
+use uuid::Uuid;
+
+/// A [Request Guard](FromRequest) providing a unique id for the current
+/// request. Every guard asking for it during the same request gets the same
+/// id, so anything logged or saved while handling it can be tied back to the
+/// request which caused it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestId(pub Uuid);
+
+impl RequestId {
+    /// Gets the id for the given request, generating it if nothing has asked
+    /// for it yet
+    pub fn of(request: &Request<'_>) -> RequestId {
+        *request.local_cache(|| RequestId(Uuid::new_v4()))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    // This can't fail
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<RequestId, ()> {
+        Outcome::Success(RequestId::of(request))
+    }
+}
+
+/// Gives every request a [`RequestId`] as soon as it's received and sends it
+/// back in the `X-Request-Id` header, so bug reports can be tied to the logs
+/// of the request they're about.
+pub struct RequestIdHeader;
+
+#[rocket::async_trait]
+impl Fairing for RequestIdHeader {
+    fn info(&self) -> Info {
+        Info { name: "Request Id", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        RequestId::of(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        response.set_header(Header::new("X-Request-Id", RequestId::of(request).to_string()));
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::config::Config;
+use crate::requestid::RequestId;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::serde::json::serde_json::json;
+use rocket::{Data, Request, Response};
+use std::sync::OnceLock;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// When a request was received, for working out how long it took
+struct RequestStart(Instant);
+
+/// The user who made a request, recorded once they've been authenticated by
+/// [`crate::auth::AuthenticatedUser`]. Stays empty for requests which don't
+/// need signing in or were rejected.
+#[derive(Default)]
+pub struct RequestUser(OnceLock<Uuid>);
+
+impl RequestUser {
+    /// Records the given user as the one who made the given request
+    pub fn record(request: &Request<'_>, user: Uuid) {
+        let _ = request.local_cache(RequestUser::default).0.set(user);
+    }
+}
+
+/// Logs one line for each request once it's been responded to, with its
+/// method, path, status, how long it took, who made it, and its
+/// [`RequestId`]. Query strings aren't logged since some of them carry
+/// tokens, e.g. for the Atom feed.
+pub struct RequestLogging;
+
+#[rocket::async_trait]
+impl Fairing for RequestLogging {
+    fn info(&self) -> Info {
+        Info { name: "Request Logging", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(config) = request.rocket().state::<Config>().map(|config| &config.request_log) else {
+            return;
+        };
+
+        if !config.enabled {
+            return;
+        }
+
+        let latency = request.local_cache(|| RequestStart(Instant::now())).0.elapsed().as_secs_f64() * 1000.0;
+        let user = request.local_cache(RequestUser::default).0.get().copied();
+        let request_id = RequestId::of(request);
+
+        if config.json {
+            let line = json!({
+                "method": request.method().as_str(),
+                "path": request.uri().path().as_str(),
+                "status": response.status().code,
+                "latency_ms": latency,
+                "user": user,
+                "request_id": request_id.0,
+            });
+            info!("{}", line);
+        } else {
+            let user = user.map_or("-".to_owned(), |user| user.to_string());
+            info!(
+                "{} {} {} {:.1}ms user={} request={}",
+                request.method(),
+                request.uri().path(),
+                response.status().code,
+                latency,
+                user,
+                request_id
+            );
+        }
+    }
+}
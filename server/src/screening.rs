@@ -0,0 +1,37 @@
+use crate::config::BlocklistConfig;
+use regex::{Regex, RegexBuilder};
+
+/// Screens pig names against the configured blocklist, so inappropriate names
+/// which slip past whoever is reviewing an import are still caught.
+pub struct Blocklist {
+    /// Each entry from the config alongside the regex it compiled to
+    entries: Vec<(String, Regex)>,
+}
+
+impl Blocklist {
+    /// Compiles the words and patterns in the given config. Words only match
+    /// whole words, patterns are used as-is, and both ignore case. Invalid
+    /// patterns are logged and skipped.
+    pub fn new(config: &BlocklistConfig) -> Self {
+        let words = config.words.iter().map(|word| (word, format!(r"\b{}\b", regex::escape(word))));
+        let patterns = config.patterns.iter().map(|pattern| (pattern, pattern.to_owned()));
+
+        let entries = words
+            .chain(patterns)
+            .filter_map(|(entry, pattern)| match RegexBuilder::new(pattern.as_str()).case_insensitive(true).build() {
+                Ok(regex) => Some((entry.to_owned(), regex)),
+                Err(err) => {
+                    error!("Unable to compile blocklist entry {:?}, ignoring it: {:?}", entry, err);
+                    None
+                }
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Returns the blocklist entry the name matches, if any
+    pub fn check(&self, name: &str) -> Option<&str> {
+        self.entries.iter().find(|(_, regex)| regex.is_match(name)).map(|(entry, _)| entry.as_str())
+    }
+}
@@ -0,0 +1,163 @@
+use crate::auth::AuthenticatedUser;
+use crate::config::Config;
+use lru::LruCache;
+use pigweb_common::pigs::Pig;
+use pigweb_common::users::{Capabilities, User};
+use rocket::http::Status;
+use rocket::serde::json::{to_value, Value};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// The most items the given user can fetch in one query, or [`None`] if
+/// there's no limit. Queries for specific ids can always fetch every one of
+/// them, since there can't be more results than ids.
+pub fn max_limit(config: &Config, auth_user: &AuthenticatedUser, ids: Option<&Vec<String>>) -> Option<u32> {
+    let limit = Capabilities::default()
+        .with_limits(config.search.max_limit, &config.search.role_limits)
+        .limit_for(&auth_user.get_roles(config))?;
+    Some(limit.max(ids.map_or(0, |ids| ids.len() as u32)))
+}
+
+/// Converts the given items to JSON with only the given fields, e.g. from
+/// [`pigweb_common::pigs::PigQuery::fields`]. Every field is kept if `fields`
+/// is [`None`].
+pub fn select_fields<T: Serialize>(items: &Vec<T>, fields: Option<Vec<&str>>) -> Result<Value, Status> {
+    let mut value = to_value(items).map_err(|err| {
+        error!("Unable to serialize fetched items: {:?}", err);
+        Status::InternalServerError
+    })?;
+
+    if let (Some(fields), Value::Array(items)) = (fields, &mut value) {
+        for item in items.iter_mut() {
+            if let Value::Object(map) = item {
+                map.retain(|key, _| fields.contains(&key.as_str()));
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+/// Limits how many searches each user can have in progress at once. Since the
+/// bulk editor fires off a new search for nearly every keystroke, without this
+/// a single user could keep the DB busy with duplicate queries.
+pub struct SearchLimiter {
+    /// The maximum number of searches a user can have in progress at once. If
+    /// this is 0, there is no limit.
+    max_concurrent: u32,
+
+    /// The number of searches each user currently has in progress
+    in_flight: Mutex<BTreeMap<Uuid, u32>>,
+}
+
+impl SearchLimiter {
+    /// Creates a new SearchLimiter allowing each user to have the given number
+    /// of searches in progress at once
+    pub fn new(max_concurrent: u32) -> Self {
+        Self { max_concurrent, in_flight: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Reserves a search for the given user, erroring with HTTP status 429 if
+    /// they already have too many in progress. The search is considered done
+    /// when the returned [`SearchPermit`] is dropped.
+    pub fn acquire(&self, user: &Uuid) -> Result<SearchPermit<'_>, Status> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(user.to_owned()).or_insert(0);
+
+        if self.max_concurrent > 0 && *count >= self.max_concurrent {
+            warn!("User {:?} has too many searches in progress, rejecting another", user);
+            return Err(Status::TooManyRequests);
+        }
+
+        *count += 1;
+        Ok(SearchPermit { limiter: self, user: user.to_owned() })
+    }
+}
+
+/// Represents a search in progress, see [`SearchLimiter::acquire`]
+pub struct SearchPermit<'a> {
+    /// The limiter which handed out this permit
+    limiter: &'a SearchLimiter,
+
+    /// The user performing the search
+    user: Uuid,
+}
+
+impl Drop for SearchPermit<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.limiter.in_flight.lock().unwrap();
+
+        // Remove the user entirely when they're done so the map doesn't grow forever
+        if let Some(count) = in_flight.get_mut(&self.user) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                in_flight.remove(&self.user);
+            }
+        }
+    }
+}
+
+/// Caches the results of recent pig searches, see [`QueryCache`]
+pub type PigCache = QueryCache<Vec<Pig>>;
+
+/// Caches the results of recent user searches, see [`QueryCache`]
+pub type UserCache = QueryCache<Vec<User>>;
+
+/// Caches usernames keyed by the user's id, see [`QueryCache`]. Only signing
+/// in changes a username, so it's only cleared then.
+pub type UsernameCache = QueryCache<String>;
+
+/// Holds onto the results of recent queries so identical ones submitted by
+/// multiple clients (e.g. everyone refreshing the first page at once) only hit
+/// the DB once. Once full, the least recently used results are dropped first.
+/// This should be cleared whenever the data it holds is modified.
+pub struct QueryCache<T> {
+    /// How long results are kept before they're considered stale
+    ttl: Duration,
+
+    /// The results of each query, keyed by the query's URL, along with when
+    /// they were saved. If this is [`None`], caching is disabled.
+    entries: Option<Mutex<LruCache<String, (Instant, T)>>>,
+}
+
+impl<T: Clone> QueryCache<T> {
+    /// Creates a new QueryCache holding up to the given number of results for
+    /// the given duration. If either is 0, nothing is cached.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        let entries = NonZeroUsize::new(capacity).filter(|_| !ttl.is_zero()).map(|cap| Mutex::new(LruCache::new(cap)));
+        Self { ttl, entries }
+    }
+
+    /// Returns the cached results for the given query, if they're still fresh
+    pub fn get(&self, key: &str) -> Option<T> {
+        let mut entries = self.entries.as_ref()?.lock().unwrap();
+
+        match entries.get(key) {
+            Some((saved, res)) if saved.elapsed() < self.ttl => Some(res.to_owned()),
+            Some(_) => {
+                // the results are stale, might as well make room for new ones
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Saves the results for the given query
+    pub fn insert(&self, key: String, res: &T) {
+        if let Some(entries) = self.entries.as_ref() {
+            entries.lock().unwrap().put(key, (Instant::now(), res.to_owned()));
+        }
+    }
+
+    /// Removes all cached results
+    pub fn clear(&self) {
+        if let Some(entries) = self.entries.as_ref() {
+            entries.lock().unwrap().clear();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::config::Config;
+use crate::MIGRATIONS;
+use diesel::{PgConnection, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use rocket::http::uri::Absolute;
+use rocket::tokio::net::TcpStream;
+use rocket::tokio::time::timeout;
+use rocket::{Build, Rocket};
+use std::ops::DerefMut;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How long to wait when connecting to the OIDC provider
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The outcome of a single self-test check
+struct Check {
+    /// What was checked
+    name: &'static str,
+
+    /// Whether the server can't work properly if this check fails
+    hard: bool,
+
+    /// What went right or wrong, if the check failed
+    result: Result<String, String>,
+}
+
+/// Checks everything the server needs to work before it starts taking
+/// requests, then logs a summary. If [`crate::config::SelfTestConfig::strict`]
+/// is set, the server refuses to start if a hard check fails. This must be
+/// attached after the config.
+pub async fn run_self_test(rocket: Rocket<Build>) -> Result<Rocket<Build>, Rocket<Build>> {
+    let Some(config) = rocket.state::<Config>() else {
+        return Ok(rocket);
+    };
+
+    if !config.self_test.enabled {
+        return Ok(rocket);
+    }
+
+    let mut checks = Vec::new();
+    if let Some(db_connection) = rocket.state::<Mutex<PgConnection>>() {
+        let mut db_connection = db_connection.lock().unwrap();
+        checks.push(Check { name: "Database", hard: true, result: check_database(db_connection.deref_mut()) });
+        checks.push(Check { name: "Migrations", hard: true, result: check_migrations(db_connection.deref_mut()) });
+    }
+    checks.push(Check { name: "OIDC", hard: false, result: check_oidc(config).await });
+    checks.push(Check { name: "Client files", hard: true, result: check_client_path(config) });
+
+    // Log the results as a table so problems stand out
+    let width = checks.iter().map(|check| check.name.len()).max().unwrap_or_default();
+    info!("Self-test results:");
+    for check in &checks {
+        match &check.result {
+            Ok(msg) => info!("  PASS  {:width$}  {}", check.name, msg),
+            Err(msg) if check.hard => error!("  FAIL  {:width$}  {}", check.name, msg),
+            Err(msg) => warn!("  WARN  {:width$}  {}", check.name, msg),
+        }
+    }
+
+    let failed = checks.iter().any(|check| check.hard && check.result.is_err());
+    if failed && config.self_test.strict {
+        error!("Refusing to start since the self-test failed. Set self_test.strict to false to start anyway.");
+        return Err(rocket);
+    }
+
+    Ok(rocket)
+}
+
+/// Makes sure we can send a query to the database and get a response
+fn check_database(db_connection: &mut PgConnection) -> Result<String, String> {
+    diesel::sql_query("SELECT 1")
+        .execute(db_connection)
+        .map(|_| "Query succeeded".to_owned())
+        .map_err(|e| format!("Unable to query the database: {:?}", e))
+}
+
+/// Makes sure every migration the server knows about has been applied
+fn check_migrations(db_connection: &mut PgConnection) -> Result<String, String> {
+    match db_connection.has_pending_migration(MIGRATIONS) {
+        Ok(false) => Ok("Schema is up to date".to_owned()),
+        Ok(true) => Err("There are migrations which haven't been applied".to_owned()),
+        Err(e) => Err(format!("Unable to check migrations: {:?}", e)),
+    }
+}
+
+/// Makes sure we can connect to the OIDC provider's endpoints, otherwise
+/// nobody can sign in
+async fn check_oidc(config: &Config) -> Result<String, String> {
+    let oidc = config.oidc.as_ref().ok_or_else(|| "OIDC isn't configured".to_owned())?;
+
+    for uri in [&oidc.auth_uri, &oidc.token_uri] {
+        let address = socket_address(uri).ok_or_else(|| format!("Unable to parse URI {:?}", uri))?;
+        match timeout(CONNECT_TIMEOUT, TcpStream::connect(&address)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(format!("Unable to connect to {}: {:?}", address, e)),
+            Err(_) => return Err(format!("Timed out connecting to {}", address)),
+        }
+    }
+
+    Ok("Provider is reachable".to_owned())
+}
+
+/// Makes sure the client has been built where we expect it, otherwise every
+/// page is a 404
+fn check_client_path(config: &Config) -> Result<String, String> {
+    let index = PathBuf::from(&config.client_path).join("index.html");
+    match index.is_file() {
+        true => Ok(format!("Found {:?}", index)),
+        false => Err(format!("{:?} doesn't exist", index)),
+    }
+}
+
+/// Gets the `host:port` to connect to for the given URI, using the default
+/// port for the scheme if there isn't one
+fn socket_address(uri: &str) -> Option<String> {
+    let uri = Absolute::parse(uri).ok()?;
+    let authority = uri.authority()?;
+    let port = authority.port().unwrap_or(if uri.scheme() == "http" { 80 } else { 443 });
+
+    Some(format!("{}:{}", authority.host(), port))
+}
@@ -0,0 +1,129 @@
+use crate::auth::AuthenticatedUser;
+use crate::changes::ChangeFeed;
+use crate::clock::Clock;
+use crate::config::Config;
+use crate::search::PigCache;
+use diesel::sql_types::{Text, Timestamp};
+use diesel::{PgConnection, RunQueryDsl};
+use pigweb_common::changes::{ChangeAction, ChangeTarget};
+use pigweb_common::tags::Tag;
+use pigweb_common::users::Roles;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+
+/// Returns a list of all tag api routes
+pub fn get_tag_api_routes() -> Vec<Route> {
+    routes![api_tag_rename, api_tag_delete, api_tag_fetch]
+}
+
+/// Replaces the tag `from` with the tag `to` on every pig which has it. If
+/// any pigs already have `to`, this merges the two tags together. Responds
+/// with the number of pigs which were changed.
+#[patch("/rename?<from>&<to>")]
+async fn api_tag_rename(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    from: &str,
+    to: &str,
+) -> Result<Json<usize>, Status> {
+    if !auth_user.has_role(config, Roles::PigAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let to = to.trim();
+    if to.is_empty() {
+        return Err(Status::BadRequest);
+    }
+
+    // Remove both tags before adding the new one back so pigs which had both don't end up with it twice
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::sql_query(
+        "UPDATE pigs SET tags = array_append(array_remove(array_remove(tags, $1), $2), $2), updated_at = $3 \
+        WHERE $1 = ANY(tags)",
+    )
+    .bind::<Text, _>(from)
+    .bind::<Text, _>(to)
+    .bind::<Timestamp, _>(clock.now_naive())
+    .execute(db_connection.deref_mut());
+
+    if let Ok(changed) = sql_res {
+        pig_cache.clear();
+        if changed > 0 {
+            changes.publish_many(ChangeTarget::Pig, ChangeAction::Updated);
+        }
+        info!("User {:?} renamed tag {:?} to {:?} on {} pigs", auth_user.user.id, from, to, changed);
+        Ok(Json(changed))
+    } else {
+        error!("Unable to rename tag {:?} to {:?}: {:?}", from, to, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Removes the given tag from every pig which has it. Responds with the number
+/// of pigs which were changed.
+#[delete("/delete?<name>")]
+async fn api_tag_delete(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    pig_cache: &State<PigCache>,
+    changes: &State<ChangeFeed>,
+    name: &str,
+) -> Result<Json<usize>, Status> {
+    if !auth_user.has_role(config, Roles::PigAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res =
+        diesel::sql_query("UPDATE pigs SET tags = array_remove(tags, $1), updated_at = $2 WHERE $1 = ANY(tags)")
+            .bind::<Text, _>(name)
+            .bind::<Timestamp, _>(clock.now_naive())
+            .execute(db_connection.deref_mut());
+
+    if let Ok(changed) = sql_res {
+        pig_cache.clear();
+        if changed > 0 {
+            changes.publish_many(ChangeTarget::Pig, ChangeAction::Updated);
+        }
+        info!("User {:?} removed tag {:?} from {} pigs", auth_user.user.id, name, changed);
+        Ok(Json(changed))
+    } else {
+        error!("Unable to delete tag {:?}: {:?}", name, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Returns a JSON list of every tag in use along with the number of pigs which
+/// have it, sorted by name.
+#[get("/fetch")]
+async fn api_tag_fetch(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+) -> Result<Json<Vec<Tag>>, Status> {
+    if !auth_user.has_role(config, Roles::PigViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::sql_query(
+        "SELECT tag AS name, count(*) AS count FROM pigs, unnest(tags) AS tag GROUP BY tag ORDER BY tag",
+    )
+    .load::<Tag>(db_connection.deref_mut());
+
+    if let Ok(tags) = sql_res {
+        Ok(Json(tags))
+    } else {
+        error!("Unable to load tag counts: {:?}", sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
@@ -0,0 +1,149 @@
+use crate::config::TracingConfig;
+use crate::requestid::RequestId;
+use diesel::connection::{Instrumentation, InstrumentationEvent};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use rocket::fairing::{AdHoc, Fairing, Info, Kind};
+use rocket::route::{Handler, Outcome};
+use rocket::{Data, Request, Response, Route};
+use tracing::field::{display, Empty};
+use tracing::{info_span, Instrument, Span};
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Starts exporting spans to the configured OTLP endpoint. Returns [`None`]
+/// if tracing is disabled or couldn't be set up, in which case spans are
+/// still created but go nowhere.
+pub fn init_tracing(config: &TracingConfig) -> Option<SdkTracerProvider> {
+    let endpoint = config.endpoint.as_ref()?;
+
+    let exporter = match SpanExporter::builder().with_http().with_endpoint(endpoint).build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            warn!("Unable to export traces to {:?}, tracing is disabled: {:?}", endpoint, e);
+            return None;
+        }
+    };
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+        .build();
+
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("pigweb"));
+    if let Err(e) = tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer)) {
+        warn!("Unable to install the tracing subscriber, tracing is disabled: {:?}", e);
+        return None;
+    }
+
+    Some(provider)
+}
+
+/// Sends any spans which haven't been exported yet when the server shuts down
+pub fn tracing_shutdown_fairing(provider: SdkTracerProvider) -> AdHoc {
+    AdHoc::on_shutdown("Tracing", move |_| {
+        Box::pin(async move {
+            if let Err(e) = provider.shutdown() {
+                warn!("Unable to export the last traces: {:?}", e);
+            }
+        })
+    })
+}
+
+/// The span covering a whole request, from when Rocket receives it until the
+/// response is sent. Handler and query spans are created inside it.
+struct RequestSpan(Span);
+
+/// Returns the span for the given request, creating it if the request hasn't
+/// been traced yet
+fn request_span<'r>(request: &'r Request<'_>) -> &'r Span {
+    &request
+        .local_cache(|| {
+            let request_id = RequestId::of(request);
+            RequestSpan(info_span!(
+                "request",
+                http.method = %request.method(),
+                http.target = %request.uri(),
+                http.status_code = Empty,
+                request_id = %request_id.0,
+            ))
+        })
+        .0
+}
+
+/// Opens a span for each request and records its response status, see
+/// [`RequestSpan`]
+pub struct RequestTracing;
+
+#[rocket::async_trait]
+impl Fairing for RequestTracing {
+    fn info(&self) -> Info {
+        Info { name: "Request Tracing", kind: Kind::Request | Kind::Response }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request_span(request);
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        request_span(request).record("http.status_code", response.status().code);
+    }
+}
+
+/// Wraps each of the given routes so their handlers are traced, see
+/// [`TracedHandler`]
+pub fn traced(routes: Vec<Route>) -> Vec<Route> {
+    routes
+        .into_iter()
+        .map(|mut route| {
+            let name = route.name.as_deref().unwrap_or("unnamed").to_owned();
+            route.handler = Box::new(TracedHandler { handler: route.handler, name });
+            route
+        })
+        .collect()
+}
+
+/// Runs a route's handler inside a span named after the route, so time spent
+/// in request guards and fairings can be told apart from the handler itself
+#[derive(Clone)]
+struct TracedHandler {
+    /// The handler generated for the route
+    handler: Box<dyn Handler>,
+
+    /// The name of the route's function
+    name: String,
+}
+
+#[rocket::async_trait]
+impl Handler for TracedHandler {
+    async fn handle<'r>(&self, request: &'r Request<'_>, data: Data<'r>) -> Outcome<'r> {
+        let span = info_span!(parent: request_span(request), "handler", route = %self.name);
+        self.handler.handle(request, data).instrument(span).await
+    }
+}
+
+/// Opens a span for each query made through a connection, inside whichever
+/// span is current when it's made
+#[derive(Default)]
+pub struct QueryTracing {
+    /// The span of the query currently running, if any
+    span: Option<Span>,
+}
+
+impl Instrumentation for QueryTracing {
+    fn on_connection_event(&mut self, event: InstrumentationEvent<'_>) {
+        match event {
+            InstrumentationEvent::StartQuery { query, .. } => {
+                self.span = Some(info_span!("query", db.system = "postgresql", db.statement = %query, error = Empty));
+            }
+            InstrumentationEvent::FinishQuery { error, .. } => {
+                if let (Some(span), Some(error)) = (self.span.take(), error) {
+                    span.record("error", display(error));
+                }
+            }
+            _ => {}
+        }
+    }
+}
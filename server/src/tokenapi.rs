@@ -0,0 +1,229 @@
+use crate::auth::AuthenticatedUser;
+use crate::clock::Clock;
+use crate::config::Config;
+use chrono::NaiveDateTime;
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult, RunQueryDsl, SelectableHelper,
+};
+use pigweb_common::tokens::{ApiToken, ApiTokenRequest, ApiTokenSecret};
+use pigweb_common::users::{Roles, User};
+use pigweb_common::{parse_uuid, schema, TOKEN_API_ROOT};
+use rocket::http::Status;
+use rocket::response::status::Created;
+use rocket::serde::json::Json;
+use rocket::{Route, State};
+use sha2::{Digest, Sha256};
+use std::ops::DerefMut;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Returns a list of all API token routes
+pub fn get_token_api_routes() -> Vec<Route> {
+    routes![api_token_list, api_token_create, api_token_revoke, api_token_rotate]
+}
+
+/// Generates a new random secret for a token, along with its hash
+fn new_secret() -> (String, String) {
+    let secret = format!("pigweb_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let hash = hash_secret(secret.as_str());
+    (secret, hash)
+}
+
+/// Hashes the secret the way it's saved in the DB
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret))
+}
+
+/// Finds the token with the given secret and the user it belongs to, and
+/// records that it was used. Returns [`None`] if there's no token with the
+/// secret, it expired, or its owner no longer exists.
+pub fn authenticate_token(
+    db_connection: &mut PgConnection,
+    clock: &Clock,
+    secret: &str,
+) -> QueryResult<Option<(User, ApiToken)>> {
+    let token = schema::api_tokens::table
+        .filter(schema::api_tokens::hash.eq(hash_secret(secret)))
+        .select(ApiToken::as_select())
+        .first(db_connection)
+        .optional()?;
+
+    let now = clock.now_naive();
+    let Some(token) = token.filter(|token| !token.is_expired(now)) else {
+        return Ok(None);
+    };
+
+    // The system user isn't in the DB, it's only used when OIDC isn't configured
+    let system_user = User::get_system_user();
+    let user = match token.owner == system_user.id {
+        true => Some(system_user),
+        false => schema::users::table
+            .filter(schema::users::id.eq(token.owner))
+            .select(User::as_select())
+            .first(db_connection)
+            .optional()?,
+    };
+
+    let Some(user) = user else {
+        return Ok(None);
+    };
+
+    diesel::update(schema::api_tokens::table)
+        .filter(schema::api_tokens::id.eq(token.id))
+        .set(schema::api_tokens::last_used.eq(now))
+        .execute(db_connection)?;
+
+    Ok(Some((user, token)))
+}
+
+/// Returns a JSON list of the user's API tokens, newest first. If `all` is
+/// true, every user's tokens are listed instead, which requires
+/// [`Roles::UserAdmin`]. Tokens can't be managed with a token.
+#[get("/list?<all>")]
+async fn api_token_list(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    all: Option<bool>,
+) -> Result<Json<Vec<ApiToken>>, Status> {
+    let all = all.unwrap_or(false);
+    if auth_user.token.is_some() || (all && !auth_user.has_role(config, Roles::UserAdmin)) {
+        return Err(Status::Forbidden);
+    }
+
+    let mut sql_query = schema::api_tokens::table.order(schema::api_tokens::created.desc()).into_boxed();
+    if !all {
+        sql_query = sql_query.filter(schema::api_tokens::owner.eq(auth_user.user.id));
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    match sql_query.select(ApiToken::as_select()).load(db_connection.deref_mut()) {
+        Ok(tokens) => Ok(Json(tokens)),
+        Err(err) => {
+            error!("Unable to load API tokens: {:?}", err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Creates a new API token for the user, responding with it and its secret.
+/// The secret can't be retrieved again later. Returns HTTP status 403 if the
+/// token asks for a role the user doesn't have, or 422 if it has no name, no
+/// roles, or has already expired.
+#[post("/create", data = "<request>")]
+async fn api_token_create(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    request: Json<ApiTokenRequest>,
+) -> Result<Created<Json<ApiTokenSecret>>, Status> {
+    if auth_user.token.is_some() {
+        return Err(Status::Forbidden);
+    }
+
+    if request.name.trim().is_empty()
+        || request.roles.is_empty()
+        || request.expires.is_some_and(|expires| expires <= clock.now_naive())
+    {
+        return Err(Status::UnprocessableEntity);
+    }
+
+    // Tokens can't do anything their owner can't
+    if !request.roles.is_subset(&auth_user.get_roles(config)) {
+        return Err(Status::Forbidden);
+    }
+
+    let (secret, hash) = new_secret();
+    let token = ApiToken::new(&request, &auth_user.user.id, hash, clock.now_naive());
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::insert_into(schema::api_tokens::table).values(&token).execute(db_connection.deref_mut());
+
+    if sql_res.is_ok() {
+        info!("User {:?} created API token {:?} with roles {:?}", auth_user.user.id, token.id, token.roles);
+        Ok(Created::new(TOKEN_API_ROOT.to_owned() + "list").body(Json(ApiTokenSecret { token, secret })))
+    } else {
+        error!("Unable to save new API token {:?}: {:?}", token, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Deletes the API token with the given id so it can't be used anymore,
+/// returning HTTP status 204 if successful. Users can only revoke their own
+/// tokens unless they have [`Roles::UserAdmin`].
+#[delete("/revoke?<id>")]
+async fn api_token_revoke(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    id: &str,
+) -> Result<Status, Status> {
+    if auth_user.token.is_some() {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+
+    let mut sql_query = diesel::delete(schema::api_tokens::table).filter(schema::api_tokens::id.eq(uuid)).into_boxed();
+    if !auth_user.has_role(config, Roles::UserAdmin) {
+        sql_query = sql_query.filter(schema::api_tokens::owner.eq(auth_user.user.id));
+    }
+
+    let mut db_connection = db_connection.lock().unwrap();
+    match sql_query.execute(db_connection.deref_mut()) {
+        Ok(0) => Err(Status::NotFound),
+        Ok(_) => {
+            info!("User {:?} revoked API token {:?}", auth_user.user.id, uuid);
+            Ok(Status::NoContent)
+        }
+        Err(err) => {
+            error!("Unable to revoke API token {:?}: {:?}", uuid, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Replaces the secret of the user's API token with the given id, responding
+/// with the token and its new secret. The old secret stops working right away.
+/// The token keeps its name, roles, and expiry, but counts as newly created
+/// and unused.
+#[post("/rotate?<id>")]
+async fn api_token_rotate(
+    auth_user: AuthenticatedUser,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    id: &str,
+) -> Result<Json<ApiTokenSecret>, Status> {
+    if auth_user.token.is_some() {
+        return Err(Status::Forbidden);
+    }
+
+    let uuid = parse_uuid(id)?;
+    let (secret, hash) = new_secret();
+
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::update(schema::api_tokens::table)
+        .filter(schema::api_tokens::id.eq(uuid))
+        .filter(schema::api_tokens::owner.eq(auth_user.user.id))
+        .set((
+            schema::api_tokens::hash.eq(hash),
+            schema::api_tokens::created.eq(clock.now_naive()),
+            schema::api_tokens::last_used.eq(None::<NaiveDateTime>),
+        ))
+        .returning(ApiToken::as_returning())
+        .get_result(db_connection.deref_mut())
+        .optional();
+
+    match sql_res {
+        Ok(Some(token)) => {
+            info!("User {:?} rotated API token {:?}", auth_user.user.id, uuid);
+            Ok(Json(ApiTokenSecret { token, secret }))
+        }
+        Ok(None) => Err(Status::NotFound),
+        Err(err) => {
+            error!("Unable to rotate API token {:?}: {:?}", uuid, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
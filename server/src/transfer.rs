@@ -0,0 +1,191 @@
+use diesel::sql_types::Text;
+use diesel::{Connection, PgConnection, QueryableByName, RunQueryDsl};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Every table in the database, ordered so rows which reference another table
+/// come after the rows they reference.
+const TABLES: [&str; 10] = [
+    "users",
+    "api_tokens",
+    "notifications",
+    "pigs",
+    "pig_changes",
+    "pig_snapshots",
+    "role_imports",
+    "saved_filters",
+    "bulk_imports",
+    "bulk_job_logs",
+];
+
+/// The only format exports can be written in, one JSON object per line
+const FORMAT_JSONL: &str = "jsonl";
+
+/// An admin command given on the command line instead of starting the server
+#[derive(Debug)]
+pub enum Command {
+    /// Writes every table to its own file in the given directory
+    Export(PathBuf),
+
+    /// Reads every table from its own file in the given directory
+    Import(PathBuf),
+}
+
+impl Command {
+    /// Parses the command from the args the server was started with. Returns
+    /// [`None`] if there isn't one, or an error describing what's wrong with it.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let command = match args.next() {
+            Some(command) => command,
+            None => return Ok(None),
+        };
+
+        let dir_flag = match command.as_str() {
+            "export" => "--out",
+            "import" => "--in",
+            _ => return Err(format!("Unknown command {:?}, expected \"export\" or \"import\".", command)),
+        };
+
+        let mut format = None;
+        let mut dir = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => format = args.next(),
+                flag if flag == dir_flag => dir = args.next().map(PathBuf::from),
+                _ => return Err(format!("Unknown argument {:?} for {}.", arg, command)),
+            }
+        }
+
+        // jsonl is the default so the format doesn't have to be given, but we
+        // still want to reject anything else in case more are added later
+        if let Some(format) = format.filter(|format| format != FORMAT_JSONL) {
+            return Err(format!("Unsupported format {:?}, only \"{}\" is supported.", format, FORMAT_JSONL));
+        }
+
+        let dir = dir.ok_or_else(|| format!("Missing {} <dir> for {}.", dir_flag, command))?;
+        Ok(Some(if command == "export" { Self::Export(dir) } else { Self::Import(dir) }))
+    }
+
+    /// Runs the command against the given database
+    pub fn run(&self, db_connection: &mut PgConnection) -> Result<(), String> {
+        match self {
+            Self::Export(dir) => export(db_connection, dir),
+            Self::Import(dir) => import(db_connection, dir),
+        }
+    }
+}
+
+/// A single row of any table, serialized by Postgres
+#[derive(QueryableByName)]
+struct JsonRow {
+    #[diesel(sql_type = Text)]
+    row: String,
+}
+
+/// Describes why an export or import failed. Transactions need an error which
+/// can be made from a Diesel error, so messages are wrapped in this.
+struct TransferError(String);
+
+impl From<diesel::result::Error> for TransferError {
+    fn from(err: diesel::result::Error) -> Self {
+        Self(format!("{:?}", err))
+    }
+}
+
+/// Writes each table to `<dir>/<table>.jsonl`, creating the directory if needed
+fn export(db_connection: &mut PgConnection, dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Unable to create directory {:?}: {:?}", dir, e))?;
+
+    // Read everything in one transaction so the tables are consistent with each other
+    db_connection
+        .transaction(|db_connection| {
+            for table in TABLES {
+                let rows = diesel::sql_query(format!("SELECT row_to_json(t)::text AS row FROM {} t", table))
+                    .load::<JsonRow>(db_connection)
+                    .map_err(|e| TransferError(format!("Unable to read table {}: {:?}", table, e)))?;
+
+                let path = table_path(dir, table);
+                let file = File::create(&path)
+                    .map_err(|e| TransferError(format!("Unable to create file {:?}: {:?}", path, e)))?;
+                let mut writer = BufWriter::new(file);
+                for row in &rows {
+                    writeln!(writer, "{}", row.row)
+                        .map_err(|e| TransferError(format!("Unable to write to {:?}: {:?}", path, e)))?;
+                }
+                writer.flush().map_err(|e| TransferError(format!("Unable to write to {:?}: {:?}", path, e)))?;
+
+                println!("Exported {} rows from {}", rows.len(), table);
+            }
+
+            Ok(())
+        })
+        .map_err(|err: TransferError| err.0)
+}
+
+/// Reads each table from `<dir>/<table>.jsonl`. Missing files are skipped and
+/// rows which already exist are left alone. If anything fails, nothing is
+/// imported.
+fn import(db_connection: &mut PgConnection, dir: &Path) -> Result<(), String> {
+    db_connection
+        .transaction(|db_connection| {
+            for table in TABLES {
+                let path = table_path(dir, table);
+                if !path.exists() {
+                    println!("Skipping {}, {:?} doesn't exist", table, path);
+                    continue;
+                }
+
+                let file =
+                    File::open(&path).map_err(|e| TransferError(format!("Unable to open file {:?}: {:?}", path, e)))?;
+                let sql = format!(
+                    "INSERT INTO {0} SELECT * FROM json_populate_record(NULL::{0}, $1::json) ON CONFLICT DO NOTHING",
+                    table
+                );
+
+                let mut imported = 0;
+                for (i, line) in BufReader::new(file).lines().enumerate() {
+                    let line = line.map_err(|e| TransferError(format!("Unable to read {:?}: {:?}", path, e)))?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    imported += diesel::sql_query(&sql).bind::<Text, _>(&line).execute(db_connection).map_err(|e| {
+                        TransferError(format!("Unable to import line {} of {:?}: {:?}", i + 1, path, e))
+                    })?;
+                }
+
+                println!("Imported {} rows into {}", imported, table);
+            }
+
+            Ok(())
+        })
+        .map_err(|err: TransferError| err.0)
+}
+
+/// Gets the file the given table is exported to
+fn table_path(dir: &Path, table: &str) -> PathBuf {
+    dir.join(format!("{}.{}", table, FORMAT_JSONL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TABLES;
+    use std::collections::BTreeSet;
+
+    /// A table missing from [`TABLES`] would silently be left out of exports
+    #[test]
+    fn exports_every_table() {
+        let schema = include_str!("../../common/src/schema.rs");
+        let mut lines = schema.lines();
+        let mut tables = BTreeSet::new();
+        while let Some(line) = lines.next() {
+            if line.trim() == "diesel::table! {" {
+                let name = lines.next().and_then(|line| line.split_whitespace().next());
+                tables.extend(name);
+            }
+        }
+
+        assert_eq!(tables, BTreeSet::from(TABLES));
+    }
+}
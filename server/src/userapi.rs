@@ -1,12 +1,20 @@
 use crate::auth::AuthenticatedUser;
+use crate::clock::Clock;
 use crate::config::Config;
-use chrono::Utc;
-use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl, SelectableHelper};
-use pigweb_common::users::{Roles, User, UserFetchResponse, UserQuery};
+use crate::notificationapi::send_notifications;
+use crate::search::{max_limit, select_fields, SearchLimiter, UserCache, UsernameCache};
+use chrono::NaiveDateTime;
+use diesel::{
+    Connection, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+    SelectableHelper,
+};
+use pigweb_common::notifications::{Notification, NotificationKind};
+use pigweb_common::users::{RoleConfig, RoleImport, Roles, User, UserProvision, UserQuery, WithCreator};
 use pigweb_common::{parse_uuid, schema};
-use rocket::http::Status;
-use rocket::serde::json::Json;
-use rocket::{Route, State};
+use rocket::data::Capped;
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::{json, serde_json, Json, Value};
+use rocket::{Build, Rocket, Route, State};
 use std::collections::{BTreeMap, BTreeSet};
 use std::ops::DerefMut;
 use std::sync::Mutex;
@@ -14,23 +22,37 @@ use uuid::Uuid;
 
 /// Returns a list of all user api routes
 pub fn get_user_api_routes() -> Vec<Route> {
-    routes![api_user_fetch, api_user_roles, api_user_expire]
+    routes![
+        api_user_fetch,
+        api_user_names,
+        api_user_roles,
+        api_user_roles_export,
+        api_user_roles_import,
+        api_user_provision,
+        api_user_expire
+    ]
 }
 
-/// Returns a list of users which match the given query. If the requester has
+/// Returns a list of users which match the given query, shaped like a
+/// [`pigweb_common::users::UserFetchResponse`]. If the requester has
 /// [`Roles::UserViewer`], they will be sent the full data for each user.
-/// Otherwise, only a mapping of ids to usernames will be returned.
+/// Otherwise, or if only the ids and usernames were asked for, only a mapping
+/// of ids to usernames will be returned.
 #[get("/fetch?<query..>")]
 async fn api_user_fetch(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    search_limiter: &State<SearchLimiter>,
+    user_cache: &State<UserCache>,
     query: UserQuery,
-) -> Result<Json<UserFetchResponse>, Status> {
+) -> Result<Json<Value>, Status> {
+    let _permit = search_limiter.acquire(&auth_user.user.id)?;
+    let max_limit = max_limit(config, &auth_user, query.id.as_ref());
+    let query = query.with_max_limit(max_limit);
+
     // Fetch the users from the DB
-    let sql_query = query.to_db_select();
-    let mut db_connection = db_connection.lock().unwrap();
-    let sql_res = sql_query.select(User::as_select()).load(db_connection.deref_mut());
+    let sql_res = fetch_users(db_connection, user_cache, &query);
 
     if let Ok(users) = sql_res {
         let mut ids_to_names: BTreeMap<Uuid, String> = BTreeMap::new();
@@ -40,11 +62,16 @@ async fn api_user_fetch(
             ids_to_names.insert(user.id.to_owned(), user.username.to_owned());
         }
 
-        let mut res = UserFetchResponse::default().with_usernames(ids_to_names);
+        let mut res = json!({ "usernames": ids_to_names, "users": null });
+
+        // The mapping already has every id and username, so only send the users themselves if more was asked for
+        let fields = query.field_list();
+        let more_fields =
+            fields.as_ref().is_none_or(|fields| fields.iter().any(|field| !["id", "username"].contains(field)));
 
         // add the actual users if requester has access
-        if auth_user.has_role(config, Roles::UserViewer) {
-            res = res.with_users(users);
+        if more_fields && auth_user.has_role(config, Roles::UserViewer) {
+            res["users"] = select_fields(&users, fields)?;
         }
 
         Ok(Json(res))
@@ -54,6 +81,57 @@ async fn api_user_fetch(
     }
 }
 
+/// Returns a mapping of the given user ids to their usernames, for showing who
+/// created things. Only usernames are sent, so anyone signed in can use this.
+/// Ids which don't belong to a user are left out.
+#[post("/names", data = "<ids>")]
+async fn api_user_names(
+    auth_user: AuthenticatedUser,
+    db_connection: &State<Mutex<PgConnection>>,
+    search_limiter: &State<SearchLimiter>,
+    username_cache: &State<UsernameCache>,
+    ids: Json<BTreeSet<Uuid>>,
+) -> Result<Json<BTreeMap<Uuid, String>>, Status> {
+    let mut res: BTreeMap<Uuid, String> = BTreeMap::new();
+    let mut missing: Vec<Uuid> = Vec::new();
+
+    for id in ids.iter() {
+        match username_cache.get(id.to_string().as_str()) {
+            Some(username) => {
+                res.insert(id.to_owned(), username);
+            }
+            None => missing.push(id.to_owned()),
+        }
+    }
+
+    // Everything was cached, no need to bother the DB
+    if missing.is_empty() {
+        return Ok(Json(res));
+    }
+
+    let _permit = search_limiter.acquire(&auth_user.user.id)?;
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res: QueryResult<Vec<(Uuid, String)>> = schema::users::table
+        .filter(schema::users::id.eq_any(&missing))
+        .select((schema::users::id, schema::users::username))
+        .load(db_connection.deref_mut());
+
+    match sql_res {
+        Ok(usernames) => {
+            for (id, username) in usernames {
+                username_cache.insert(id.to_string(), &username);
+                res.insert(id, username);
+            }
+
+            Ok(Json(res))
+        }
+        Err(err) => {
+            error!("Unable to load usernames for {:?}: {:?}", missing, err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
 /// Returns a mapping of user ids to the roles each [`User`] has been provided
 /// by their groups.
 #[get("/roles?<query..>")]
@@ -61,6 +139,7 @@ async fn api_user_roles(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    user_cache: &State<UserCache>,
     query: UserQuery,
 ) -> Result<Json<BTreeMap<Uuid, BTreeSet<Roles>>>, Status> {
     if !auth_user.has_role(config, Roles::UserViewer) {
@@ -68,9 +147,7 @@ async fn api_user_roles(
     }
 
     // Fetch the users from the DB
-    let sql_query = query.to_db_select();
-    let mut db_connection = db_connection.lock().unwrap();
-    let sql_res = sql_query.select(User::as_select()).load(db_connection.deref_mut());
+    let sql_res = fetch_users(db_connection, user_cache, &query);
 
     if let Ok(users) = sql_res {
         let mut res: BTreeMap<Uuid, BTreeSet<Roles>> = BTreeMap::new();
@@ -88,6 +165,229 @@ async fn api_user_roles(
     }
 }
 
+/// Returns the role config currently in effect as JSON, or as TOML if the
+/// format is `toml`. This can be edited, reviewed, and sent back to
+/// [`api_user_roles_import`].
+#[get("/roles/export?<format>")]
+async fn api_user_roles_export(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    format: Option<&str>,
+) -> Result<(ContentType, String), Status> {
+    if !auth_user.has_role(config, Roles::UserViewer) {
+        return Err(Status::Forbidden);
+    }
+
+    format_roles(&config.role_config(), format)
+}
+
+/// Replaces the role config currently in effect with the one in the request
+/// body, formatted as JSON or as TOML if the format is `toml`. Responds with
+/// the newly applied config in the same format. Returns HTTP status 409 if the
+/// requester would lose [`Roles::UserAdmin`] by applying it, 413 if the config
+/// is over the `string` limit, or 422 if it has no groups while OIDC is
+/// configured.
+#[post("/roles/import?<format>", data = "<body>")]
+async fn api_user_roles_import(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    clock: &State<Clock>,
+    format: Option<&str>,
+    body: Capped<String>,
+) -> Result<(ContentType, String), Status> {
+    if !auth_user.has_role(config, Roles::UserAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    // Rocket only rejects bodies over the limit with 400, so say why
+    if !body.is_complete() {
+        return Err(Status::PayloadTooLarge);
+    }
+
+    // Parse the config, unknown roles or ids are rejected here
+    let roles_res: Result<RoleConfig, String> = match format {
+        Some("toml") => toml::from_str(body.as_str()).map_err(|err| err.to_string()),
+        _ => serde_json::from_str(body.as_str()).map_err(|err| err.to_string()),
+    };
+
+    let roles = roles_res.map_err(|err| {
+        warn!("Unable to parse imported role config: {:?}", err);
+        Status::BadRequest
+    })?;
+
+    // Unlike the config file, an imported config without groups doesn't mean everyone gets every role
+    if config.oidc.is_some() && roles.groups.is_empty() {
+        warn!("User {:?} tried to import a role config without any groups", auth_user.user.id);
+        return Err(Status::UnprocessableEntity);
+    }
+
+    // Don't let admins lock themselves out
+    if config.oidc.is_some() && !get_roles_from(&roles, &auth_user.user).contains(&Roles::UserAdmin) {
+        return Err(Status::Conflict);
+    }
+
+    // Keep a record of the import so it's still in effect after a restart
+    let import = RoleImport::new(&auth_user.user.id, &roles, clock.now_naive());
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = diesel::insert_into(schema::role_imports::table).values(&import).execute(db_connection.deref_mut());
+
+    if sql_res.is_ok() {
+        info!("User {:?} imported a new role config: {:?}", auth_user.user.id, roles);
+        *config.imported_roles.write().unwrap() = Some(roles);
+        format_roles(&config.role_config(), format)
+    } else {
+        error!("Unable to save role import {:?}: {:?}", import, sql_res.unwrap_err());
+        Err(Status::InternalServerError)
+    }
+}
+
+/// Creates a [`User`] for each line of the CSV in the request body, so roles
+/// can be granted to people and things transferred to them before they first
+/// sign in. Each line is `username,subject,issuer,groups` with the groups
+/// separated by `;`, and a header line starting with `username` is skipped.
+/// Users which already exist with the same subject and issuer are left as
+/// they are, and will be updated from their ID token when they sign in. If
+/// `dry_run` is true, nothing is created. Responds with a [`UserProvision`],
+/// or HTTP status 413 if the CSV is over the `string` limit.
+#[post("/provision?<dry_run>", data = "<body>")]
+async fn api_user_provision(
+    auth_user: AuthenticatedUser,
+    config: &State<Config>,
+    db_connection: &State<Mutex<PgConnection>>,
+    user_cache: &State<UserCache>,
+    clock: &State<Clock>,
+    dry_run: Option<bool>,
+    body: Capped<String>,
+) -> Result<Json<UserProvision>, Status> {
+    if !auth_user.has_role(config, Roles::UserAdmin) {
+        return Err(Status::Forbidden);
+    }
+
+    if !body.is_complete() {
+        return Err(Status::PayloadTooLarge);
+    }
+
+    let mut res = UserProvision::default();
+    let mut users: Vec<User> = Vec::new();
+    let now = clock.now_naive();
+
+    for (i, line) in body.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_provision_line(line, i == 0, now) {
+            Ok(Some(user)) => {
+                if users.iter().any(|u| u.sso_subject == user.sso_subject && u.sso_issuer == user.sso_issuer) {
+                    res.invalid
+                        .push(format!("Line {}: the subject and issuer are already used on another line", i + 1));
+                } else {
+                    users.push(user);
+                }
+            }
+            Ok(None) => {}
+            Err(err) => res.invalid.push(format!("Line {}: {}", i + 1, err)),
+        }
+    }
+
+    let dry_run = dry_run.unwrap_or(false);
+    let sql_res = db_connection.lock().unwrap().transaction(|db_connection| {
+        for user in users {
+            let existing = schema::users::table
+                .filter(schema::users::sso_subject.eq(&user.sso_subject))
+                .filter(schema::users::sso_issuer.eq(&user.sso_issuer))
+                .select(User::as_select())
+                .first(db_connection)
+                .optional()?;
+
+            match existing {
+                Some(existing) => res.existing.push(existing),
+                None if dry_run => res.created.push(user),
+                None => {
+                    res.created.push(diesel::insert_into(schema::users::table).values(&user).get_result(db_connection)?)
+                }
+            }
+        }
+
+        QueryResult::Ok(())
+    });
+
+    match sql_res {
+        Ok(()) => {
+            if !dry_run && !res.created.is_empty() {
+                user_cache.clear();
+                info!("User {:?} pre-created {} users", auth_user.user.id, res.created.len());
+            }
+
+            Ok(Json(res))
+        }
+        Err(err) => {
+            error!("Unable to pre-create users: {:?}", err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// Reads a [`User`] created at the given time from a line of the CSV sent to
+/// [`api_user_provision`]. Returns [`None`] if it's the `first` line and
+/// that's a header, so a user actually called "username" further down isn't
+/// skipped.
+fn parse_provision_line(line: &str, first: bool, now: NaiveDateTime) -> Result<Option<User>, String> {
+    let fields = parse_csv_line(line)?;
+    if first && fields.first().is_some_and(|field| field.eq_ignore_ascii_case("username")) {
+        return Ok(None);
+    }
+
+    if fields.len() < 3 || fields.len() > 4 {
+        return Err(format!("expected 3 or 4 fields but found {}", fields.len()));
+    }
+
+    let (username, subject, issuer) = (fields[0].trim(), fields[1].trim(), fields[2].trim());
+    if username.is_empty() || subject.is_empty() || issuer.is_empty() {
+        return Err("the username, subject, and issuer are required".to_owned());
+    }
+
+    let groups = fields
+        .get(3)
+        .map(|groups| groups.split(';').map(str::trim).filter(|g| !g.is_empty()).map(str::to_owned).collect())
+        .unwrap_or_default();
+
+    Ok(Some(User::new(username.to_owned(), groups, subject.to_owned(), issuer.to_owned(), None, now)))
+}
+
+/// Splits a line of CSV into its fields. Fields may be quoted to contain
+/// commas, with quotes inside them doubled, but can't span multiple lines.
+fn parse_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut res = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => res.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("a quoted field isn't closed".to_owned());
+    }
+
+    res.push(field);
+    Ok(res)
+}
+
 /// Invalidates the session of the [`User`] with the given id. The target will
 /// only notice the next time they attempt to make a request requiring them to
 /// be authenticated, at which point their session cookies will be cleared.
@@ -96,6 +396,8 @@ async fn api_user_expire(
     auth_user: AuthenticatedUser,
     config: &State<Config>,
     db_connection: &State<Mutex<PgConnection>>,
+    user_cache: &State<UserCache>,
+    clock: &State<Clock>,
     id: &str,
 ) -> Result<Json<User>, Status> {
     if !auth_user.has_role(config, Roles::UserAdmin) {
@@ -105,7 +407,7 @@ async fn api_user_expire(
     let mut db_connection = db_connection.lock().unwrap();
 
     let uuid = parse_uuid(id)?;
-    let now = Utc::now().naive_utc();
+    let now = clock.now_naive();
 
     // Tell the DB to change the expiration for the user with the given id to the current time
     let sql_res = diesel::update(schema::users::table)
@@ -114,6 +416,15 @@ async fn api_user_expire(
         .get_result(db_connection.deref_mut());
 
     if sql_res.is_ok() {
+        user_cache.clear();
+
+        // they'll see this once they sign back in
+        if uuid != auth_user.user.id {
+            let message = format!("{} ended your session, so you had to sign in again", auth_user.user.username);
+            let notification = Notification::new(&uuid, NotificationKind::SessionExpired, message, None, now);
+            send_notifications(db_connection.deref_mut(), &vec![notification]);
+        }
+
         Ok(Json(sql_res.unwrap()))
     } else {
         error!("Unable to invalidate session for user {:?}: {:?}", uuid, sql_res.unwrap_err());
@@ -121,6 +432,103 @@ async fn api_user_expire(
     }
 }
 
+/// Converts the role config to JSON, or to TOML if the format is `toml`
+fn format_roles(roles: &RoleConfig, format: Option<&str>) -> Result<(ContentType, String), Status> {
+    let res = match format {
+        Some("toml") => toml::to_string_pretty(roles).map(|s| (ContentType::Plain, s)).map_err(|err| err.to_string()),
+        _ => serde_json::to_string_pretty(roles).map(|s| (ContentType::JSON, s)).map_err(|err| err.to_string()),
+    };
+
+    res.map_err(|err| {
+        error!("Unable to format role config {:?}: {:?}", roles, err);
+        Status::InternalServerError
+    })
+}
+
+/// Applies the most recently imported [`RoleConfig`] to the managed
+/// [`Config`], if there is one. This must be attached after the config.
+pub async fn load_imported_roles(rocket: Rocket<Build>) -> Rocket<Build> {
+    if let (Some(config), Some(db_connection)) = (rocket.state::<Config>(), rocket.state::<Mutex<PgConnection>>()) {
+        let mut db_connection = db_connection.lock().unwrap();
+        let sql_res = schema::role_imports::table
+            .order(schema::role_imports::imported.desc())
+            .select(RoleImport::as_select())
+            .first(db_connection.deref_mut())
+            .optional();
+
+        match sql_res {
+            Ok(Some(import)) => {
+                info!("Using the role config imported by {:?} at {:?}", import.importer, import.imported);
+                *config.imported_roles.write().unwrap() = Some(import.roles);
+            }
+            Ok(None) => {}
+            Err(err) => error!("Unable to load imported role config: {:?}", err),
+        }
+    }
+
+    rocket
+}
+
+/// Loads the users matching the given query, using the cached results if the
+/// same query was made recently.
+fn fetch_users(
+    db_connection: &State<Mutex<PgConnection>>,
+    user_cache: &State<UserCache>,
+    query: &UserQuery,
+) -> QueryResult<Vec<User>> {
+    let cache_key = query.to_yuri();
+    if let Some(users) = user_cache.get(cache_key.as_str()) {
+        return Ok(users);
+    }
+
+    let sql_query = query.to_db_select();
+    let mut db_connection = db_connection.lock().unwrap();
+    let sql_res = sql_query.select(User::as_select()).load(db_connection.deref_mut());
+
+    if let Ok(users) = sql_res.as_ref() {
+        user_cache.insert(cache_key, users);
+    }
+
+    sql_res
+}
+
+/// Wraps each item for a fetch response. If the query asked for them with
+/// `include=creator`, the username of each item's creator is filled in too so
+/// the client doesn't have to look each one up separately. They're all looked
+/// up at once, reading the creator's id from each item with the given function.
+/// Returns HTTP status 500 if the usernames can't be loaded.
+pub fn with_creators<T>(
+    db_connection: &Mutex<PgConnection>,
+    items: Vec<T>,
+    include_creator: bool,
+    creator: impl Fn(&T) -> Uuid,
+) -> Result<Vec<WithCreator<T>>, Status> {
+    let mut items: Vec<WithCreator<T>> = items.into_iter().map(WithCreator::new).collect();
+    if !include_creator {
+        return Ok(items);
+    }
+
+    let ids: BTreeSet<Uuid> = items.iter().map(|with| creator(&with.item)).collect();
+    let sql_res = schema::users::table
+        .filter(schema::users::id.eq_any(ids.iter().collect::<Vec<&Uuid>>()))
+        .select((schema::users::id, schema::users::username))
+        .load::<(Uuid, String)>(db_connection.lock().unwrap().deref_mut());
+
+    let usernames: BTreeMap<Uuid, String> = match sql_res {
+        Ok(usernames) => usernames.into_iter().collect(),
+        Err(err) => {
+            error!("Unable to load creator usernames for {:?}: {:?}", ids, err);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    for with in items.iter_mut() {
+        with.creator_username = usernames.get(&creator(&with.item)).cloned();
+    }
+
+    Ok(items)
+}
+
 /// Whether the user is in a group which provides the role.
 ///
 /// ***Always returns true if OIDC or groups are not configured.***
@@ -136,22 +544,43 @@ pub fn user_has_role(config: &Config, user: &User, role: Roles) -> bool {
 /// Gets all roles the user has been provided by their groups.
 ///
 /// ***Returns a set of all roles if the OIDC or groups are not configured.***
+/// Only the groups in the config file count for this, an imported role config
+/// always has to grant roles itself.
 pub fn get_user_roles(config: &Config, user: &User) -> BTreeSet<Roles> {
+    if config.oidc.is_none() {
+        return Roles::values().collect::<BTreeSet<Roles>>();
+    }
+
+    if let Some(imported) = config.imported_roles.read().unwrap().as_ref() {
+        return get_roles_from(imported, user);
+    }
+
     // If groups aren't configured, all users have all access
-    if config.oidc.is_none() || config.groups.is_empty() {
+    if config.groups.is_empty() {
         return Roles::values().collect::<BTreeSet<Roles>>();
     }
 
+    get_roles_from(&config.role_config(), user)
+}
+
+/// Gets all roles the given [`RoleConfig`] provides the user, both from their
+/// groups and any granted to them directly.
+fn get_roles_from(role_config: &RoleConfig, user: &User) -> BTreeSet<Roles> {
     let mut res = BTreeSet::new();
 
     // for each group the user has
     for group in &user.groups {
         // try to find the roles in that group
-        if let Some(roles) = config.groups.get(group) {
+        if let Some(roles) = role_config.groups.get(group) {
             // add the group's roles to the response
             res.append(&mut roles.clone())
         }
     }
 
+    // add any roles the user was given specifically
+    if let Some(roles) = role_config.users.get(&user.id) {
+        res.append(&mut roles.clone())
+    }
+
     res
 }
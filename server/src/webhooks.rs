@@ -0,0 +1,166 @@
+use crate::config::{Config, WebhookConfig, WebhookEvent};
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use pigweb_common::bulk::BulkImport;
+use rocket::serde::json::serde_json;
+use rocket::serde::Serialize;
+use sha2::Sha256;
+use std::sync::LazyLock;
+use std::time::Duration;
+use ureq::{Agent, AgentBuilder};
+use uuid::Uuid;
+
+/// The header each request's signature is sent in, if the webhook has a
+/// [`WebhookConfig::secret`]
+const SIGNATURE_HEADER: &str = "X-PigWeb-Signature";
+
+/// The header with the name of the event, so receivers can route requests
+/// without reading the body
+const EVENT_HEADER: &str = "X-PigWeb-Event";
+
+/// How long to wait to connect to a webhook before giving up
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for a webhook to respond once connected. Each request ties
+/// up a blocking thread until it's answered, so one which hangs can't be
+/// waited on forever.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends every webhook, so connections to the same receiver can be reused
+static AGENT: LazyLock<Agent> =
+    LazyLock::new(|| AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).timeout_read(READ_TIMEOUT).build());
+
+/// What's sent to webhooks when a [`BulkImport`] is finished
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportFinished {
+    /// Always [`WebhookEvent::BulkImportFinished`], so receivers can tell
+    /// events apart
+    pub event: WebhookEvent,
+
+    /// The id of the import
+    pub import: Uuid,
+
+    /// The name of the import
+    pub name: String,
+
+    /// The id of the user who started the import
+    pub creator: Uuid,
+
+    /// How many names were accepted as new pigs
+    pub accepted: usize,
+
+    /// How many names were rejected
+    pub rejected: usize,
+
+    /// When the import was finished
+    pub finished: Option<NaiveDateTime>,
+}
+
+impl From<&BulkImport> for ImportFinished {
+    fn from(import: &BulkImport) -> Self {
+        Self {
+            event: WebhookEvent::BulkImportFinished,
+            import: import.id,
+            name: import.name.to_owned(),
+            creator: import.creator,
+            accepted: import.accepted.len(),
+            rejected: import.rejected.len(),
+            finished: import.finished,
+        }
+    }
+}
+
+/// What's sent to webhooks when pigs are created or deleted. Pigs changed by
+/// the same request are sent together.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PigsChanged {
+    /// Either [`WebhookEvent::PigCreated`] or [`WebhookEvent::PigDeleted`]
+    pub event: WebhookEvent,
+
+    /// The id of the user who created or deleted the pigs
+    pub actor: Uuid,
+
+    /// Each pig which was created or deleted
+    pub pigs: Vec<WebhookPig>,
+}
+
+/// A pig as it's sent to webhooks
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct WebhookPig {
+    /// The id of the pig
+    pub id: Uuid,
+
+    /// The name of the pig
+    pub name: String,
+}
+
+/// Lets the webhooks know the given [`BulkImport`] was just finished. This
+/// also goes to [`crate::config::BulkConfig::webhook`] if it's set.
+pub fn notify_import_finished(config: &Config, import: &BulkImport) {
+    let mut hooks = receivers(config, WebhookEvent::BulkImportFinished);
+    if let Some(url) = config.bulk.webhook.to_owned() {
+        hooks.push(WebhookConfig { url, secret: None, events: Default::default() });
+    }
+
+    send(hooks, WebhookEvent::BulkImportFinished, &ImportFinished::from(import));
+}
+
+/// Lets the webhooks know the given `(id, name)` pairs were just created or
+/// deleted by the given user
+pub fn notify_pigs(config: &Config, event: WebhookEvent, pigs: &[(Uuid, String)], actor: &Uuid) {
+    if pigs.is_empty() {
+        return;
+    }
+
+    let pigs = pigs.iter().map(|(id, name)| WebhookPig { id: id.to_owned(), name: name.to_owned() }).collect();
+    send(receivers(config, event), event, &PigsChanged { event, actor: actor.to_owned(), pigs });
+}
+
+/// The webhooks which want to know about the given event
+fn receivers(config: &Config, event: WebhookEvent) -> Vec<WebhookConfig> {
+    config.webhooks.iter().filter(|hook| hook.receives(event)).cloned().collect()
+}
+
+/// POSTs the payload to each of the given webhooks. The requests are sent in
+/// the background so a slow receiver doesn't hold up the response, and
+/// failures are only logged.
+fn send<T: Serialize>(hooks: Vec<WebhookConfig>, event: WebhookEvent, payload: &T) {
+    if hooks.is_empty() {
+        return;
+    }
+
+    // every receiver gets the exact bytes which were signed
+    let body = match serde_json::to_string(payload) {
+        Ok(body) => body,
+        Err(err) => {
+            error!("Unable to serialize {} webhook: {:?}", event.name(), err);
+            return;
+        }
+    };
+
+    rocket::tokio::task::spawn_blocking(move || {
+        for hook in hooks {
+            let mut request =
+                AGENT.post(&hook.url).set("Content-Type", "application/json").set(EVENT_HEADER, event.name());
+
+            if let Some(secret) = hook.secret.as_ref() {
+                request = request.set(SIGNATURE_HEADER, &sign(secret, &body));
+            }
+
+            if let Err(err) = request.send_string(&body) {
+                error!("Unable to send {} webhook to {:?}: {:?}", event.name(), hook.url, err);
+            }
+        }
+    });
+}
+
+/// Signs the body with the secret, as `sha256=` followed by the hex of its
+/// HMAC-SHA256
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
@@ -0,0 +1,298 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use pigweb_common::bulk::{BulkImport, BulkQuery};
+use pigweb_common::pigs::{Pig, PigQuery};
+use pigweb_common::users::Roles;
+use pigweb_common::{query, yuri, AUTH_API_ROOT, BULK_API_ROOT, PIG_API_ROOT};
+use std::collections::BTreeSet;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long to wait for the server to start taking requests
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait between checking whether the server is ready
+const STARTUP_POLL: Duration = Duration::from_millis(250);
+
+/// How far to move the server's clock before importing, in seconds
+const TRAVEL_SECONDS: i64 = 60 * 60;
+
+/// When the server's clock starts, so times it sets can be checked exactly
+fn clock_start() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()
+}
+
+/// Boots the server against a temporary database, walks through the happy path
+/// of the API, then cleans up after itself. Exits with status 1 if any step
+/// fails.
+///
+/// - `PIGWEB_E2E_DATABASE` is the connection string used to create and drop
+///   the temporary database, in keyword/value format. Defaults to the
+///   `postgres` database with the usual `PG*` env variables.
+/// - `PIGWEB_E2E_SERVER` is the server binary to run. Defaults to the debug
+///   build in `target`.
+fn main() {
+    let admin_connection = std::env::var("PIGWEB_E2E_DATABASE").unwrap_or("dbname='postgres'".to_owned());
+    let server_path = std::env::var("PIGWEB_E2E_SERVER")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/debug/pigweb_server"));
+
+    let result = TempDatabase::create(&admin_connection).and_then(|database| {
+        let server = TestServer::start(&server_path, &database)?;
+        run_steps(&server)
+    });
+
+    if let Err(err) = result {
+        eprintln!("FAIL  {}", err);
+        std::process::exit(1);
+    }
+
+    println!("All steps passed.");
+}
+
+/// Walks through everything a user would normally do, in order
+fn run_steps(server: &TestServer) -> Result<(), String> {
+    step("Sign in", || sign_in(server))?;
+    let pig = step("Create pig", || create_pig(server, "E2E Pig"))?;
+    step("Fetch pig", || expect_pigs(server, &[pig.id], 1))?;
+    let now = step("Travel in time", || travel(server, TRAVEL_SECONDS))?;
+    let mut ids = step("Bulk import", || bulk_import(server, now))?;
+    step("Fetch imported pigs", || expect_pigs(server, &ids, ids.len()))?;
+
+    ids.push(pig.id);
+    step("Delete pigs", || ids.iter().try_for_each(|id| delete_pig(server, id)))?;
+    step("Fetch deleted pigs", || expect_pigs(server, &ids, 0))?;
+
+    Ok(())
+}
+
+/// Runs a single step, printing whether it passed
+fn step<T>(name: &str, run: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let res = run().map_err(|err| format!("{}: {}", name, err))?;
+    println!("PASS  {}", name);
+    Ok(res)
+}
+
+/// Makes sure we're signed in with every role. There's no OIDC provider for
+/// the test server, so every request is made as the system user.
+fn sign_in(server: &TestServer) -> Result<(), String> {
+    let roles: BTreeSet<Roles> = server.get(&yuri!(AUTH_API_ROOT))?;
+    match roles.contains(&Roles::PigEditor) && roles.contains(&Roles::BulkEditor) {
+        true => Ok(()),
+        false => Err(format!("Missing roles needed for the other steps, got {:?}", roles)),
+    }
+}
+
+/// Creates a new pig with the given name
+fn create_pig(server: &TestServer, name: &str) -> Result<Pig, String> {
+    let pig: Pig = server.send("POST", &yuri!(PIG_API_ROOT, "create" ;? query!("name" = name)), None)?;
+    match pig.name == name {
+        true => Ok(pig),
+        false => Err(format!("Created pig has the wrong name: {:?}", pig)),
+    }
+}
+
+/// Makes sure fetching the pigs with the given ids finds the given number of
+/// them
+fn expect_pigs(server: &TestServer, ids: &[Uuid], expected: usize) -> Result<(), String> {
+    let found: Vec<Pig> = server.get(&PigQuery::default().with_ids(&ids.to_vec()).to_yuri())?;
+    match found.len() == expected {
+        true => Ok(()),
+        false => Err(format!("Expected {} pigs, found {:?}", expected, found)),
+    }
+}
+
+/// Moves the server's clock forward by the given number of seconds, and makes
+/// sure it moved exactly that far. Returns the new time.
+fn travel(server: &TestServer, seconds: i64) -> Result<NaiveDateTime, String> {
+    let now: NaiveDateTime =
+        server.send("POST", &yuri!("/api/clock/travel" ;? query!("seconds" = &seconds.to_string())), None)?;
+    match now == clock_start() + chrono::Duration::seconds(seconds) {
+        true => Ok(now),
+        false => Err(format!("Clock moved to {} instead of {} seconds after {}", now, seconds, clock_start())),
+    }
+}
+
+/// Imports names which aren't like any existing pig, so they should all be
+/// accepted right away and the import finished at the given time. Returns the
+/// ids of the pigs which were created.
+fn bulk_import(server: &TestServer, now: NaiveDateTime) -> Result<Vec<Uuid>, String> {
+    let names = vec!["Quarterly Bacon Audit".to_owned(), "Sir Oinksalot".to_owned()];
+    let import: BulkImport =
+        server.send("POST", &yuri!(BULK_API_ROOT, "create"), Some(serde_json::to_value(&names).unwrap()))?;
+
+    // Make sure the import was saved the way it was returned
+    let imports: Vec<BulkImport> = server.get(&BulkQuery::default().with_id(&import.id).to_yuri())?;
+    match imports.first() {
+        Some(saved) if saved.finished == Some(now) && saved.accepted.len() == names.len() => {
+            Ok(saved.accepted.to_owned())
+        }
+        Some(saved) => Err(format!("Import wasn't finished properly: {:?}", saved)),
+        None => Err(format!("Unable to find import {}", import.id)),
+    }
+}
+
+/// Deletes the given pig
+fn delete_pig(server: &TestServer, id: &Uuid) -> Result<(), String> {
+    server.send_empty("DELETE", &yuri!(PIG_API_ROOT, "delete" ;? query!("id" = &id.to_string())), None)
+}
+
+/// A database made just for this run, which is dropped when it goes out of
+/// scope
+struct TempDatabase {
+    /// The connection string for the maintenance database
+    admin_connection: String,
+
+    /// The name of the temporary database
+    name: String,
+}
+
+impl TempDatabase {
+    /// Creates a new database with a random name
+    fn create(admin_connection: &str) -> Result<Self, String> {
+        let name = format!("pigweb_e2e_{}", Uuid::new_v4().simple());
+        let mut db_connection = PgConnection::establish(admin_connection)
+            .map_err(|e| format!("Unable to connect to {:?}: {:?}", admin_connection, e))?;
+        diesel::sql_query(format!("CREATE DATABASE {}", name))
+            .execute(&mut db_connection)
+            .map_err(|e| format!("Unable to create database {}: {:?}", name, e))?;
+
+        println!("Created database {}", name);
+        Ok(Self { admin_connection: admin_connection.to_owned(), name })
+    }
+
+    /// Gets the connection string for this database. Later keywords override
+    /// earlier ones, so only the database name has to change.
+    fn connection_string(&self) -> String {
+        format!("{} dbname='{}'", self.admin_connection, self.name)
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        let res = PgConnection::establish(&self.admin_connection).map_err(|e| format!("{:?}", e)).and_then(
+            |mut db_connection| {
+                diesel::sql_query(format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.name))
+                    .execute(&mut db_connection)
+                    .map_err(|e| format!("{:?}", e))
+            },
+        );
+
+        match res {
+            Ok(_) => println!("Dropped database {}", self.name),
+            Err(err) => eprintln!("Unable to drop database {}, remove it by hand: {}", self.name, err),
+        }
+    }
+}
+
+/// The server being tested, which is killed when it goes out of scope
+struct TestServer {
+    /// The running server process
+    process: Child,
+
+    /// Where to send requests, without a trailing slash
+    base_url: String,
+}
+
+impl TestServer {
+    /// Starts the server against the given database and waits for it to be
+    /// ready. OIDC isn't configured, so all requests are made as the system
+    /// user. The server's clock only moves when told to, see [`travel`].
+    fn start(server_path: &PathBuf, database: &TempDatabase) -> Result<Self, String> {
+        // Let the OS pick a free port, there's a tiny window for something else to grab it before the server does
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| format!("Unable to find a free port: {:?}", e))?
+            .port();
+
+        let process = Command::new(server_path)
+            .env("PIGWEB_CONFIG", "e2e-does-not-exist.toml")
+            .env("PIGWEB_ADDRESS", "127.0.0.1")
+            .env("PIGWEB_PORT", port.to_string())
+            .env("PIGWEB_LOG_LEVEL", "critical")
+            .env("PIGWEB_DATABASE__URI", database.connection_string())
+            .env("PIGWEB_TEST_CLOCK", clock_start().format("%Y-%m-%dT%H:%M:%S").to_string())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Unable to start {:?}, was it built? {:?}", server_path, e))?;
+
+        let mut server = Self { process, base_url: format!("http://127.0.0.1:{}", port) };
+        server.wait_until_ready()?;
+
+        println!("Started server at {}", server.base_url);
+        Ok(server)
+    }
+
+    /// Polls `/api/ready` until the server responds or gives up
+    fn wait_until_ready(&mut self) -> Result<(), String> {
+        let started = Instant::now();
+        while started.elapsed() < STARTUP_TIMEOUT {
+            if let Ok(Some(status)) = self.process.try_wait() {
+                return Err(format!("Server exited during startup with {}", status));
+            }
+
+            if ureq::get(&self.url("/api/ready")).call().is_ok_and(|res| res.status() == 204) {
+                return Ok(());
+            }
+
+            sleep(STARTUP_POLL);
+        }
+
+        Err(format!("Server wasn't ready after {:?}", STARTUP_TIMEOUT))
+    }
+
+    /// Gets the full URL for the given path
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Sends a GET request and parses the JSON response
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        self.send("GET", path, None)
+    }
+
+    /// Sends a request with the given JSON body and parses the JSON response
+    fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<T, String> {
+        self.call(method, path, body)?
+            .into_json()
+            .map_err(|e| format!("Unable to parse the response to {} {}: {:?}", method, path, e))
+    }
+
+    /// Sends a request with the given JSON body, ignoring the response body
+    fn send_empty(&self, method: &str, path: &str, body: Option<serde_json::Value>) -> Result<(), String> {
+        self.call(method, path, body).map(|_| ())
+    }
+
+    /// Sends a request, turning any error status into an error message
+    fn call(&self, method: &str, path: &str, body: Option<serde_json::Value>) -> Result<ureq::Response, String> {
+        let req = ureq::request(method, &self.url(path));
+        let res = match body {
+            Some(body) => req.send_json(body),
+            None => req.call(),
+        };
+
+        res.map_err(|e| match e {
+            ureq::Error::Status(status, res) => {
+                format!("{} {} responded with {}: {}", method, path, status, res.into_string().unwrap_or_default())
+            }
+            e => format!("Unable to send {} {}: {:?}", method, path, e),
+        })
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // The server has to stop before its database can be dropped
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}